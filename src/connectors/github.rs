@@ -1,20 +1,93 @@
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::{Arc, LazyLock};
+use std::task::{Context, Poll};
 
+use bytes::Bytes;
 use futures_util::{StreamExt, TryStreamExt};
-use graphql_client::{reqwest::post_graphql_blocking as post_graphql, GraphQLQuery};
-use octocrab::Octocrab;
+use graphql_client::GraphQLQuery;
+use http::{header, HeaderValue};
+use http_body_util::{BodyExt, Full};
+use octocrab::{AuthState, Octocrab, OctocrabBuilder};
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::base_uri::BaseUriLayer;
+use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
 use octocrab::models::{CommentId, IssueState};
+use octocrab::models::issues::{Issue, IssueStateReason};
 use octocrab::params::State;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use regex::Regex;
 use tokio::pin;
 use tokio::runtime::Runtime;
+use tower::{BoxError, Service};
 
-use gittask::{Comment, Label, Task};
-use crate::connectors::{RemoteConnector, RemoteTaskState};
+use gittask::{Comment, Label, Task, TaskContext};
+use crate::connectors::transport::{connector_transport, HttpTransport};
+use crate::connectors::{fetch_concurrently, get_concurrency, http_error, parse_remote_id, label_description, label_description_changed, priority_from_labels, strip_comment_marker, with_comment_marker, with_priority_label, RemoteConnector, RemoteTaskState, PRIORITY, STATE_REASON};
 use crate::util::color_str_to_rgb_str;
 
-pub struct GithubRemoteConnector;
+const GITHUB_API_URL: &str = "https://api.github.com";
+const GITHUB_UPLOAD_URL: &str = "https://uploads.github.com";
+
+pub struct GithubRemoteConnector {
+    context: TaskContext,
+    transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl GithubRemoteConnector {
+    pub fn new(context: &TaskContext) -> Self {
+        Self {
+            context: context.clone(),
+            transport: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_transport(context: &TaskContext, transport: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            context: context.clone(),
+            transport: Some(transport),
+        }
+    }
+
+    fn get_octocrab_instance(&self) -> Result<Arc<Octocrab>, String> {
+        let transport = connector_transport(&self.context, self.type_name(), &self.transport)?;
+        let api_url = get_api_base_url(&self.context);
+        RUNTIME.block_on(get_octocrab_instance(transport, api_url, get_token(&self.context)))
+    }
+}
+
+/// Lets octocrab send its requests through a transport, the blocking call runs off the async workers.
+#[derive(Clone)]
+struct TransportService {
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl<B> Service<http::Request<B>> for TransportService
+where
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = http::Response<Full<Bytes>>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let transport = self.transport.clone();
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = body.collect().await.map_err(Into::into)?.to_bytes();
+            let request = http::Request::from_parts(parts, body.to_vec());
+            let response = tokio::task::spawn_blocking(move || transport.send(request)).await??;
+            Ok(response.map(|body| Full::new(Bytes::from(body))))
+        })
+    }
+}
 
 static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
     Runtime::new().unwrap()
@@ -25,8 +98,21 @@ impl RemoteConnector for GithubRemoteConnector {
         "github"
     }
 
+    fn get_config_options(&self) -> Option<Vec<String>> {
+        Some(vec!["task.github.url".to_string(), "task.github.token".to_string(), "task.github.concurrency".to_string()])
+    }
+
+    fn max_body_length(&self) -> Option<usize> {
+        Some(65536)
+    }
+
+    fn marks_comments(&self) -> bool {
+        true
+    }
+
     fn supports_remote(&self, url: &str) -> Option<(String, String)> {
-        match Regex::new("((https://)|(git@))github.com[/:](?P<user>[a-zA-Z0-9-]+)/(?P<repo>[a-zA-Z0-9-]+)(\\.git)?").unwrap().captures(url) {
+        let host = regex::escape(&get_host(&self.context));
+        match Regex::new(&format!("((https://)|(git@)){host}[/:](?P<user>[a-zA-Z0-9-]+)/(?P<repo>[a-zA-Z0-9-]+)(\\.git)?")).unwrap().captures(url) {
             Some(caps) if caps.len() >= 3 => {
                 let user = caps.name("user")?.as_str().to_string();
                 let repo = caps.name("repo")?.as_str().to_string();
@@ -36,6 +122,10 @@ impl RemoteConnector for GithubRemoteConnector {
         }
     }
 
+    fn task_url(&self, user: &String, repo: &String, task_id: &String) -> String {
+        format!("https://{}/{user}/{repo}/issues/{task_id}", get_host(&self.context))
+    }
+
     fn list_remote_tasks(
         &self,
         user: &String,
@@ -44,23 +134,18 @@ impl RemoteConnector for GithubRemoteConnector {
         with_labels: bool,
         limit: Option<usize>,
         state: RemoteTaskState,
-        task_statuses: &Vec<String>
+        since: Option<u64>,
+        task_statuses: &Vec<String>,
+        progress: Option<&dyn Fn(usize, usize)>
     ) -> Result<Vec<Task>, String> {
         let state = match state {
             RemoteTaskState::Open(_, _) => State::Open,
-            RemoteTaskState::Closed(_, _) => State::Closed,
+            RemoteTaskState::Closed(_, _, _) => State::Closed,
             RemoteTaskState::All => State::All,
         };
-        RUNTIME.block_on(
-            list_issues(
-                user,
-                repo,
-                with_comments,
-                with_labels,
-                limit,
-                state,
-                task_statuses
-            ))
+        let crab = self.get_octocrab_instance()?;
+        let query = IssueQuery { with_comments, with_labels, limit, state, since };
+        RUNTIME.block_on(list_issues(&crab, user, repo, query, task_statuses, get_concurrency(&self.context, self.type_name()), progress))
     }
 
     fn get_remote_task(
@@ -71,38 +156,48 @@ impl RemoteConnector for GithubRemoteConnector {
         with_comments: bool,
         with_labels: bool,
         task_statuses: &Vec<String>
-    ) -> Result<Task, String> {
+    ) -> Result<Option<Task>, String> {
+        let crab = self.get_octocrab_instance()?;
         RUNTIME.block_on(
             get_issue(
-                &user, &repo, task_id.parse().unwrap(), with_comments, with_labels, task_statuses
+                &crab, user, repo, parse_remote_id(task_id)?, with_comments, with_labels, task_statuses
             )
         )
     }
 
     fn create_remote_task(&self, user: &String, repo: &String, task: &Task) -> Result<String, String> {
-        match get_token_from_env() {
-            Some(_) => RUNTIME.block_on(create_issue(user, repo, task)),
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+        match get_token(&self.context) {
+            Some(_) => RUNTIME.block_on(create_issue(&self.context, &self.get_octocrab_instance()?, user, repo, task)),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 
     fn create_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment: &Comment) -> Result<String, String> {
-        match get_token_from_env() {
-            Some(_) => RUNTIME.block_on(create_comment(user, repo, task_id, comment)),
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+        match get_token(&self.context) {
+            Some(_) => RUNTIME.block_on(create_comment(&self.get_octocrab_instance()?, user, repo, task_id, comment)),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
+        }
+    }
+
+    fn list_remote_comment_texts(&self, user: &String, repo: &String, task_id: &String) -> Result<Vec<(String, String)>, String> {
+        match get_token(&self.context) {
+            Some(_) => RUNTIME.block_on(list_remote_comment_texts(&self.get_octocrab_instance()?, user, repo, parse_remote_id(task_id)?)),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 
     fn create_remote_label(&self, user: &String, repo: &String, task_id: &String, label: &Label) -> Result<(), String> {
-        match get_token_from_env() {
+        match get_token(&self.context) {
             Some(_) => RUNTIME.block_on(
                 add_label(
+                    &self.context,
+                    &self.get_octocrab_instance()?,
                     user,
                     repo,
-                    task_id.parse().unwrap(),
+                    parse_remote_id(task_id)?,
                     label
                 )),
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 
@@ -114,38 +209,45 @@ impl RemoteConnector for GithubRemoteConnector {
         labels: Option<&Vec<Label>>,
         state: RemoteTaskState
     ) -> Result<(), String> {
-        match get_token_from_env() {
+        match get_token(&self.context) {
             Some(_) => {
-                let state = match state {
-                    RemoteTaskState::Closed(_, _) => IssueState::Closed,
-                    _ => IssueState::Open,
+                let (state, state_reason) = match state {
+                    RemoteTaskState::Closed(_, _, reason) => (IssueState::Closed, reason.as_deref().and_then(parse_state_reason)),
+                    _ => (IssueState::Open, None),
                 };
+                let labels = labels.map(|labels| with_priority_label(labels, task));
+                let crab = self.get_octocrab_instance()?;
+                if let Some(labels) = labels.as_ref().filter(|labels| !labels.is_empty()) {
+                    RUNTIME.block_on(prepare_labels(&self.context, user, repo, labels, &crab));
+                }
                 RUNTIME.block_on(
                     update_issue(
+                        &crab,
                         user,
                         repo,
-                        task.get_id().unwrap().parse().unwrap(),
+                        parse_remote_id(&task.get_id().unwrap())?,
                         task.get_property("name").unwrap(),
                         task.get_property("description").unwrap(),
-                        labels,
-                        state
+                        labels.as_ref(),
+                        state,
+                        state_reason
                     ))
             },
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 
-    fn update_remote_comment(&self, user: &String, repo: &String, _task_id: &String, comment_id: &String, text: &String) -> Result<(), String> {
-        match get_token_from_env() {
-            Some(_) => RUNTIME.block_on(update_comment(user, repo, comment_id.parse().unwrap(), text)),
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+    fn update_remote_comment(&self, user: &String, repo: &String, _task_id: &String, comment_id: &String, comment: &Comment) -> Result<(), String> {
+        match get_token(&self.context) {
+            Some(_) => RUNTIME.block_on(update_comment(&self.get_octocrab_instance()?, user, repo, parse_remote_id(comment_id)?, &comment.get_text())),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 
     fn delete_remote_task(&self, user: &String, repo: &String, task_id: &String) -> Result<(), String> {
-        match get_token_from_env() {
+        match get_token(&self.context) {
             Some(token) => {
-                let issue_id = RUNTIME.block_on(get_issue_id(user, repo, task_id.parse().unwrap()));
+                let issue_id = RUNTIME.block_on(get_issue_id(&self.get_octocrab_instance()?, user, repo, parse_remote_id(task_id)?));
                 if issue_id.is_err() {
                     return Err("Could not match task ID with GitHub internal issue ID.".to_string());
                 }
@@ -154,17 +256,17 @@ impl RemoteConnector for GithubRemoteConnector {
                     issue_id,
                 };
 
-                let client = reqwest::blocking::Client::builder()
-                    .user_agent("git-task/".to_owned() + env!("CARGO_PKG_VERSION"))
-                    .default_headers(
-                        std::iter::once((
-                            reqwest::header::AUTHORIZATION,
-                            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
-                        )).collect(),
-                    )
-                    .build().unwrap();
-
-                let response_body = post_graphql::<DeleteIssue, _>(&client, "https://api.github.com/graphql", variables).expect("Failed to make GraphQL request");
+                let request = http::Request::post(get_graphql_url(&get_host(&self.context)))
+                    .header(header::USER_AGENT, user_agent())
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_vec(&DeleteIssue::build_query(variables)).map_err(|e| e.to_string())?)
+                    .map_err(|e| e.to_string())?;
+                let response = connector_transport(&self.context, self.type_name(), &self.transport)?.send(request)?;
+                if !response.status().is_success() {
+                    return Err(format!("GraphQL request failed: {} {}", response.status(), String::from_utf8_lossy(response.body())));
+                }
+                let response_body: graphql_client::Response<delete_issue::ResponseData> = serde_json::from_slice(response.body()).map_err(|e| e.to_string())?;
 
                 if let Some(errors) = response_body.errors {
                     if !errors.is_empty() {
@@ -183,21 +285,21 @@ impl RemoteConnector for GithubRemoteConnector {
                     None => Err("Response data not found".to_string())
                 }
             },
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string()),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string()),
         }
     }
 
     fn delete_remote_comment(&self, user: &String, repo: &String, _task_id: &String, comment_id: &String) -> Result<(), String> {
-        match get_token_from_env() {
-            Some(_) => RUNTIME.block_on(delete_comment(user, repo, comment_id.parse().unwrap())),
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+        match get_token(&self.context) {
+            Some(_) => RUNTIME.block_on(delete_comment(&self.get_octocrab_instance()?, user, repo, parse_remote_id(comment_id)?)),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 
     fn delete_remote_label(&self, user: &String, repo: &String, task_id: &String, name: &String) -> Result<(), String> {
-        match get_token_from_env() {
-            Some(_) => RUNTIME.block_on(delete_label(user, repo, task_id.parse().unwrap(), name)),
-            None => Err("Could not find GITHUB_TOKEN environment variable.".to_string())
+        match get_token(&self.context) {
+            Some(_) => RUNTIME.block_on(delete_label(&self.get_octocrab_instance()?, user, repo, parse_remote_id(task_id)?, name)),
+            None => Err("Could not find a GitHub token, set task.github.token or GITHUB_TOKEN environment variable.".to_string())
         }
     }
 }
@@ -210,79 +312,101 @@ impl RemoteConnector for GithubRemoteConnector {
 )]
 struct DeleteIssue;
 
-async fn list_issues(
-    user: &String,
-    repo: &String,
+/// Which issues `list_issues` fetches and what it fetches along with them.
+struct IssueQuery {
     with_comments: bool,
     with_labels: bool,
     limit: Option<usize>,
     state: State,
-    task_statuses: &Vec<String>
+    since: Option<u64>,
+}
+
+async fn list_issues(
+    crab: &Arc<Octocrab>,
+    user: &String,
+    repo: &String,
+    query: IssueQuery,
+    task_statuses: &Vec<String>,
+    concurrency: usize,
+    progress: Option<&dyn Fn(usize, usize)>
 ) -> Result<Vec<Task>, String> {
-    let mut result = vec![];
-    let crab = get_octocrab_instance().await;
-    let stream = crab.issues(user, repo)
+    let IssueQuery { with_comments, with_labels, limit, state, since } = query;
+    let issues = crab.issues(user, repo);
+    let mut list = issues
         .list()
         .state(state)
-        .per_page(100)
+        .per_page(100);
+    if let Some(since) = since.and_then(|since| chrono::DateTime::from_timestamp(since as i64, 0)) {
+        list = list.since(since);
+    }
+    let page = list
         .send()
-        .await.map_err(|e| e.to_string())?
-        .into_stream(&crab);
-    pin!(stream);
-    let mut count = 0;
-    while let Some(issue) = stream.try_next().await.map_err(|e| e.to_string())? {
-        if limit.is_some() && count >= limit.unwrap() {
-            break;
-        }
-        count += 1;
-        let mut props = HashMap::new();
-        props.insert(String::from("name"), issue.title);
-        props.insert(String::from("status"), if issue.state == IssueState::Open { task_statuses.first().unwrap().clone() } else { task_statuses.last().unwrap().clone() } );
-        props.insert(String::from("description"), issue.body.unwrap_or(String::new()));
-        props.insert(String::from("created"), issue.created_at.timestamp().to_string());
-        props.insert(String::from("author"), issue.user.login);
+        .await.map_err(http_error)?;
+    let expected = page.number_of_pages().map_or(page.items.len(), |pages| pages as usize * 100);
+    let total = limit.map_or(expected, |limit| limit.min(expected));
+    let issues = page.into_stream(crab).take(limit.unwrap_or(usize::MAX));
 
-        let mut task = Task::from_properties(issue.number.to_string(), props).unwrap();
+    let result = fetch_concurrently(issues, total, concurrency, progress, |issue| async move {
+        let issue = issue.map_err(http_error)?;
+        let mut task = issue_to_task(&issue, task_statuses);
 
         if with_comments {
-            let task_comments = list_issue_comments(&user, &repo, issue.number).await;
+            let task_comments = list_issue_comments(crab, user, repo, issue.number).await;
             task.set_comments(task_comments);
         }
 
         if with_labels {
             if !issue.labels.is_empty() {
-                let labels = issue.labels.iter()
-                    .map(|l| Label::new(
-                        l.name.clone(),
-                        Some(l.color.clone()),
-                        l.description.clone()
-                    ))
-                    .collect();
+                let labels = issue.labels.iter().map(to_label).collect();
                 task.set_labels(labels);
             }
         }
 
-        result.push(task);
+        Ok(task)
+    }).await;
+
+    result.into_iter().collect()
+}
+
+fn issue_to_task(issue: &Issue, task_statuses: &[String]) -> Task {
+    let mut props = HashMap::new();
+    props.insert(String::from("name"), issue.title.clone());
+    props.insert(String::from("status"), if issue.state == IssueState::Open { task_statuses.first().unwrap().clone() } else { task_statuses.last().unwrap().clone() } );
+    props.insert(String::from("description"), issue.body.clone().unwrap_or_default());
+    props.insert(String::from("created"), issue.created_at.timestamp().to_string());
+    props.insert(String::from("author"), issue.user.login.clone());
+    if let Some(reason) = issue.state_reason.as_ref().filter(|_| issue.state == IssueState::Closed).and_then(format_state_reason) {
+        props.insert(STATE_REASON.to_string(), reason);
+    }
+    if let Some(priority) = priority_from_labels(issue.labels.iter().map(|label| label.name.as_str())) {
+        props.insert(PRIORITY.to_string(), priority.to_string());
     }
 
-    Ok(result)
+    Task::from_properties(issue.number.to_string(), props).unwrap()
+}
+
+fn format_state_reason(reason: &IssueStateReason) -> Option<String> {
+    serde_json::to_value(reason).ok()?.as_str().map(|reason| reason.to_string())
+}
+
+fn parse_state_reason(reason: &str) -> Option<IssueStateReason> {
+    serde_json::from_value(serde_json::Value::String(reason.to_string())).ok()
 }
 
-async fn list_issue_comments(user: &String, repo: &String, n: u64) -> Vec<Comment> {
+async fn list_issue_comments(crab: &Arc<Octocrab>, user: &String, repo: &String, n: u64) -> Vec<Comment> {
     let mut result = vec![];
-    let crab = get_octocrab_instance().await;
     let stream = crab.issues(user, repo)
         .list_comments(n)
         .per_page(100)
         .send()
         .await.unwrap()
-        .into_stream(&crab);
+        .into_stream(crab);
     pin!(stream);
     while let Some(comment) = stream.try_next().await.unwrap() {
         let comment = Comment::new(comment.id.to_string(), HashMap::from([
             ("author".to_string(), comment.user.login),
             ("created".to_string(), comment.created_at.timestamp().to_string()),
-        ]), comment.body.unwrap());
+        ]), strip_comment_marker(&comment.body.unwrap()));
         result.push(comment);
     }
 
@@ -290,60 +414,47 @@ async fn list_issue_comments(user: &String, repo: &String, n: u64) -> Vec<Commen
 }
 
 async fn get_issue(
+    crab: &Arc<Octocrab>,
     user: &String,
     repo: &String,
     n: u64,
     with_comments: bool,
     with_labels: bool,
     task_statuses: &Vec<String>
-) -> Result<Task, String> {
-    let crab = get_octocrab_instance().await;
+) -> Result<Option<Task>, String> {
     let issue = crab.issues(user, repo).get(n).await;
     match issue {
         Ok(issue) => {
-            let mut props = HashMap::new();
-            props.insert(String::from("name"), issue.title);
-            props.insert(String::from("status"), if issue.state == IssueState::Open { task_statuses.first().unwrap().clone() } else { task_statuses.last().unwrap().clone() } );
-            props.insert(String::from("description"), issue.body.unwrap_or(String::new()));
-            props.insert(String::from("created"), issue.created_at.timestamp().to_string());
-            props.insert(String::from("author"), issue.user.login);
-
-            let mut task = Task::from_properties(n.to_string(), props).unwrap();
+            let mut task = issue_to_task(&issue, task_statuses);
 
             if with_comments {
-                let task_comments = list_issue_comments(user, repo, issue.number).await;
+                let task_comments = list_issue_comments(crab, user, repo, issue.number).await;
                 task.set_comments(task_comments);
             }
 
             if with_labels {
-                let labels = issue.labels.iter()
-                    .map(|l| Label::new(
-                        l.name.to_string(),
-                        Some(l.color.to_string()),
-                        l.description.clone()
-                    )).collect();
+                let labels = issue.labels.iter().map(to_label).collect();
                 task.set_labels(labels);
             }
 
-            Ok(task)
+            Ok(Some(task))
         },
+        Err(octocrab::Error::GitHub { source, .. }) if source.status_code == http::StatusCode::NOT_FOUND => Ok(None),
         Err(e) => Err(e.to_string())
     }
 }
 
-async fn create_issue(user: &String, repo: &String, task: &Task) -> Result<String, String> {
-    let crab = get_octocrab_instance().await;
+async fn create_issue(context: &TaskContext, crab: &Arc<Octocrab>, user: &String, repo: &String, task: &Task) -> Result<String, String> {
     let crab_issues = crab.issues(user, repo);
     let mut create_builder = crab_issues.create(task.get_property("name").unwrap());
     if let Some(description) = task.get_property("description") {
         create_builder = create_builder.body(description);
     }
-    if let Some(labels) = task.get_labels() {
-        if !labels.is_empty() {
-            prepare_labels(user, repo, labels, &crab).await;
-            let labels = labels.iter().map(|l| l.get_name()).collect::<Vec<_>>();
-            create_builder = create_builder.labels(labels);
-        }
+    let labels = with_priority_label(task.get_labels().as_deref().unwrap_or_default(), task);
+    if !labels.is_empty() {
+        prepare_labels(context, user, repo, &labels, crab).await;
+        let labels = labels.iter().map(|l| l.get_name()).collect::<Vec<_>>();
+        create_builder = create_builder.labels(labels);
     }
     match create_builder.send().await {
         Ok(issue) => Ok(issue.number.to_string()),
@@ -351,22 +462,39 @@ async fn create_issue(user: &String, repo: &String, task: &Task) -> Result<Strin
     }
 }
 
-async fn create_comment(user: &String, repo: &String, task_id: &String, comment: &Comment) -> Result<String, String> {
-    let crab = get_octocrab_instance().await;
-    match crab.issues(user, repo).create_comment(task_id.parse().unwrap(), comment.get_text()).await {
+async fn create_comment(crab: &Arc<Octocrab>, user: &String, repo: &String, task_id: &String, comment: &Comment) -> Result<String, String> {
+    let n = parse_remote_id(task_id)?;
+    match crab.issues(user, repo).create_comment(n, with_comment_marker(comment)).await {
         Ok(comment) => Ok(comment.id.to_string()),
         Err(e) => Err(e.to_string())
     }
 }
 
+async fn list_remote_comment_texts(crab: &Arc<Octocrab>, user: &String, repo: &String, n: u64) -> Result<Vec<(String, String)>, String> {
+    let mut result = vec![];
+    let stream = crab.issues(user, repo)
+        .list_comments(n)
+        .per_page(100)
+        .send()
+        .await.map_err(|e| e.to_string())?
+        .into_stream(crab);
+    pin!(stream);
+    while let Some(comment) = stream.try_next().await.map_err(|e| e.to_string())? {
+        result.push((comment.id.to_string(), comment.body.unwrap_or_default()));
+    }
+
+    Ok(result)
+}
+
 async fn add_label(
+    context: &TaskContext,
+    crab: &Arc<Octocrab>,
     user: &String,
     repo: &String,
     n: u64,
     label: &Label,
 ) -> Result<(), String> {
-    let crab = get_octocrab_instance().await;
-    let _ = prepare_labels(user, repo, &vec![label.clone()], &crab).await;
+    prepare_labels(context, user, repo, &vec![label.clone()], crab).await;
     let add_label_body = vec![label.get_name()];
     crab
         .issues(user, repo)
@@ -376,47 +504,75 @@ async fn add_label(
         .map_err(|e| e.to_string())
 }
 
+/// Creates the missing repository labels and updates changed descriptions, failures are
+/// reported as warnings since the issue itself can still be pushed.
 async fn prepare_labels(
+    context: &TaskContext,
     user: &String,
     repo: &String,
     labels: &Vec<Label>,
     crab: &Arc<Octocrab>)
 {
-    let existing_labels_stream = crab
+    let existing_labels_stream = match crab
         .issues(user, repo)
         .list_labels_for_repo()
         .per_page(100)
         .send()
-        .await.unwrap()
-        .into_stream(&crab);
+        .await {
+        Ok(page) => page.into_stream(crab),
+        Err(e) => {
+            context.add_warning(format!("Could not list the labels of {user}/{repo}: {e}"));
+            return;
+        }
+    };
     pin!(existing_labels_stream);
     let mut labels_to_create = labels.clone();
     while let Some(Ok(label)) = existing_labels_stream.next().await {
         if let Some(pos) = labels_to_create.iter().position(|l| l.get_name() == label.name) {
-            labels_to_create.remove(pos);
+            let local_label = labels_to_create.remove(pos);
+            if label_description_changed(&local_label, label.description.as_deref()) {
+                let name = utf8_percent_encode(&label.name, NON_ALPHANUMERIC);
+                if let Err(e) = crab
+                    .patch::<octocrab::models::Label, _, _>(
+                        format!("/repos/{user}/{repo}/labels/{name}"),
+                        Some(&serde_json::json!({ "description": local_label.get_description() })),
+                    )
+                    .await {
+                    context.add_warning(format!("Could not update label {}: {e}", label.name));
+                }
+            }
         }
     }
     for l in labels_to_create.iter() {
-        let _ = crab
+        if let Err(e) = crab
             .issues(user, repo)
             .create_label(
                 l.get_name(),
                 color_str_to_rgb_str(&l.get_color()),
                 l.get_description().unwrap_or_else(|| "".to_string()),
             )
-            .await;
+            .await {
+            context.add_warning(format!("Could not create label {}: {e}", l.get_name()));
+        }
     }
 }
 
-async fn update_issue(user: &String, repo: &String, n: u64, title: &String, body: &String, labels: Option<&Vec<Label>>, state: IssueState) -> Result<(), String> {
-    let crab = get_octocrab_instance().await;
+fn to_label(label: &octocrab::models::Label) -> Label {
+    Label::new(
+        label.name.clone(),
+        Some(label.color.clone()),
+        label_description(label.description.as_deref())
+    )
+}
+
+async fn update_issue(crab: &Arc<Octocrab>, user: &String, repo: &String, n: u64, title: &String, body: &String, labels: Option<&Vec<Label>>, state: IssueState, state_reason: Option<IssueStateReason>) -> Result<(), String> {
     let crab_issues = crab.issues(user, repo);
     let mut update_builder = crab_issues.update(n).title(title).body(body).state(state);
+    if let Some(state_reason) = state_reason {
+        update_builder = update_builder.state_reason(state_reason);
+    }
     let label_list;
     if let Some(labels) = labels {
-        if !labels.is_empty() {
-            prepare_labels(user, repo, labels, &crab).await;
-        }
         label_list = labels.iter().map(|l| l.get_name()).collect::<Vec<_>>();
         update_builder = update_builder.labels(&label_list);
     }
@@ -426,16 +582,14 @@ async fn update_issue(user: &String, repo: &String, n: u64, title: &String, body
     }
 }
 
-async fn update_comment(user: &String, repo: &String, n: u64, text: &String) -> Result<(), String> {
-    let crab = get_octocrab_instance().await;
+async fn update_comment(crab: &Arc<Octocrab>, user: &String, repo: &String, n: u64, text: &String) -> Result<(), String> {
     match crab.issues(user, repo).update_comment(CommentId(n), text).await {
         Ok(_) => Ok(()),
         Err(e) => Err(e.to_string())
     }
 }
 
-async fn delete_comment(user: &String, repo: &String, n: u64) -> Result<(), String> {
-    let crab = get_octocrab_instance().await;
+async fn delete_comment(crab: &Arc<Octocrab>, user: &String, repo: &String, n: u64) -> Result<(), String> {
     match crab.issues(user, repo).delete_comment(CommentId(n)).await {
         Ok(_) => Ok(()),
         Err(e) => Err(e.to_string())
@@ -443,13 +597,12 @@ async fn delete_comment(user: &String, repo: &String, n: u64) -> Result<(), Stri
 }
 
 pub async fn delete_label(
+    crab: &Arc<Octocrab>,
     user: &String,
     repo: &String,
     n: u64,
     label_name: &str,
 ) -> Result<(), String> {
-    let crab = get_octocrab_instance().await;
-
     crab
         .issues(user, repo)
         .remove_label(n, label_name)
@@ -458,8 +611,7 @@ pub async fn delete_label(
         .map_err(|e| e.to_string())
 }
 
-async fn get_issue_id(user: &String, repo: &String, n: u64) -> Result<String, String> {
-    let crab = get_octocrab_instance().await;
+async fn get_issue_id(crab: &Arc<Octocrab>, user: &String, repo: &String, n: u64) -> Result<String, String> {
     let issue = crab.issues(user, repo).get(n).await;
     match issue {
         Ok(issue) => Ok(issue.node_id),
@@ -467,26 +619,396 @@ async fn get_issue_id(user: &String, repo: &String, n: u64) -> Result<String, St
     }
 }
 
-async fn get_octocrab_instance() -> Arc<Octocrab> {
-    match get_token_from_env() {
-        Some(token) => Arc::new(Octocrab::builder().personal_token(token).build().unwrap()),
-        None => octocrab::instance()
+async fn get_octocrab_instance(transport: Arc<dyn HttpTransport>, api_url: Option<String>, token: Option<String>) -> Result<Arc<Octocrab>, String> {
+    let (base_uri, upload_uri) = match api_url {
+        Some(api_url) => (api_url.clone(), api_url),
+        None => (GITHUB_API_URL.to_string(), GITHUB_UPLOAD_URL.to_string()),
+    };
+    let base_uri: http::Uri = base_uri.parse().map_err(|e: http::uri::InvalidUri| e.to_string())?;
+    let upload_uri: http::Uri = upload_uri.parse().map_err(|e: http::uri::InvalidUri| e.to_string())?;
+    let auth_header = match token {
+        Some(token) => Some(HeaderValue::from_str(&format!("Bearer {token}")).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let headers = vec![(header::USER_AGENT, HeaderValue::from_str(&user_agent()).map_err(|e| e.to_string())?)];
+    let crab = OctocrabBuilder::new_empty()
+        .with_service(TransportService { transport })
+        .with_layer(&ExtraHeadersLayer::new(Arc::new(headers)))
+        .with_layer(&BaseUriLayer::new(base_uri.clone()))
+        .with_layer(&AuthHeaderLayer::new(auth_header, base_uri, upload_uri))
+        .with_auth(AuthState::None)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(Arc::new(crab))
+}
+
+fn user_agent() -> String {
+    "git-task/".to_owned() + env!("CARGO_PKG_VERSION")
+}
+
+fn get_host(context: &TaskContext) -> String {
+    let url = match context.get_config_value("task.github.url") {
+        Ok(url) if !url.is_empty() => url,
+        _ => return "github.com".to_string(),
+    };
+
+    let host = url.trim_start_matches("https://").trim_start_matches("http://");
+    host.split('/').next().unwrap_or(host).to_string()
+}
+
+/// Enterprise instances are reached over HTTPS unless `task.github.url` explicitly starts with `http://`.
+fn get_api_base_url(context: &TaskContext) -> Option<String> {
+    let scheme = match context.get_config_value("task.github.url") {
+        Ok(url) if url.starts_with("http://") => "http",
+        _ => "https",
+    };
+    match get_host(context).as_str() {
+        "github.com" => None,
+        host => Some(format!("{scheme}://{host}/api/v3/")),
+    }
+}
+
+fn get_graphql_url(host: &str) -> String {
+    match host {
+        "github.com" => "https://api.github.com/graphql".to_string(),
+        host => format!("https://{host}/api/graphql"),
     }
 }
 
-fn get_token_from_env() -> Option<String> {
-    std::env::var("GITHUB_TOKEN").or_else(|_| std::env::var("GITHUB_API_TOKEN")).ok()
+fn get_token(context: &TaskContext) -> Option<String> {
+    crate::connectors::get_token(context, "task.github.token", "github", &["GITHUB_TOKEN", "GITHUB_API_TOKEN"], &format!("https://{}", get_host(context)))
 }
 
 #[cfg(test)]
 mod test {
+    use std::cell::RefCell;
+    use std::env::temp_dir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use crate::connectors::{find_marked_comment, get_http_timeout};
+    use crate::connectors::replay::{silent_listener, Exchange, ReplayTransport};
     use super::*;
 
     #[test]
     fn test_remote_url() {
-        let connector = GithubRemoteConnector {};
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let connector = GithubRemoteConnector::new(&context);
 
         assert!(connector.supports_remote("git@github.com:VIK-777/java-telegram-meetup-bot.git").is_some());
         assert!(connector.supports_remote("https://github.com/jhspetersson/fselect.git").is_some());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_enterprise_url() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let connector = GithubRemoteConnector::new(&context);
+
+        assert_eq!(get_host(&context), "github.com");
+        assert_eq!(get_api_base_url(&context), None);
+        assert_eq!(get_graphql_url(&get_host(&context)), "https://api.github.com/graphql");
+
+        context.set_config_value("task.github.url", "https://github.acme.corp/").unwrap();
+        assert_eq!(get_host(&context), "github.acme.corp");
+        assert_eq!(get_api_base_url(&context), Some("https://github.acme.corp/api/v3/".to_string()));
+        assert_eq!(get_graphql_url(&get_host(&context)), "https://github.acme.corp/api/graphql");
+
+        assert_eq!(connector.supports_remote("git@github.acme.corp:platform/billing-service.git"), Some(("platform".to_string(), "billing-service".to_string())));
+        assert!(connector.supports_remote("https://github.acme.corp/platform/billing-service").is_some());
+        assert!(connector.supports_remote("https://github.com/jhspetersson/fselect.git").is_none());
+
+        context.set_config_value("task.github.url", "github.acme.corp").unwrap();
+        assert_eq!(get_host(&context), "github.acme.corp");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_http_timeout() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (_listener, url) = silent_listener();
+        context.set_config_value("task.github.url", &url).unwrap();
+        context.set_config_value("task.http.timeout", "1").unwrap();
+        assert_eq!(get_http_timeout(&context), Duration::from_secs(1));
+
+        let connector = GithubRemoteConnector::new(&context);
+        let started = Instant::now();
+        let result = connector.list_remote_tasks(&"user".to_string(), &"repo".to_string(), false, false, None, RemoteTaskState::All, None, &vec!["OPEN".to_string()], None);
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_label_description_round_trip() {
+        let remote_labels: Vec<octocrab::models::Label> = serde_json::from_str(include_str!("../../resources/test/github_labels.json")).unwrap();
+        let labels = remote_labels.iter().map(to_label).collect::<Vec<_>>();
+        assert_eq!(labels[0].get_description(), Some("Something isn't working".to_string()));
+        assert_eq!(labels[1].get_description(), None);
+
+        assert!(!label_description_changed(&labels[0], remote_labels[0].description.as_deref()));
+        assert!(!label_description_changed(&labels[1], remote_labels[1].description.as_deref()));
+        let local = Label::new("triage".to_string(), None, Some("Needs a first look".to_string()));
+        assert!(label_description_changed(&local, remote_labels[1].description.as_deref()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_issue_state_reason() {
+        let issue: Issue = serde_json::from_str(include_str!("../../resources/test/github_issue_not_planned.json")).unwrap();
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+
+        let task = issue_to_task(&issue, &task_statuses);
+        assert_eq!(task.get_id(), Some("1347".to_string()));
+        assert_eq!(task.get_property("status").unwrap(), "CLOSED");
+        assert_eq!(task.get_property(STATE_REASON).unwrap(), "not_planned");
+        assert_eq!(task.get_property(PRIORITY).unwrap(), "high");
+
+        let reason = parse_state_reason(task.get_property(STATE_REASON).unwrap()).unwrap();
+        assert_eq!(reason, IssueStateReason::NotPlanned);
+        assert_eq!(format_state_reason(&reason), Some("not_planned".to_string()));
+        assert_eq!(parse_state_reason("unknown"), None);
+    }
+
+    #[test]
+    fn test_task_url() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let connector = GithubRemoteConnector::new(&context);
+        let (user, repo, id) = ("jhspetersson".to_string(), "git-task".to_string(), "42".to_string());
+        assert_eq!(connector.task_url(&user, &repo, &id), "https://github.com/jhspetersson/git-task/issues/42");
+
+        context.set_config_value("task.github.url", "https://github.example.com/").unwrap();
+        assert_eq!(connector.task_url(&user, &repo, &id), "https://github.example.com/jhspetersson/git-task/issues/42");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_remote_tasks_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let issues_path = "/api/v3/repos/octocat/Hello-World/issues";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(&format!("{issues_path}?state=all&per_page=100&page=2"), 200, include_str!("../../resources/test/github_issues_page_2.json")),
+            Exchange::new(issues_path, 200, include_str!("../../resources/test/github_issues_page_1.json"))
+                .header("Link", &format!("<{{url}}{issues_path}?state=all&per_page=100&page=2>; rel=\"next\", <{{url}}{issues_path}?state=all&per_page=100&page=2>; rel=\"last\"")),
+        ]));
+        context.set_config_value("task.github.url", "https://github.example.com").unwrap();
+        context.set_config_value("task.github.token", "replayed").unwrap();
+
+        let connector = GithubRemoteConnector::with_transport(&context, transport.clone());
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let progress = RefCell::new(vec![]);
+        let report = |current: usize, total: usize| progress.borrow_mut().push((current, total));
+        let (user, repo) = ("octocat".to_string(), "Hello-World".to_string());
+        let tasks = connector.list_remote_tasks(&user, &repo, false, true, None, RemoteTaskState::All, None, &task_statuses, Some(&report)).unwrap();
+
+        assert_eq!(tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>(), vec!["3", "2", "1"]);
+        assert_eq!(tasks[0].get_property(PRIORITY).unwrap(), "high");
+        assert_eq!(tasks[0].get_labels().as_ref().unwrap().len(), 2);
+        assert_eq!(tasks[2].get_property("status").unwrap(), "CLOSED");
+        assert_eq!(progress.borrow().last(), Some(&(3, 3)));
+        assert_eq!(transport.requests().len(), 2);
+
+        let error = connector.list_remote_tasks(&user, &"Gone".to_string(), false, true, None, RemoteTaskState::All, None, &task_statuses, None).err().unwrap();
+        assert!(error.contains("Not Found"), "{error}");
+        assert!(connector.get_remote_task(&user, &"Gone".to_string(), &"1".to_string(), false, false, &task_statuses).unwrap().is_none());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    /// Replays exchanges slowly enough for requests to overlap and records how many ran at once.
+    struct CountingTransport {
+        replay: ReplayTransport,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    impl HttpTransport for CountingTransport {
+        fn send(&self, request: http::Request<Vec<u8>>) -> Result<http::Response<Vec<u8>>, String> {
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(50));
+            let response = self.replay.send(request);
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            response
+        }
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.set_config_value("task.github.url", "https://concurrency.example.com").unwrap();
+        context.set_config_value("task.github.token", "replayed").unwrap();
+
+        let issues_path = "/api/v3/repos/octocat/Hello-World/issues";
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let (user, repo) = ("octocat".to_string(), "Hello-World".to_string());
+        for limit in [1, 2] {
+            context.set_config_value("task.github.concurrency", &limit.to_string()).unwrap();
+            let mut exchanges = (1..=3).map(|n| Exchange::new(&format!("{issues_path}/{n}/comments"), 200, "[]")).collect::<Vec<_>>();
+            exchanges.push(Exchange::new(&format!("{issues_path}?state=all&per_page=100&page=2"), 200, include_str!("../../resources/test/github_issues_page_2.json")));
+            exchanges.push(Exchange::new(issues_path, 200, include_str!("../../resources/test/github_issues_page_1.json"))
+                .header("Link", &format!("<{{url}}{issues_path}?state=all&per_page=100&page=2>; rel=\"next\", <{{url}}{issues_path}?state=all&per_page=100&page=2>; rel=\"last\"")));
+            let transport = Arc::new(CountingTransport {
+                replay: ReplayTransport::new(exchanges.into_iter().flat_map(|exchange| [exchange.clone(), exchange]).collect()),
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
+            });
+
+            std::thread::scope(|scope| {
+                let pulls = (0..2).map(|_| scope.spawn(|| {
+                    let connector = GithubRemoteConnector::with_transport(&context, transport.clone());
+                    connector.list_remote_tasks(&user, &repo, true, false, None, RemoteTaskState::All, None, &task_statuses, None).unwrap()
+                })).collect::<Vec<_>>();
+                for pull in pulls {
+                    assert_eq!(pull.join().unwrap().len(), 3);
+                }
+            });
+            assert_eq!(transport.replay.requests().len(), 10);
+            assert_eq!(transport.max_in_flight.load(Ordering::SeqCst), limit);
+        }
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_retry_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let issues_path = "/api/v3/repos/octocat/Hello-World/issues";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(issues_path, 429, r#"{"message": "API rate limit exceeded"}"#).header("Retry-After", "1"),
+            Exchange::new(issues_path, 200, include_str!("../../resources/test/github_issues_page_2.json")),
+        ]));
+        context.set_config_value("task.github.url", "https://github.example.com").unwrap();
+        context.set_config_value("task.github.token", "replayed").unwrap();
+
+        let connector = GithubRemoteConnector::with_transport(&context, transport.clone());
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let (user, repo) = ("octocat".to_string(), "Hello-World".to_string());
+        let started = Instant::now();
+        let tasks = connector.list_remote_tasks(&user, &repo, false, true, None, RemoteTaskState::All, None, &task_statuses, None).unwrap();
+
+        assert_eq!(tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>(), vec!["1"]);
+        assert_eq!(transport.requests().iter().filter(|request| request.starts_with(&format!("GET {issues_path}"))).count(), 2);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_remote_comment_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let comments_path = "/api/v3/repos/octocat/Hello-World/issues/1/comments";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(comments_path, 200, include_str!("../../resources/test/github_comments.json")),
+            Exchange::new(comments_path, 201, include_str!("../../resources/test/github_comment_created.json")),
+        ]));
+        context.set_config_value("task.github.url", "https://github.example.com").unwrap();
+        context.set_config_value("task.github.token", "replayed").unwrap();
+
+        let connector = GithubRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo, task_id) = ("octocat".to_string(), "Hello-World".to_string(), "1".to_string());
+        let remote_comments = connector.list_remote_comment_texts(&user, &repo, &task_id).unwrap();
+        let comment = Comment::new("1".to_string(), HashMap::new(), "Fixed in 1.2".to_string());
+        assert_eq!(find_marked_comment(&remote_comments, &comment), Some("1002".to_string()));
+
+        let comment = Comment::new("1".to_string(), HashMap::new(), "Released".to_string());
+        assert_eq!(find_marked_comment(&remote_comments, &comment), None);
+        assert_eq!(connector.create_remote_comment(&user, &repo, &task_id, &comment).unwrap(), "1003");
+        assert_eq!(transport.requests().iter().filter(|request| request.starts_with(&format!("GET {comments_path}"))).count(), 1);
+        assert_eq!(transport.requests().iter().filter(|request| request.starts_with(&format!("POST {comments_path}"))).count(), 1);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_remote_task_priority_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let repo_path = "/api/v3/repos/octocat/Hello-World";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(&format!("{repo_path}/labels?"), 200, include_str!("../../resources/test/github_labels.json")),
+            Exchange::new(&format!("{repo_path}/labels"), 201, r#"{"id": 208045949, "node_id": "MDU6TGFiZWwyMDgwNDU5NDk=", "url": "https://api.github.com/repos/octocat/Hello-World/labels/priority::high", "name": "priority::high", "color": "ff6666", "default": false, "description": null}"#),
+            Exchange::new(&format!("{repo_path}/issues"), 201, include_str!("../../resources/test/github_issue_not_planned.json")),
+        ]));
+        context.set_config_value("task.github.url", "https://github.example.com").unwrap();
+        context.set_config_value("task.github.token", "replayed").unwrap();
+
+        let connector = GithubRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo) = ("octocat".to_string(), "Hello-World".to_string());
+        let mut task = Task::new("Prioritized".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_property(PRIORITY, "high");
+        assert_eq!(connector.create_remote_task(&user, &repo, &task).unwrap(), "1347");
+
+        let requests = transport.requests();
+        let issue_request = requests.iter().position(|request| *request == format!("POST {repo_path}/issues")).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&transport.bodies()[issue_request]).unwrap();
+        assert_eq!(body["labels"], serde_json::json!(["priority::high"]));
+        assert!(requests.contains(&format!("POST {repo_path}/labels")));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_remote_label_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let labels_path = "/api/v3/repos/octocat/Hello-World/labels";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(&format!("{labels_path}?"), 200, include_str!("../../resources/test/github_labels.json")),
+            Exchange::new(&format!("{labels_path}/good%20first%20issue"), 500, r#"{"message": "Server Error"}"#),
+            Exchange::new("/api/v3/repos/octocat/Hello-World/issues/1/labels", 200, include_str!("../../resources/test/github_labels.json")),
+        ]));
+        context.set_config_value("task.github.url", "https://github.example.com").unwrap();
+        context.set_config_value("task.github.token", "replayed").unwrap();
+
+        let connector = GithubRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo, task_id) = ("octocat".to_string(), "Hello-World".to_string(), "1".to_string());
+        let label = Label::new("good first issue".to_string(), None, Some("Small and well defined".to_string()));
+        connector.create_remote_label(&user, &repo, &task_id, &label).unwrap();
+
+        assert!(transport.requests().contains(&format!("PATCH {labels_path}/good%20first%20issue")));
+        let warnings = context.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Could not update label good first issue: "));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+}
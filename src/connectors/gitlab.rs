@@ -1,18 +1,27 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::sync::Arc;
 
 use gitlab::api::issues::{IssueScope, IssueState};
 use gitlab::api::projects::issues::IssueStateEvent;
-use gitlab::api::{Pagination, Query};
-use gitlab::Gitlab;
+use gitlab::api::{ApiError, Pagination, Query, RestClient};
+use bytes::Bytes;
+use chrono::{NaiveDate, NaiveTime};
+use http::{header, HeaderValue};
 use regex::Regex;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
-use gittask::{Comment, Label, Task, TaskContext};
-use crate::connectors::{RemoteConnector, RemoteTaskState};
+use gittask::{parse_timestamp, Comment, Label, Task, TaskContext};
+use crate::connectors::transport::{connector_transport, HttpTransport};
+use crate::connectors::{ConnectorCapabilities, http_error, parse_remote_id, label_description, label_description_changed, priority_from_labels, report_progress, strip_comment_marker, with_comment_marker, with_priority_label, RemoteConnector, RemoteTaskState, DUE, PRIORITY, WEIGHT};
 use crate::util::{color_str_to_rgb_str, parse_datetime_to_seconds};
 
 pub struct GitlabRemoteConnector {
     context: TaskContext,
+    transport: Option<Arc<dyn HttpTransport>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -29,6 +38,10 @@ struct Issue {
     created_at: String,
     state: String,
     labels: Vec<String>,
+    #[serde(default)]
+    weight: Option<u64>,
+    #[serde(default)]
+    due_date: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -43,12 +56,64 @@ struct GitlabComment {
 struct GitlabLabel {
     name: String,
     color: String,
-    description: String,
+    description: Option<String>,
+}
+
+/// REST client for the `gitlab` endpoints, unlike `gitlab::Gitlab` it sends through the connector transport.
+struct GitlabClient {
+    transport: Arc<dyn HttpTransport>,
+    rest_url: Url,
+    token: String,
+}
+
+#[derive(Debug)]
+struct TransportError(String);
+
+impl Display for TransportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+fn transport_error<E: ToString>(error: E) -> ApiError<TransportError> {
+    ApiError::client(TransportError(error.to_string()))
+}
+
+impl RestClient for GitlabClient {
+    type Error = TransportError;
+
+    fn rest_endpoint(&self, endpoint: &str) -> Result<Url, ApiError<Self::Error>> {
+        Ok(self.rest_url.join(endpoint)?)
+    }
+}
+
+impl gitlab::api::Client for GitlabClient {
+    fn rest(&self, request: http::request::Builder, body: Vec<u8>) -> Result<http::Response<Bytes>, ApiError<Self::Error>> {
+        let request = request.header("PRIVATE-TOKEN", self.token_header().map_err(transport_error)?);
+        let response = self.transport.send(request.body(body).map_err(transport_error)?).map_err(transport_error)?;
+        Ok(response.map(Bytes::from))
+    }
+}
+
+impl GitlabClient {
+    fn token_header(&self) -> Result<HeaderValue, http::header::InvalidHeaderValue> {
+        let mut token = HeaderValue::from_str(&self.token)?;
+        token.set_sensitive(true);
+        Ok(token)
+    }
 }
 
 #[derive(Deserialize)]
 struct DeleteIssueResult {}
 
+#[derive(Deserialize)]
+struct GitlabUpload {
+    url: String,
+    full_path: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct DeleteIssueNoteResult {}
 
@@ -56,8 +121,26 @@ impl GitlabRemoteConnector {
     pub fn new(context: &TaskContext) -> Self {
         Self {
             context: context.clone(),
+            transport: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_transport(context: &TaskContext, transport: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            context: context.clone(),
+            transport: Some(transport),
         }
     }
+
+    fn get_client(&self) -> Result<GitlabClient, String> {
+        let rest_url = Url::parse(&format!("{}/api/v4/", get_base_url(&self.context).trim_end_matches('/'))).map_err(|e| e.to_string())?;
+        Ok(GitlabClient {
+            transport: connector_transport(&self.context, self.type_name(), &self.transport)?,
+            rest_url,
+            token: get_token(&self.context)?,
+        })
+    }
 }
 impl RemoteConnector for GitlabRemoteConnector {
     fn type_name(&self) -> &str {
@@ -65,20 +148,48 @@ impl RemoteConnector for GitlabRemoteConnector {
     }
 
     fn get_config_options(&self) -> Option<Vec<String>> {
-        Some(vec!["task.gitlab.url".to_string()])
+        Some(vec!["task.gitlab.url".to_string(), "task.gitlab.token".to_string()])
+    }
+
+    fn capabilities(&self) -> ConnectorCapabilities {
+        ConnectorCapabilities {
+            fetch_tasks: true,
+            push_tasks: true,
+            delete_tasks: true,
+            comments: true,
+            labels: true,
+            attachments: true,
+            ..Default::default()
+        }
+    }
+
+    fn max_body_length(&self) -> Option<usize> {
+        Some(1048576)
+    }
+
+    fn marks_comments(&self) -> bool {
+        true
     }
 
     fn supports_remote(&self, url: &str) -> Option<(String, String)> {
-        match Regex::new(&(get_base_url(&self.context) + "([a-z0-9-]+)/([a-z0-9-]+)\\.?")).unwrap().captures(url) {
-            Some(caps) if caps.len() == 3 => {
-                let user = caps.get(1)?.as_str().to_string();
-                let repo = caps.get(2)?.as_str().to_string();
+        let host = get_host(&self.context);
+        let domain = regex::escape(host.split(['/', ':']).next().unwrap_or(&host));
+        let host = regex::escape(&host);
+        let pattern = format!("^(https?://([^@/]+@)?{host}/|(ssh://)?git@{domain}(:[0-9]+)?[:/])(?P<user>[\\w.-]+(/[\\w.-]+)*)/(?P<repo>[\\w.-]+?)(\\.git)?/?$");
+        match Regex::new(&pattern).unwrap().captures(url) {
+            Some(caps) => {
+                let user = caps.name("user")?.as_str().to_string();
+                let repo = caps.name("repo")?.as_str().to_string();
                 Some((user, repo))
             },
             _ => None,
         }
     }
 
+    fn task_url(&self, user: &String, repo: &String, task_id: &String) -> String {
+        format!("{}{user}/{repo}/-/issues/{task_id}", get_base_url(&self.context))
+    }
+
     fn list_remote_tasks(
         &self,
         user: &String,
@@ -87,29 +198,24 @@ impl RemoteConnector for GitlabRemoteConnector {
         with_labels: bool,
         limit: Option<usize>,
         state: RemoteTaskState,
-        task_statuses: &Vec<String>
+        since: Option<u64>,
+        task_statuses: &Vec<String>,
+        progress: Option<&dyn Fn(usize, usize)>
     ) -> Result<Vec<Task>, String> {
         let state = match state {
             RemoteTaskState::Open(_, _) => Some(IssueState::Opened),
-            RemoteTaskState::Closed(_, _) => Some(IssueState::Closed),
+            RemoteTaskState::Closed(_, _, _) => Some(IssueState::Closed),
             RemoteTaskState::All => None,
         };
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
 
         let labels = match with_labels {
             true => {
                 let mut endpoint = gitlab::api::projects::labels::Labels::builder();
                 let endpoint = endpoint.project(user.to_string() + "/" + repo);
                 let endpoint = endpoint.build().unwrap();
-                let labels: Vec<GitlabLabel> = gitlab::api::paged(endpoint, Pagination::All).query(&client).unwrap();
-                let labels = labels.iter()
-                    .map(|gl| Label::new(
-                        gl.name.to_string(),
-                        Some(gl.color.to_string()),
-                        Some(gl.description.to_string())
-                    ))
-                    .collect::<Vec<_>>();
-                labels
+                let labels: Vec<GitlabLabel> = gitlab::api::paged(endpoint, Pagination::All).query(&client).map_err(http_error)?;
+                labels.iter().map(to_label).collect::<Vec<_>>()
             },
             false => vec![]
         };
@@ -120,25 +226,22 @@ impl RemoteConnector for GitlabRemoteConnector {
             Some(state) => endpoint.state(state),
             None => endpoint
         };
+        if let Some(since) = since.and_then(|since| chrono::DateTime::from_timestamp(since as i64, 0)) {
+            endpoint = endpoint.updated_after(since);
+        }
         let endpoint = endpoint.build().unwrap();
         let pagination = match limit {
             Some(limit) => Pagination::Limit(limit),
             None => Pagination::All
         };
-        let issues: Vec<Issue> = gitlab::api::paged(endpoint, pagination).query(&client).map_err(|e| e.to_string())?;
+        let issues: Vec<Issue> = gitlab::api::paged(endpoint, pagination).query(&client).map_err(http_error)?;
+        let total = issues.len();
         let mut result = vec![];
         for issue in issues {
-            let mut props = HashMap::new();
-            props.insert(String::from("name"), issue.title);
-            props.insert(String::from("description"), issue.description);
-            props.insert(String::from("status"), if issue.state == "opened" { task_statuses.first().unwrap().clone() } else { task_statuses.last().unwrap().clone() });
-            props.insert(String::from("created"), parse_datetime_to_seconds(issue.created_at));
-            props.insert(String::from("author"), issue.author.username);
-
-            let mut task = Task::from_properties(issue.iid.to_string(), props).unwrap();
+            let mut task = issue_to_task(&issue, task_statuses);
 
             if with_comments {
-                let comments = list_issue_comments(&client, &user, &repo, &issue.iid.to_string());
+                let comments = list_issue_comments(&client, &user, &repo, issue.iid);
                 task.set_comments(comments);
             }
 
@@ -153,6 +256,7 @@ impl RemoteConnector for GitlabRemoteConnector {
             }
 
             result.push(task);
+            report_progress(progress, result.len(), total);
         }
 
         Ok(result)
@@ -166,26 +270,19 @@ impl RemoteConnector for GitlabRemoteConnector {
         with_comments: bool,
         with_labels: bool,
         task_statuses: &Vec<String>
-    ) -> Result<Task, String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+    ) -> Result<Option<Task>, String> {
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::Issue::builder();
         let mut endpoint = endpoint.project(user.to_string() + "/" + repo);
-        endpoint = endpoint.issue(task_id.parse().unwrap());
+        endpoint = endpoint.issue(parse_remote_id(task_id)?);
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
             Ok(issue) => {
                 let issue: Issue = issue;
-                let mut props = HashMap::new();
-                props.insert(String::from("name"), issue.title);
-                props.insert(String::from("description"), issue.description);
-                props.insert(String::from("status"), if issue.state == "opened" { task_statuses.first().unwrap().clone() } else { task_statuses.last().unwrap().clone() });
-                props.insert(String::from("created"), parse_datetime_to_seconds(issue.created_at));
-                props.insert(String::from("author"), issue.author.username);
-
-                let mut task = Task::from_properties(task_id.to_string(), props).unwrap();
+                let mut task = issue_to_task(&issue, task_statuses);
 
                 if with_comments {
-                    let comments = list_issue_comments(&client, &user, &repo, task_id);
+                    let comments = list_issue_comments(&client, &user, &repo, issue.iid);
                     task.set_comments(comments);
                 }
 
@@ -193,48 +290,54 @@ impl RemoteConnector for GitlabRemoteConnector {
                     let mut endpoint = gitlab::api::projects::labels::Labels::builder();
                     let endpoint = endpoint.project(user.to_string() + "/" + repo);
                     let endpoint = endpoint.build().unwrap();
-                    let labels: Vec<GitlabLabel> = gitlab::api::paged(endpoint, Pagination::All).query(&client).unwrap();
+                    let labels: Vec<GitlabLabel> = gitlab::api::paged(endpoint, Pagination::All).query(&client).map_err(http_error)?;
                     let labels = issue.labels.iter()
                         .map(|l| labels.iter().find(|gl| gl.name == l.to_string()).unwrap())
-                        .map(|gl| Label::new(gl.name.to_string(), Some(gl.color.to_string()), Some(gl.description.to_string())))
+                        .map(to_label)
                         .collect();
                     task.set_labels(labels);
                 }
 
-                Ok(task)
+                Ok(Some(task))
             },
-            Err(e) => Err(e.to_string())
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(http_error(e))
         }
     }
 
     fn create_remote_task(&self, user: &String, repo: &String, task: &Task) -> Result<String, String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::CreateIssue::builder();
         let endpoint = endpoint.project(user.to_string() + "/" + repo);
         endpoint.title(task.get_property("name").unwrap());
         endpoint.description(task.get_property("description").unwrap());
-        if let Some(labels) = task.get_labels() {
-            prepare_labels(&client, &user, &repo, &labels);
+        let labels = with_priority_label(task.get_labels().as_deref().unwrap_or_default(), task);
+        if !labels.is_empty() {
+            prepare_labels(&self.context, &client, user, repo, &labels);
             let labels = labels.iter().map(|l| l.get_name()).collect::<Vec<_>>();
             endpoint.labels(labels);
         }
         let endpoint = endpoint.build().unwrap();
-        let issue: Issue = endpoint.query(&client).unwrap();
+        let issue: Issue = endpoint.query(&client).map_err(http_error)?;
 
         Ok(issue.iid.to_string())
     }
 
     fn create_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment: &Comment) -> Result<String, String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::notes::CreateIssueNote::builder();
-        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
-        endpoint.body(comment.get_text().clone());
+        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
+        endpoint.body(with_comment_marker(comment));
         let endpoint = endpoint.build().unwrap();
-        let comment: GitlabComment = endpoint.query(&client).unwrap();
+        let comment: GitlabComment = endpoint.query(&client).map_err(http_error)?;
 
         Ok(comment.id.to_string())
     }
 
+    fn list_remote_comment_texts(&self, user: &String, repo: &String, task_id: &String) -> Result<Vec<(String, String)>, String> {
+        list_remote_comment_texts(&self.get_client()?, user, repo, task_id)
+    }
+
     fn create_remote_label(
         &self,
         user: &String,
@@ -242,19 +345,19 @@ impl RemoteConnector for GitlabRemoteConnector {
         task_id: &String,
         label: &Label,
     ) -> Result<(), String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::Issue::builder();
         let mut endpoint = endpoint.project(user.to_string() + "/" + repo);
-        endpoint = endpoint.issue(task_id.parse().unwrap());
+        endpoint = endpoint.issue(parse_remote_id(task_id)?);
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
             Ok(issue) => {
                 let issue: Issue = issue;
                 let label_name = label.get_name();
                 if !issue.labels.contains(&label_name) {
-                    prepare_labels(&client, &user, &repo, &vec![label.clone()]);
+                    prepare_labels(&self.context, &client, user, repo, std::slice::from_ref(label));
                     let mut endpoint = gitlab::api::projects::issues::EditIssue::builder();
-                    let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
+                    let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
                     endpoint.add_label(label_name);
                     let endpoint = endpoint.build().unwrap();
                     match endpoint.query(&client) {
@@ -262,13 +365,13 @@ impl RemoteConnector for GitlabRemoteConnector {
                             let _: Issue = issue;
                             Ok(())
                         },
-                        Err(e) => Err(e.to_string())
+                        Err(e) => Err(http_error(e))
                     }
                 } else {
                     Ok(())
                 }
             },
-            Err(e) => Err(e.to_string())
+            Err(e) => Err(http_error(e))
         }
     }
 
@@ -280,16 +383,23 @@ impl RemoteConnector for GitlabRemoteConnector {
         labels: Option<&Vec<Label>>,
         state: RemoteTaskState
     ) -> Result<(), String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::EditIssue::builder();
-        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task.get_id().unwrap().parse().unwrap());
+        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(&task.get_id().unwrap())?);
         endpoint.title(task.get_property("name").unwrap());
         endpoint.description(task.get_property("description").unwrap());
         if let Some(labels) = labels {
-            prepare_labels(&client, &user, &repo, &labels);
+            let labels = with_priority_label(labels, task);
+            prepare_labels(&self.context, &client, user, repo, &labels);
             let labels = labels.iter().map(|l| l.get_name()).collect::<Vec<_>>();
             endpoint.labels(labels);
         }
+        if let Some(weight) = task.get_property(WEIGHT).and_then(|weight| weight.parse().ok()) {
+            endpoint.weight(weight);
+        }
+        if let Some(due_date) = get_due_date(task) {
+            endpoint.due_date(due_date);
+        }
         endpoint.state_event(if let RemoteTaskState::Open(_, _) = state { IssueStateEvent::Reopen } else { IssueStateEvent::Close });
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
@@ -297,52 +407,52 @@ impl RemoteConnector for GitlabRemoteConnector {
                 let _: Issue = issue;
                 Ok(())
             },
-            Err(e) => Err(e.to_string())
+            Err(e) => Err(http_error(e))
         }
     }
 
-    fn update_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment_id: &String, text: &String) -> Result<(), String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+    fn update_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment_id: &String, comment: &Comment) -> Result<(), String> {
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::notes::EditIssueNote::builder();
-        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
-        endpoint.note(comment_id.parse().unwrap());
-        endpoint.body(text.as_str());
+        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
+        endpoint.note(parse_remote_id(comment_id)?);
+        endpoint.body(comment.get_text());
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
             Ok(comment) => {
                 let _: GitlabComment = comment;
                 Ok(())
             },
-            Err(e) => Err(e.to_string())
+            Err(e) => Err(http_error(e))
         }
     }
 
     fn delete_remote_task(&self, user: &String, repo: &String, task_id: &String) -> Result<(), String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::DeleteIssue::builder();
-        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
+        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
             Ok(result) => {
                 let _: DeleteIssueResult = result;
                 Ok(())
             },
-            Err(e) => if e.to_string().contains("204") { Ok(()) } else { Err(e.to_string()) }
+            Err(e) => if e.to_string().contains("204") { Ok(()) } else { Err(http_error(e)) }
         }
     }
 
     fn delete_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment_id: &String) -> Result<(), String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::notes::DeleteIssueNote::builder();
-        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
-        endpoint.note(comment_id.parse().unwrap());
+        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
+        endpoint.note(parse_remote_id(comment_id)?);
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
             Ok(result) => {
                 let _: DeleteIssueNoteResult = result;
                 Ok(())
             },
-            Err(e) => if e.to_string().contains("204") { Ok(()) } else { Err(e.to_string()) }
+            Err(e) => if e.to_string().contains("204") { Ok(()) } else { Err(http_error(e)) }
         }
     }
 
@@ -353,9 +463,9 @@ impl RemoteConnector for GitlabRemoteConnector {
         task_id: &String,
         label_name: &String,
     ) -> Result<(), String> {
-        let client = get_client(&self.context, get_token_from_env().unwrap().as_str());
+        let client = self.get_client()?;
         let mut endpoint = gitlab::api::projects::issues::EditIssue::builder();
-        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
+        let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
         endpoint.remove_label(label_name);
         let endpoint = endpoint.build().unwrap();
         match endpoint.query(&client) {
@@ -363,14 +473,39 @@ impl RemoteConnector for GitlabRemoteConnector {
                 let _: Issue = issue;
                 Ok(())
             },
-            Err(e) => Err(e.to_string())
+            Err(e) => Err(http_error(e))
         }
     }
+
+    fn upload_attachment(&self, user: &String, repo: &String, name: &str, bytes: &[u8]) -> Result<String, String> {
+        let client = self.get_client()?;
+        let project = (user.to_string() + "/" + repo).replace('/', "%2F");
+        let url = client.rest_endpoint(&format!("projects/{project}/uploads")).map_err(http_error)?;
+        let form = Form::new().part("file", Part::bytes(bytes.to_vec()).file_name(name.to_string()));
+        let content_type = format!("multipart/form-data; boundary={}", form.boundary());
+        let mut body = vec![];
+        form.into_reader().read_to_end(&mut body).map_err(|e| e.to_string())?;
+        let request = http::Request::post(url.as_str())
+            .header("PRIVATE-TOKEN", client.token_header().map_err(|e| e.to_string())?)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body)
+            .map_err(|e| e.to_string())?;
+        let response = client.transport.send(request)?;
+        let status = response.status();
+        let body = response.into_body();
+        if !status.is_success() {
+            return Err(format!("Failed to upload {name}: {status} {}", String::from_utf8_lossy(&body)));
+        }
+
+        let upload: GitlabUpload = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+        let path = upload.full_path.unwrap_or_else(|| format!("/{user}/{repo}{}", upload.url));
+        Ok(get_base_url(&self.context).trim_end_matches('/').to_string() + &path)
+    }
 }
 
-fn list_issue_comments(client: &Gitlab, user: &String, repo: &String, task_id: &String) -> Vec<Comment> {
+fn list_issue_comments(client: &GitlabClient, user: &str, repo: &str, iid: u64) -> Vec<Comment> {
     let mut endpoint = gitlab::api::projects::issues::notes::IssueNotes::builder();
-    let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(task_id.parse().unwrap());
+    let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(iid);
     let endpoint = endpoint.build().unwrap();
     match gitlab::api::paged(endpoint, Pagination::All).query(client) {
         Ok(comments) => {
@@ -380,7 +515,7 @@ fn list_issue_comments(client: &Gitlab, user: &String, repo: &String, task_id: &
                 let comment = Comment::new(comment.id.to_string(), HashMap::from([
                     ("author".to_string(), comment.author.username),
                     ("created".to_string(), parse_datetime_to_seconds(comment.created_at)),
-                ]), comment.body);
+                ]), strip_comment_marker(&comment.body));
                 result.push(comment);
             }
             result
@@ -392,15 +527,42 @@ fn list_issue_comments(client: &Gitlab, user: &String, repo: &String, task_id: &
     }
 }
 
-fn prepare_labels(client: &Gitlab, user: &String, repo: &String, labels: &Vec<Label>) {
+fn list_remote_comment_texts(client: &GitlabClient, user: &str, repo: &str, task_id: &str) -> Result<Vec<(String, String)>, String> {
+    let mut endpoint = gitlab::api::projects::issues::notes::IssueNotes::builder();
+    let endpoint = endpoint.project(user.to_string() + "/" + repo).issue(parse_remote_id(task_id)?);
+    let endpoint = endpoint.build().unwrap();
+    let comments: Vec<GitlabComment> = gitlab::api::paged(endpoint, Pagination::All).query(client).map_err(http_error)?;
+
+    Ok(comments.into_iter().map(|comment| (comment.id.to_string(), comment.body)).collect())
+}
+
+/// Creates the missing project labels and updates changed descriptions, failures are
+/// reported as warnings since the issue itself can still be pushed.
+fn prepare_labels(context: &TaskContext, client: &GitlabClient, user: &str, repo: &str, labels: &[Label]) {
     let mut endpoint = gitlab::api::projects::labels::Labels::builder();
     let endpoint = endpoint.project(user.to_string() + "/" + repo);
     let endpoint = endpoint.build().unwrap();
-    let existing_labels: Vec<GitlabLabel> = gitlab::api::paged(endpoint, Pagination::All).query(client).unwrap();
-    let mut labels_to_create = labels.clone();
+    let existing_labels: Vec<GitlabLabel> = match gitlab::api::paged(endpoint, Pagination::All).query(client) {
+        Ok(existing_labels) => existing_labels,
+        Err(e) => {
+            context.add_warning(format!("Could not list the labels of {user}/{repo}: {}", http_error(e)));
+            return;
+        }
+    };
+    let mut labels_to_create = labels.to_vec();
     for label in existing_labels {
         if let Some(pos) = labels_to_create.iter().position(|l| l.get_name() == label.name) {
-            labels_to_create.remove(pos);
+            let local_label = labels_to_create.remove(pos);
+            if label_description_changed(&local_label, label.description.as_deref()) {
+                let mut endpoint = gitlab::api::projects::labels::EditLabel::builder();
+                let endpoint = endpoint.project(user.to_string() + "/" + repo);
+                endpoint.label(label.name.as_str());
+                endpoint.description(local_label.get_description().unwrap());
+                let endpoint = endpoint.build().unwrap();
+                if let Err(e) = gitlab::api::ignore(endpoint).query(client) {
+                    context.add_warning(format!("Could not update label {}: {}", label.name, http_error(e)));
+                }
+            }
         }
     }
     for l in labels_to_create.iter() {
@@ -412,21 +574,48 @@ fn prepare_labels(client: &Gitlab, user: &String, repo: &String, labels: &Vec<La
             endpoint.description(description);
         }
         let endpoint = endpoint.build().unwrap();
-        gitlab::api::ignore(endpoint).query(client).unwrap();
+        if let Err(e) = gitlab::api::ignore(endpoint).query(client) {
+            context.add_warning(format!("Could not create label {}: {}", l.get_name(), http_error(e)));
+        }
     }
 }
 
-fn get_client(context: &TaskContext, token: &str) -> Gitlab {
-    let base_url = get_base_url(&context);
-    let gitlab_domain = match Regex::new("(https://)?(?P<domain>[^/]+)").unwrap().captures(&base_url) {
-        Some(caps) if caps.name("domain").is_some() => caps.name("domain").unwrap().as_str().to_string(),
-        _ => "gitlab.com".to_string(),
-    };
-    Gitlab::new(gitlab_domain, token).unwrap()
+fn to_label(label: &GitlabLabel) -> Label {
+    Label::new(
+        label.name.to_string(),
+        Some(label.color.to_string()),
+        label_description(label.description.as_deref())
+    )
 }
 
-fn get_token_from_env() -> Option<String> {
-    std::env::var("GITLAB_TOKEN").or_else(|_| std::env::var("GITLAB_API_TOKEN")).ok()
+fn get_token(context: &TaskContext) -> Result<String, String> {
+    crate::connectors::get_token(context, "task.gitlab.token", "gitlab", &["GITLAB_TOKEN", "GITLAB_API_TOKEN"], &get_base_url(context))
+        .ok_or_else(|| "Could not find a Gitlab token, set task.gitlab.token or GITLAB_TOKEN environment variable.".to_string())
+}
+
+fn issue_to_task(issue: &Issue, task_statuses: &[String]) -> Task {
+    let mut props = HashMap::new();
+    props.insert(String::from("name"), issue.title.clone());
+    props.insert(String::from("description"), issue.description.clone());
+    props.insert(String::from("status"), if issue.state == "opened" { task_statuses.first().unwrap().clone() } else { task_statuses.last().unwrap().clone() });
+    props.insert(String::from("created"), parse_datetime_to_seconds(issue.created_at.clone()));
+    props.insert(String::from("author"), issue.author.username.clone());
+    if let Some(priority) = priority_from_labels(issue.labels.iter().map(String::as_str)) {
+        props.insert(PRIORITY.to_string(), priority.to_string());
+    }
+    if let Some(weight) = issue.weight {
+        props.insert(WEIGHT.to_string(), weight.to_string());
+    }
+    if let Some(due) = issue.due_date.as_ref().and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()) {
+        props.insert(DUE.to_string(), due.and_time(NaiveTime::MIN).and_utc().timestamp().to_string());
+    }
+
+    Task::from_properties(issue.iid.to_string(), props).unwrap()
+}
+
+fn get_due_date(task: &Task) -> Option<NaiveDate> {
+    let seconds = task.get_property(DUE).and_then(|due| parse_timestamp(due))?;
+    chrono::DateTime::from_timestamp(seconds as i64, 0).map(|due| due.date_naive())
 }
 
 fn get_base_url(context: &TaskContext) -> String {
@@ -449,19 +638,39 @@ fn get_base_url(context: &TaskContext) -> String {
     result
 }
 
+fn is_not_found(error: &ApiError<TransportError>) -> bool {
+    match error {
+        ApiError::GitlabService { status, .. }
+        | ApiError::GitlabWithStatus { status, .. }
+        | ApiError::GitlabObjectWithStatus { status, .. }
+        | ApiError::GitlabUnrecognizedWithStatus { status, .. } => *status == http::StatusCode::NOT_FOUND,
+        _ => false,
+    }
+}
+
+/// Host of the GitLab instance, including a path prefix for instances not served from the root.
+/// The API client appends `/api/v4/` to it.
+fn get_host(context: &TaskContext) -> String {
+    let base_url = get_base_url(context);
+    base_url.trim_start_matches("https://").trim_start_matches("http://").trim_end_matches('/').to_string()
+}
+
 #[cfg(test)]
 mod test {
     use std::env::temp_dir;
-    use git2::Repository;
+    use std::time::{Duration, Instant};
+    use crate::util::init_test_repo;
     use uuid::Uuid;
     use gittask::TaskContext;
+    use crate::connectors::find_marked_comment;
+    use crate::connectors::replay::{silent_listener, Exchange, ReplayTransport};
     use super::*;
 
     #[test]
     fn test_remote_url() {
         let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
         std::fs::create_dir_all(repo_dir.clone()).unwrap();
-        let _repo = Repository::init(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
         let context = TaskContext::new(repo_dir.display().to_string());
 
         let connector = GitlabRemoteConnector::new(&context);
@@ -481,4 +690,295 @@ mod test {
 
         std::fs::remove_dir_all(repo_dir).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_self_hosted_url() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let connector = GitlabRemoteConnector::new(&context);
+        context.set_config_value("task.gitlab.url", "https://git.example.com/gitlab").unwrap();
+        assert_eq!(get_host(&context), "git.example.com/gitlab");
+        assert_eq!(get_base_url(&context), "https://git.example.com/gitlab/");
+
+        assert_eq!(connector.supports_remote("https://git.example.com/gitlab/team/tools.git"), Some(("team".to_string(), "tools".to_string())));
+        assert_eq!(connector.supports_remote("https://git.example.com/gitlab/Team/backend/Core_API"), Some(("Team/backend".to_string(), "Core_API".to_string())));
+        assert_eq!(connector.supports_remote("git@git.example.com:team/backend/api.git"), Some(("team/backend".to_string(), "api".to_string())));
+        assert_eq!(connector.supports_remote("ssh://git@git.example.com:2222/team/api.git"), Some(("team".to_string(), "api".to_string())));
+        assert!(connector.supports_remote("https://gitlab.com/team/tools.git").is_none());
+        assert!(connector.supports_remote("https://git.example.com.evil.org/gitlab/team/tools.git").is_none());
+
+        context.set_config_value("task.gitlab.url", "http://gitlab.local:8080").unwrap();
+        assert_eq!(get_host(&context), "gitlab.local:8080");
+        assert_eq!(connector.supports_remote("http://gitlab.local:8080/team/tools"), Some(("team".to_string(), "tools".to_string())));
+        assert_eq!(connector.supports_remote("git@gitlab.local:team/tools.git"), Some(("team".to_string(), "tools".to_string())));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_label_description_round_trip() {
+        let remote_labels: Vec<GitlabLabel> = serde_json::from_str(include_str!("../../resources/test/gitlab_labels.json")).unwrap();
+        let labels = remote_labels.iter().map(to_label).collect::<Vec<_>>();
+        assert_eq!(labels[0].get_description(), Some("Something isn't working".to_string()));
+        assert_eq!(labels[1].get_description(), None);
+
+        assert!(!label_description_changed(&labels[0], remote_labels[0].description.as_deref()));
+        let local = Label::new("triage".to_string(), None, Some("Needs a first look".to_string()));
+        assert!(label_description_changed(&local, remote_labels[1].description.as_deref()));
+    }
+
+    #[test]
+    fn test_task_url() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let connector = GitlabRemoteConnector::new(&context);
+        let (user, repo, id) = ("utils".to_string(), "rust-gitlab".to_string(), "7".to_string());
+        context.set_config_value("task.gitlab.url", "gitlab.kitware.com").unwrap();
+        assert_eq!(connector.task_url(&user, &repo, &id), "https://gitlab.kitware.com/utils/rust-gitlab/-/issues/7");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_priority_weight_and_due_date_round_trip() {
+        let issues: Vec<Issue> = serde_json::from_str(include_str!("../../resources/test/gitlab_issues.json")).unwrap();
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+
+        let task = issue_to_task(&issues[0], &task_statuses);
+        assert_eq!(task.get_property(PRIORITY).unwrap(), "medium");
+        assert_eq!(task.get_property(WEIGHT).unwrap(), "3");
+        assert_eq!(task.get_property(DUE).unwrap(), "1719705600");
+        assert_eq!(get_due_date(&task), NaiveDate::from_ymd_opt(2024, 6, 30));
+
+        let mut task = task.clone();
+        task.set_property(DUE, "2024-06-30T00:00:00Z");
+        assert_eq!(get_due_date(&task), NaiveDate::from_ymd_opt(2024, 6, 30));
+
+        let labels = issues[0].labels.iter().map(|name| Label::new(name.clone(), None, None)).collect::<Vec<_>>();
+        task.set_property(PRIORITY, "critical");
+        let labels = with_priority_label(&labels, &task).iter().map(|label| label.get_name()).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["release", "priority::critical"]);
+
+        let task = issue_to_task(&issues[1], &task_statuses);
+        assert_eq!(task.get_property("status").unwrap(), "CLOSED");
+        assert!(task.get_property(PRIORITY).is_none());
+        assert!(task.get_property(WEIGHT).is_none());
+        assert!(task.get_property(DUE).is_none());
+        assert_eq!(get_due_date(&task), None);
+    }
+
+    #[test]
+    fn test_due_date_push_round_trip() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let issues: Vec<Issue> = serde_json::from_str(include_str!("../../resources/test/gitlab_issues.json")).unwrap();
+        let issue_path = "/api/v4/projects/jhspetersson%2Fgit-task/issues/12";
+        let issue = serde_json::to_string(&issues[0]).unwrap();
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(issue_path, 200, &issue),
+            Exchange::new(issue_path, 200, &issue),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+
+        let connector = GitlabRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let mut task = issue_to_task(&issues[0], &task_statuses);
+        let open = || RemoteTaskState::Open("OPEN".to_string(), "OPEN".to_string());
+        connector.update_remote_task(&user, &repo, &task, None, open()).unwrap();
+        task.delete_property(DUE);
+        connector.update_remote_task(&user, &repo, &task, None, open()).unwrap();
+
+        let bodies = transport.bodies();
+        let due_dates = bodies.iter().map(|body| body.split('&').find_map(|pair| pair.strip_prefix("due_date="))).collect::<Vec<_>>();
+        assert_eq!(due_dates, vec![Some("2024-06-30"), None]);
+        assert!(transport.requests().iter().all(|request| request.starts_with(&format!("PUT {issue_path}"))));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_remote_task_priority_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let issues: Vec<Issue> = serde_json::from_str(include_str!("../../resources/test/gitlab_issues.json")).unwrap();
+        let project_path = "/api/v4/projects/jhspetersson%2Fgit-task";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(&format!("{project_path}/labels"), 200, include_str!("../../resources/test/gitlab_labels.json")),
+            Exchange::new(&format!("{project_path}/labels"), 201, r##"{"id": 5, "name": "priority::high", "color": "#FF6666", "text_color": "#FFFFFF", "description": null}"##),
+            Exchange::new(&format!("{project_path}/issues"), 201, &serde_json::to_string(&issues[0]).unwrap()),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+
+        let connector = GitlabRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let mut task = Task::new("Prioritized".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_property(PRIORITY, "high");
+        assert_eq!(connector.create_remote_task(&user, &repo, &task).unwrap(), "12");
+
+        let requests = transport.requests();
+        let issue_request = requests.iter().position(|request| request.starts_with(&format!("POST {project_path}/issues"))).unwrap();
+        let bodies = transport.bodies();
+        let labels = bodies[issue_request].split('&').find_map(|pair| pair.strip_prefix("labels="));
+        assert_eq!(labels, Some("priority%3A%3Ahigh"));
+        assert!(requests.iter().any(|request| request.starts_with(&format!("POST {project_path}/labels"))));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_remote_tasks_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let project_path = "/api/v4/projects/jhspetersson%2Fgit-task";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(&format!("{project_path}/labels"), 200, r##"[{"id": 3, "name": "release", "color": "#428bca", "text_color": "#FFFFFF", "description": null}]"##),
+            Exchange::new(&format!("{project_path}/issues"), 200, include_str!("../../resources/test/gitlab_issues.json")),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+
+        let connector = GitlabRemoteConnector::with_transport(&context, transport.clone());
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let tasks = connector.list_remote_tasks(&user, &repo, false, true, None, RemoteTaskState::All, None, &task_statuses, None).unwrap();
+
+        assert_eq!(tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>(), vec!["12", "13"]);
+        assert_eq!(tasks[0].get_property(PRIORITY).unwrap(), "medium");
+        assert!(tasks[0].get_labels().as_ref().unwrap().iter().any(|label| label.get_name() == "release"));
+
+        let error = connector.list_remote_tasks(&user, &"gone".to_string(), false, false, None, RemoteTaskState::All, None, &task_statuses, None).err().unwrap();
+        assert!(error.contains("404"), "{error}");
+        assert!(connector.get_remote_task(&user, &"gone".to_string(), &"12".to_string(), false, false, &task_statuses).unwrap().is_none());
+        assert!(transport.requests().iter().any(|request| request.starts_with("GET /api/v4/projects/jhspetersson%2Fgone/issues")));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let project_path = "/api/v4/projects/jhspetersson%2Fgit-task";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(project_path, 429, r#"{"message": "Retry later"}"#).header("Retry-After", "1"),
+            Exchange::new(&format!("{project_path}/issues"), 200, include_str!("../../resources/test/gitlab_issues.json")),
+            Exchange::new(project_path, 429, r#"{"message": "Retry later"}"#),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+
+        let connector = GitlabRemoteConnector::with_transport(&context, transport.clone());
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let started = Instant::now();
+        let tasks = connector.list_remote_tasks(&user, &repo, false, false, None, RemoteTaskState::All, None, &task_statuses, None).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(transport.requests().len(), 2);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        let error = connector.list_remote_tasks(&user, &repo, false, false, None, RemoteTaskState::All, None, &task_statuses, None).err().unwrap();
+        assert!(error.contains("429") && error.contains("Retry later"), "{error}");
+        assert_eq!(transport.requests().len(), 3);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_upload_attachment_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let uploads_path = "/api/v4/projects/jhspetersson%2Fgit-task/uploads";
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(uploads_path, 201, r#"{"id": 5, "alt": "trace", "url": "/uploads/66dbcd21/trace.log", "full_path": "/-/project/42/uploads/66dbcd21/trace.log", "markdown": "[trace.log](/uploads/66dbcd21/trace.log)"}"#),
+            Exchange::new(uploads_path, 201, r#"{"alt": "trace", "url": "/uploads/77ecde32/trace.log", "markdown": "[trace.log](/uploads/77ecde32/trace.log)"}"#),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+
+        let connector = GitlabRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        assert_eq!(connector.upload_attachment(&user, &repo, "trace.log", b"panicked at src/main.rs").unwrap(), "https://gitlab.example.com/-/project/42/uploads/66dbcd21/trace.log");
+        assert_eq!(connector.upload_attachment(&user, &repo, "trace.log", b"panicked at src/main.rs").unwrap(), "https://gitlab.example.com/jhspetersson/git-task/uploads/77ecde32/trace.log");
+        assert_eq!(transport.requests().iter().filter(|request| *request == &format!("POST {uploads_path}")).count(), 2);
+
+        let error = connector.upload_attachment(&user, &"gone".to_string(), "trace.log", b"").err().unwrap();
+        assert!(error.contains("404"), "{error}");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_http_timeout() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (_listener, url) = silent_listener();
+        context.set_config_value("task.gitlab.url", &url).unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+        context.set_config_value("task.http.timeout", "1").unwrap();
+
+        let connector = GitlabRemoteConnector::new(&context);
+        let started = Instant::now();
+        let error = connector.list_remote_tasks(&"user".to_string(), &"repo".to_string(), false, false, None, RemoteTaskState::All, None, &vec!["OPEN".to_string()], None).err().unwrap();
+        assert!(error.contains("task.http.timeout"), "{error}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_remote_comment_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let notes_path = "/api/v4/projects/jhspetersson%2Fgit-task/issues/12/notes";
+        let marked_note = r#"[{"id": 501, "body": "Done\n\n<!-- git-task:comment:22cee932dbce -->", "author": {"username": "jhspetersson"}, "created_at": "2024-05-01T10:00:00.000Z"}]"#;
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new(notes_path, 200, marked_note),
+            Exchange::new(notes_path, 201, r#"{"id": 502, "body": "New", "author": {"username": "jhspetersson"}, "created_at": "2024-05-02T10:00:00.000Z"}"#),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+
+        let connector = GitlabRemoteConnector::with_transport(&context, transport.clone());
+        let (user, repo, task_id) = ("jhspetersson".to_string(), "git-task".to_string(), "12".to_string());
+        let remote_comments = connector.list_remote_comment_texts(&user, &repo, &task_id).unwrap();
+        let comment = Comment::new("1".to_string(), HashMap::new(), "Done".to_string());
+        assert_eq!(find_marked_comment(&remote_comments, &comment), Some("501".to_string()));
+
+        let comment = Comment::new("1".to_string(), HashMap::new(), "New".to_string());
+        assert_eq!(find_marked_comment(&remote_comments, &comment), None);
+        assert_eq!(connector.create_remote_comment(&user, &repo, &task_id, &comment).unwrap(), "502");
+        assert_eq!(transport.requests().iter().filter(|request| request.starts_with(&format!("GET {notes_path}"))).count(), 1);
+        assert_eq!(transport.requests().iter().filter(|request| request.starts_with(&format!("POST {notes_path}"))).count(), 1);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+}
@@ -3,14 +3,14 @@ use std::sync::LazyLock;
 
 use chrono::DateTime;
 use jira_v3_openapi::{apis::configuration::Configuration, apis::issues_api};
-use jira_v3_openapi::apis::{issue_comments_api, issue_search_api};
-use jira_v3_openapi::models::IssueTransition;
+use jira_v3_openapi::apis::{issue_comments_api, issue_search_api, projects_api};
+use jira_v3_openapi::models::{visibility, EntityProperty, IssueTransition, IssueTypeWithStatus, Visibility};
 use regex::Regex;
 use tokio::runtime::Runtime;
 
 use gittask::{Task, Comment, Label, TaskContext};
 
-use crate::connectors::{RemoteConnector, RemoteTaskState};
+use crate::connectors::{comment_key, get_http_timeout, http_error, normalize_priority, report_progress, strip_comment_marker, ConnectorCapabilities, RemoteConnector, RemoteTaskState, PRIORITY, VISIBILITY};
 use crate::util::error_message;
 
 pub struct JiraRemoteConnector {
@@ -21,6 +21,11 @@ impl JiraRemoteConnector {
     pub fn new(context: &TaskContext) -> Self {
         Self { context: context.clone() }
     }
+
+    /// Whether descriptions and comments are converted between Markdown and Jira rich text.
+    fn is_markdown(&self) -> bool {
+        self.context.get_config_value("task.jira.markdown").is_ok_and(|markdown| markdown == "true")
+    }
 }
 
 static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
@@ -36,9 +41,27 @@ impl RemoteConnector for JiraRemoteConnector {
         Some(vec![
             "task.jira.url".to_string(),
             "task.jira.user".to_string(),
+            "task.jira.token".to_string(),
+            "task.jira.markdown".to_string(),
         ])
     }
 
+    fn capabilities(&self) -> ConnectorCapabilities {
+        ConnectorCapabilities {
+            fetch_tasks: true,
+            push_tasks: true,
+            delete_tasks: true,
+            comments: true,
+            labels: true,
+            comment_visibility: true,
+            ..Default::default()
+        }
+    }
+
+    fn max_body_length(&self) -> Option<usize> {
+        Some(32767)
+    }
+
     fn supports_remote(&self, _url: &str) -> Option<(String, String)> {
         if let Some(url) = get_base_url(&self.context) {
             match Regex::new(r"https://([^/]+)\.atlassian\.net/jira/software/projects/([^/]+)").unwrap().captures(&url) {
@@ -54,6 +77,21 @@ impl RemoteConnector for JiraRemoteConnector {
         }
     }
 
+    fn task_url(&self, domain: &String, project: &String, task_id: &String) -> String {
+        format!("https://{domain}.atlassian.net/browse/{}", task_id_to_issue_key(project, task_id))
+    }
+
+    fn list_remote_statuses(&self, domain: &String, project: &String) -> Result<Vec<String>, String> {
+        let config = get_configuration(&self.context, domain)?;
+
+        RUNTIME.block_on(async {
+            match projects_api::get_all_statuses(&config, project).await {
+                Ok(issue_types) => Ok(collect_statuses(&issue_types)),
+                Err(e) => Err(format!("Failed to get statuses: {}", http_error(e))),
+            }
+        })
+    }
+
     fn list_remote_tasks(
         &self,
         domain: &String,
@@ -62,7 +100,9 @@ impl RemoteConnector for JiraRemoteConnector {
         with_labels: bool,
         limit: Option<usize>,
         state: RemoteTaskState,
-        task_statuses: &Vec<String>
+        since: Option<u64>,
+        task_statuses: &Vec<String>,
+        progress: Option<&dyn Fn(usize, usize)>
     ) -> Result<Vec<Task>, String> {
         let config = get_configuration(&self.context, domain)?;
 
@@ -70,11 +110,16 @@ impl RemoteConnector for JiraRemoteConnector {
         let last_status = task_statuses.last().unwrap_or_else(|| &done_status);
         let jql = match state {
             RemoteTaskState::Open(_, _) => format!("project = {} AND status != '{}'", project, last_status),
-            RemoteTaskState::Closed(_, _) => format!("project = {} AND status = '{}'", project, last_status),
+            RemoteTaskState::Closed(_, _, _) => format!("project = {} AND status = '{}'", project, last_status),
             RemoteTaskState::All => format!("project = {}", project),
         };
+        let jql = match since {
+            Some(since) => format!("{jql} AND updated >= \"-{}m\"", get_minutes_since(since)),
+            None => jql,
+        };
         
-        let mut field_list = vec!["summary".to_string(), "description".to_string(), "status".to_string(), "created".to_string(), "creator".to_string()];
+        let markdown = self.is_markdown();
+        let mut field_list = vec!["summary".to_string(), "description".to_string(), "status".to_string(), "created".to_string(), "creator".to_string(), "priority".to_string()];
         if with_comments {
             field_list.push("comment".to_string());
         }
@@ -98,20 +143,25 @@ impl RemoteConnector for JiraRemoteConnector {
             match issues {
                 Ok(response) => {
                     let mut tasks = vec![];
-                    for issue in response.issues.unwrap_or_default() {
+                    let issues = response.issues.unwrap_or_default();
+                    let total = issues.len();
+                    for issue in issues {
                         let mut props = HashMap::new();
                         if let Some(fields) = issue.fields {
                             props.insert("name".to_string(), fields.get("summary").unwrap().as_str().unwrap().to_string());
-                            props.insert("description".to_string(), parse_adf(fields.get("description").unwrap()));
+                            props.insert("description".to_string(), parse_adf(fields.get("description").unwrap(), markdown));
                             props.insert("status".to_string(), parse_status(fields.get("status").unwrap()));
                             props.insert("created".to_string(), parse_to_unix_timestamp(fields.get("created").unwrap().as_str().unwrap()).unwrap());
                             props.insert("author".to_string(), parse_creator(fields.get("creator").unwrap()));
+                            if let Some(priority) = fields.get("priority").and_then(parse_priority) {
+                                props.insert(PRIORITY.to_string(), priority);
+                            }
 
                             let mut task = Task::from_properties(issue_key_to_task_id(&issue.key.unwrap()), props).unwrap();
 
                             if with_comments {
                                 if let Some(comments) = fields.get("comment") {
-                                    let task_comments = parse_comments(comments);
+                                    let task_comments = parse_comments(comments, markdown);
                                     task.set_comments(task_comments);
                                 }
                             }
@@ -126,12 +176,13 @@ impl RemoteConnector for JiraRemoteConnector {
                             }
 
                             tasks.push(task);
+                            report_progress(progress, tasks.len(), total);
                         }
                     }
 
                     Ok(tasks)
                 },
-                Err(e) => Err(e.to_string()),
+                Err(e) => Err(http_error(e)),
             }
         })
     }
@@ -144,10 +195,11 @@ impl RemoteConnector for JiraRemoteConnector {
         with_comments: bool,
         with_labels: bool,
         _task_statuses: &Vec<String>
-    ) -> Result<Task, String> {
+    ) -> Result<Option<Task>, String> {
         let config = get_configuration(&self.context, domain)?;
 
-        let mut field_list = vec!["summary".to_string(), "description".to_string(), "status".to_string(), "created".to_string(), "creator".to_string()];
+        let markdown = self.is_markdown();
+        let mut field_list = vec!["summary".to_string(), "description".to_string(), "status".to_string(), "created".to_string(), "creator".to_string(), "priority".to_string()];
         if with_comments {
             field_list.push("comment".to_string());
         }
@@ -170,16 +222,19 @@ impl RemoteConnector for JiraRemoteConnector {
                     let mut props = HashMap::new();
                     if let Some(fields) = issue.fields {
                         props.insert("name".to_string(), fields.get("summary").unwrap().as_str().unwrap().to_string());
-                        props.insert("description".to_string(), parse_adf(fields.get("description").unwrap()));
+                        props.insert("description".to_string(), parse_adf(fields.get("description").unwrap(), markdown));
                         props.insert("status".to_string(), parse_status(fields.get("status").unwrap()));
                         props.insert("created".to_string(), parse_to_unix_timestamp(fields.get("created").unwrap().as_str().unwrap())?);
                         props.insert("author".to_string(), parse_creator(fields.get("creator").unwrap()));
+                        if let Some(priority) = fields.get("priority").and_then(parse_priority) {
+                            props.insert(PRIORITY.to_string(), priority);
+                        }
 
                         let mut task = Task::from_properties(issue_key_to_task_id(&issue.key.unwrap()), props)?;
 
                         if with_comments {
                             if let Some(comments) = fields.get("comment") {
-                                let task_comments = parse_comments(comments);
+                                let task_comments = parse_comments(comments, markdown);
                                 task.set_comments(task_comments);
                             }
                         }
@@ -193,12 +248,13 @@ impl RemoteConnector for JiraRemoteConnector {
                             }
                         }
 
-                        Ok(task)
+                        Ok(Some(task))
                     } else {
                         Err("Failed to get issue: no fields returned.".to_string())
                     }                    
                 },
-                Err(e) => Err(e.to_string()),
+                Err(jira_v3_openapi::apis::Error::ResponseError(response)) if response.status.as_u16() == 404 => Ok(None),
+                Err(e) => Err(http_error(e)),
             }
         })
     }
@@ -212,29 +268,14 @@ impl RemoteConnector for JiraRemoteConnector {
         let config = get_configuration(&self.context, domain)?;
 
         RUNTIME.block_on(async {
-            let mut issue_details = jira_v3_openapi::models::IssueUpdateDetails {
-                fields: Some(std::collections::HashMap::from([
-                    ("project".to_string(), serde_json::json!({
-                        "key": project
-                    })),
-                    ("summary".to_string(), serde_json::json!(
-                        task.get_property("name").unwrap()
-                    )),
-                    ("description".to_string(),
-                        format_adf(task.get_property("description").unwrap())
-                    ),
-                    ("issuetype".to_string(), serde_json::json!({
-                        "name": "Task"
-                    })),
-                ])),
+            let mut fields = issue_fields(task, task.get_labels().as_ref(), self.is_markdown());
+            fields.insert("project".to_string(), serde_json::json!({ "key": project }));
+            fields.insert("issuetype".to_string(), serde_json::json!({ "name": "Task" }));
+
+            let issue_details = jira_v3_openapi::models::IssueUpdateDetails {
+                fields: Some(fields),
                 ..Default::default()
             };
-            
-            if let Some(labels) = task.get_labels() {
-                issue_details.fields.as_mut().unwrap().insert("labels".to_string(), serde_json::json!(
-                    labels.iter().map(|l| l.get_name()).collect::<Vec<String>>()
-                ));
-            }
 
             match issues_api::create_issue(&config, issue_details, None).await {
                 Ok(response) => {
@@ -261,14 +302,19 @@ impl RemoteConnector for JiraRemoteConnector {
         let config = get_configuration(&self.context, domain)?;
 
         RUNTIME.block_on(async {
-            let comment_body = jira_v3_openapi::models::Comment {
-                body: Some(Some(format_adf(&comment.get_text()))),
-                ..Default::default()
-            };
+            let issue_key = task_id_to_issue_key(project, task_id);
+            let key = comment_key(comment);
+            let remote_comments = list_remote_comment_keys(&config, &issue_key).await?;
+            if let Some((remote_comment_id, _)) = remote_comments.into_iter().find(|(_, remote_key)| *remote_key == key) {
+                return Ok(remote_comment_id);
+            }
+
+            let mut comment_body = comment_payload(comment, self.is_markdown());
+            comment_body.properties = Some(vec![comment_key_property(&key)]);
 
             match issue_comments_api::add_comment(
                 &config,
-                task_id_to_issue_key(project, task_id).as_str(),
+                issue_key.as_str(),
                 comment_body,
                 None,
             ).await {
@@ -361,16 +407,7 @@ impl RemoteConnector for JiraRemoteConnector {
         let config = get_configuration(&self.context, domain)?;
 
         RUNTIME.block_on(async {
-            let mut fields = HashMap::new();
-            fields.insert("summary".to_string(), serde_json::json!(task.get_property("name").unwrap()));
-            fields.insert("description".to_string(), format_adf(task.get_property("description").unwrap()));
-            
-            if let Some(labels) = labels {
-                fields.insert("labels".to_string(), serde_json::json!(
-                    labels.iter().map(|l| l.get_name()).collect::<Vec<String>>()
-                ));
-            }
-
+            let fields = issue_fields(task, labels, self.is_markdown());
             let issue_details = jira_v3_openapi::models::IssueUpdateDetails {
                 fields: Some(fields),
                 ..Default::default()
@@ -394,7 +431,7 @@ impl RemoteConnector for JiraRemoteConnector {
             if result.is_ok() {
                 let (local_status, remote_status) = match state {
                     RemoteTaskState::Open(s1, s2) => (s1, s2),
-                    RemoteTaskState::Closed(s1, s2) => (s1, s2),
+                    RemoteTaskState::Closed(s1, s2, _) => (s1, s2),
                     _ => ("".to_string(), "".to_string())
                 };
                 if local_status != remote_status {
@@ -444,15 +481,12 @@ impl RemoteConnector for JiraRemoteConnector {
         project: &String,
         task_id: &String,
         comment_id: &String,
-        text: &String
+        comment: &Comment
     ) -> Result<(), String> {
         let config = get_configuration(&self.context, domain)?;
 
         RUNTIME.block_on(async {
-            let comment = jira_v3_openapi::models::Comment {
-                body: Some(Some(format_adf(text))),
-                ..Default::default()
-            };
+            let comment = comment_payload(comment, self.is_markdown());
 
             match issue_comments_api::update_comment(
                 &config,
@@ -575,10 +609,10 @@ impl RemoteConnector for JiraRemoteConnector {
     }
 }
 
-fn get_token_from_env() -> Result<String, String> {
-    std::env::var("JIRA_TOKEN")
-        .or_else(|_| std::env::var("JIRA_API_TOKEN"))
-        .map_err(|_| "No JIRA_TOKEN nor JIRA_API_TOKEN env variable set".to_string())
+fn get_token(context: &TaskContext, domain: &String) -> Result<String, String> {
+    let url = get_base_url(context).unwrap_or_else(|| format!("https://{domain}.atlassian.net"));
+    crate::connectors::get_token(context, "task.jira.token", "jira", &["JIRA_TOKEN", "JIRA_API_TOKEN"], &url)
+        .ok_or_else(|| "No Jira token found, set task.jira.token config option or JIRA_TOKEN env variable".to_string())
 }
 
 fn get_jira_user(context: &TaskContext) -> Result<String, String> {
@@ -609,9 +643,13 @@ fn get_base_url(context: &TaskContext) -> Option<String> {
 
 fn get_configuration(context: &TaskContext, domain: &String) -> Result<Configuration, String> {
     let email = get_jira_user(&context)?;
-    let token = get_token_from_env()?;
+    let token = get_token(context, domain)?;
 
     let mut config = Configuration::new();
+    config.client = reqwest::Client::builder()
+        .timeout(get_http_timeout(context))
+        .build()
+        .map_err(|e| e.to_string())?;
     config.basic_auth = Some((email, Some(token)));
     config.base_path = format!("https://{}.atlassian.net", domain);
     Ok(config)
@@ -625,43 +663,89 @@ fn task_id_to_issue_key(project: &String, id: &String) -> String {
     format!("{}-{}", project, id)
 }
 
-fn parse_adf(description: &serde_json::Value) -> String {
-    if let serde_json::Value::Object(doc) = description {
-        if let Some(serde_json::Value::String(doc_type)) = doc.get("type") {
-            if doc_type == "doc" {
-                if let Some(serde_json::Value::Array(content)) = doc.get("content") {
-                    return content.iter().map(|v| {
-                        if let serde_json::Value::Object(node) = v {
-                            if let Some(serde_json::Value::String(node_type)) = node.get("type") {
-                                if node_type == "paragraph" {
-                                    if let Some(serde_json::Value::Array(paragraph_content)) = node.get("content") {
-                                        return paragraph_content.iter().map(|v| {
-                                            if let serde_json::Value::Object(paragraph_node) = v {
-                                                if let Some(serde_json::Value::String(paragraph_node_type)) = paragraph_node.get("type") {
-                                                    if paragraph_node_type == "text" {
-                                                        if let Some(serde_json::Value::String(text)) = paragraph_node.get("text") {
-                                                            return text.to_string();
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            "".to_string()
-                                        }).collect::<Vec<String>>().join(" ");
-                                    }
-                                }
-                            }
-                        }
-                        "".to_string()
-                    }).collect::<Vec<String>>().join("\n");
-                }
+/// Text of an ADF document. With `markdown` headings, lists, code blocks and bold or code marks
+/// come back as Markdown, otherwise as plain text. Unknown nodes contribute the text they contain.
+fn parse_adf(doc: &serde_json::Value, markdown: bool) -> String {
+    adf_blocks(doc, markdown, 0)
+}
+
+fn adf_blocks(node: &serde_json::Value, markdown: bool, indent: usize) -> String {
+    let separator = if markdown { "\n\n" } else { "\n" };
+    adf_content(node).iter()
+        .map(|block| adf_block(block, markdown, indent))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn adf_content(node: &serde_json::Value) -> &[serde_json::Value] {
+    node.get("content").and_then(|content| content.as_array()).map_or(&[], |content| content.as_slice())
+}
+
+fn adf_block(node: &serde_json::Value, markdown: bool, indent: usize) -> String {
+    match node.get("type").and_then(|node_type| node_type.as_str()).unwrap_or_default() {
+        "paragraph" => adf_inline(node, markdown),
+        "heading" if markdown => {
+            let level = node.pointer("/attrs/level").and_then(|level| level.as_u64()).unwrap_or(1).clamp(1, 6) as usize;
+            format!("{} {}", "#".repeat(level), adf_inline(node, markdown))
+        },
+        "heading" => adf_inline(node, markdown),
+        "bulletList" | "orderedList" => adf_list(node, markdown, indent),
+        "codeBlock" => {
+            let text = adf_inline(node, false);
+            match markdown {
+                true => format!("```{}\n{text}\n```", node.pointer("/attrs/language").and_then(|lang| lang.as_str()).unwrap_or_default()),
+                false => text,
             }
-        }
+        },
+        "blockquote" if markdown => adf_blocks(node, markdown, indent).lines().map(|line| format!("> {line}")).collect::<Vec<_>>().join("\n"),
+        "rule" if markdown => "---".to_string(),
+        "text" | "hardBreak" | "mention" | "emoji" | "inlineCard" => adf_inline_node(node, markdown),
+        _ => adf_blocks(node, markdown, indent),
     }
+}
 
-    "".to_string()
+fn adf_list(node: &serde_json::Value, markdown: bool, indent: usize) -> String {
+    let ordered = node.get("type").and_then(|node_type| node_type.as_str()) == Some("orderedList");
+    let start = node.pointer("/attrs/order").and_then(|order| order.as_u64()).unwrap_or(1);
+    adf_content(node).iter().enumerate().map(|(n, item)| {
+        let marker = if ordered { format!("{}.", start + n as u64) } else { "-".to_string() };
+        let padding = "  ".repeat(indent);
+        adf_content(item).iter().enumerate().map(|(k, block)| {
+            match block.get("type").and_then(|node_type| node_type.as_str()) {
+                Some("bulletList" | "orderedList") => adf_list(block, markdown, indent + 1),
+                _ if k == 0 => format!("{padding}{marker} {}", adf_block(block, markdown, indent + 1)),
+                _ => format!("{padding}  {}", adf_block(block, markdown, indent + 1)),
+            }
+        }).collect::<Vec<_>>().join("\n")
+    }).collect::<Vec<_>>().join("\n")
+}
+
+fn adf_inline(node: &serde_json::Value, markdown: bool) -> String {
+    adf_content(node).iter().map(|inline| adf_inline_node(inline, markdown)).collect()
+}
+
+fn adf_inline_node(node: &serde_json::Value, markdown: bool) -> String {
+    let attr = |name: &str| node.pointer(&format!("/attrs/{name}")).and_then(|value| value.as_str()).unwrap_or_default().to_string();
+    match node.get("type").and_then(|node_type| node_type.as_str()).unwrap_or_default() {
+        "text" => {
+            let text = node.get("text").and_then(|text| text.as_str()).unwrap_or_default().to_string();
+            let marks = node.get("marks").and_then(|marks| marks.as_array()).map_or(&[][..], |marks| marks.as_slice());
+            let has_mark = |mark_type: &str| marks.iter().any(|mark| mark.get("type").and_then(|t| t.as_str()) == Some(mark_type));
+            match markdown {
+                true if has_mark("code") => format!("`{text}`"),
+                true if has_mark("strong") => format!("**{text}**"),
+                _ => text,
+            }
+        },
+        "hardBreak" => "\n".to_string(),
+        "mention" | "emoji" => attr("text"),
+        "inlineCard" => attr("url"),
+        _ => adf_inline(node, markdown),
+    }
 }
 
-fn format_adf(text: &String) -> serde_json::Value {
+/// Plain text as a single ADF paragraph.
+fn format_adf(text: &str) -> serde_json::Value {
     serde_json::json!({
         "type": "doc",
         "version": 1,
@@ -679,6 +763,149 @@ fn format_adf(text: &String) -> serde_json::Value {
     })
 }
 
+fn to_jira_body(text: &str, markdown: bool) -> serde_json::Value {
+    if markdown { markdown_to_adf(text) } else { format_adf(text) }
+}
+
+static MD_HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(#{1,6}) (.*)$").unwrap());
+static MD_LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^( *)([-*+]|\d+\.) (.*)$").unwrap());
+static MD_INLINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\*\*([^*\n]+)\*\*|`([^`\n]+)`").unwrap());
+
+/// Nesting level, whether it's numbered, and the text of a Markdown list item.
+type ListItem<'a> = (usize, bool, &'a str);
+
+/// Converts Markdown headings, lists, code blocks, bold and inline code into ADF nodes,
+/// any other text is kept as paragraphs.
+fn markdown_to_adf(text: &str) -> serde_json::Value {
+    let mut content = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    let mut list: Vec<ListItem> = vec![];
+    let mut code: Option<(&str, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        if let Some((lang, lines)) = code.as_mut() {
+            match line.trim_end() == "```" {
+                true => {
+                    content.push(adf_code_block(lang, lines));
+                    code = None;
+                },
+                false => lines.push(line),
+            }
+            continue;
+        }
+        if let Some(lang) = line.trim_end().strip_prefix("```") {
+            flush_markdown_blocks(&mut content, &mut paragraph, &mut list);
+            code = Some((lang.trim(), vec![]));
+        } else if let Some(captures) = MD_HEADING.captures(line) {
+            flush_markdown_blocks(&mut content, &mut paragraph, &mut list);
+            content.push(serde_json::json!({
+                "type": "heading",
+                "attrs": { "level": captures[1].len() },
+                "content": markdown_inline_to_adf(captures.get(2).unwrap().as_str()),
+            }));
+        } else if let Some(captures) = MD_LIST_ITEM.captures(line) {
+            flush_markdown_blocks(&mut content, &mut paragraph, &mut vec![]);
+            list.push((captures[1].len() / 2, captures[2].ends_with('.'), captures.get(3).unwrap().as_str()));
+        } else if line.trim().is_empty() {
+            flush_markdown_blocks(&mut content, &mut paragraph, &mut list);
+        } else {
+            flush_markdown_blocks(&mut content, &mut vec![], &mut list);
+            paragraph.push(line);
+        }
+    }
+    if let Some((lang, lines)) = code {
+        content.push(adf_code_block(lang, &lines));
+    }
+    flush_markdown_blocks(&mut content, &mut paragraph, &mut list);
+
+    serde_json::json!({ "type": "doc", "version": 1, "content": content })
+}
+
+fn flush_markdown_blocks(content: &mut Vec<serde_json::Value>, paragraph: &mut Vec<&str>, list: &mut Vec<ListItem>) {
+    if !paragraph.is_empty() {
+        content.push(adf_paragraph(paragraph));
+        paragraph.clear();
+    }
+    if !list.is_empty() {
+        content.extend(markdown_list_to_adf(list));
+        list.clear();
+    }
+}
+
+/// Items nested deeper than the first one belong to the item before them, numbered and bullet runs
+/// on the same level become separate lists.
+fn markdown_list_to_adf(items: &[ListItem]) -> Vec<serde_json::Value> {
+    let level = items[0].0;
+    let mut lists: Vec<(bool, Vec<serde_json::Value>)> = vec![];
+    let mut i = 0;
+    while i < items.len() {
+        let (_, ordered, text) = items[i];
+        let end = items[i + 1..].iter().position(|item| item.0 <= level).map_or(items.len(), |pos| i + 1 + pos);
+        let mut item_content = vec![adf_paragraph(&[text])];
+        if end > i + 1 {
+            item_content.extend(markdown_list_to_adf(&items[i + 1..end]));
+        }
+        let item = serde_json::json!({ "type": "listItem", "content": item_content });
+        match lists.last_mut() {
+            Some((list_ordered, list_items)) if *list_ordered == ordered => list_items.push(item),
+            _ => lists.push((ordered, vec![item])),
+        }
+        i = end;
+    }
+    lists.into_iter().map(|(ordered, list_items)| serde_json::json!({
+        "type": if ordered { "orderedList" } else { "bulletList" },
+        "content": list_items,
+    })).collect()
+}
+
+fn adf_paragraph(lines: &[&str]) -> serde_json::Value {
+    let mut content = vec![];
+    for (n, line) in lines.iter().enumerate() {
+        if n > 0 {
+            content.push(serde_json::json!({ "type": "hardBreak" }));
+        }
+        content.extend(markdown_inline_to_adf(line));
+    }
+    serde_json::json!({ "type": "paragraph", "content": content })
+}
+
+fn adf_code_block(lang: &str, lines: &[&str]) -> serde_json::Value {
+    let mut block = serde_json::json!({ "type": "codeBlock", "content": [] });
+    if !lang.is_empty() {
+        block["attrs"] = serde_json::json!({ "language": lang });
+    }
+    if !lines.is_empty() {
+        block["content"] = serde_json::json!([{ "type": "text", "text": lines.join("\n") }]);
+    }
+    block
+}
+
+fn markdown_inline_to_adf(text: &str) -> Vec<serde_json::Value> {
+    let mut nodes = vec![];
+    let mut last = 0;
+    let push_text = |nodes: &mut Vec<serde_json::Value>, text: &str, mark: Option<&str>| {
+        if text.is_empty() {
+            return;
+        }
+        let mut node = serde_json::json!({ "type": "text", "text": text });
+        if let Some(mark) = mark {
+            node["marks"] = serde_json::json!([{ "type": mark }]);
+        }
+        nodes.push(node);
+    };
+    for captures in MD_INLINE.captures_iter(text) {
+        let found = captures.get(0).unwrap();
+        push_text(&mut nodes, &text[last..found.start()], None);
+        match captures.get(1) {
+            Some(bold) => push_text(&mut nodes, bold.as_str(), Some("strong")),
+            None => push_text(&mut nodes, &captures[2], Some("code")),
+        }
+        last = found.end();
+    }
+    push_text(&mut nodes, &text[last..], None);
+    nodes
+}
+
 fn parse_creator(creator: &serde_json::Value) -> String {
     if let serde_json::Value::Object(creator) = creator {
         if let Some(serde_json::Value::String(display_name)) = creator.get("emailAddress") {
@@ -689,6 +916,17 @@ fn parse_creator(creator: &serde_json::Value) -> String {
     "".to_string()
 }
 
+/// Statuses of all issue type workflows, each listed once.
+fn collect_statuses(issue_types: &[IssueTypeWithStatus]) -> Vec<String> {
+    let mut statuses: Vec<String> = vec![];
+    for name in issue_types.iter().flat_map(|issue_type| &issue_type.statuses).filter_map(|status| status.name.as_ref()) {
+        if !statuses.contains(name) {
+            statuses.push(name.clone());
+        }
+    }
+    statuses
+}
+
 fn parse_status(status: &serde_json::Value) -> String {
     if let serde_json::Value::Object(status) = status {
         if let Some(serde_json::Value::String(status_name)) = status.get("name") {
@@ -699,6 +937,45 @@ fn parse_status(status: &serde_json::Value) -> String {
     "".to_string()
 }
 
+/// Maps a Jira priority, including the legacy Blocker..Trivial scheme, to a priority level.
+fn parse_priority(priority: &serde_json::Value) -> Option<String> {
+    let name = priority.get("name")?.as_str()?;
+    let level = match name.to_lowercase().as_str() {
+        "highest" | "blocker" | "critical" => "critical",
+        "high" | "major" => "high",
+        "medium" | "normal" => "medium",
+        "low" | "lowest" | "minor" | "trivial" => "low",
+        _ => normalize_priority(name)?,
+    };
+    Some(level.to_string())
+}
+
+/// Issue fields git-task keeps in sync on create and update: summary, description, priority and labels.
+fn issue_fields(task: &Task, labels: Option<&Vec<Label>>, markdown: bool) -> HashMap<String, serde_json::Value> {
+    let mut fields = HashMap::new();
+    fields.insert("summary".to_string(), serde_json::json!(task.get_property("name").unwrap()));
+    fields.insert("description".to_string(), to_jira_body(task.get_property("description").unwrap(), markdown));
+    if let Some(priority) = task.get_property(PRIORITY).and_then(|priority| format_priority(priority)) {
+        fields.insert("priority".to_string(), priority);
+    }
+    if let Some(labels) = labels {
+        fields.insert("labels".to_string(), serde_json::json!(
+            labels.iter().map(|l| l.get_name()).collect::<Vec<String>>()
+        ));
+    }
+    fields
+}
+
+fn format_priority(priority: &str) -> Option<serde_json::Value> {
+    let name = match normalize_priority(priority)? {
+        "critical" => "Highest",
+        "high" => "High",
+        "medium" => "Medium",
+        _ => "Low",
+    };
+    Some(serde_json::json!({ "name": name }))
+}
+
 fn parse_author(author: &serde_json::Value) -> String {
     if let serde_json::Value::Object(author) = author {
         if let Some(serde_json::Value::String(display_name)) = author.get("displayName") {
@@ -709,6 +986,11 @@ fn parse_author(author: &serde_json::Value) -> String {
     "".to_string()
 }
 
+fn get_minutes_since(since: u64) -> u64 {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    now.saturating_sub(since).div_ceil(60) + 1
+}
+
 fn parse_to_unix_timestamp(date_str: &str) -> Result<String, String> {
     let dt = DateTime::parse_from_str(date_str, "%Y-%m-%dT%H:%M:%S%.3f%z")
         .map_err(|e| e.to_string())?;
@@ -718,19 +1000,100 @@ fn parse_to_unix_timestamp(date_str: &str) -> Result<String, String> {
     Ok(timestamp.to_string())
 }
 
-fn parse_comments(comments: &serde_json::Value) -> Vec<Comment> {
+fn comment_payload(comment: &Comment, markdown: bool) -> jira_v3_openapi::models::Comment {
+    jira_v3_openapi::models::Comment {
+        body: Some(Some(to_jira_body(&comment.get_text(), markdown))),
+        visibility: comment.get_all_properties().get(VISIBILITY).and_then(|v| parse_visibility(v)),
+        ..Default::default()
+    }
+}
+
+const COMMENT_KEY_PROPERTY: &str = "git-task";
+
+/// Jira shows HTML comments as text, so the key of a pushed comment is kept in a comment property instead.
+fn comment_key_property(key: &str) -> EntityProperty {
+    EntityProperty {
+        key: Some(COMMENT_KEY_PROPERTY.to_string()),
+        value: Some(Some(serde_json::json!({ "key": key }))),
+    }
+}
+
+fn parse_comment_key(properties: &[EntityProperty]) -> Option<String> {
+    properties.iter()
+        .find(|property| property.key.as_deref() == Some(COMMENT_KEY_PROPERTY))
+        .and_then(|property| property.value.clone().flatten())
+        .and_then(|value| value.get("key").and_then(|key| key.as_str()).map(|key| key.to_string()))
+}
+
+/// IDs and keys of all comments on the issue pushed by git-task, fetched page by page.
+async fn list_remote_comment_keys(config: &Configuration, issue_key: &str) -> Result<Vec<(String, String)>, String> {
+    let mut result = vec![];
+    let mut fetched = 0;
+    loop {
+        let page = issue_comments_api::get_comments(config, issue_key, Some(fetched), Some(100), None, Some("properties")).await
+            .map_err(|e| format!("Failed to list comments: {}", e))?;
+        let comments = page.comments.unwrap_or_default();
+        if comments.is_empty() {
+            break;
+        }
+        fetched += comments.len() as i64;
+        result.extend(comments.into_iter().filter_map(|comment| {
+            let key = parse_comment_key(comment.properties.as_deref().unwrap_or_default())?;
+            comment.id.map(|id| (id, key))
+        }));
+        if page.total.is_none_or(|total| fetched >= total) {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+fn parse_visibility(visibility: &str) -> Option<Visibility> {
+    let (visibility_type, value) = match visibility.split_once(':') {
+        Some(("group", value)) => (visibility::Type::Group, value),
+        Some(("role", value)) => (visibility::Type::Role, value),
+        _ => (visibility::Type::Role, visibility),
+    };
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(Visibility {
+        r#type: Some(visibility_type),
+        value: Some(value.to_string()),
+        ..Default::default()
+    })
+}
+
+fn format_visibility(visibility: &Visibility) -> Option<String> {
+    let value = visibility.value.as_ref()?;
+    match visibility.r#type {
+        Some(visibility::Type::Group) => Some(format!("group:{value}")),
+        _ => Some(value.clone()),
+    }
+}
+
+fn parse_comments(comments: &serde_json::Value, markdown: bool) -> Vec<Comment> {
     if let serde_json::Value::Object(comments) = comments {
         if let Some(serde_json::Value::Array(comments_array)) = comments.get("comments") {
             return comments_array.iter().filter_map(|v| {
                 match v {
                     serde_json::Value::Object(comment) => {
+                        let mut props = HashMap::from([
+                            ("author".to_string(), parse_author(comment.get("author").unwrap())),
+                            ("created".to_string(), parse_to_unix_timestamp(comment.get("created").unwrap().as_str().unwrap()).unwrap()),
+                        ]);
+                        let visibility = comment.get("visibility")
+                            .and_then(|v| serde_json::from_value::<Visibility>(v.clone()).ok())
+                            .and_then(|v| format_visibility(&v));
+                        if let Some(visibility) = visibility {
+                            props.insert(VISIBILITY.to_string(), visibility);
+                        }
                         Some(Comment::new(
                             comment.get("id").unwrap().as_str().unwrap().to_string(),
-                            HashMap::from([
-                                ("author".to_string(), parse_author(comment.get("author").unwrap())),
-                                ("created".to_string(), parse_to_unix_timestamp(comment.get("created").unwrap().as_str().unwrap()).unwrap()),
-                            ]),
-                            parse_adf(comment.get("body").unwrap())
+                            props,
+                            strip_comment_marker(&parse_adf(comment.get("body").unwrap(), markdown))
                         ))
                     }
                     _ => { None }
@@ -740,4 +1103,97 @@ fn parse_comments(comments: &serde_json::Value) -> Vec<Comment> {
     }
 
     vec![]
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use gittask::TaskContext;
+    use super::*;
+
+    #[test]
+    fn test_task_url() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let connector = JiraRemoteConnector::new(&context);
+        let (domain, project, id) = ("example".to_string(), "GT".to_string(), "12".to_string());
+        assert_eq!(connector.task_url(&domain, &project, &id), "https://example.atlassian.net/browse/GT-12");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_comment_visibility() {
+        let comments = parse_comments(&serde_json::from_str(include_str!("../../resources/test/jira_comments.json")).unwrap(), false);
+        let visibility = comments.iter().map(|c| c.get_all_properties().get(VISIBILITY).cloned()).collect::<Vec<_>>();
+        assert_eq!(visibility, vec![Some("Developers".to_string()), Some("group:jira-admins".to_string()), None]);
+        assert_eq!(comments[2].get_text(), "Fixed in 1.2");
+
+        let payload = serde_json::to_value(comment_payload(&comments[0], false)).unwrap();
+        assert_eq!(payload["visibility"], serde_json::json!({ "type": "role", "value": "Developers" }));
+        let payload = serde_json::to_value(comment_payload(&comments[1], false)).unwrap();
+        assert_eq!(payload["visibility"], serde_json::json!({ "type": "group", "value": "jira-admins" }));
+        let payload = serde_json::to_value(comment_payload(&comments[2], false)).unwrap();
+        assert!(payload.get("visibility").is_none());
+
+        let mut payload = comment_payload(&comments[2], false);
+        payload.properties = Some(vec![comment_key_property("464021da5e6a")]);
+        let payload = serde_json::to_value(payload).unwrap();
+        assert_eq!(payload["properties"], serde_json::json!([{ "key": "git-task", "value": { "key": "464021da5e6a" } }]));
+        assert!(!payload["body"].to_string().contains("git-task"));
+        assert_eq!(parse_comment_key(&[comment_key_property("464021da5e6a")]), Some("464021da5e6a".to_string()));
+        assert_eq!(parse_comment_key(&[]), None);
+
+        assert_eq!(parse_visibility("role:Administrators").unwrap().value, Some("Administrators".to_string()));
+        assert!(parse_visibility("group:").is_none());
+    }
+
+    #[test]
+    fn test_collect_statuses() {
+        let issue_types: Vec<IssueTypeWithStatus> = serde_json::from_str(include_str!("../../resources/test/jira_statuses.json")).unwrap();
+        assert_eq!(collect_statuses(&issue_types), vec!["To Do", "In Progress", "Done", "In Review"]);
+        assert!(collect_statuses(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_priority_round_trip() {
+        let priorities: Vec<serde_json::Value> = serde_json::from_str(include_str!("../../resources/test/jira_priorities.json")).unwrap();
+        let levels = priorities.iter().map(parse_priority).collect::<Vec<_>>();
+        assert_eq!(levels, ["critical", "high", "medium", "low", "low", "critical"].map(|level| Some(level.to_string())));
+        assert_eq!(parse_priority(&serde_json::json!({ "name": "Someday" })), None);
+        assert_eq!(parse_priority(&serde_json::Value::Null), None);
+
+        assert_eq!(format_priority("CRITICAL"), Some(serde_json::json!({ "name": "Highest" })));
+        for level in ["critical", "high", "medium", "low"] {
+            assert_eq!(parse_priority(&format_priority(level).unwrap()).as_deref(), Some(level));
+        }
+        assert_eq!(format_priority("someday"), None);
+
+        let mut task = Task::new("Prioritized".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        assert!(!issue_fields(&task, None, false).contains_key("priority"));
+        task.set_property(PRIORITY, "high");
+        assert_eq!(issue_fields(&task, None, false)["priority"], serde_json::json!({ "name": "High" }));
+    }
+
+    #[test]
+    fn test_markdown_adf_conversion() {
+        let adf: serde_json::Value = serde_json::from_str(include_str!("../../resources/test/jira_description.json")).unwrap();
+        let markdown = "# Title\n\nSome **bold** and `code` text,\non two lines.\n\n## Steps\n\n1. First\n  - nested\n2. Second\n\n```rust\nlet x = **y**;\n```\n\n> quote stays";
+        assert_eq!(parse_adf(&adf, true), markdown);
+        assert_eq!(markdown_to_adf(markdown), adf);
+        assert_eq!(to_jira_body(markdown, true), adf);
+
+        assert_eq!(parse_adf(&adf, false), "Title\nSome bold and code text,\non two lines.\nSteps\n1. First\n  - nested\n2. Second\nlet x = **y**;\n> quote stays");
+        assert_eq!(to_jira_body("# Title", false), format_adf("# Title"));
+        assert_eq!(parse_adf(&format_adf("# Title\n**kept**"), true), "# Title\n**kept**");
+
+        let adf = markdown_to_adf("- item\n1. numbered\n```\nunterminated");
+        let types = adf_content(&adf).iter().map(|node| node["type"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(types, vec!["bulletList", "orderedList", "codeBlock"]);
+    }
+}
@@ -2,18 +2,75 @@ mod github;
 mod gitlab;
 mod jira;
 mod redmine;
+#[cfg(test)]
+mod replay;
+mod transport;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::pin::pin;
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use git2::{ObjectType, Oid};
+
+use regex::Regex;
 
 use gittask::{Comment, Label, Task, TaskContext};
+use crate::property::PRIORITY_LEVELS;
+use crate::status::StatusManager;
 use crate::connectors::github::GithubRemoteConnector;
 use crate::connectors::gitlab::GitlabRemoteConnector;
 use crate::connectors::jira::JiraRemoteConnector;
 use crate::connectors::redmine::RedmineRemoteConnector;
 
-#[derive(Debug, PartialEq)]
+const DEFAULT_HTTP_TIMEOUT: u64 = 30;
+const DEFAULT_CONCURRENCY: usize = 1;
+const TRUNCATION_MARKER: &str = "…";
+const LABEL_SCOPE_SEPARATOR: &str = "::";
+pub(crate) const STATE_REASON: &str = "state_reason";
+pub(crate) const URL: &str = "url";
+pub(crate) const PRIORITY: &str = "priority";
+pub(crate) const DUE: &str = "due";
+pub(crate) const WEIGHT: &str = "weight";
+pub(crate) const SYNCED_LABELS: &str = "synced_labels";
+pub(crate) const REMOTE_MISSING: &str = "remote_missing";
+pub(crate) const VISIBILITY: &str = "visibility";
+
+/// Comment property backed by an optional provider feature, with the capability it needs.
+type CommentFeature = (&'static str, fn(&ConnectorCapabilities) -> bool);
+
+const COMMENT_FEATURES: [CommentFeature; 1] = [(VISIBILITY, |capabilities| capabilities.comment_visibility)];
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum RemoteTaskState {
     All,
     Open(String, String),
-    Closed(String, String),
+    Closed(String, String, Option<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchedRemote {
+    pub connector_type: String,
+    pub user: String,
+    pub repo: String,
+    pub url: String,
+}
+
+/// Operations a connector is able to perform against its remote.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConnectorCapabilities {
+    /// A single remote task can be fetched by its ID.
+    pub fetch_tasks: bool,
+    pub push_tasks: bool,
+    pub delete_tasks: bool,
+    pub comments: bool,
+    pub labels: bool,
+    pub milestones: bool,
+    pub reactions: bool,
+    pub attachments: bool,
+    /// Comments can be restricted to a group or role.
+    pub comment_visibility: bool,
 }
 
 pub trait RemoteConnector {
@@ -21,22 +78,68 @@ pub trait RemoteConnector {
     fn get_config_options(&self) -> Option<Vec<String>> {
         None
     }
+    fn capabilities(&self) -> ConnectorCapabilities {
+        ConnectorCapabilities {
+            fetch_tasks: true,
+            push_tasks: true,
+            delete_tasks: true,
+            comments: true,
+            labels: true,
+            ..Default::default()
+        }
+    }
     fn supports_remote(&self, url: &str) -> Option<(String, String)>;
-    fn list_remote_tasks(&self, user: &String, repo: &String, with_comments: bool, with_labels: bool, limit: Option<usize>, state: RemoteTaskState, task_statuses: &Vec<String>) -> Result<Vec<Task>, String>;
-    fn get_remote_task(&self, user: &String, repo: &String, task_id: &String, with_comments: bool, with_labels: bool, task_statuses: &Vec<String>) -> Result<Task, String>;
+    /// Longest description or comment text the provider accepts, in characters.
+    fn max_body_length(&self) -> Option<usize> {
+        None
+    }
+    /// Whether created comments carry the `with_comment_marker` marker, which counts against the length limit.
+    fn marks_comments(&self) -> bool {
+        false
+    }
+    fn task_url(&self, user: &String, repo: &String, task_id: &String) -> String;
+    /// Statuses an issue can have on the remote, providers without workflows only know open and closed.
+    fn list_remote_statuses(&self, _user: &String, _repo: &String) -> Result<Vec<String>, String> {
+        Ok(vec!["open".to_string(), "closed".to_string()])
+    }
+    fn list_remote_tasks(&self, user: &String, repo: &String, with_comments: bool, with_labels: bool, limit: Option<usize>, state: RemoteTaskState, since: Option<u64>, task_statuses: &Vec<String>, progress: Option<&dyn Fn(usize, usize)>) -> Result<Vec<Task>, String>;
+    /// Fetches a single remote task, `None` when the remote reports it doesn't exist or isn't visible.
+    fn get_remote_task(&self, user: &String, repo: &String, task_id: &String, with_comments: bool, with_labels: bool, task_statuses: &Vec<String>) -> Result<Option<Task>, String>;
     fn create_remote_task(&self, user: &String, repo: &String, task: &Task) -> Result<String, String>;
     fn create_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment: &Comment) -> Result<String, String>;
+    /// Id and text of the comments on a remote task, for connectors that `marks_comments` to find
+    /// the comments an interrupted push created already.
+    fn list_remote_comment_texts(&self, _user: &String, _repo: &String, _task_id: &String) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![])
+    }
     fn create_remote_label(&self, user: &String, repo: &String, task_id: &String, label: &Label) -> Result<(), String>;
     fn update_remote_task(&self, user: &String, repo: &String, task: &Task, labels: Option<&Vec<Label>>, state: RemoteTaskState) -> Result<(), String>;
-    fn update_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment_id: &String, text: &String) -> Result<(), String>;
+    fn update_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment_id: &String, comment: &Comment) -> Result<(), String>;
     fn delete_remote_task(&self, user: &String, repo: &String, task_id: &String) -> Result<(), String>;
     fn delete_remote_comment(&self, user: &String, repo: &String, task_id: &String, comment_id: &String) -> Result<(), String>;
     fn delete_remote_label(&self, user: &String, repo: &String, task_id: &String, name: &String) -> Result<(), String>;
+    /// Uploads a file to the remote and returns the URL it is served from.
+    fn upload_attachment(&self, _user: &String, _repo: &String, _name: &str, _bytes: &[u8]) -> Result<String, String> {
+        Err(unsupported_error(self, "attachments"))
+    }
+}
+
+pub(crate) fn unsupported_error<C: RemoteConnector + ?Sized>(connector: &C, operation: &str) -> String {
+    format!("{} connector does not support {operation}", connector.type_name())
+}
+
+pub(crate) fn unsupported_message<C: RemoteConnector + ?Sized>(connector: &C, operation: &str) -> String {
+    format!("ERROR: {}", unsupported_error(connector, operation))
+}
+
+/// Issue or comment number of an ID, IDs from the `uuid` and `prefix-<name>` schemes were never issued by a remote.
+pub(crate) fn parse_remote_id(id: &str) -> Result<u64, String> {
+    id.parse().map_err(|_| format!("ID {id} is not known to the remote, only numeric IDs are"))
 }
 
 fn connectors(context: &TaskContext) -> [Box<dyn RemoteConnector>; 4] {
     [
-        Box::new(GithubRemoteConnector),
+        Box::new(GithubRemoteConnector::new(&context)),
         Box::new(GitlabRemoteConnector::new(&context)),
         Box::new(JiraRemoteConnector::new(&context)),
         Box::new(RedmineRemoteConnector::new(&context)),
@@ -66,10 +169,814 @@ pub fn get_matching_remote_connectors(context: &TaskContext,
     result
 }
 
-pub(crate) fn get_config_options_from_connectors(context: &TaskContext) -> Vec<String> {
-    connectors(&context)
-        .iter()
-        .filter_map(|c| c.get_config_options())
-        .flatten()
+pub fn describe_matching_remotes(context: &TaskContext,
+                                 remotes: Vec<String>,
+                                 connector_type: &Option<String>
+) -> Vec<MatchedRemote> {
+    remotes.into_iter()
+        .flat_map(|remote| {
+            get_matching_remote_connectors(context, vec![remote.clone()], connector_type)
+                .into_iter()
+                .map(move |(connector, user, repo)| MatchedRemote {
+                    connector_type: connector.type_name().to_string(),
+                    user,
+                    repo,
+                    url: remote.clone(),
+                })
+        })
         .collect()
 }
+
+/// Identifies a pushed comment across clones: local comment ids are per clone, so the key is
+/// hashed from the author, creation time and text instead.
+pub(crate) fn comment_key(comment: &Comment) -> String {
+    let props = comment.get_all_properties();
+    let field = |name: &str| props.get(name).map_or("", |value| value.as_str());
+    let content = format!("{}\n{}\n{}", field("author"), field("created"), comment.get_text());
+    let hash = Oid::hash_object(ObjectType::Blob, content.as_bytes()).map(|oid| oid.to_string()).unwrap_or_default();
+    hash[..12.min(hash.len())].to_string()
+}
+
+fn comment_marker(comment: &Comment) -> String {
+    format!("<!-- git-task:comment:{} -->", comment_key(comment))
+}
+
+pub(crate) fn with_comment_marker(comment: &Comment) -> String {
+    format!("{}\n\n{}", comment.get_text(), comment_marker(comment))
+}
+
+pub(crate) fn strip_comment_marker(text: &str) -> String {
+    match Regex::new("\\s*<!-- git-task:comment:[^ ]+ -->\\s*$").unwrap().find(text) {
+        Some(m) => text[..m.start()].to_string(),
+        None => text.to_string(),
+    }
+}
+
+pub(crate) fn find_marked_comment(remote_comments: &[(String, String)], comment: &Comment) -> Option<String> {
+    let marker = comment_marker(comment);
+    remote_comments.iter()
+        .find(|(_, text)| text.contains(&marker))
+        .map(|(id, _)| id.clone())
+}
+
+pub(crate) fn label_description(description: Option<&str>) -> Option<String> {
+    description.filter(|d| !d.is_empty()).map(|d| d.to_string())
+}
+
+pub(crate) fn label_description_changed(local: &Label, remote_description: Option<&str>) -> bool {
+    match label_description(local.get_description().as_deref()) {
+        Some(description) => label_description(remote_description).as_ref() != Some(&description),
+        None => false,
+    }
+}
+
+pub(crate) fn merge_label_descriptions(local_labels: Option<&Vec<Label>>, remote_labels: &[Label]) -> Vec<Label> {
+    remote_labels.iter()
+        .map(|remote| {
+            let mut label = remote.clone();
+            if label_description(remote.get_description().as_deref()).is_none() {
+                let local_description = local_labels
+                    .and_then(|labels| labels.iter().find(|l| l.get_name() == remote.get_name()))
+                    .and_then(|l| label_description(l.get_description().as_deref()));
+                if let Some(description) = local_description {
+                    label.set_description(description);
+                }
+            }
+            label
+        })
+        .collect()
+}
+
+pub(crate) fn merge_labels_for_push(local_labels: &[Label], remote_labels: &[Label], synced_labels: Option<&String>, replace: bool) -> Vec<Label> {
+    let mut labels = local_labels.to_vec();
+    if !replace {
+        let synced_labels = synced_labels.map(|synced| synced.split(',').map(normalize_label_name).collect::<Vec<_>>()).unwrap_or_default();
+        labels.extend(remote_labels.iter()
+            .filter(|remote| local_labels.iter().any(|local| same_label_name(local, remote))
+                || !synced_labels.contains(&normalize_label_name(&remote.get_name())))
+            .cloned());
+    }
+    dedupe_labels(labels)
+}
+
+fn normalize_label_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+fn same_label_name(label: &Label, other: &Label) -> bool {
+    normalize_label_name(&label.get_name()) == normalize_label_name(&other.get_name())
+}
+
+/// Collapses labels whose names differ only in case into the first one,
+/// which takes the color and description of a duplicate when it has none.
+pub(crate) fn dedupe_labels(labels: Vec<Label>) -> Vec<Label> {
+    let mut result: Vec<Label> = vec![];
+    for label in labels {
+        match result.iter_mut().find(|existing| same_label_name(existing, &label)) {
+            Some(existing) => {
+                if existing.get_color().is_empty() && !label.get_color().is_empty() {
+                    existing.set_color(label.get_color());
+                }
+                if let (None, Some(description)) = (label_description(existing.get_description().as_deref()), label_description(label.get_description().as_deref())) {
+                    existing.set_description(description);
+                }
+            },
+            None => result.push(label),
+        }
+    }
+    result
+}
+
+/// Labels pulled from a remote, deduplicated against each other and against local spellings of the same labels.
+pub(crate) fn merge_pulled_labels(local_labels: Option<&Vec<Label>>, remote_labels: &[Label]) -> Vec<Label> {
+    let mut labels = merge_label_descriptions(local_labels, remote_labels);
+    labels.extend(local_labels.into_iter().flatten()
+        .filter(|local| remote_labels.iter().any(|remote| same_label_name(local, remote)))
+        .cloned());
+    dedupe_labels(labels)
+}
+
+pub(crate) fn record_synced_labels(task: &mut Task) {
+    match task.get_labels() {
+        Some(labels) if !labels.is_empty() => {
+            let names = labels.iter().map(|label| label.get_name()).collect::<Vec<_>>().join(",");
+            task.set_property(SYNCED_LABELS, &names);
+        },
+        _ => { task.delete_property(SYNCED_LABELS); }
+    }
+}
+
+/// Uploads the attachments queued for a task and links them in its description, the local
+/// task is saved after each upload so a failed one doesn't upload the others twice.
+/// Connectors without uploads leave them queued with a warning.
+pub(crate) fn upload_pending_attachments(context: &TaskContext, connector: &dyn RemoteConnector, user: &String, repo: &String, mut task: Task) -> Result<Task, String> {
+    let id = task.get_id().unwrap_or_default();
+    let mut files = context.get_pending_attachments(&id);
+    if files.is_empty() {
+        return Ok(task);
+    }
+    if !connector.capabilities().attachments {
+        context.add_warning(format!("{}, {} file(s) attached to task ID {id} stay pending", unsupported_error(connector, "attachments"), files.len()));
+        return Ok(task);
+    }
+
+    while let Some(file) = files.first().cloned() {
+        let bytes = std::fs::read(&file).map_err(|e| format!("Can't read attachment {file}: {e}"))?;
+        let name = std::path::Path::new(&file).file_name().map_or_else(|| file.clone(), |name| name.to_string_lossy().to_string());
+        let url = connector.upload_attachment(user, repo, &name, &bytes)?;
+        println!("Uploaded {name} to {url}");
+
+        let link = format!("[{name}]({url})");
+        let description = match task.get_property("description").map(|description| description.trim_end()) {
+            Some(description) if !description.is_empty() => format!("{description}\n\n{link}"),
+            _ => link,
+        };
+        task.set_property("description", &description);
+        context.update_task(task.clone())?;
+        files.remove(0);
+        context.set_pending_attachments(&id, &files)?;
+    }
+
+    Ok(task)
+}
+
+/// Recognized priority level of a value, compared ignoring case.
+pub(crate) fn normalize_priority(value: &str) -> Option<&'static str> {
+    PRIORITY_LEVELS.iter().map(|(level, _)| *level).find(|level| level.eq_ignore_ascii_case(value.trim()))
+}
+
+/// Priority carried by a `priority::<level>` scoped label.
+pub(crate) fn priority_from_labels<'a>(names: impl IntoIterator<Item = &'a str>) -> Option<&'static str> {
+    names.into_iter()
+        .filter_map(|name| name.split_once(LABEL_SCOPE_SEPARATOR))
+        .find(|(scope, _)| scope.eq_ignore_ascii_case(PRIORITY))
+        .and_then(|(_, level)| normalize_priority(level))
+}
+
+/// Replaces the `priority::` labels with the one matching the task priority,
+/// labels are left as is when the task has no recognized priority.
+pub(crate) fn with_priority_label(labels: &[Label], task: &Task) -> Vec<Label> {
+    let Some(priority) = task.get_property(PRIORITY).and_then(|priority| normalize_priority(priority)) else {
+        return labels.to_vec();
+    };
+    let name = format!("{PRIORITY}{LABEL_SCOPE_SEPARATOR}{priority}");
+    let color = PRIORITY_LEVELS.iter().find(|(level, _)| *level == priority).map(|(_, color)| color.to_string());
+    let label = labels.iter().find(|label| label.get_name().eq_ignore_ascii_case(&name)).cloned()
+        .unwrap_or_else(|| Label::new(name, color, None));
+    let mut result = labels.iter().filter(|label| !label.matches(&format!("{PRIORITY}{LABEL_SCOPE_SEPARATOR}"))).cloned().collect::<Vec<_>>();
+    result.push(label);
+    result
+}
+
+fn normalize_status_name(status: &str) -> String {
+    status.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+/// Pairs every remote status with the local status of the same name (ignoring case and separators), if any.
+pub(crate) fn map_remote_statuses(remote_statuses: &[String], local_statuses: &[String]) -> Vec<(String, Option<String>)> {
+    remote_statuses.iter()
+        .map(|remote| {
+            let local = local_statuses.iter().find(|local| normalize_status_name(local) == normalize_status_name(remote));
+            (remote.clone(), local.cloned())
+        })
+        .collect()
+}
+
+pub(crate) fn report_progress(progress: Option<&dyn Fn(usize, usize)>, current: usize, total: usize) {
+    if let Some(progress) = progress {
+        progress(current, total);
+    }
+}
+
+pub(crate) fn fit_task_for_push(context: &TaskContext, connector: &dyn RemoteConnector, task: &Task) -> Result<Task, String> {
+    let limit = get_max_body_length(context, connector);
+    let truncate = is_truncate_enabled(context);
+    let mut task = task.clone();
+    if let Some(description) = task.get_property("description") {
+        let description = fit_to_limit(description, limit, truncate, "Description")?;
+        task.set_property("description", &description);
+    }
+    if let Some(comments) = task.get_comments().clone() {
+        let comment_limit = comment_length_limit(connector, limit);
+        let comments = comments.iter().map(|comment| fit_comment(comment, comment_limit, truncate)).collect::<Result<Vec<_>, _>>()?;
+        task.set_comments(comments);
+    }
+    Ok(task)
+}
+
+pub(crate) fn fit_comment_for_push(context: &TaskContext, connector: &dyn RemoteConnector, comment: &Comment) -> Result<Comment, String> {
+    fit_comment(comment, comment_length_limit(connector, get_max_body_length(context, connector)), is_truncate_enabled(context))
+}
+
+/// Room left for the comment text once the marker is appended.
+fn comment_length_limit(connector: &dyn RemoteConnector, limit: Option<usize>) -> Option<usize> {
+    match connector.marks_comments() {
+        true => limit.map(|limit| limit.saturating_sub(comment_marker_length())),
+        false => limit,
+    }
+}
+
+fn comment_marker_length() -> usize {
+    with_comment_marker(&Comment::new(String::new(), HashMap::new(), String::new())).chars().count()
+}
+
+/// Features the comment relies on that the connector doesn't support. Such a comment is held
+/// back, e.g. a restricted one would become visible to everyone on the remote.
+pub(crate) fn unsupported_comment_features(connector: &dyn RemoteConnector, comment: &Comment) -> Vec<&'static str> {
+    let capabilities = connector.capabilities();
+    COMMENT_FEATURES.iter()
+        .filter(|(prop, supported)| !supported(&capabilities) && comment.get_all_properties().contains_key(*prop))
+        .map(|(prop, _)| *prop)
+        .collect()
+}
+
+fn fit_comment(comment: &Comment, limit: Option<usize>, truncate: bool) -> Result<Comment, String> {
+    let mut comment = comment.clone();
+    let what = format!("Comment {}", comment.get_id().unwrap_or_default());
+    comment.set_text(fit_to_limit(&comment.get_text(), limit, truncate, &what)?);
+    Ok(comment)
+}
+
+fn get_max_body_length(context: &TaskContext, connector: &dyn RemoteConnector) -> Option<usize> {
+    match context.get_config_value(&format!("task.{}.maxlength", connector.type_name())) {
+        Ok(value) => value.parse::<usize>().ok().filter(|limit| *limit > 0),
+        Err(_) => connector.max_body_length(),
+    }
+}
+
+/// Max number of requests a connector keeps in flight per host, `task.<connector>.concurrency`; only GitHub fetches concurrently.
+pub(crate) fn get_concurrency(context: &TaskContext, connector_type: &str) -> usize {
+    context.get_config_value(&format!("task.{connector_type}.concurrency")).ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+/// Runs `fetch` for every item as the stream yields it, with at most `limit` of them in flight, the
+/// results keep the order of the items. `total` is the expected count for progress, corrected at the end.
+pub(crate) async fn fetch_concurrently<S, R, F, Fut>(items: S, total: usize, limit: usize, progress: Option<&dyn Fn(usize, usize)>, fetch: F) -> Vec<R>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = R>,
+{
+    let mut results = pin!(items.map(fetch).buffered(limit.max(1)));
+    let mut result = vec![];
+    while let Some(item) = results.next().await {
+        result.push(item);
+        report_progress(progress, result.len(), total.max(result.len()));
+    }
+    if result.len() < total {
+        report_progress(progress, result.len(), result.len());
+    }
+    result
+}
+
+fn is_truncate_enabled(context: &TaskContext) -> bool {
+    context.get_config_value("task.push.truncate").is_ok_and(|truncate| truncate == "true")
+}
+
+fn fit_to_limit(text: &str, limit: Option<usize>, truncate: bool, what: &str) -> Result<String, String> {
+    let length = text.chars().count();
+    match limit {
+        Some(limit) if length > limit => match truncate {
+            true => {
+                let mut result = text.chars().take(limit.saturating_sub(TRUNCATION_MARKER.chars().count())).collect::<String>();
+                result.push_str(TRUNCATION_MARKER);
+                Ok(result)
+            },
+            false => Err(format!("{what} is {length} characters long, the remote limit is {limit}. Shorten it or set task.push.truncate to true")),
+        },
+        _ => Ok(text.to_string()),
+    }
+}
+
+/// The first non-empty source wins: config key, `GIT_TASK_<PROVIDER>_TOKEN`,
+/// provider-specific env variables, then git credential helper for the remote URL.
+pub(crate) fn get_token(context: &TaskContext, config_key: &str, provider: &str, env_vars: &[&str], url: &str) -> Option<String> {
+    let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+    context.get_config_value(config_key).ok().and_then(non_empty)
+        .or_else(|| std::env::var(format!("GIT_TASK_{}_TOKEN", provider.to_uppercase())).ok().and_then(non_empty))
+        .or_else(|| env_vars.iter().find_map(|var| std::env::var(var).ok().and_then(non_empty)))
+        .or_else(|| context.get_credential_password(url).and_then(non_empty))
+}
+
+pub(crate) fn get_http_timeout(context: &TaskContext) -> Duration {
+    let seconds = context.get_config_value("task.http.timeout").ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    Duration::from_secs(seconds)
+}
+
+fn is_timeout(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(e) = source {
+        if e.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::TimedOut)
+            || e.to_string().contains("timed out") {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Error message of a failed request, including the cause when the error itself is only a category,
+/// like GitHub API errors.
+pub(crate) fn http_error<E: Error + 'static>(error: E) -> String {
+    if is_timeout(&error) {
+        format!("Request timed out, the limit can be raised with task.http.timeout ({error})")
+    } else {
+        match error.source().map(|source| source.to_string()) {
+            Some(source) if !error.to_string().contains(&source) => format!("{error}: {source}"),
+            _ => error.to_string(),
+        }
+    }
+}
+
+pub(crate) fn get_remote_task_state(status_manager: &StatusManager, local_status: &str, remote_status: &str, state_reason: Option<String>) -> RemoteTaskState {
+    let closed = status_manager.is_closed_like(local_status).unwrap_or_else(|| {
+        eprintln!("WARNING: status {local_status} is not listed in task.status.openlike or task.status.closedlike, treating it as open");
+        false
+    });
+
+    if closed {
+        RemoteTaskState::Closed(local_status.to_string(), remote_status.to_string(), state_reason)
+    } else {
+        RemoteTaskState::Open(local_status.to_string(), remote_status.to_string())
+    }
+}
+
+pub(crate) fn get_config_options_from_connectors(context: &TaskContext) -> Vec<String> {
+    let mut result = vec!["task.http.timeout".to_string(), "task.push.truncate".to_string()];
+    for connector in connectors(context) {
+        result.extend(connector.get_config_options().unwrap_or_default());
+        result.push(format!("task.{}.maxlength", connector.type_name()));
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::env::temp_dir;
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use gittask::TaskContext;
+    use crate::connectors::replay::{Exchange, ReplayTransport};
+    use super::*;
+
+    #[test]
+    fn test_describe_matching_remotes() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.set_config_value("task.gitlab.url", "gitlab.kitware.com").unwrap();
+
+        let remotes = vec![
+            "https://github.com/jhspetersson/fselect.git".to_string(),
+            "https://gitlab.kitware.com/utils/rust-gitlab.git".to_string(),
+        ];
+
+        let matched = describe_matching_remotes(&context, remotes.clone(), &Some("github".to_string()));
+        assert_eq!(matched, vec![MatchedRemote {
+            connector_type: "github".to_string(),
+            user: "jhspetersson".to_string(),
+            repo: "fselect".to_string(),
+            url: remotes[0].clone(),
+        }]);
+
+        let matched = describe_matching_remotes(&context, remotes.clone(), &None);
+        let github = matched.iter().filter(|m| m.connector_type == "github").collect::<Vec<_>>();
+        assert_eq!(github.len(), 1);
+        assert_eq!(github[0].url, remotes[0]);
+        let gitlab = matched.iter().filter(|m| m.connector_type == "gitlab").collect::<Vec<_>>();
+        assert_eq!(gitlab.len(), 1);
+        assert_eq!(gitlab[0].url, remotes[1]);
+        assert_eq!((gitlab[0].user.as_str(), gitlab[0].repo.as_str()), ("utils", "rust-gitlab"));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_comment_marker_create_new() {
+        let comment = Comment::new("3".to_string(), HashMap::new(), "Fixed in 1.2".to_string());
+        let other_clone = Comment::new("3".to_string(), HashMap::new(), "Another one".to_string());
+        let remote_comments = vec![
+            ("2001".to_string(), "Unrelated".to_string()),
+            ("2002".to_string(), with_comment_marker(&other_clone)),
+            ("2003".to_string(), "Fixed in 1.2\n\n<!-- git-task:comment:3 -->".to_string()),
+        ];
+
+        assert_eq!(find_marked_comment(&remote_comments, &comment), None);
+        let text = with_comment_marker(&comment);
+        assert_eq!(text, format!("Fixed in 1.2\n\n<!-- git-task:comment:{} -->", comment_key(&comment)));
+        assert_eq!(strip_comment_marker(&text), "Fixed in 1.2");
+
+        let props = |author: &str| HashMap::from([("author".to_string(), author.to_string()), ("created".to_string(), "1700000000".to_string())]);
+        let key = comment_key(&Comment::new("1".to_string(), props("alice"), "Done".to_string()));
+        assert_eq!(key.len(), 12);
+        assert_eq!(comment_key(&Comment::new("7".to_string(), props("alice"), "Done".to_string())), key);
+        assert_ne!(comment_key(&Comment::new("1".to_string(), props("bob"), "Done".to_string())), key);
+    }
+
+    #[test]
+    fn test_comment_marker_skip_duplicate() {
+        let comment = Comment::new("3".to_string(), HashMap::new(), "Fixed in 1.2".to_string());
+        let remote_comments = vec![
+            ("2001".to_string(), "Unrelated".to_string()),
+            ("2002".to_string(), with_comment_marker(&comment)),
+        ];
+
+        assert_eq!(find_marked_comment(&remote_comments, &comment), Some("2002".to_string()));
+        assert_eq!(strip_comment_marker("No marker here"), "No marker here");
+    }
+
+    #[test]
+    fn test_merge_label_descriptions() {
+        let local_labels = vec![
+            Label::new("bug".to_string(), None, Some("Local bug description".to_string())),
+            Label::new("triage".to_string(), None, Some("Needs a first look".to_string())),
+        ];
+        let remote_labels = vec![
+            Label::new("bug".to_string(), Some("f29513".to_string()), Some("Something isn't working".to_string())),
+            Label::new("triage".to_string(), Some("ededed".to_string()), None),
+            Label::new("docs".to_string(), Some("0075ca".to_string()), None),
+        ];
+
+        let labels = merge_label_descriptions(Some(&local_labels), &remote_labels);
+        assert_eq!(labels[0].get_description(), Some("Something isn't working".to_string()));
+        assert_eq!(labels[1].get_description(), Some("Needs a first look".to_string()));
+        assert_eq!(labels[1].get_color(), "ededed");
+        assert_eq!(labels[2].get_description(), None);
+
+        assert_eq!(merge_label_descriptions(None, &remote_labels), remote_labels);
+    }
+
+    #[test]
+    fn test_merge_labels_for_push() {
+        let label = |name: &str| Label::new(name.to_string(), None, None);
+        let names = |labels: Vec<Label>| labels.iter().map(|l| l.get_name()).collect::<Vec<_>>();
+        let local_labels = vec![label("bug"), label("urgent")];
+        let remote_labels = vec![label("bug"), label("docs"), label("wontfix")];
+        let synced_labels = "bug,docs".to_string();
+
+        let labels = merge_labels_for_push(&local_labels, &remote_labels, Some(&synced_labels), false);
+        assert_eq!(names(labels), vec!["bug", "urgent", "wontfix"]);
+
+        let labels = merge_labels_for_push(&local_labels, &remote_labels, None, false);
+        assert_eq!(names(labels), vec!["bug", "urgent", "docs", "wontfix"]);
+
+        let labels = merge_labels_for_push(&local_labels, &remote_labels, Some(&synced_labels), true);
+        assert_eq!(names(labels), vec!["bug", "urgent"]);
+
+        let labels = merge_labels_for_push(&[], &remote_labels, Some(&synced_labels), false);
+        assert_eq!(names(labels), vec!["wontfix"]);
+    }
+
+    #[test]
+    fn test_dedupe_labels() {
+        let label = |name: &str, color: Option<&str>| Label::new(name.to_string(), color.map(|c| c.to_string()), None);
+        let summary = |labels: Vec<Label>| labels.iter().map(|l| (l.get_name(), l.get_color())).collect::<Vec<_>>();
+
+        let github_labels = vec![label("Bug", None), label("docs", Some("0075ca"))];
+        let gitlab_labels = vec![label("bug", Some("d73a4a")), label("DOCS", Some("cccccc")), label("gitlab-only", None)];
+        let mut labels = github_labels.clone();
+        labels.extend(gitlab_labels.clone());
+        assert_eq!(summary(dedupe_labels(labels)), vec![
+            ("Bug".to_string(), "d73a4a".to_string()),
+            ("docs".to_string(), "0075ca".to_string()),
+            ("gitlab-only".to_string(), String::new()),
+        ]);
+
+        let labels = merge_pulled_labels(Some(&github_labels), &[label("BUG", None), label("bug", None)]);
+        assert_eq!(summary(labels), vec![("BUG".to_string(), String::new())]);
+
+        let labels = merge_pulled_labels(Some(&gitlab_labels), &github_labels);
+        assert_eq!(summary(labels), vec![
+            ("Bug".to_string(), "d73a4a".to_string()),
+            ("docs".to_string(), "0075ca".to_string()),
+        ]);
+
+        let synced_labels = "bug".to_string();
+        let labels = merge_labels_for_push(&github_labels, &gitlab_labels, Some(&synced_labels), false);
+        assert_eq!(summary(labels), vec![
+            ("Bug".to_string(), "d73a4a".to_string()),
+            ("docs".to_string(), "0075ca".to_string()),
+            ("gitlab-only".to_string(), String::new()),
+        ]);
+    }
+
+    #[test]
+    fn test_map_remote_statuses() {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let local_statuses = strings(&["OPEN", "IN_PROGRESS", "CLOSED"]);
+
+        let mapping = map_remote_statuses(&strings(&["To Do", "In Progress", "Done"]), &local_statuses);
+        assert_eq!(mapping, vec![
+            ("To Do".to_string(), None),
+            ("In Progress".to_string(), Some("IN_PROGRESS".to_string())),
+            ("Done".to_string(), None),
+        ]);
+
+        let mapping = map_remote_statuses(&strings(&["open", "closed"]), &local_statuses);
+        assert!(mapping.iter().all(|(_, local)| local.is_some()));
+    }
+
+    #[test]
+    fn test_record_synced_labels() {
+        let mut task = Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_labels(vec![Label::new("bug".to_string(), None, None), Label::new("docs".to_string(), None, None)]);
+        record_synced_labels(&mut task);
+        assert_eq!(task.get_property(SYNCED_LABELS).unwrap(), "bug,docs");
+
+        task.set_labels(vec![]);
+        record_synced_labels(&mut task);
+        assert!(task.get_property(SYNCED_LABELS).is_none());
+    }
+
+    #[test]
+    fn test_get_remote_task_state_custom_statuses() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let mut status_manager = StatusManager::new(&context);
+        status_manager.add_status("TRIAGE".to_string(), "t".to_string(), "Blue".to_string(), false).unwrap();
+        status_manager.add_status("DECLINED".to_string(), "d".to_string(), "Magenta".to_string(), false).unwrap();
+        status_manager.add_status("BLOCKED".to_string(), "b".to_string(), "Purple".to_string(), false).unwrap();
+        context.set_config_value("task.status.openlike", "TRIAGE").unwrap();
+        context.set_config_value("task.status.closedlike", "DECLINED, WONTFIX").unwrap();
+        let status_manager = StatusManager::new(&context);
+
+        assert_eq!(get_remote_task_state(&status_manager, "TRIAGE", "CLOSED", None), RemoteTaskState::Open("TRIAGE".to_string(), "CLOSED".to_string()));
+        assert_eq!(
+            get_remote_task_state(&status_manager, "DECLINED", "OPEN", Some("not_planned".to_string())),
+            RemoteTaskState::Closed("DECLINED".to_string(), "OPEN".to_string(), Some("not_planned".to_string()))
+        );
+        assert_eq!(get_remote_task_state(&status_manager, "CLOSED", "OPEN", None), RemoteTaskState::Closed("CLOSED".to_string(), "OPEN".to_string(), None));
+        assert_eq!(get_remote_task_state(&status_manager, "BLOCKED", "OPEN", None), RemoteTaskState::Open("BLOCKED".to_string(), "OPEN".to_string()));
+        assert_eq!(status_manager.is_closed_like("BLOCKED"), None);
+        assert_eq!(status_manager.is_closed_like("OPEN"), Some(false));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_token_precedence() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (key, url) = ("task.tokentest.token", "https://tokens.example.com");
+        let get = || get_token(&context, key, "tokentest", &["TOKENTEST_LEGACY_TOKEN"], url);
+
+        context.set_config_value("credential.https://tokens.example.com.helper", "!f() { echo username=git; echo password=from-helper; }; f").unwrap();
+        assert_eq!(get().unwrap(), "from-helper");
+
+        unsafe { std::env::set_var("TOKENTEST_LEGACY_TOKEN", "from-legacy") };
+        assert_eq!(get().unwrap(), "from-legacy");
+
+        context.set_config_value(key, "").unwrap();
+        unsafe { std::env::set_var("GIT_TASK_TOKENTEST_TOKEN", "from-env") };
+        assert_eq!(get().unwrap(), "from-env");
+
+        context.set_config_value(key, "from-config").unwrap();
+        assert_eq!(get().unwrap(), "from-config");
+
+        unsafe {
+            std::env::remove_var("GIT_TASK_TOKENTEST_TOKEN");
+            std::env::remove_var("TOKENTEST_LEGACY_TOKEN");
+        }
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_fit_to_limit() {
+        assert_eq!(fit_to_limit("short", Some(10), false, "Description").unwrap(), "short");
+        assert_eq!(fit_to_limit("long description", None, false, "Description").unwrap(), "long description");
+        assert_eq!(fit_to_limit("long description", Some(10), false, "Description").unwrap_err(),
+            "Description is 16 characters long, the remote limit is 10. Shorten it or set task.push.truncate to true");
+
+        let truncated = fit_to_limit("long description", Some(10), true, "Description").unwrap();
+        assert_eq!(truncated, "long desc…");
+        assert_eq!(truncated.chars().count(), 10);
+    }
+
+    #[test]
+    fn test_fit_task_for_push() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let connector = GithubRemoteConnector::new(&context);
+
+        let mut task = Task::new("Task".to_string(), "x".repeat(70000), "OPEN".to_string(), None).unwrap();
+        assert!(fit_task_for_push(&context, &connector, &task).err().unwrap().starts_with("Description is 70000 characters long, the remote limit is 65536"));
+
+        context.set_config_value("task.push.truncate", "true").unwrap();
+        let fitted = fit_task_for_push(&context, &connector, &task).unwrap();
+        assert_eq!(fitted.get_property("description").unwrap().chars().count(), 65536);
+        assert!(fitted.get_property("description").unwrap().ends_with("…"));
+
+        context.set_config_value("task.github.maxlength", "100").unwrap();
+        context.set_config_value("task.push.truncate", "false").unwrap();
+        task.set_property("description", "Short");
+        let comment = task.add_comment(Some("7".to_string()), HashMap::new(), "y".repeat(101), None);
+        let comment_limit = 100 - comment_marker_length();
+        assert_eq!(fit_task_for_push(&context, &connector, &task).err().unwrap(),
+            format!("Comment 7 is 101 characters long, the remote limit is {comment_limit}. Shorten it or set task.push.truncate to true"));
+        assert!(fit_comment_for_push(&context, &connector, &comment).is_err());
+
+        context.set_config_value("task.push.truncate", "true").unwrap();
+        let fitted = fit_comment_for_push(&context, &connector, &comment).unwrap();
+        assert_eq!(with_comment_marker(&fitted).chars().count(), 100);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_connector_capabilities() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for connector in connectors(&context) {
+            let capabilities = connector.capabilities();
+            assert!(!capabilities.milestones, "{}", connector.type_name());
+            assert!(!capabilities.reactions, "{}", connector.type_name());
+            assert_eq!(capabilities.attachments, connector.type_name() == "gitlab", "{}", connector.type_name());
+            assert_eq!(capabilities.comment_visibility, connector.type_name() == "jira", "{}", connector.type_name());
+            match connector.type_name() {
+                "redmine" => {
+                    assert_eq!(capabilities, ConnectorCapabilities::default());
+                    assert_eq!(unsupported_message(connector.as_ref(), "labels"), "ERROR: redmine connector does not support labels");
+                    let error = connector.upload_attachment(&String::new(), &String::new(), "log.txt", b"log").err().unwrap();
+                    assert_eq!(error, "redmine connector does not support attachments");
+                },
+                _ => {
+                    assert!(capabilities.fetch_tasks && capabilities.push_tasks && capabilities.delete_tasks, "{}", connector.type_name());
+                    assert!(capabilities.comments && capabilities.labels, "{}", connector.type_name());
+                },
+            }
+        }
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_upload_pending_attachments() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let file = repo_dir.join("trace.log");
+        std::fs::write(&file, "panicked at src/main.rs").unwrap();
+        let file = file.display().to_string();
+
+        let task = Task::new("Crash".to_string(), "Crashes on start".to_string(), "OPEN".to_string(), None).unwrap();
+        let task = context.create_task(task).unwrap();
+        context.set_pending_attachments("1", &[file.clone()]).unwrap();
+        assert_eq!(context.get_pending_attachments("1"), vec![file.clone()]);
+        assert!(context.get_pending_attachments("2").is_empty());
+        assert!(context.find_task("1").unwrap().unwrap().get_all_properties().values().all(|value| !value.contains("trace.log")));
+
+        let transport = Arc::new(ReplayTransport::new(vec![]));
+        context.set_config_value("task.github.url", "https://github.example.com").unwrap();
+        let github = GithubRemoteConnector::with_transport(&context, transport.clone());
+        let unchanged = upload_pending_attachments(&context, &github, &user, &repo, task.clone()).unwrap();
+        assert_eq!(unchanged.get_property("description").unwrap(), "Crashes on start");
+        assert_eq!(context.get_pending_attachments("1"), vec![file]);
+        assert!(transport.requests().is_empty());
+        assert_eq!(context.take_warnings(), vec!["github connector does not support attachments, 1 file(s) attached to task ID 1 stay pending".to_string()]);
+
+        let transport = Arc::new(ReplayTransport::new(vec![
+            Exchange::new("/api/v4/projects/jhspetersson%2Fgit-task/uploads", 201, r#"{"alt": "trace", "url": "/uploads/66dbcd21/trace.log", "full_path": "/-/project/42/uploads/66dbcd21/trace.log"}"#),
+        ]));
+        context.set_config_value("task.gitlab.url", "https://gitlab.example.com").unwrap();
+        context.set_config_value("task.gitlab.token", "replayed").unwrap();
+        let gitlab = GitlabRemoteConnector::with_transport(&context, transport);
+        let uploaded = upload_pending_attachments(&context, &gitlab, &user, &repo, task).unwrap();
+        let description = "Crashes on start\n\n[trace.log](https://gitlab.example.com/-/project/42/uploads/66dbcd21/trace.log)";
+        assert_eq!(uploaded.get_property("description").unwrap(), description);
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property("description").unwrap(), description);
+        assert!(context.get_pending_attachments("1").is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_priority_labels() {
+        assert_eq!(normalize_priority(" High "), Some("high"));
+        assert_eq!(normalize_priority("urgent"), None);
+        assert_eq!(priority_from_labels(["bug", "Priority::Critical"]), Some("critical"));
+        assert_eq!(priority_from_labels(["bug", "priority::someday"]), None);
+
+        let labels = vec![
+            Label::new("bug".to_string(), Some("f29513".to_string()), None),
+            Label::new("priority::low".to_string(), Some("00ff00".to_string()), None),
+        ];
+        let mut task = Task::new("Prioritized".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let names = |labels: Vec<Label>| labels.iter().map(|label| label.get_name()).collect::<Vec<_>>();
+        assert_eq!(names(with_priority_label(&labels, &task)), vec!["bug", "priority::low"]);
+
+        task.set_property(PRIORITY, "HIGH");
+        let pushed = with_priority_label(&labels, &task);
+        assert_eq!(pushed[1].get_color(), "LightRed");
+        let pushed = names(pushed);
+        assert_eq!(pushed, vec!["bug", "priority::high"]);
+        assert_eq!(priority_from_labels(pushed.iter().map(String::as_str)), Some("high"));
+
+        task.set_property(PRIORITY, "low");
+        assert_eq!(with_priority_label(&labels, &task)[1].get_color(), "00ff00");
+    }
+
+    #[test]
+    fn test_fetch_concurrently() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        assert_eq!(get_concurrency(&context, "github"), DEFAULT_CONCURRENCY);
+        context.set_config_value("task.github.concurrency", "2").unwrap();
+        assert_eq!(get_concurrency(&context, "github"), 2);
+        context.set_config_value("task.github.concurrency", "0").unwrap();
+        assert_eq!(get_concurrency(&context, "github"), DEFAULT_CONCURRENCY);
+        let options = get_config_options_from_connectors(&context);
+        assert!(options.contains(&"task.github.concurrency".to_string()));
+        assert!(!options.iter().any(|option| option.ends_with(".concurrency") && option != "task.github.concurrency"));
+
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let request = |n: usize| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                for _ in 0..n % 3 + 1 {
+                    tokio::task::yield_now().await;
+                }
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                n * 10
+            }
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        for limit in [1, 2, 3] {
+            max_in_flight.store(0, Ordering::SeqCst);
+            let progress = RefCell::new(vec![]);
+            let report = |current: usize, total: usize| progress.borrow_mut().push((current, total));
+            let results = runtime.block_on(fetch_concurrently(futures_util::stream::iter(1..=7), 9, limit, Some(&report), request));
+            assert_eq!(results, vec![10, 20, 30, 40, 50, 60, 70]);
+            assert_eq!(max_in_flight.load(Ordering::SeqCst), limit);
+            assert_eq!(progress.borrow().first(), Some(&(1, 9)));
+            assert_eq!(progress.borrow().last(), Some(&(7, 7)));
+        }
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+}
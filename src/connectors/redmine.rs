@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
-use redmine_api::api::issues::{Issue, ListIssues};
+use redmine_api::api::enumerations::IssuePriorityEssentials;
+use redmine_api::api::issue_statuses::{IssueStatus, IssueStatusesWrapper, ListIssueStatuses};
+use redmine_api::api::issues::{ComparableFilter, Issue, ListIssues};
 use redmine_api::api::Redmine;
 
 use gittask::{Task, Comment, Label, TaskContext};
 
-use crate::connectors::{RemoteConnector, RemoteTaskState};
+use crate::connectors::{get_http_timeout, get_token, http_error, normalize_priority, report_progress, ConnectorCapabilities, RemoteConnector, RemoteTaskState, PRIORITY};
 
 pub struct RedmineRemoteConnector {
     context: TaskContext,
@@ -25,15 +27,31 @@ impl RemoteConnector for RedmineRemoteConnector {
     fn get_config_options(&self) -> Option<Vec<String>> {
         Some(vec![
             "task.redmine.url".to_string(),
-            "task.redmine.api_key".to_string(),
+            "task.redmine.token".to_string(),
         ])
     }
 
+    fn capabilities(&self) -> ConnectorCapabilities {
+        ConnectorCapabilities::default()
+    }
+
     #[allow(unused)]
     fn supports_remote(&self, url: &str) -> Option<(String, String)> {
         Some((String::new(), String::new()))
     }
 
+    fn task_url(&self, domain: &String, _project: &String, task_id: &String) -> String {
+        let url = get_base_url(&self.context, domain).unwrap_or_else(|_| format!("https://{domain}"));
+        format!("{}/issues/{task_id}", url.trim_end_matches('/'))
+    }
+
+    fn list_remote_statuses(&self, domain: &String, _project: &String) -> Result<Vec<String>, String> {
+        let redmine = get_redmine_instance(&self.context, domain)?;
+        let endpoint = ListIssueStatuses::builder().build().map_err(|e| e.to_string())?;
+        let statuses = redmine.json_response_body::<_, IssueStatusesWrapper<IssueStatus>>(&endpoint).map_err(http_error)?;
+        Ok(statuses.issue_statuses.into_iter().map(|status| status.name).collect())
+    }
+
     fn list_remote_tasks(
         &self,
         domain: &String,
@@ -42,17 +60,19 @@ impl RemoteConnector for RedmineRemoteConnector {
         _with_labels: bool,
         _limit: Option<usize>,
         _state: RemoteTaskState,
-        task_statuses: &Vec<String>
+        since: Option<u64>,
+        task_statuses: &Vec<String>,
+        progress: Option<&dyn Fn(usize, usize)>
     ) -> Result<Vec<Task>, String> {
         let redmine = get_redmine_instance(&self.context, domain)?;
-        let endpoint = ListIssues::builder().build().map_err(|e| e.to_string())?;
-        let issues = redmine.json_response_body_all_pages::<_, Issue>(&endpoint).map_err(|e| e.to_string())?;
-        let mut tasks = Vec::new();
-        for issue in issues {
-            let task = issue_to_task(&issue, task_statuses)?;
-            tasks.push(task);
+        let mut endpoint = ListIssues::builder();
+        if let Some(since) = since {
+            let since = time::OffsetDateTime::from_unix_timestamp(since as i64).map_err(|e| e.to_string())?;
+            endpoint.updated_on(ComparableFilter::GreaterThanOrEqual(since));
         }
-        Ok(tasks)
+        let endpoint = endpoint.build().map_err(|e| e.to_string())?;
+        let issues = redmine.json_response_body_all_pages::<_, Issue>(&endpoint).map_err(http_error)?;
+        issues_to_tasks(&issues, task_statuses, progress)
     }
 
     #[allow(unused)]
@@ -64,7 +84,7 @@ impl RemoteConnector for RedmineRemoteConnector {
         with_comments: bool,
         with_labels: bool,
         task_statuses: &Vec<String>
-    ) -> Result<Task, String> {
+    ) -> Result<Option<Task>, String> {
         let redmine = get_redmine_instance(&self.context, domain)?;
 
         todo!()
@@ -106,7 +126,7 @@ impl RemoteConnector for RedmineRemoteConnector {
     }
 
     #[allow(unused)]
-    fn update_remote_comment(&self, domain: &String, project: &String, task_id: &String, comment_id: &String, text: &String) -> Result<(), String> {
+    fn update_remote_comment(&self, domain: &String, project: &String, task_id: &String, comment_id: &String, comment: &Comment) -> Result<(), String> {
         let redmine = get_redmine_instance(&self.context, domain)?;
 
         todo!()
@@ -135,10 +155,13 @@ impl RemoteConnector for RedmineRemoteConnector {
 }
 
 fn get_redmine_instance(context: &TaskContext, domain: &String) -> Result<Redmine, String> {
-    let client = redmine_api::reqwest::blocking::Client::builder().use_rustls_tls().build()
+    let client = redmine_api::reqwest::blocking::Client::builder()
+        .use_rustls_tls()
+        .timeout(get_http_timeout(context))
+        .build()
         .map_err(|e| e.to_string())?;
     let url = get_base_url(&context, domain)?;
-    let api_key = get_api_key(&context)?;
+    let api_key = get_api_key(context, &url)?;
     Redmine::new(client, url.parse().unwrap(), &*api_key).map_err(|e| e.to_string())
 }
 
@@ -152,13 +175,19 @@ fn get_base_url(context: &TaskContext, domain: &String) -> Result<String, String
     }
 }
 
-fn get_api_key(context: &TaskContext) -> Result<String, String> {
-    match context.get_config_value("task.redmine.api_key") {
-        Ok(key) => Ok(key),
-        _ => std::env::var("REDMINE_API_KEY")
-            .or_else(|_| std::env::var("REDMINE_TOKEN"))
-            .map_err(|_| "No Redmine API key found. Set task.redmine.api_key config or REDMINE_API_KEY environment variable.".to_string())
+fn get_api_key(context: &TaskContext, url: &str) -> Result<String, String> {
+    get_token(context, "task.redmine.token", "redmine", &["REDMINE_API_KEY", "REDMINE_TOKEN"], url)
+        .ok_or_else(|| "No Redmine API key found. Set task.redmine.token config or REDMINE_API_KEY environment variable.".to_string())
+}
+
+fn issues_to_tasks(issues: &[Issue], task_statuses: &Vec<String>, progress: Option<&dyn Fn(usize, usize)>) -> Result<Vec<Task>, String> {
+    let mut tasks = Vec::new();
+    for (i, issue) in issues.iter().enumerate() {
+        let task = issue_to_task(issue, task_statuses)?;
+        tasks.push(task);
+        report_progress(progress, i + 1, issues.len());
     }
+    Ok(tasks)
 }
 
 fn issue_to_task(issue: &Issue, task_statuses: &Vec<String>) -> Result<Task, String> {
@@ -181,5 +210,103 @@ fn issue_to_task(issue: &Issue, task_statuses: &Vec<String>) -> Result<Task, Str
     let author = &issue.author;
     props.insert("author".to_string(), author.name.clone());
     
+    if let Some(priority) = parse_priority(&issue.priority) {
+        props.insert(PRIORITY.to_string(), priority.to_string());
+    }
+
     Task::from_properties(issue.id.to_string(), props).map_err(|e| e.to_string())
-}
\ No newline at end of file
+}
+
+/// Maps the default Redmine priorities (Low, Normal, High, Urgent, Immediate) by id,
+/// custom ones by name.
+fn parse_priority(priority: &IssuePriorityEssentials) -> Option<&'static str> {
+    match priority.id {
+        1 => Some("low"),
+        2 => Some("medium"),
+        3 => Some("high"),
+        4 | 5 => Some("critical"),
+        _ => normalize_priority(&priority.name),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::env::temp_dir;
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use crate::connectors::replay::{Exchange, ReplayServer};
+    use super::*;
+
+    #[test]
+    fn test_issues_to_tasks_progress() {
+        let issues: Vec<Issue> = serde_json::from_str(include_str!("../../resources/test/redmine_issues.json")).unwrap();
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let calls = RefCell::new(vec![]);
+        let progress = |current: usize, total: usize| calls.borrow_mut().push((current, total));
+
+        let tasks = issues_to_tasks(&issues, &task_statuses, Some(&progress)).unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[2].get_property("status").unwrap(), "CLOSED");
+        assert_eq!(tasks[0].get_property(PRIORITY).unwrap(), "medium");
+        assert_eq!(tasks[2].get_property(PRIORITY).unwrap(), "critical");
+        assert_eq!(calls.into_inner(), vec![(1, 3), (2, 3), (3, 3)]);
+
+        let tasks = issues_to_tasks(&issues, &task_statuses, None).unwrap();
+        assert_eq!(tasks.len(), 3);
+    }
+
+    #[test]
+    fn test_task_url() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let connector = RedmineRemoteConnector::new(&context);
+        let (domain, project, id) = ("redmine.example.com".to_string(), String::new(), "1024".to_string());
+        assert_eq!(connector.task_url(&domain, &project, &id), "https://redmine.example.com/issues/1024");
+
+        context.set_config_value("task.redmine.url", "https://tracker.example.com/").unwrap();
+        assert_eq!(connector.task_url(&domain, &project, &id), "https://tracker.example.com/issues/1024");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_remote_tasks_replay() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let issues = include_str!("../../resources/test/redmine_issues.json");
+        let server = ReplayServer::start(vec![
+            Exchange::new("/issues.json", 200, &format!(r#"{{"issues": {issues}, "total_count": 3, "offset": 0, "limit": 100}}"#)),
+            Exchange::new("/issue_statuses.json", 200, r#"{"issue_statuses": [{"id": 1, "name": "New", "is_closed": false}, {"id": 5, "name": "Closed", "is_closed": true}]}"#),
+        ]);
+        context.set_config_value("task.redmine.url", server.url()).unwrap();
+        context.set_config_value("task.redmine.token", "replayed").unwrap();
+
+        let connector = RedmineRemoteConnector::new(&context);
+        let task_statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        let (domain, project) = ("redmine.example.com".to_string(), String::new());
+        let tasks = connector.list_remote_tasks(&domain, &project, false, false, None, RemoteTaskState::All, None, &task_statuses, None).unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[2].get_property("status").unwrap(), "CLOSED");
+        assert!(server.requests()[0].starts_with("GET /issues.json"));
+
+        assert_eq!(connector.list_remote_statuses(&domain, &project).unwrap(), vec!["New", "Closed"]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        let priority = |id: u64, name: &str| IssuePriorityEssentials { id, name: name.to_string() };
+        assert_eq!(parse_priority(&priority(1, "Low")), Some("low"));
+        assert_eq!(parse_priority(&priority(5, "Immediate")), Some("critical"));
+        assert_eq!(parse_priority(&priority(7, "High")), Some("high"));
+        assert_eq!(parse_priority(&priority(8, "Someday")), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Recorded responses for connector tests, served in process to connectors that take an
+//! injected transport, or by a local HTTP server the others are pointed at through their URL settings.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use http::{Request, Response};
+
+use crate::connectors::transport::HttpTransport;
+
+const URL_PLACEHOLDER: &str = "{url}";
+
+/// Recorded response served once to the first request whose target starts with `path`.
+/// `{url}` in headers and body is replaced with the server URL, e.g. for `Link` headers.
+#[derive(Clone)]
+pub(crate) struct Exchange {
+    path: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Exchange {
+    pub(crate) fn new(path: &str, status: u16, body: &str) -> Exchange {
+        Exchange {
+            path: path.to_string(),
+            status,
+            headers: vec![],
+            body: body.to_string(),
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &str, value: &str) -> Exchange {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Transport answering requests from recorded exchanges, `{url}` is replaced with the scheme and host of the request.
+pub(crate) struct ReplayTransport {
+    exchanges: Mutex<Vec<Exchange>>,
+    requests: Mutex<Vec<(String, String)>>,
+}
+
+impl ReplayTransport {
+    pub(crate) fn new(exchanges: Vec<Exchange>) -> ReplayTransport {
+        ReplayTransport { exchanges: Mutex::new(exchanges), requests: Mutex::new(vec![]) }
+    }
+
+    /// Method and target of every request sent so far, e.g. `GET /repos/user/repo/issues?page=2`.
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().iter().map(|(request, _)| request.clone()).collect()
+    }
+
+    /// Body of every request sent so far, in the same order as `requests()`.
+    pub(crate) fn bodies(&self) -> Vec<String> {
+        self.requests.lock().unwrap().iter().map(|(_, body)| body.clone()).collect()
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>, String> {
+        let uri = request.uri();
+        let target = uri.path_and_query().map_or("/", |target| target.as_str());
+        let url = format!("{}://{}", uri.scheme_str().unwrap_or("http"), uri.authority().map_or("", |authority| authority.as_str()));
+        self.requests.lock().unwrap().push((format!("{} {target}", request.method()), String::from_utf8_lossy(request.body()).to_string()));
+
+        let exchange = take_exchange(&mut self.exchanges.lock().unwrap(), target);
+        let mut response = Response::builder()
+            .status(exchange.status)
+            .header("Content-Type", "application/json");
+        for (name, value) in &exchange.headers {
+            response = response.header(name, value.replace(URL_PLACEHOLDER, &url));
+        }
+        response.body(exchange.body.replace(URL_PLACEHOLDER, &url).into_bytes()).map_err(|e| e.to_string())
+    }
+}
+
+pub(crate) struct ReplayServer {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ReplayServer {
+    pub(crate) fn start(exchanges: Vec<Exchange>) -> ReplayServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let url = url.clone();
+            let requests = requests.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || {
+                let mut exchanges = exchanges;
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        serve(stream, &url, &mut exchanges, &requests);
+                    }
+                }
+            })
+        };
+
+        ReplayServer { url, requests, stopped, handle: Some(handle) }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Method and target of every request received so far, e.g. `GET /repos/user/repo/issues?page=2`.
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://"));
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Listener that takes connections but never answers them, for timeout tests; keep it alive
+/// for as long as its URL is used.
+pub(crate) fn silent_listener() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    (listener, url)
+}
+
+fn serve(stream: TcpStream, url: &str, exchanges: &mut Vec<Exchange>, requests: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() || request_line.is_empty() {
+        return;
+    }
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') && name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    let _ = reader.read_exact(&mut body);
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    requests.lock().unwrap().push(format!("{method} {target}"));
+
+    let exchange = take_exchange(exchanges, target);
+    let body = exchange.body.replace(URL_PLACEHOLDER, url);
+    let mut response = format!("HTTP/1.1 {} Replayed\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n", exchange.status, body.len());
+    for (name, value) in &exchange.headers {
+        response.push_str(&format!("{name}: {}\r\n", value.replace(URL_PLACEHOLDER, url)));
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+
+    let mut stream = reader.into_inner();
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
+
+fn take_exchange(exchanges: &mut Vec<Exchange>, target: &str) -> Exchange {
+    match exchanges.iter().position(|exchange| target.starts_with(&exchange.path)) {
+        Some(pos) => exchanges.remove(pos),
+        None => Exchange::new(target, 404, r#"{"message": "Not Found"}"#),
+    }
+}
@@ -0,0 +1,169 @@
+//! HTTP transport the REST connectors send their requests through, the default one is a
+//! blocking reqwest client, tests inject recorded responses instead.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, LazyLock, Mutex};
+use std::time::Duration;
+
+use http::{header, Request, Response, StatusCode};
+
+use gittask::TaskContext;
+use crate::connectors::{get_concurrency, get_http_timeout, http_error};
+
+const MAX_RETRIES: usize = 3;
+const MAX_RETRY_DELAY: u64 = 60;
+
+/// Requests in flight per host, shared by every transport so the cap holds across connector instances.
+static IN_FLIGHT: LazyLock<(Mutex<HashMap<String, usize>>, Condvar)> = LazyLock::new(|| (Mutex::new(HashMap::new()), Condvar::new()));
+
+pub(crate) trait HttpTransport: Send + Sync {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>, String>;
+}
+
+pub(crate) struct ReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+impl ReqwestTransport {
+    pub(crate) fn new(timeout: Duration) -> Result<ReqwestTransport, String> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(http_error)?;
+        Ok(ReqwestTransport { client })
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>, String> {
+        let response = self.client.execute(request.try_into().map_err(http_error)?).map_err(http_error)?;
+        let mut builder = Response::builder()
+            .status(response.status())
+            .version(response.version());
+        for (name, value) in response.headers() {
+            builder = builder.header(name, value);
+        }
+        let body = response.bytes().map_err(http_error)?;
+        builder.body(body.to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+/// Blocks a request until fewer than `limit` requests to its host are in flight, pages and
+/// per-issue fetches included.
+pub(crate) struct LimitedTransport {
+    inner: Arc<dyn HttpTransport>,
+    limit: usize,
+}
+
+impl HttpTransport for LimitedTransport {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>, String> {
+        let host = request.uri().authority().map_or(String::new(), |authority| authority.to_string());
+        let _permit = HostPermit::acquire(host, self.limit);
+        self.inner.send(request)
+    }
+}
+
+struct HostPermit {
+    host: String,
+}
+
+impl HostPermit {
+    fn acquire(host: String, limit: usize) -> HostPermit {
+        let (in_flight, released) = &*IN_FLIGHT;
+        let mut in_flight = in_flight.lock().unwrap();
+        while in_flight.get(&host).is_some_and(|count| *count >= limit) {
+            in_flight = released.wait(in_flight).unwrap();
+        }
+        *in_flight.entry(host.clone()).or_default() += 1;
+        HostPermit { host }
+    }
+}
+
+impl Drop for HostPermit {
+    fn drop(&mut self) {
+        let (in_flight, released) = &*IN_FLIGHT;
+        if let Some(count) = in_flight.lock().unwrap().get_mut(&self.host) {
+            *count -= 1;
+        }
+        released.notify_all();
+    }
+}
+
+/// Resends requests the remote throttled with 429 or 503 after the delay its `Retry-After` header asks for.
+pub(crate) struct RetryTransport {
+    inner: Arc<dyn HttpTransport>,
+}
+
+impl HttpTransport for RetryTransport {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>, String> {
+        let mut retries = 0;
+        loop {
+            let response = self.inner.send(copy_request(&request))?;
+            match retry_delay(&response) {
+                Some(delay) if retries < MAX_RETRIES => {
+                    std::thread::sleep(delay);
+                    retries += 1;
+                },
+                _ => return Ok(response),
+            }
+        }
+    }
+}
+
+/// Transport for a connector, `transport` is the injected one if any. Requests are capped
+/// by `task.<connector>.concurrency` and retried when throttled.
+pub(crate) fn connector_transport(context: &TaskContext, connector_type: &str, transport: &Option<Arc<dyn HttpTransport>>) -> Result<Arc<dyn HttpTransport>, String> {
+    let inner: Arc<dyn HttpTransport> = match transport {
+        Some(transport) => transport.clone(),
+        None => Arc::new(ReqwestTransport::new(get_http_timeout(context))?),
+    };
+    let limited = LimitedTransport { inner, limit: get_concurrency(context, connector_type) };
+    Ok(Arc::new(RetryTransport { inner: Arc::new(limited) }))
+}
+
+fn copy_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+    let mut copy = Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+    copy
+}
+
+/// Delay requested by a throttled response, either in seconds or as an HTTP date.
+fn retry_delay(response: &Response<Vec<u8>>) -> Option<Duration> {
+    if !matches!(response.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE) {
+        return None;
+    }
+    let value = response.headers().get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    let seconds = match value.parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => {
+            let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (at.timestamp() - chrono::Utc::now().timestamp()).max(0) as u64
+        },
+    };
+    Some(Duration::from_secs(seconds.min(MAX_RETRY_DELAY)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let response = |status: u16, retry_after: Option<&str>| {
+            let mut builder = Response::builder().status(status);
+            if let Some(retry_after) = retry_after {
+                builder = builder.header(header::RETRY_AFTER, retry_after);
+            }
+            builder.body(vec![]).unwrap()
+        };
+
+        assert_eq!(retry_delay(&response(429, Some("2"))), Some(Duration::from_secs(2)));
+        assert_eq!(retry_delay(&response(503, Some("3600"))), Some(Duration::from_secs(MAX_RETRY_DELAY)));
+        assert_eq!(retry_delay(&response(429, Some("Wed, 21 Oct 2015 07:28:00 GMT"))), Some(Duration::ZERO));
+        assert_eq!(retry_delay(&response(429, None)), None);
+        assert_eq!(retry_delay(&response(200, Some("2"))), None);
+    }
+}
@@ -1,29 +1,66 @@
 use git2::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeSeq;
 use serde_json;
 use std::borrow::ToOwned;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
 use std::ops::Deref;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 const NAME: &'static str = "name";
 const DESCRIPTION: &'static str = "description";
 const STATUS: &'static str = "status";
 const CREATED: &'static str = "created";
+const DELETED: &str = "deleted";
+const UPDATED: &str = "updated";
+
+const COMMENTS_PREFIX: &str = "comments-";
+const RENUMBER_MESSAGE: &str = "Renumber tasks";
+const RENAME_SEPARATOR: &str = " -> ";
+const SHARD_PREFIX: &str = "shard-";
+const TASKS_REF_PREFIX: &str = "refs/tasks/";
+const SNAPSHOTS_REF_PREFIX: &str = "refs/tasks-snapshots/";
+const DEPENDS_ON: &str = "depends_on";
+const LABEL_SCOPE_SEPARATOR: &str = "::";
+const MERGED_INTO: &str = "merged_into";
+const TIME_PROPERTIES: [&str; 3] = [CREATED, UPDATED, "due"];
+const VOLATILE_PROPERTIES: [&str; 3] = [CREATED, UPDATED, "synced_labels"];
+const REFERENCE_PROPERTIES: [&str; 2] = ["parent", DEPENDS_ON];
+
+const COMMIT_ATTEMPTS: usize = 5;
+const LOCK_FILE: &str = "task.lock";
+const DEFAULT_LOCK_TIMEOUT: u64 = 10;
+const LOCK_STALE_AFTER: u64 = 600;
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Task {
     id: Option<String>,
+    #[serde(serialize_with = "serialize_sorted")]
     props: HashMap<String, String>,
     comments: Option<Vec<Comment>>,
     labels: Option<Vec<Label>>,
+    /// Fields unknown to this version, kept so that saving the task doesn't drop them.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Comment {
     id: Option<String>,
+    #[serde(serialize_with = "serialize_sorted")]
     props: HashMap<String, String>,
     text: String,
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -33,32 +70,176 @@ pub struct Label {
     description: Option<String>,
 }
 
+#[derive(Clone, Deserialize)]
+pub struct TaskSummary {
+    id: Option<String>,
+    props: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TaskFilter {
+    pub ids: Option<Vec<String>>,
+    pub statuses: Option<Vec<String>>,
+    pub keyword: Option<String>,
+    pub author: Option<String>,
+    pub label: Option<String>,
+    pub created_from: Option<u64>,
+    pub created_until: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TaskState {
+    Open,
+    Closed,
+    All,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchMode {
+    /// Any invalid task fails the whole batch.
+    Atomic,
+    /// Invalid tasks are skipped and reported, the rest is committed.
+    BestEffort,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchReport {
+    pub updated: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+#[derive(Clone)]
+pub enum FindResult {
+    Unique(Task),
+    Ambiguous(Vec<String>),
+    NotFound,
+}
+
+thread_local! {
+    /// Lock files taken by this thread, nested writes from the same thread reuse them.
+    static HELD_LOCKS: RefCell<HashMap<PathBuf, usize>> = RefCell::new(HashMap::new());
+}
+
+/// Held for the duration of a write to the tasks ref, the lock file is removed when the outermost holder drops it.
+pub struct TaskLock {
+    path: PathBuf,
+}
+
+impl TaskLock {
+    fn hold(path: PathBuf) -> Self {
+        HELD_LOCKS.with_borrow_mut(|held| *held.entry(path.clone()).or_default() += 1);
+        TaskLock { path }
+    }
+}
+
+impl Drop for TaskLock {
+    fn drop(&mut self) {
+        let released = HELD_LOCKS.with_borrow_mut(|held| {
+            let depth = held.entry(self.path.clone()).or_default();
+            *depth = depth.saturating_sub(1);
+            *depth == 0 && held.remove(&self.path).is_some()
+        });
+        if released {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct TaskContext {
     repository_path: String,
+    namespace: Option<String>,
+    /// Non-fatal problems like failed non-strict hooks, shared between clones.
+    warnings: Arc<Mutex<Vec<String>>>,
+}
+
+type Change = (u64, String, TaskAction, String, Oid);
+/// Number of changes before a renumbering commit and its old to new IDs.
+type Renumber = (usize, HashMap<String, String>);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TaskAction {
+    Create,
+    Update,
+    UpdateStatus,
+    Delete,
+    Deleted,
+    Restore,
+    Touched,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskBundle {
+    task: Task,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history: Option<Vec<TaskBundleEntry>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskBundleEntry {
+    timestamp: u64,
+    author: String,
+    action: TaskAction,
+}
+
+#[derive(Deserialize)]
+struct GithubExportIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    user: Option<GithubExportUser>,
+    created_at: Option<String>,
+    #[serde(default)]
+    labels: Vec<GithubExportLabel>,
+    #[serde(default)]
+    comments: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct GithubExportUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GithubExportLabel {
+    name: String,
+    color: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubExportComment {
+    id: u64,
+    body: Option<String>,
+    user: Option<GithubExportUser>,
+    created_at: Option<String>,
 }
 
 impl Task {
     pub fn new(name: String, description: String, status: String, author: Option<String>) -> Result<Task, &'static str> {
-        if !name.is_empty() && !status.is_empty() {
-            Ok(Self::construct_task(name, description, status, author, None))
-        } else {
-            Err("Name or status is empty")
-        }
+        Self::check_name_and_status(&name, &status)?;
+        Ok(Self::construct_task(name, description, status, author, None))
     }
 
     pub fn from_properties(id: String, mut props: HashMap<String, String>) -> Result<Task, &'static str> {
-        let name = props.get(NAME).unwrap_or(&"".to_owned()).to_owned();
-        let status = props.get(STATUS).unwrap_or(&"".to_owned()).to_owned();
+        let name = props.get(NAME).map(String::as_str).unwrap_or_default();
+        let status = props.get(STATUS).map(String::as_str).unwrap_or_default();
+        Self::check_name_and_status(name, status)?;
 
-        if !name.is_empty() && !status.is_empty() {
-            if !props.contains_key("created") {
-                props.insert("created".to_string(), get_current_timestamp().to_string());
-            }
+        if !props.contains_key("created") {
+            props.insert("created".to_string(), get_current_timestamp().to_string());
+        }
 
-            Ok(Task{ id: Some(id), props, comments: None, labels: None })
-        } else {
-            Err("Name or status is empty")
+        Ok(Task{ id: Some(id), props, comments: None, labels: None, extra: BTreeMap::new() })
+    }
+
+    fn check_name_and_status(name: &str, status: &str) -> Result<(), &'static str> {
+        match (name.is_empty(), status.is_empty()) {
+            (true, true) => Err("Name and status are empty"),
+            (true, false) => Err("Name is empty"),
+            (false, true) => Err("Status is empty"),
+            (false, false) => Ok(()),
         }
     }
 
@@ -79,6 +260,7 @@ impl Task {
             props,
             comments: None,
             labels: None,
+            extra: BTreeMap::new(),
         }
     }
 
@@ -122,7 +304,7 @@ impl Task {
             self.comments = Some(vec![]);
         }
 
-        let id = Some(id.unwrap_or_else(|| (self.comments.as_ref().unwrap().len() + 1).to_string()));
+        let id = Some(id.unwrap_or_else(|| next_comment_id(self.comments.as_ref().unwrap()).to_string()));
 
         if !props.contains_key("created") {
             props.insert("created".to_string(), get_current_timestamp().to_string());
@@ -138,6 +320,7 @@ impl Task {
             id,
             props,
             text,
+            extra: BTreeMap::new(),
         };
 
         self.comments.as_mut().unwrap().push(comment.clone());
@@ -165,6 +348,40 @@ impl Task {
         Ok(())
     }
 
+    pub fn is_deleted(&self) -> bool {
+        self.props.get(DELETED).is_some_and(|value| value == "true")
+    }
+
+    pub fn get_updated(&self) -> Option<u64> {
+        self.props.get(UPDATED).and_then(|updated| parse_timestamp(updated))
+    }
+
+    /// Seconds until the `due` timestamp, negative when overdue.
+    pub fn due_in(&self) -> Option<i64> {
+        self.props.get("due")
+            .and_then(|due| parse_timestamp(due))
+            .map(|due| due as i64 - get_current_timestamp() as i64)
+    }
+
+    /// Hex digest of the task content, ignoring the ID, timestamps and sync bookkeeping.
+    pub fn fingerprint(&self) -> String {
+        let props = self.props.iter()
+            .filter(|(name, _)| !VOLATILE_PROPERTIES.contains(&name.as_str()))
+            .collect::<BTreeMap<_, _>>();
+        let mut labels = self.labels.iter().flatten()
+            .map(|label| (&label.name, &label.color, &label.description))
+            .collect::<Vec<_>>();
+        labels.sort();
+        let comments = self.comments.iter().flatten().map(|comment| &comment.text).collect::<Vec<_>>();
+
+        let content = serde_json::to_vec(&(props, labels, comments)).unwrap();
+        Oid::hash_object(ObjectType::Blob, &content).map(|oid| oid.to_string()).unwrap_or_default()
+    }
+
+    fn touch(&mut self) {
+        self.props.insert(UPDATED.to_owned(), get_current_timestamp().to_string());
+    }
+
     pub fn get_labels(&self) -> &Option<Vec<Label>> {
         &self.labels
     }
@@ -185,6 +402,15 @@ impl Task {
         label
     }
 
+    /// Adds a label replacing the other labels of its scope, e.g. `priority::high` replaces `priority::low`.
+    pub fn add_scoped_label(&mut self, name: String, description: Option<String>, color: Option<String>) -> Label {
+        let label = Label::new(name.clone(), color.clone(), description.clone());
+        if let (Some(scope), Some(labels)) = (label.get_scope(), self.labels.as_mut()) {
+            labels.retain(|existing| existing.get_scope().is_none_or(|existing_scope| !existing_scope.eq_ignore_ascii_case(&scope)));
+        }
+        self.add_label(name, description, color)
+    }
+
     pub fn set_labels(&mut self, labels: Vec<Label>) {
         self.labels = Some(labels);
     }
@@ -205,6 +431,20 @@ impl Task {
         Ok(())
     }
 
+    pub fn move_label(&mut self, name: &str, new_index: usize) -> Result<(), String> {
+        let labels = self.labels.as_mut().ok_or_else(|| "Task has no labels".to_string())?;
+        let index = labels.iter().position(|label| label.name == name)
+            .ok_or_else(|| format!("Label with name '{name}' not found"))?;
+        if new_index >= labels.len() {
+            return Err(format!("Label index {new_index} is out of range, task has {} labels", labels.len()));
+        }
+
+        let label = labels.remove(index);
+        labels.insert(new_index, label);
+
+        Ok(())
+    }
+
     pub fn get_label_by_name(&self, name: &str) -> Option<&Label> {
         self.labels
             .as_ref()
@@ -218,6 +458,7 @@ impl Comment {
             id: Some(id),
             props,
             text,
+            extra: BTreeMap::new(),
         }
     }
 
@@ -256,10 +497,32 @@ impl Label {
         self.name.to_string()
     }
 
+    pub fn get_scope(&self) -> Option<String> {
+        self.name.rfind(LABEL_SCOPE_SEPARATOR).map(|pos| self.name[..pos].to_string())
+    }
+
+    /// Matches the label name ignoring case, a query ending with `::` matches the whole scope.
+    pub fn matches(&self, query: &str) -> bool {
+        match query.strip_suffix(LABEL_SCOPE_SEPARATOR) {
+            Some(scope) => self.get_scope().is_some_and(|label_scope| label_scope.eq_ignore_ascii_case(scope)),
+            None => self.name.eq_ignore_ascii_case(query),
+        }
+    }
+
     pub fn get_color(&self) -> String {
         self.color.clone().unwrap_or_else(|| String::from(""))
     }
 
+    pub fn contrasting_text_color(&self) -> &'static str {
+        match parse_hex_color(&self.get_color()) {
+            Some((r, g, b)) => {
+                let luminance = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) / 255.0;
+                if luminance > 0.5 { "black" } else { "white" }
+            },
+            None => "white",
+        }
+    }
+
     pub fn set_color(&mut self, color: String) {
         self.color = Some(color);
     }
@@ -280,466 +543,5057 @@ macro_rules! map_err {
 }
 
 
-impl TaskContext {
-    pub fn new(repository_path: String) -> Self {
-        Self {
-            repository_path,
-        }
+impl TaskSummary {
+    pub fn get_id(&self) -> Option<String> {
+        self.id.clone()
     }
 
-    pub fn list_tasks(&self) -> Result<Vec<Task>, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref = map_err!(repo.find_reference(&self.get_ref_path()));
-        let task_tree = map_err!(task_ref.peel_to_tree());
-
-        let mut result = vec![];
-
-        let _ = map_err!(task_tree.walk(TreeWalkMode::PreOrder, |_, entry| {
-            let oid = entry.id();
-            let blob = repo.find_blob(oid).unwrap();
-            let content = blob.content();
-
-            let task = serde_json::from_slice(content).unwrap();
-            result.push(task);
+    pub fn get_property(&self, prop: &str) -> Option<&String> {
+        self.props.get(prop)
+    }
 
-            TreeWalkResult::Ok
-        }));
+    pub fn get_all_properties(&self) -> &HashMap<String, String> {
+        &self.props
+    }
+}
 
-        Ok(result)
+impl TaskFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        let created = task.get_property(CREATED).and_then(|created| parse_timestamp(created));
+
+        self.ids.as_ref().is_none_or(|ids| task.get_id().is_some_and(|id| ids.contains(&id)))
+            && self.statuses.as_ref().is_none_or(|statuses| task.get_property(STATUS).is_some_and(|status| statuses.contains(status)))
+            && self.keyword.as_ref().is_none_or(|keyword| task.get_all_properties().values().any(|value| value.contains(keyword.as_str())))
+            && self.author.as_ref().is_none_or(|author| task.get_property("author").is_some_and(|task_author| task_author.to_lowercase() == author.to_lowercase()))
+            && self.label.as_ref().is_none_or(|label| task.get_labels().as_ref()
+                .is_some_and(|labels| labels.iter().any(|l| l.matches(label))))
+            && self.created_from.is_none_or(|from| created.is_some_and(|created| created >= from))
+            && self.created_until.is_none_or(|until| created.is_some_and(|created| created <= until))
     }
+}
 
-    pub fn find_task(&self, id: &str) -> Result<Option<Task>, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref = repo.find_reference(&self.get_ref_path());
-        match task_ref {
-            Ok(task_ref) => {
-                let task_tree = map_err!(task_ref.peel_to_tree());
-                let result = match task_tree.get_name(id) {
-                    Some(entry) => {
-                        let oid = entry.id();
-                        let blob = map_err!(repo.find_blob(oid));
-                        let content = blob.content();
-                        let task = serde_json::from_slice(content).unwrap();
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaskStats {
+    pub total: usize,
+    pub by_status: HashMap<String, usize>,
+    pub by_author: HashMap<String, usize>,
+}
 
-                        Some(task)
-                    },
-                    None => None,
-                };
+/// Task storage behind filtering, statistics and the operations that only read and write tasks,
+/// implemented by `TaskContext` (git) and `InMemoryTaskStore`.
+pub trait TaskStore {
+    fn list_tasks(&self) -> Result<Vec<Task>, String>;
+    fn find_task(&self, id: &str) -> Result<Option<Task>, String>;
+    fn create_task(&self, task: Task) -> Result<Task, String>;
+    fn update_task(&self, task: Task) -> Result<String, String>;
+    fn delete_tasks(&self, ids: &[&str]) -> Result<(), String>;
+
+    fn find_matching(&self, filter: &TaskFilter) -> Result<Vec<Task>, String> {
+        let mut tasks = self.list_tasks()?.into_iter().filter(|task| filter.matches(task)).collect::<Vec<_>>();
+        let ids = tasks.iter().filter_map(|task| task.get_id()).collect::<Vec<_>>();
+        let order = sort_ids(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>()).into_iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        tasks.sort_by_key(|task| task.get_id().and_then(|id| order.iter().position(|ordered| *ordered == id)));
+        Ok(tasks)
+    }
 
-                Ok(result)
-            },
-            Err(_) => Ok(None)
+    fn get_stats(&self) -> Result<TaskStats, String> {
+        let mut stats = TaskStats::default();
+        for task in self.list_tasks()? {
+            stats.total += 1;
+            if let Some(status) = task.get_property(STATUS) {
+                *stats.by_status.entry(status.to_owned()).or_insert(0) += 1;
+            }
+            if let Some(author) = task.get_property("author") {
+                *stats.by_author.entry(author.to_owned()).or_insert(0) += 1;
+            }
         }
+        Ok(stats)
     }
+}
 
-    pub fn delete_tasks(&self, ids: &[&str]) -> Result<(), String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref = map_err!(repo.find_reference(&self.get_ref_path()));
-        let task_tree = map_err!(task_ref.peel_to_tree());
-
-        let mut treebuilder = map_err!(repo.treebuilder(Some(&task_tree)));
-        for id in ids {
-            map_err!(treebuilder.remove(id));
-        }
-        let tree_oid = map_err!(treebuilder.write());
+impl TaskStore for TaskContext {
+    fn list_tasks(&self) -> Result<Vec<Task>, String> {
+        TaskContext::list_tasks(self)
+    }
 
-        let parent_commit = map_err!(task_ref.peel_to_commit());
-        let parents = vec![parent_commit];
-        let me = &map_err!(repo.signature());
+    fn find_task(&self, id: &str) -> Result<Option<Task>, String> {
+        TaskContext::find_task(self, id)
+    }
 
-        let mut ids = ids.iter().map(|id| id.parse::<u64>().unwrap()).collect::<Vec<_>>();
-        ids.sort();
-        let ids = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
-        map_err!(repo.commit(Some(&self.get_ref_path()), me, me, format!("Delete task {}", ids).as_str(), &map_err!(repo.find_tree(tree_oid)), &parents.iter().collect::<Vec<_>>()));
+    fn create_task(&self, task: Task) -> Result<Task, String> {
+        TaskContext::create_task(self, task)
+    }
 
-        Ok(())
+    fn update_task(&self, task: Task) -> Result<String, String> {
+        TaskContext::update_task(self, task)
     }
 
-    pub fn clear_tasks(&self) -> Result<u64, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref = map_err!(repo.find_reference(&self.get_ref_path()));
-        let task_tree = map_err!(task_ref.peel_to_tree());
+    fn delete_tasks(&self, ids: &[&str]) -> Result<(), String> {
+        TaskContext::delete_tasks(self, ids)
+    }
+}
 
-        let mut treebuilder = map_err!(repo.treebuilder(Some(&task_tree)));
-        let task_count = treebuilder.len() as u64;
-        map_err!(treebuilder.clear());
-        let tree_oid = map_err!(treebuilder.write());
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum RecordedOperation {
+    Create { task: Task },
+    Update { task: Task },
+    Delete { ids: Vec<String> },
+}
 
-        let parent_commit = map_err!(task_ref.peel_to_commit());
-        let parents = vec![parent_commit];
-        let me = &map_err!(repo.signature());
+/// Wraps a `TaskStore` and records its successful mutating calls as a log for `TaskContext::replay`.
+pub struct RecordingTaskStore<S: TaskStore> {
+    store: S,
+    operations: RefCell<Vec<RecordedOperation>>,
+}
 
-        map_err!(repo.commit(Some(&self.get_ref_path()), me, me, "Clear tasks", &map_err!(repo.find_tree(tree_oid)), &parents.iter().collect::<Vec<_>>()));
+impl<S: TaskStore> RecordingTaskStore<S> {
+    pub fn new(store: S) -> Self {
+        Self { store, operations: RefCell::new(vec![]) }
+    }
 
-        Ok(task_count)
+    pub fn get_operations(&self) -> Vec<RecordedOperation> {
+        self.operations.borrow().clone()
     }
 
-    pub fn create_task(&self, mut task: Task) -> Result<Task, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref_result = repo.find_reference(&self.get_ref_path());
-        let source_tree = match task_ref_result {
-            Ok(ref reference) => {
-                match reference.peel_to_tree() {
-                    Ok(tree) => Some(tree),
-                    _ => None
-                }
-            }
-            _ => { None }
-        };
+    pub fn get_log(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&*self.operations.borrow()).map_err(|e| e.to_string())
+    }
+}
 
-        if task.get_id().is_none() {
-            let id = self.get_next_id().unwrap_or_else(|_| "1".to_string());
-            task.set_id(id);
-        }
-        let string_content = serde_json::to_string(&task).unwrap();
-        let content = string_content.as_bytes();
-        let oid = map_err!(repo.blob(content));
-        let mut treebuilder = map_err!(repo.treebuilder(source_tree.as_ref()));
-        map_err!(treebuilder.insert(&task.get_id().unwrap(), oid, FileMode::Blob.into()));
-        let tree_oid = map_err!(treebuilder.write());
+impl<S: TaskStore> TaskStore for RecordingTaskStore<S> {
+    fn list_tasks(&self) -> Result<Vec<Task>, String> {
+        self.store.list_tasks()
+    }
 
-        let me = &map_err!(repo.signature());
-        let mut parents = vec![];
-        if task_ref_result.is_ok() {
-            let parent_commit = map_err!(task_ref_result).peel_to_commit();
-            if parent_commit.is_ok() {
-                parents.push(map_err!(parent_commit));
-            }
-        }
-        map_err!(repo.commit(Some(&self.get_ref_path()), me, me, format!("Create task {}", &task.get_id().unwrap_or_else(|| String::from("?"))).as_str(), &map_err!(repo.find_tree(tree_oid)), &parents.iter().collect::<Vec<_>>()));
+    fn find_task(&self, id: &str) -> Result<Option<Task>, String> {
+        self.store.find_task(id)
+    }
 
+    fn create_task(&self, task: Task) -> Result<Task, String> {
+        let task = self.store.create_task(task)?;
+        self.operations.borrow_mut().push(RecordedOperation::Create { task: task.clone() });
         Ok(task)
     }
 
-    pub fn update_task(&self, task: Task) -> Result<String, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref_result = map_err!(repo.find_reference(&self.get_ref_path()));
-        let parent_commit = map_err!(task_ref_result.peel_to_commit());
-        let source_tree = map_err!(task_ref_result.peel_to_tree());
-        let string_content = serde_json::to_string(&task).unwrap();
-        let content = string_content.as_bytes();
-        let oid = map_err!(repo.blob(content));
-        let mut treebuilder = map_err!(repo.treebuilder(Some(&source_tree)));
-        map_err!(treebuilder.insert(&task.get_id().unwrap(), oid, FileMode::Blob.into()));
-        let tree_oid = map_err!(treebuilder.write());
-
-        let me = &map_err!(repo.signature());
-        let parents = vec![parent_commit];
-        map_err!(repo.commit(Some(&self.get_ref_path()), me, me, format!("Update task {}", &task.get_id().unwrap()).as_str(), &map_err!(repo.find_tree(tree_oid)), &parents.iter().collect::<Vec<_>>()));
+    fn update_task(&self, task: Task) -> Result<String, String> {
+        let id = self.store.update_task(task.clone())?;
+        self.operations.borrow_mut().push(RecordedOperation::Update { task });
+        Ok(id)
+    }
 
-        Ok(task.get_id().unwrap())
+    fn delete_tasks(&self, ids: &[&str]) -> Result<(), String> {
+        self.store.delete_tasks(ids)?;
+        self.operations.borrow_mut().push(RecordedOperation::Delete { ids: ids.iter().map(|id| id.to_string()).collect() });
+        Ok(())
     }
+}
 
-    fn get_next_id(&self) -> Result<String, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let task_ref = map_err!(repo.find_reference(&self.get_ref_path()));
-        let task_tree = map_err!(task_ref.peel_to_tree());
+/// Keeps tasks in memory, for tests of logic built on top of `TaskStore`.
+#[derive(Default)]
+pub struct InMemoryTaskStore {
+    tasks: RefCell<BTreeMap<String, Task>>,
+}
 
-        let mut result = 0;
+impl InMemoryTaskStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
-        let _ = map_err!(task_tree.walk(TreeWalkMode::PreOrder, |_, entry| {
-        let entry_name = entry.name().unwrap();
-        match entry_name.parse::<i64>() {
-            Ok(id) => {
-                if id > result {
-                    result = id;
-                }
-            },
-            _ => return TreeWalkResult::Skip
-        };
+impl TaskStore for InMemoryTaskStore {
+    fn list_tasks(&self) -> Result<Vec<Task>, String> {
+        Ok(self.tasks.borrow().values().filter(|task| !task.is_deleted()).cloned().collect())
+    }
 
-        TreeWalkResult::Ok
-    }));
+    fn find_task(&self, id: &str) -> Result<Option<Task>, String> {
+        Ok(self.tasks.borrow().get(id).cloned())
+    }
 
-        Ok((result + 1).to_string())
+    fn create_task(&self, mut task: Task) -> Result<Task, String> {
+        let mut tasks = self.tasks.borrow_mut();
+        let id = match task.get_id() {
+            Some(id) => id,
+            None => (tasks.keys().filter_map(|id| id.parse::<u64>().ok()).max().unwrap_or(0) + 1).to_string(),
+        };
+        task.set_id(id.clone());
+        tasks.insert(id, task.clone());
+        Ok(task)
     }
 
-    pub fn update_task_id(&self, id: &str, new_id: &str) -> Result<(), String> {
-        let mut task = self.find_task(&id)?.unwrap();
-        task.set_id(new_id.to_string());
-        self.create_task(task)?;
-        self.delete_tasks(&[&id])?;
+    fn update_task(&self, mut task: Task) -> Result<String, String> {
+        let id = task.get_id().ok_or_else(|| "Task has no ID".to_string())?;
+        task.touch();
+        self.tasks.borrow_mut().insert(id.clone(), task);
+        Ok(id)
+    }
 
+    fn delete_tasks(&self, ids: &[&str]) -> Result<(), String> {
+        let mut tasks = self.tasks.borrow_mut();
+        for id in ids {
+            tasks.remove(*id);
+        }
         Ok(())
     }
+}
 
-    pub fn update_comment_id(&self, task_id: &str, id: &str, new_id: &str) -> Result<(), String> {
-        let mut task = self.find_task(&task_id)?.unwrap().clone();
-        let comments = task.get_comments();
-        match comments {
-            Some(comments) => {
-                let updated_comments = comments.iter().map(|c| {
-                    if c.get_id().unwrap() == id {
-                        let mut c = c.clone();
-                        c.set_id(new_id.to_string());
-                        c
-                    } else {
-                        c.clone()
-                    }
-                }).collect::<Vec<_>>();
-                task.set_comments(updated_comments);
-                self.update_task(task)?;
-            },
-            None => {}
+impl TaskContext {
+    /// The path is resolved to the root of its repository (or worktree) when there is one.
+    pub fn new(repository_path: String) -> Self {
+        Self {
+            repository_path: resolve_repository_path(&repository_path).unwrap_or(repository_path),
+            namespace: None,
+            warnings: Arc::new(Mutex::new(vec![])),
         }
+    }
 
-        Ok(())
+    /// Stores tasks under `refs/tasks/<namespace>` instead of the configured ref.
+    pub fn with_namespace(repository_path: String, namespace: Option<String>) -> Self {
+        Self {
+            namespace,
+            ..Self::new(repository_path)
+        }
     }
 
-    pub fn list_remotes(&self, remote: &Option<String>) -> Result<Vec<String>, String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
-        let remotes = map_err!(repo.remotes());
-        Ok(remotes.iter()
-            .filter(|s| remote.is_none() || remote.as_ref().unwrap().as_str() == s.unwrap())
-            .map(|s| repo.find_remote(s.unwrap()).unwrap().url().unwrap().to_owned())
-            .collect())
+    /// Like `new`, but fails when the path is not inside a git repository.
+    pub fn open(repository_path: &str) -> Result<Self, String> {
+        Ok(Self {
+            repository_path: resolve_repository_path(repository_path)?,
+            namespace: None,
+            warnings: Arc::new(Mutex::new(vec![])),
+        })
     }
-    pub fn get_config_value(&self, key: &str) -> Result<String, String> {
+
+    pub fn from_cwd() -> Result<Self, String> {
+        let cwd = std::env::current_dir().map_err(|e| format!("Can't get current directory: {e}"))?;
+        Self::open(&cwd.display().to_string())
+    }
+
+    pub fn list_namespaces(&self) -> Result<Vec<String>, String> {
         let repo = map_err!(Repository::discover(&self.repository_path));
-        let config = map_err!(repo.config());
-        Ok(map_err!(config.get_string(key)))
+        let mut namespaces = map_err!(repo.references_glob(&format!("{TASKS_REF_PREFIX}*")))
+            .filter_map(|reference| reference.ok()?.name()?.strip_prefix(TASKS_REF_PREFIX).map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+        namespaces.sort();
+        Ok(namespaces)
     }
-    pub fn get_current_user(&self) -> Result<Option<String>, String> {
+
+    pub fn list_tasks(&self) -> Result<Vec<Task>, String> {
+        Ok(self.list_tasks_include_deleted()?.into_iter().filter(|task| !task.is_deleted()).collect())
+    }
+
+    /// Writes the matching tasks ordered by ID as a JSON array. Each task is read from the tree
+    /// and written before the next one is loaded, so memory use doesn't grow with the task count.
+    /// Returns the number of exported tasks.
+    pub fn export_tasks_to<W: Write>(&self, writer: &mut W, filter: &TaskFilter, limit: Option<usize>, pretty: bool) -> Result<usize, String> {
         let repo = map_err!(Repository::discover(&self.repository_path));
-        let me = &map_err!(repo.signature());
-        match me.name() {
-            Some(name) => Ok(Some(String::from(name))),
-            _ => match me.email() {
-                Some(email) => Ok(Some(String::from(email))),
-                _ => Ok(None),
+        let containers = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => task_containers(&repo, &map_err!(task_ref.peel_to_tree()))?,
+            Err(_) => vec![],
+        };
+
+        let mut names = HashMap::new();
+        for (index, tree) in containers.iter().enumerate() {
+            for entry in tree.iter().filter(|entry| entry.kind() == Some(ObjectType::Blob)) {
+                if let Some(name) = entry.name() {
+                    names.insert(name.to_string(), index);
+                }
             }
         }
+        let ids = sort_ids(&names.keys().map(|name| name.as_str()).collect::<Vec<_>>());
+
+        let tasks = ids.into_iter()
+            .map(|id| -> Result<Option<Task>, String> {
+                let tree = &containers[names[id]];
+                let blob = map_err!(repo.find_blob(tree.get_name(id).unwrap().id()));
+                let Some(mut task) = self.parse_task_entry::<Task>(id, blob.content()) else {
+                    return Ok(None);
+                };
+                if task.is_deleted() || !filter.matches(&task) {
+                    return Ok(None);
+                }
+                if let Some(comments) = read_split_comments(&repo, tree, id, 0, usize::MAX)? {
+                    task.set_comments(comments);
+                }
+                Ok(Some(task))
+            })
+            .filter_map(|task| task.transpose())
+            .take(limit.unwrap_or(usize::MAX));
+
+        match pretty {
+            true => write_task_array(&mut serde_json::Serializer::pretty(writer), tasks),
+            false => write_task_array(&mut serde_json::Serializer::new(writer), tasks),
+        }
     }
-    pub fn get_ref_path(&self) -> String {
-        self.get_config_value("task.ref").unwrap_or_else(|_| "refs/tasks/tasks".to_string())
+
+    pub fn export_tasks(&self, filter: &TaskFilter, limit: Option<usize>, pretty: bool) -> Result<String, String> {
+        let mut result = vec![];
+        self.export_tasks_to(&mut result, filter, limit, pretty)?;
+        String::from_utf8(result).map_err(|e| e.to_string())
     }
-    pub fn set_config_value(&self, key: &str, value: &str) -> Result<(), String> {
+
+    pub fn list_task_summaries(&self) -> Result<Vec<TaskSummary>, String> {
         let repo = map_err!(Repository::discover(&self.repository_path));
-        let mut config = map_err!(repo.config());
-        map_err!(config.set_str(key, value));
+        let task_ref = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => task_ref,
+            Err(_) => return Ok(vec![]),
+        };
+        let task_tree = map_err!(task_ref.peel_to_tree());
+
+        let mut result = vec![];
+
+        for tree in task_containers(&repo, &task_tree)? {
+            for entry in tree.iter().filter(|entry| entry.kind() == Some(ObjectType::Blob)) {
+                let blob = map_err!(repo.find_blob(entry.id()));
+                let Some(summary) = self.parse_task_entry::<TaskSummary>(entry.name().unwrap_or_default(), blob.content()) else {
+                    continue;
+                };
+                if summary.get_property(DELETED).is_none_or(|deleted| deleted != "true") {
+                    result.push(summary);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the matching tasks with their count and the total number of stored tasks.
+    /// The total comes from the tree entries alone, so soft-deleted tasks are included in it.
+    pub fn query_tasks_counted(&self, filter: &TaskFilter) -> Result<(Vec<Task>, usize, usize), String> {
+        let tasks = self.find_matching(filter)?;
+        let filtered_count = tasks.len();
+        Ok((tasks, filtered_count, self.count_tasks()?))
+    }
+
+    pub fn count_tasks(&self) -> Result<usize, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => count_task_entries(&repo, &map_err!(task_ref.peel_to_tree())),
+            Err(_) => Ok(0),
+        }
+    }
+
+    /// Checks for at least one task entry without reading any task.
+    pub fn has_tasks(&self) -> Result<bool, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_tree = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => map_err!(task_ref.peel_to_tree()),
+            Err(_) => return Ok(false),
+        };
+
+        Ok(task_containers(&repo, &task_tree)?.iter().any(|tree| tree.iter().any(|entry| {
+            entry.kind() == Some(ObjectType::Blob)
+        })))
+    }
+
+    pub fn find_duplicate_candidates(&self, threshold: f64) -> Result<Vec<(String, String, f64)>, String> {
+        let names = self.list_task_summaries()?.into_iter()
+            .filter_map(|summary| Some((summary.get_id()?, normalize_name(summary.get_property(NAME)?))))
+            .filter(|(_, name)| !name.is_empty())
+            .collect::<HashMap<_, _>>();
+        let ids = names.keys().map(|id| id.as_str()).collect::<Vec<_>>();
+        let names = sort_ids(&ids).into_iter().map(|id| (id, &names[id])).collect::<Vec<_>>();
+
+        let mut result = vec![];
+        for (i, (id, name)) in names.iter().enumerate() {
+            for (other_id, other_name) in &names[i + 1..] {
+                let (shorter, longer) = if name.len() < other_name.len() { (name.len(), other_name.len()) } else { (other_name.len(), name.len()) };
+                if (shorter as f64) / (longer as f64) < threshold {
+                    continue;
+                }
+
+                let score = 1.0 - levenshtein(name, other_name) as f64 / longer as f64;
+                if score >= threshold {
+                    result.push((id.to_string(), other_id.to_string(), score));
+                }
+            }
+        }
+        result.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        Ok(result)
+    }
+
+    pub fn find_tasks_by_label(&self, name: &str) -> Result<Vec<Task>, String> {
+        Ok(self.list_tasks()?.into_iter()
+            .filter(|task| match task.get_labels() {
+                Some(labels) => labels.iter().any(|label| label.matches(name)),
+                None => false,
+            })
+            .collect())
+    }
+
+    pub fn list_tasks_include_deleted(&self) -> Result<Vec<Task>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_ref = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => task_ref,
+            Err(_) => return Ok(vec![]),
+        };
+        let task_tree = map_err!(task_ref.peel_to_tree());
+
+        self.read_tasks(&repo, &task_tree)
+    }
+
+    /// Tasks as they were at a commit of the tasks ref, given as any rev-spec (hash, ref name, `tasks~2`).
+    pub fn list_tasks_at(&self, commit: &str) -> Result<Vec<Task>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let object = repo.revparse_single(commit).map_err(|e| format!("Unknown revision {commit}: {}", e.message()))?;
+        let task_tree = map_err!(object.peel_to_tree());
+
+        Ok(self.read_tasks(&repo, &task_tree)?.into_iter().filter(|task| !task.is_deleted()).collect())
+    }
+
+    pub fn validate_store(&self) -> Result<Vec<String>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_ref = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => task_ref,
+            Err(_) => return Ok(vec![]),
+        };
+        let task_tree = map_err!(task_ref.peel_to_tree());
+
+        let mut result = vec![];
+        for (i, tree) in task_containers(&repo, &task_tree)?.into_iter().enumerate() {
+            let shard = match i {
+                0 => String::new(),
+                _ => format!("{}/", tree_name(&task_tree, tree.id())),
+            };
+            for entry in tree.iter() {
+                let name = shard.clone() + entry.name().unwrap_or_default();
+                match entry.kind() {
+                    Some(ObjectType::Blob) => {
+                        let blob = map_err!(repo.find_blob(entry.id()));
+                        if serde_json::from_slice::<Task>(blob.content()).is_err() {
+                            result.push(name);
+                        }
+                    },
+                    Some(ObjectType::Tree) if entry.name().unwrap_or_default().starts_with(COMMENTS_PREFIX) => {
+                        let comments_tree = map_err!(repo.find_tree(entry.id()));
+                        for comment_entry in comments_tree.iter() {
+                            let blob = map_err!(repo.find_blob(comment_entry.id()));
+                            if serde_json::from_slice::<Comment>(blob.content()).is_err() {
+                                result.push(format!("{name}/{}", comment_entry.name().unwrap_or_default()));
+                            }
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn find_task(&self, id: &str) -> Result<Option<Task>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_ref = repo.find_reference(&self.get_ref_path());
+        match task_ref {
+            Ok(task_ref) => {
+                let task_tree = task_container(&repo, &map_err!(task_ref.peel_to_tree()), id)?;
+                let result = match task_tree.get_name(id) {
+                    Some(entry) => {
+                        let oid = entry.id();
+                        let blob = map_err!(repo.find_blob(oid));
+                        let content = blob.content();
+                        let mut task: Task = serde_json::from_slice(content).map_err(|e| format!("Task {id} can't be read: {e}"))?;
+                        if let Some(comments) = read_split_comments(&repo, &task_tree, id, 0, usize::MAX)? {
+                            task.set_comments(comments);
+                        }
+
+                        Some(task)
+                    },
+                    None => None,
+                };
+
+                Ok(result)
+            },
+            Err(_) => Ok(None)
+        }
+    }
+
+    /// Applies a log produced by `RecordingTaskStore` in order, keeping the recorded task IDs.
+    pub fn replay(&self, log: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let operations: Vec<RecordedOperation> = serde_json::from_str(log).map_err(|e| format!("Can't parse operation log: {e}"))?;
+        for operation in operations {
+            match operation {
+                RecordedOperation::Create { task } => { self.create_task_unchecked(task)?; },
+                RecordedOperation::Update { task } => { self.update_task(task)?; },
+                RecordedOperation::Delete { ids } => self.delete_tasks(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>())?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds a task by its ID or by an unambiguous prefix of it; an exact ID always wins.
+    pub fn find_task_by_prefix(&self, prefix: &str) -> Result<FindResult, String> {
+        if let Some(task) = self.find_task(prefix)? {
+            return Ok(FindResult::Unique(task));
+        }
+
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let Ok(task_ref) = repo.find_reference(&self.get_ref_path()) else {
+            return Ok(FindResult::NotFound);
+        };
+        let task_tree = map_err!(task_ref.peel_to_tree());
+        let candidates = task_containers(&repo, &task_tree)?.iter()
+            .flat_map(|tree| tree.iter()
+                .filter(|entry| entry.kind() == Some(ObjectType::Blob))
+                .filter_map(|entry| entry.name().map(|name| name.to_string()))
+                .collect::<Vec<_>>())
+            .filter(|name| !name.starts_with(COMMENTS_PREFIX) && name.starts_with(prefix))
+            .collect::<Vec<_>>();
+
+        match candidates.len() {
+            0 => Ok(FindResult::NotFound),
+            1 => match self.find_task(&candidates[0])? {
+                Some(task) => Ok(FindResult::Unique(task)),
+                None => Ok(FindResult::NotFound),
+            },
+            _ => {
+                let ids = candidates.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+                Ok(FindResult::Ambiguous(sort_ids(&ids).into_iter().map(|id| id.to_string()).collect()))
+            }
+        }
+    }
+
+    pub fn delete_tasks(&self, ids: &[&str]) -> Result<(), String> {
+        let _lock = self.lock()?;
+        if !self.has_task_ref()? {
+            return Ok(());
+        }
+
+        let message = format!("Delete task {}", sort_ids(ids).join(", "));
+        self.commit_tasks(|repo, task_tree| {
+            let task_tree = task_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(task_tree)));
+            for id in ids {
+                if remove_task(repo, &mut treebuilder, id)?.is_none() {
+                    return Err(format!("Task {id} not found"));
+                }
+            }
+            Ok((map_err!(treebuilder.write()), message.clone()))
+        })
+    }
+
+    pub fn clear_tasks(&self) -> Result<u64, String> {
+        let _lock = self.lock()?;
+        if !self.has_task_ref()? {
+            return Ok(0);
+        }
+
+        let mut task_count = 0;
+        self.commit_tasks(|repo, task_tree| {
+            let task_tree = task_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(task_tree)));
+            task_count = count_task_entries(repo, task_tree)? as u64;
+            map_err!(treebuilder.clear());
+            Ok((map_err!(treebuilder.write()), "Clear tasks".to_string()))
+        })?;
+
+        Ok(task_count)
+    }
+
+    pub fn create_task(&self, task: Task) -> Result<Task, String> {
+        if self.is_strict_status() {
+            self.validate_status(task.get_property(STATUS).map_or("", |status| status.as_str()))?;
+        }
+
+        self.create_task_unchecked(task)
+    }
+
+    /// Creates a task without validating its status, even in strict status mode.
+    pub fn create_task_unchecked(&self, mut task: Task) -> Result<Task, String> {
+        let _lock = self.lock()?;
+        let generate_id = task.get_id().is_none();
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        let mut string_content = String::new();
+
+        if !task.has_property(UPDATED) {
+            let created = task.get_property(CREATED).cloned().unwrap_or_else(|| get_current_timestamp().to_string());
+            task.set_property(UPDATED, &created);
+        }
+        self.apply_time_format(&mut task);
+
+        self.commit_tasks(|repo, source_tree| {
+            let taken = |id: &str| source_tree.is_some_and(|tree| task_container(repo, tree, id).is_ok_and(|tree| tree.get_name(id).is_some()));
+            if generate_id && task.get_id().is_none_or(|id| taken(&id)) {
+                task.set_id(self.get_next_id_from(source_tree)?);
+            }
+            string_content = serde_json::to_string(&task).unwrap();
+
+            let mut treebuilder = map_err!(repo.treebuilder(source_tree));
+            write_task(repo, &mut treebuilder, &task, split_comments, sharded)?;
+            Ok((map_err!(treebuilder.write()), format!("Create task {}", &task.get_id().unwrap_or_else(|| String::from("?")))))
+        })?;
+
+        self.run_hook("create", &string_content)?;
+
+        Ok(task)
+    }
+
+    pub fn update_task(&self, task: Task) -> Result<String, String> {
+        let message = format!("Update task {}", &task.get_id().unwrap());
+        self.update_task_with_message(task, &message)
+    }
+
+    /// Same as `update_task`, but returns `None` when the task matches the stored one apart
+    /// from the `updated` timestamp and no commit was made, unless `task.commit.empty` is true.
+    pub fn update_task_if_changed(&self, task: Task) -> Result<Option<String>, String> {
+        let message = format!("Update task {}", &task.get_id().unwrap());
+        self.write_updated_task(task, &message, false)
+    }
+
+    /// Bumps the `updated` timestamp alone, e.g. to move the task up when sorting by recency.
+    pub fn touch_task(&self, id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let task = self.find_task(id)?.ok_or_else(|| format!("Task {id} not found"))?;
+        self.write_updated_task(task, &format!("Touch task {id}"), true)?;
+        Ok(())
+    }
+
+    /// Updates several tasks in a single commit, tasks without an ID, missing ones
+    /// and ones with an unknown status in strict mode are invalid.
+    pub fn update_tasks(&self, tasks: Vec<Task>, mode: BatchMode) -> Result<BatchReport, String> {
+        let _lock = self.lock()?;
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_tree = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => Some(map_err!(task_ref.peel_to_tree())),
+            Err(_) => None,
+        };
+        let strict_status = self.is_strict_status();
+
+        let mut report = BatchReport::default();
+        let mut valid_tasks = vec![];
+        for mut task in tasks {
+            let id = task.get_id().unwrap_or_else(|| String::from("?"));
+            let exists = task_tree.as_ref().is_some_and(|tree| task_container(&repo, tree, &id).is_ok_and(|tree| tree.get_name(&id).is_some()));
+            let validation = match task.get_id() {
+                None => Err("no ID".to_string()),
+                Some(_) if !exists => Err("not found".to_string()),
+                Some(_) if strict_status => self.validate_status(task.get_property(STATUS).map_or("", |status| status.as_str())),
+                Some(_) => Ok(()),
+            };
+            match validation {
+                Ok(_) => {
+                    task.touch();
+                    self.apply_time_format(&mut task);
+                    report.updated.push(id);
+                    valid_tasks.push(task);
+                },
+                Err(e) if mode == BatchMode::Atomic => return Err(format!("Task {id}: {e}")),
+                Err(e) => report.failed.push((id, e)),
+            }
+        }
+
+        if valid_tasks.is_empty() {
+            return Ok(report);
+        }
+
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            for task in &valid_tasks {
+                write_task(repo, &mut treebuilder, task, split_comments, sharded)?;
+            }
+            Ok((map_err!(treebuilder.write()), format!("Update tasks {}", report.updated.join(", "))))
+        })?;
+
+        for task in &valid_tasks {
+            self.run_hook("update", &serde_json::to_string(task).unwrap())?;
+        }
+
+        Ok(report)
+    }
+
+    pub fn soft_delete_task(&self, id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(id)?.ok_or_else(|| format!("Task {id} not found"))?;
+        task.set_property(DELETED, "true");
+        self.update_task_with_message(task, &format!("Soft delete task {id}"))?;
+
+        Ok(())
+    }
+
+    pub fn restore_task(&self, id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(id)?.ok_or_else(|| format!("Task {id} not found"))?;
+        if !task.delete_property(DELETED) {
+            return Err(format!("Task {id} is not deleted"));
+        }
+        self.update_task_with_message(task, &format!("Restore task {id}"))?;
+
+        Ok(())
+    }
+
+    pub fn validate_dependency(&self, task_id: &str, dep_id: &str) -> Result<(), String> {
+        if task_id == dep_id {
+            return Err(format!("Task {task_id} can't depend on itself"));
+        }
+
+        let graph = self.list_tasks()?.iter()
+            .map(|task| (task.get_id().unwrap(), get_dependencies(task)))
+            .collect::<HashMap<_, _>>();
+        match find_dependency_path(&graph, dep_id, task_id) {
+            Some(path) => Err(format!("Dependency cycle detected: {task_id} -> {}", path.join(" -> "))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn add_dependency(&self, task_id: &str, dep_id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(task_id)?.ok_or_else(|| format!("Task {task_id} not found"))?;
+        if self.find_task(dep_id)?.is_none() {
+            return Err(format!("Task {dep_id} not found"));
+        }
+        self.validate_dependency(task_id, dep_id)?;
+
+        let mut dependencies = get_dependencies(&task);
+        if dependencies.iter().any(|id| id == dep_id) {
+            return Ok(());
+        }
+        dependencies.push(dep_id.to_string());
+        task.set_property(DEPENDS_ON, &dependencies.join(","));
+        self.update_task_with_message(task, &format!("Add dependency {task_id} -> {dep_id}"))?;
+
+        Ok(())
+    }
+
+    pub fn validate_status(&self, status: &str) -> Result<(), String> {
+        let known = self.get_known_statuses();
+        if known.iter().any(|known| known == status) {
+            return Ok(());
+        }
+
+        let normalized = normalize_name(status);
+        let suggestion = known.iter()
+            .map(|known| (levenshtein(&normalize_name(known), &normalized), known))
+            .filter(|(distance, _)| *distance <= (normalized.len() / 2).max(1))
+            .min_by_key(|(distance, _)| *distance);
+        match suggestion {
+            Some((_, known)) => Err(format!("Unknown status {status}, did you mean {known}?")),
+            None => Err(format!("Unknown status {status}. Known statuses are: {}", known.join(", "))),
+        }
+    }
+
+    /// Tasks classified by `is_closed_status`, a task without a status counts as open.
+    pub fn list_tasks_by_state(&self, state: TaskState) -> Result<Vec<Task>, String> {
+        let tasks = self.list_tasks()?;
+        if state == TaskState::All {
+            return Ok(tasks);
+        }
+
+        let mut closed = HashMap::new();
+        Ok(tasks.into_iter()
+            .filter(|task| {
+                let status = task.get_property(STATUS).cloned().unwrap_or_default();
+                let is_closed = *closed.entry(status).or_insert_with_key(|status| self.is_closed_status(status));
+                is_closed == (state == TaskState::Closed)
+            })
+            .collect())
+    }
+
+    pub fn is_closed_status(&self, status: &str) -> bool {
+        let listed = |key: &str| self.get_config_value(key)
+            .is_ok_and(|value| value.split(',').any(|s| s.trim() == status));
+
+        if listed("task.status.closedlike") {
+            true
+        } else if listed("task.status.openlike") {
+            false
+        } else {
+            status == self.get_closed_status()
+                || self.get_configured_statuses().iter().any(|(name, is_done)| *is_done && name == status)
+        }
+    }
+
+    pub fn get_blocking_tasks(&self, task_id: &str) -> Result<Vec<Task>, String> {
+        let tasks = self.list_tasks()?;
+        let task = tasks.iter()
+            .find(|task| task.get_id().unwrap() == task_id)
+            .ok_or_else(|| format!("Task {task_id} not found"))?;
+        let mut result = vec![];
+        let mut visited = HashSet::from([task_id.to_string()]);
+        let mut queue = VecDeque::from(get_dependencies(task));
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+            if let Some(dependency) = tasks.iter().find(|task| task.get_id().unwrap() == id) {
+                if !dependency.get_property(STATUS).is_some_and(|status| self.is_closed_status(status)) {
+                    result.push(dependency.clone());
+                }
+                queue.extend(get_dependencies(dependency));
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn merge_tasks(&self, keep_id: &str, merge_id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        if keep_id == merge_id {
+            return Err(format!("Task {keep_id} can't be merged into itself"));
+        }
+
+        let mut tasks = self.list_tasks_include_deleted()?;
+        let mut keep = tasks.iter().find(|task| task.get_id().unwrap() == keep_id).cloned()
+            .ok_or_else(|| format!("Task {keep_id} not found"))?;
+        let mut merged = tasks.iter().find(|task| task.get_id().unwrap() == merge_id).cloned()
+            .ok_or_else(|| format!("Task {merge_id} not found"))?;
+        if merged.is_deleted() {
+            return Err(format!("Task {merge_id} is deleted"));
+        }
+
+        if let Some(comments) = merged.get_comments() {
+            let mut keep_comments = keep.get_comments().clone().unwrap_or_default();
+            for comment in comments {
+                let mut comment = comment.clone();
+                comment.set_id(next_comment_id(&keep_comments).to_string());
+                keep_comments.push(comment);
+            }
+            keep.set_comments(keep_comments);
+        }
+
+        if let Some(labels) = merged.get_labels() {
+            let mut keep_labels = keep.get_labels().clone().unwrap_or_default();
+            for label in labels {
+                if !keep_labels.iter().any(|l| l.get_name() == label.get_name()) {
+                    keep_labels.push(label.clone());
+                }
+            }
+            keep.set_labels(keep_labels);
+        }
+
+        merged.set_property(MERGED_INTO, keep_id);
+        merged.set_property(DELETED, "true");
+
+        let repoint = |task: &mut Task| {
+            let mut changed = false;
+            for prop in REFERENCE_PROPERTIES {
+                if let Some(value) = task.get_property(prop) {
+                    let ids = value.split(',').map(|id| id.trim()).collect::<Vec<_>>();
+                    if !ids.contains(&merge_id) {
+                        continue;
+                    }
+                    let task_id = task.get_id().unwrap();
+                    let mut new_ids: Vec<&str> = vec![];
+                    for id in ids.into_iter().map(|id| if id == merge_id { keep_id } else { id }) {
+                        if id != task_id && !new_ids.contains(&id) {
+                            new_ids.push(id);
+                        }
+                    }
+                    if new_ids.is_empty() {
+                        task.delete_property(prop);
+                    } else {
+                        task.set_property(prop, &new_ids.join(","));
+                    }
+                    changed = true;
+                }
+            }
+            changed
+        };
+
+        repoint(&mut keep);
+        tasks.retain(|task| task.get_id().unwrap() != keep_id && task.get_id().unwrap() != merge_id);
+        let mut modified = tasks.into_iter()
+            .filter_map(|mut task| repoint(&mut task).then_some(task))
+            .collect::<Vec<_>>();
+        modified.push(keep.clone());
+        modified.push(merged);
+        modified.iter_mut().for_each(|task| task.touch());
+
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            for task in &modified {
+                write_task(repo, &mut treebuilder, task, split_comments, sharded)?;
+            }
+            Ok((map_err!(treebuilder.write()), format!("Merge task {merge_id} into {keep_id}")))
+        })?;
+
+        self.run_hook("update", &serde_json::to_string(&keep).unwrap())?;
+
         Ok(())
     }
 
-    pub fn set_ref_path(&self, ref_path: &str, move_ref: bool) -> Result<(), String> {
-        let repo = map_err!(Repository::discover(&self.repository_path));
+    pub fn delete_matching(&self, filter: &TaskFilter, dry_run: bool) -> Result<Vec<String>, String> {
+        let _lock = self.lock()?;
+        let ids = self.find_matching(filter)?.into_iter()
+            .filter_map(|task| task.get_id())
+            .collect::<Vec<_>>();
+
+        if !dry_run && !ids.is_empty() {
+            self.delete_tasks(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>())?;
+        }
+
+        Ok(ids)
+    }
+
+    pub fn label_matching(&self, filter: &TaskFilter, label: &Label, remove: bool) -> Result<usize, String> {
+        let _lock = self.lock()?;
+        let label = &self.inherit_label_definition(label.clone());
+        let name = label.get_name();
+        let scoped = self.is_scoped_labels();
+        let modified = self.list_tasks()?.into_iter()
+            .filter(|task| filter.matches(task))
+            .filter_map(|mut task| {
+                let changed = if remove {
+                    task.delete_label(&name).is_ok()
+                } else if task.get_label_by_name(&name).is_none() {
+                    match scoped {
+                        true => { task.add_scoped_label(name.clone(), label.get_description(), label.color.clone()); },
+                        false => {
+                            let mut labels = task.get_labels().clone().unwrap_or_default();
+                            labels.push(label.clone());
+                            task.set_labels(labels);
+                        }
+                    }
+                    true
+                } else {
+                    false
+                };
+                if changed {
+                    task.touch();
+                }
+                changed.then_some(task)
+            })
+            .collect::<Vec<_>>();
+
+        if modified.is_empty() {
+            return Ok(0);
+        }
+
+        let ids = modified.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        let ids = sort_ids(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>()).join(", ");
+        let message = match remove {
+            true => format!("Remove label {name} from task {ids}"),
+            false => format!("Add label {name} to task {ids}"),
+        };
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            for task in &modified {
+                write_task(repo, &mut treebuilder, task, split_comments, sharded)?;
+            }
+            Ok((map_err!(treebuilder.write()), message.clone()))
+        })?;
+
+        for task in &modified {
+            self.run_hook("update", &serde_json::to_string(task).unwrap())?;
+        }
+
+        Ok(modified.len())
+    }
+
+    /// Tasks ordered so that blockers precede the tasks they block, tasks without dependencies come first in id order.
+    pub fn topo_order_tasks(&self) -> Result<Vec<Task>, String> {
+        let tasks = self.list_tasks()?;
+        let ids = tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        let order = sort_ids(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>());
+        let positions = order.iter().enumerate().map(|(i, id)| (*id, i)).collect::<HashMap<_, _>>();
+        let tasks = tasks.into_iter().map(|task| (task.get_id().unwrap(), task)).collect::<HashMap<_, _>>();
+
+        let mut dependencies = HashMap::new();
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for id in &order {
+            let task_dependencies = get_dependencies(&tasks[*id]).into_iter()
+                .filter(|dep_id| positions.contains_key(dep_id.as_str()))
+                .collect::<HashSet<_>>();
+            for dep_id in &task_dependencies {
+                dependents.entry(order[positions[dep_id.as_str()]]).or_default().push(id);
+            }
+            dependencies.insert(*id, task_dependencies);
+        }
+
+        let mut in_degree = dependencies.iter().map(|(id, deps)| (*id, deps.len())).collect::<HashMap<_, _>>();
+        let mut ready = in_degree.iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| Reverse((false, positions[id])))
+            .collect::<BinaryHeap<_>>();
+        let mut result = vec![];
+        while let Some(Reverse((_, position))) = ready.pop() {
+            let id = order[position];
+            result.push(tasks[id].clone());
+            for dependent in dependents.get(id).into_iter().flatten() {
+                let degree = in_degree.get_mut(dependent).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(Reverse((true, positions[dependent])));
+                }
+            }
+        }
+
+        if result.len() < order.len() {
+            let mut current = *order.iter().find(|id| in_degree[**id] > 0).unwrap();
+            let mut path = vec![current];
+            loop {
+                current = dependencies[current].iter()
+                    .map(|dep_id| dep_id.as_str())
+                    .filter(|dep_id| in_degree[dep_id] > 0)
+                    .min_by_key(|dep_id| positions[dep_id])
+                    .unwrap();
+                if let Some(start) = path.iter().position(|id| *id == current) {
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(current);
+                    return Err(format!("Dependency cycle detected: {}", cycle.join(" -> ")));
+                }
+                path.push(current);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn update_task_with_message(&self, task: Task, message: &str) -> Result<String, String> {
+        let id = task.get_id().unwrap();
+        self.write_updated_task(task, message, false).map(|_| id)
+    }
+
+    fn write_updated_task(&self, mut task: Task, message: &str, always_commit: bool) -> Result<Option<String>, String> {
+        let _lock = self.lock()?;
+        let stored_task = match always_commit || self.is_empty_commit_allowed() {
+            true => None,
+            false => self.find_task(&task.get_id().unwrap())?,
+        };
+        task.touch();
+        self.apply_time_format(&mut task);
+        if stored_task.is_some_and(|stored_task| has_same_content(&stored_task, &task)) {
+            return Ok(None);
+        }
+        let string_content = serde_json::to_string(&task).unwrap();
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            write_task(repo, &mut treebuilder, &task, split_comments, sharded)?;
+            Ok((map_err!(treebuilder.write()), message.to_string()))
+        })?;
+
+        self.run_hook("update", &string_content)?;
+
+        Ok(task.get_id())
+    }
+
+    fn apply_time_format(&self, task: &mut Task) {
+        let format = self.get_config_value("task.time.format").unwrap_or_else(|_| "epoch".to_string());
+        let reformat = |props: &mut HashMap<String, String>| {
+            for prop in TIME_PROPERTIES {
+                if let Some(seconds) = props.get(prop).and_then(|value| parse_timestamp(value)) {
+                    props.insert(prop.to_string(), format_timestamp(seconds, &format));
+                }
+            }
+        };
+
+        reformat(&mut task.props);
+        if let Some(comments) = task.comments.as_mut() {
+            comments.iter_mut().for_each(|comment| reformat(&mut comment.props));
+        }
+    }
+
+    fn has_task_ref(&self) -> Result<bool, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        Ok(repo.find_reference(&self.get_ref_path()).is_ok())
+    }
+
+    fn is_sharded_layout(&self) -> bool {
+        self.get_config_value("task.store.layout").is_ok_and(|layout| layout == "sharded")
+    }
+
+    pub fn list_label_definitions(&self) -> Result<Vec<Label>, String> {
+        match self.get_config_value("task.labels.definitions") {
+            Ok(definitions) => serde_json::from_str(&definitions).map_err(|e| format!("Invalid label definitions: {e}")),
+            Err(_) => Ok(vec![]),
+        }
+    }
+
+    /// Adds a label to the repository registry, replacing the definition with the same name.
+    pub fn define_label(&self, label: Label) -> Result<(), String> {
+        let mut definitions = self.list_label_definitions()?;
+        match definitions.iter_mut().find(|definition| definition.name == label.name) {
+            Some(definition) => *definition = label,
+            None => definitions.push(label),
+        }
+        self.save_label_definitions(&definitions)
+    }
+
+    pub fn delete_label_definition(&self, name: &str) -> Result<(), String> {
+        let mut definitions = self.list_label_definitions()?;
+        let count = definitions.len();
+        definitions.retain(|definition| definition.name != name);
+        if definitions.len() == count {
+            return Err(format!("Label definition '{name}' not found"));
+        }
+        self.save_label_definitions(&definitions)
+    }
+
+    fn save_label_definitions(&self, definitions: &[Label]) -> Result<(), String> {
+        let definitions = serde_json::to_string(definitions).map_err(|e| e.to_string())?;
+        self.set_config_value("task.labels.definitions", &definitions)
+    }
+
+    /// Fills a missing color or description of the label from its registry definition.
+    fn inherit_label_definition(&self, mut label: Label) -> Label {
+        if label.color.is_some() && label.description.is_some() {
+            return label;
+        }
+        if let Some(definition) = self.list_label_definitions().unwrap_or_default().into_iter().find(|definition| definition.name == label.name) {
+            label.color = label.color.or(definition.color);
+            label.description = label.description.or(definition.description);
+        }
+        label
+    }
+
+    /// Adds a label to the task honoring `task.labels.scoped`, a missing color or description
+    /// is taken from the label registry.
+    pub fn add_label(&self, task: &mut Task, name: String, description: Option<String>, color: Option<String>) -> Label {
+        let label = self.inherit_label_definition(Label::new(name, color, description));
+        match self.is_scoped_labels() {
+            true => task.add_scoped_label(label.name, label.description, label.color),
+            false => task.add_label(label.name, label.description, label.color),
+        }
+    }
+
+    pub fn is_scoped_labels(&self) -> bool {
+        self.get_config_value("task.labels.scoped").is_ok_and(|scoped| scoped == "true")
+    }
+
+    fn is_empty_commit_allowed(&self) -> bool {
+        self.get_config_value("task.commit.empty").is_ok_and(|empty| empty == "true")
+    }
+
+    fn is_split_comments(&self) -> bool {
+        self.get_config_value("task.comments.split").is_ok_and(|split| split == "true")
+    }
+
+    fn get_next_id_from(&self, task_tree: Option<&Tree>) -> Result<String, String> {
+        let scheme = self.get_config_value("task.id.scheme").unwrap_or_else(|_| "sequential".to_string());
+        match scheme.as_str() {
+            "sequential" => Ok((get_max_id(task_tree, "")? + 1).to_string()),
+            "uuid" => Ok(uuid::Uuid::new_v4().to_string()),
+            scheme => match scheme.strip_prefix("prefix-") {
+                Some(prefix) if !prefix.is_empty() => {
+                    let prefix = format!("{prefix}-");
+                    Ok(format!("{prefix}{}", get_max_id(task_tree, &prefix)? + 1))
+                },
+                _ => Err(format!("Unknown id scheme: {scheme}"))
+            }
+        }
+    }
+
+    fn commit_tasks<F>(&self, mut build_tree: F) -> Result<(), String>
+    where
+        F: FnMut(&Repository, Option<&Tree>) -> Result<(Oid, String), String>,
+    {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let ref_path = self.get_ref_path();
+        let me = get_signature(&repo)?;
+        let signing_key = self.get_signing_key(&repo);
+        let _lock = self.lock()?;
+
+        for _ in 0..COMMIT_ATTEMPTS {
+            let parent_commit = match repo.find_reference(&ref_path) {
+                Ok(task_ref) => Some(map_err!(task_ref.peel_to_commit())),
+                Err(_) => None,
+            };
+            let source_tree = match &parent_commit {
+                Some(commit) => Some(map_err!(commit.tree())),
+                None => None,
+            };
+
+            let (tree_oid, message) = build_tree(&repo, source_tree.as_ref())?;
+            let tree = map_err!(repo.find_tree(tree_oid));
+            let parents = parent_commit.iter().collect::<Vec<_>>();
+            let commit_oid = match &signing_key {
+                Some((program, key)) => {
+                    let buffer = map_err!(repo.commit_create_buffer(&me, &me, &message, &tree, &parents));
+                    let buffer = buffer.as_str().ok_or_else(|| "Commit buffer is not valid UTF-8".to_string())?;
+                    let signature = sign_commit_buffer(program, key, buffer)?;
+                    map_err!(repo.commit_signed(buffer, &signature, None))
+                },
+                None => map_err!(repo.commit(None, &me, &me, &message, &tree, &parents)),
+            };
+
+            let update_result = match &parent_commit {
+                Some(parent_commit) => repo.reference_matching(&ref_path, commit_oid, true, parent_commit.id(), &message),
+                None => repo.reference(&ref_path, commit_oid, false, &message),
+            };
+            match update_result {
+                Ok(_) => return Ok(()),
+                Err(e) if e.code() == ErrorCode::Modified || e.code() == ErrorCode::Exists => continue,
+                Err(e) => return Err(e.message().to_owned()),
+            }
+        }
+
+        Err(format!("{ref_path} is being modified concurrently, gave up after {COMMIT_ATTEMPTS} attempts"))
+    }
+
+    /// Takes the advisory lock that keeps git-task processes from interleaving their commits,
+    /// waiting up to `task.lock.timeout` seconds for the current holder. Abandoned locks are taken over.
+    pub fn lock(&self) -> Result<TaskLock, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let path = repo.commondir().join(LOCK_FILE);
+        let timeout = self.get_config_value("task.lock.timeout").ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(DEFAULT_LOCK_TIMEOUT);
+        if HELD_LOCKS.with_borrow(|held| held.contains_key(&path)) {
+            refresh_lock(&path);
+            return Ok(TaskLock::hold(path));
+        }
+        let started = Instant::now();
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let lock = TaskLock::hold(path);
+                    write!(file, "{}", std::process::id()).map_err(|e| e.to_string())?;
+                    return Ok(lock);
+                },
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if is_stale_lock(&path) {
+                        take_over_stale_lock(&path);
+                        continue;
+                    }
+                    if started.elapsed() >= Duration::from_secs(timeout) {
+                        let holder = std::fs::read_to_string(&path).unwrap_or_default();
+                        return Err(format!("{} is held by process {}, remove it if that process is gone", path.display(), holder.trim()));
+                    }
+                    std::thread::sleep(LOCK_POLL_INTERVAL);
+                },
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    /// GPG program and key to sign task commits with: `task.commit.sign` takes precedence over `commit.gpgsign`,
+    /// and nothing is signed without `user.signingkey`.
+    fn get_signing_key(&self, repo: &Repository) -> Option<(String, String)> {
+        let config = repo.config().ok()?;
+        let sign = match self.get_config_value("task.commit.sign") {
+            Ok(sign) => sign == "true",
+            Err(_) => config.get_bool("commit.gpgsign").unwrap_or(false),
+        };
+        if !sign {
+            return None;
+        }
+        let key = config.get_string("user.signingkey").ok().filter(|key| !key.is_empty())?;
+        let program = config.get_string("gpg.program").unwrap_or_else(|_| "gpg".to_string());
+        Some((program, key))
+    }
+
+    fn run_hook(&self, event: &str, task_json: &str) -> Result<(), String> {
+        let hook = match self.get_config_value(&format!("task.hook.{event}")) {
+            Ok(hook) if !hook.is_empty() => hook,
+            _ => return Ok(()),
+        };
+
+        match run_hook_command(&hook, task_json) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let message = format!("Hook {hook} failed: {e}");
+                if self.get_config_value("task.hook.strict").is_ok_and(|strict| strict == "true") {
+                    Err(message)
+                } else {
+                    self.warnings.lock().unwrap().push(message);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Records a warning for the command to print once it has finished.
+    pub fn add_warning(&self, message: String) {
+        self.warnings.lock().unwrap().push(message);
+    }
+
+    /// Returns and forgets the warnings collected so far.
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+
+    /// Parses the blob of a task entry, a blob that isn't a task is skipped with a warning naming
+    /// the entry, the same entries `validate_store` reports.
+    fn parse_task_entry<T: DeserializeOwned>(&self, name: &str, content: &[u8]) -> Option<T> {
+        match serde_json::from_slice(content) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.warnings.lock().unwrap().push(format!("Skipped task entry {name}, it can't be read: {e}"));
+                None
+            },
+        }
+    }
+
+    fn read_tasks(&self, repo: &Repository, task_tree: &Tree) -> Result<Vec<Task>, String> {
+        let mut result = vec![];
+
+        for tree in task_containers(repo, task_tree)? {
+            for entry in tree.iter().filter(|entry| entry.kind() == Some(ObjectType::Blob)) {
+                let name = entry.name().unwrap_or_default();
+                let blob = map_err!(repo.find_blob(entry.id()));
+                let Some(mut task) = self.parse_task_entry::<Task>(name, blob.content()) else {
+                    continue;
+                };
+                if let Some(comments) = read_split_comments(repo, &tree, name, 0, usize::MAX)? {
+                    task.set_comments(comments);
+                }
+                result.push(task);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Moves a task to a new ID, refusing an ID that another task already has.
+    pub fn update_task_id(&self, id: &str, new_id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(id)?.ok_or_else(|| format!("Task ID {id} not found"))?;
+        if id == new_id {
+            return Ok(());
+        }
+        if self.find_task(new_id)?.is_some() {
+            return Err(format!("Task ID {new_id} already exists"));
+        }
+        task.set_id(new_id.to_string());
+        self.create_task(task)?;
+        self.delete_tasks(&[&id])?;
+
+        Ok(())
+    }
+
+    pub fn get_comments_page(&self, task_id: &str, offset: usize, limit: usize) -> Result<Vec<Comment>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_ref = map_err!(repo.find_reference(&self.get_ref_path()));
+        let task_tree = task_container(&repo, &map_err!(task_ref.peel_to_tree()), task_id)?;
+
+        if let Some(comments) = read_split_comments(&repo, &task_tree, task_id, offset, limit)? {
+            return Ok(comments);
+        }
+
+        let entry = task_tree.get_name(task_id).ok_or_else(|| format!("Task {task_id} not found"))?;
+        let blob = map_err!(repo.find_blob(entry.id()));
+        let task: Task = serde_json::from_slice(blob.content()).map_err(|e| e.to_string())?;
+
+        Ok(task.comments.unwrap_or_default().into_iter().skip(offset).take(limit).collect())
+    }
+
+    pub fn split_comments(&self) -> Result<usize, String> {
+        let _lock = self.lock()?;
+        let inline_tasks = self.list_tasks_include_deleted()?.into_iter()
+            .filter(|task| task.comments.as_ref().is_some_and(|comments| !comments.is_empty()))
+            .map(|task| task.get_id().unwrap())
+            .collect::<Vec<_>>();
+        if inline_tasks.is_empty() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        let sharded = self.is_sharded_layout();
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            count = 0;
+            for id in &inline_tasks {
+                let container = task_container(repo, source_tree, id)?;
+                let entry = match container.get_name(id) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                if container.get_name(&format!("{COMMENTS_PREFIX}{id}")).is_some() {
+                    continue;
+                }
+                let blob = map_err!(repo.find_blob(entry.id()));
+                let task: Task = serde_json::from_slice(blob.content()).map_err(|e| e.to_string())?;
+                write_task(repo, &mut treebuilder, &task, true, sharded)?;
+                count += 1;
+            }
+            Ok((map_err!(treebuilder.write()), format!("Split comments of {count} task(s)")))
+        })?;
+
+        Ok(count)
+    }
+
+    /// Moves every task into the layout set by `task.store.layout`, returns the number of moved tasks.
+    pub fn migrate_store_layout(&self) -> Result<usize, String> {
+        let _lock = self.lock()?;
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_tree = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => map_err!(task_ref.peel_to_tree()),
+            Err(_) => return Ok(0),
+        };
+        let sharded = self.is_sharded_layout();
+        let tasks = self.list_tasks_include_deleted()?.into_iter()
+            .filter(|task| task_tree.get_name(&task.get_id().unwrap()).is_some() == sharded)
+            .collect::<Vec<_>>();
+        if tasks.is_empty() {
+            return Ok(0);
+        }
+
+        let message = format!("Migrate {} task(s) to {} layout", tasks.len(), if sharded { "sharded" } else { "flat" });
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            for task in &tasks {
+                write_task(repo, &mut treebuilder, task, false, sharded)?;
+            }
+            Ok((map_err!(treebuilder.write()), message.clone()))
+        })?;
+
+        Ok(tasks.len())
+    }
+
+    /// Renumbers tasks by creation time starting from 1, references and action history follow the new IDs.
+    pub fn renumber_tasks(&self) -> Result<HashMap<String, String>, String> {
+        let _lock = self.lock()?;
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        let mut mapping = HashMap::new();
+
+        self.commit_tasks(|repo, source_tree| {
+            let source_tree = source_tree.ok_or_else(|| format!("No tasks found at {}", self.get_ref_path()))?;
+            let mut tasks = self.read_tasks(repo, source_tree)?;
+            let ids = tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+            let order = sort_ids(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>()).into_iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>();
+            tasks.sort_by_key(|task| {
+                let created = task.get_property(CREATED).and_then(|created| parse_timestamp(created)).unwrap_or(0);
+                let position = order.iter().position(|id| *id == task.get_id().unwrap());
+                (created, position)
+            });
+            mapping = tasks.iter().enumerate()
+                .map(|(i, task)| (task.get_id().unwrap(), (i + 1).to_string()))
+                .collect::<HashMap<_, _>>();
+
+            let mut treebuilder = map_err!(repo.treebuilder(Some(source_tree)));
+            let mut split = HashMap::new();
+            for id in &ids {
+                split.insert(id.clone(), split_comments || remove_task(repo, &mut treebuilder, id)?.unwrap_or(false));
+            }
+            for task in &tasks {
+                let old_id = task.get_id().unwrap();
+                let mut task = task.clone();
+                task.set_id(mapping[&old_id].clone());
+                for prop in REFERENCE_PROPERTIES.iter().chain(&[MERGED_INTO]) {
+                    if let Some(value) = task.get_property(prop) {
+                        let value = value.split(',')
+                            .map(|id| id.trim())
+                            .map(|id| mapping.get(id).map(|new_id| new_id.as_str()).unwrap_or(id))
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        task.set_property(prop, &value);
+                    }
+                }
+                write_task(repo, &mut treebuilder, &task, split[&old_id], sharded)?;
+            }
+
+            let mut renames = mapping.iter().filter(|(old_id, new_id)| old_id != new_id).collect::<Vec<_>>();
+            renames.sort_by_key(|(_, new_id)| new_id.parse::<u64>().unwrap_or_default());
+            let message = renames.iter().fold(format!("{RENUMBER_MESSAGE}\n"), |message, (old_id, new_id)| {
+                message + &format!("\n{old_id}{RENAME_SEPARATOR}{new_id}")
+            });
+            Ok((map_err!(treebuilder.write()), message))
+        })?;
+
+        Ok(mapping)
+    }
+
+    pub fn reorder_labels(&self, task_id: &str, name: &str, new_index: usize) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(task_id)?.ok_or_else(|| format!("Task {task_id} not found"))?;
+        task.move_label(name, new_index)?;
+        self.update_task(task)?;
+        Ok(())
+    }
+
+    /// Adds a comment to a stored task, the author defaults to the configured git identity.
+    /// With `task.comments.id` set to `hash` the comment id is derived from its author, timestamp and text.
+    pub fn add_comment(&self, task_id: &str, mut props: HashMap<String, String>, text: String, author: Option<String>) -> Result<Comment, String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(task_id)?.ok_or_else(|| format!("Task {task_id} not found"))?;
+        let author = match author {
+            Some(author) => Some(author),
+            None if !props.contains_key("author") => self.get_current_user().unwrap_or(None),
+            None => None,
+        };
+        let id = match self.get_config_value("task.comments.id").as_deref() {
+            Ok("hash") => {
+                props.entry("created".to_string()).or_insert_with(|| get_current_timestamp().to_string());
+                if let Some(author) = &author {
+                    props.entry("author".to_string()).or_insert_with(|| author.clone());
+                }
+                let comments = task.get_comments().as_deref().unwrap_or_default();
+                Some(comment_hash_id(comments, &props, &text))
+            },
+            _ => None,
+        };
+        let comment = task.add_comment(id, props, text, author);
+        self.update_task(task)?;
+        Ok(comment)
+    }
+
+    pub fn update_comment_id(&self, task_id: &str, id: &str, new_id: &str) -> Result<(), String> {
+        let _lock = self.lock()?;
+        let mut task = self.find_task(&task_id)?.unwrap().clone();
+        let comments = task.get_comments();
+        match comments {
+            Some(comments) => {
+                if id != new_id && comments.iter().any(|c| c.get_id().as_deref() == Some(new_id)) {
+                    return Err(format!("Comment ID {new_id} already exists"));
+                }
+
+                let updated_comments = comments.iter().map(|c| {
+                    if c.get_id().unwrap() == id {
+                        let mut c = c.clone();
+                        c.set_id(new_id.to_string());
+                        c
+                    } else {
+                        c.clone()
+                    }
+                }).collect::<Vec<_>>();
+                task.set_comments(updated_comments);
+                self.update_task(task)?;
+            },
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn get_task_json(&self, id: &str, pretty: bool) -> Result<String, String> {
+        let task = self.find_task(id)?.ok_or_else(|| format!("Task {id} not found"))?;
+        let func = if pretty { serde_json::to_string_pretty } else { serde_json::to_string };
+        func(&task).map_err(|e| e.to_string())
+    }
+
+    /// Serializes one task with its comments, labels and, optionally, its change history.
+    pub fn export_task_bundle(&self, id: &str, with_history: bool) -> Result<String, String> {
+        let task = self.find_task(id)?.ok_or_else(|| format!("Task {id} not found"))?;
+        let history = match with_history {
+            true => Some(self.get_changes(0, u64::MAX)?.into_iter()
+                .filter(|(_, _, _, task_id, _)| task_id == id)
+                .map(|(timestamp, author, action, _, _)| TaskBundleEntry { timestamp, author, action })
+                .collect()),
+            false => None,
+        };
+        serde_json::to_string_pretty(&TaskBundle { task, history }).map_err(|e| e.to_string())
+    }
+
+    /// Recreates a task from a bundle, a new ID is assigned if the original one is taken.
+    pub fn import_task_bundle(&self, bundle: &str) -> Result<String, String> {
+        let _lock = self.lock()?;
+        let mut task = serde_json::from_str::<TaskBundle>(bundle).map_err(|e| e.to_string())?.task;
+        let existing = self.list_tasks_include_deleted()?;
+        if task.id.as_ref().is_some_and(|id| existing.iter().any(|task| task.id.as_ref() == Some(id))) {
+            task.id = None;
+        }
+        Ok(self.create_task(task)?.get_id().unwrap())
+    }
+
+    pub fn changelog(&self, since: u64, until: u64) -> Result<Vec<(u64, String, TaskAction, String)>, String> {
+        let changes = self.get_changes(since, until)?;
+        Ok(changes.into_iter().map(|(timestamp, author, action, id, _)| (timestamp, author, action, id)).collect())
+    }
+
+    pub fn tasks_changed_between(&self, since: u64, until: u64) -> Result<Vec<(Task, TaskAction)>, String> {
+        let mut latest: Vec<(String, TaskAction, Oid)> = vec![];
+        for (_, _, action, id, commit_id) in self.get_changes(since, until)? {
+            latest.retain(|(existing_id, _, _)| *existing_id != id);
+            latest.push((id, action, commit_id));
+        }
+
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let mut result = vec![];
+        for (id, action, commit_id) in latest {
+            let commit = map_err!(repo.find_commit(commit_id));
+            let tree = match action {
+                TaskAction::Delete => map_err!(map_err!(commit.parent(0)).tree()),
+                _ => map_err!(commit.tree()),
+            };
+            let tree = task_container(&repo, &tree, &id)?;
+            let task = tree.get_name(&id)
+                .and_then(|entry| get_blob_task(&repo, entry.id()))
+                .map(|mut task| {
+                    if let Ok(Some(comments)) = read_split_comments(&repo, &tree, &id, 0, usize::MAX) {
+                        task.set_comments(comments);
+                    }
+                    task
+                });
+            if let Some(task) = task {
+                result.push((task, action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Actions recorded for a task, oldest first. Renumbering is followed back to the former IDs of the task.
+    pub fn get_task_history(&self, id: &str) -> Result<Vec<TaskAction>, String> {
+        let (changes, renumbers) = self.get_changes_in_commit_order(0, u64::MAX)?;
+        let mut renumbers = renumbers.into_iter().rev().peekable();
+        let mut id = Some(id.to_string());
+        let mut result = vec![];
+        for (i, (timestamp, _, action, task_id, _)) in changes.into_iter().enumerate().rev() {
+            while let Some((position, _)) = renumbers.peek() && *position > i {
+                let (_, renames) = renumbers.next().unwrap();
+                id = id.and_then(|id| match renames.iter().find(|(_, new_id)| **new_id == id) {
+                    Some((old_id, _)) => Some(old_id.clone()),
+                    // the ID belonged to another task before, this one didn't exist yet
+                    None if renames.contains_key(&id) => None,
+                    None => Some(id),
+                });
+            }
+            if id.as_ref() == Some(&task_id) {
+                result.push((timestamp, action));
+            }
+        }
+        result.reverse();
+        result.sort_by_key(|(timestamp, _)| *timestamp);
+        Ok(result.into_iter().map(|(_, action)| action).collect())
+    }
+
+    /// Like `get_task_history`, keeps only the given kinds of actions.
+    pub fn get_task_history_filtered(&self, id: &str, kinds: &[TaskAction]) -> Result<Vec<TaskAction>, String> {
+        Ok(self.get_task_history(id)?.into_iter().filter(|action| kinds.contains(action)).collect())
+    }
+
+    /// Reports when a comment was added (`Create`), edited (`Update`) and removed (`Delete`), oldest first.
+    pub fn get_comment_history(&self, task_id: &str, comment_id: &str) -> Result<Vec<TaskAction>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_ref = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => task_ref,
+            Err(_) => return Ok(vec![]),
+        };
+        let head = map_err!(task_ref.peel_to_commit());
+
+        let mut revwalk = map_err!(repo.revwalk());
+        map_err!(revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE));
+        map_err!(revwalk.push(head.id()));
+
+        let mut result = vec![];
+        let mut previous: Option<String> = None;
+        for oid in revwalk {
+            let tree = map_err!(map_err!(repo.find_commit(map_err!(oid))).tree());
+            let tree = task_container(&repo, &tree, task_id)?;
+            let comments = match read_split_comments(&repo, &tree, task_id, 0, usize::MAX)? {
+                Some(comments) => Some(comments),
+                None => tree.get_name(task_id)
+                    .and_then(|entry| get_blob_task(&repo, entry.id()))
+                    .and_then(|task| task.get_comments().clone()),
+            };
+            let current = comments.unwrap_or_default().into_iter()
+                .find(|comment| comment.get_id().is_some_and(|id| id == comment_id))
+                .map(|comment| serde_json::to_string(&comment).unwrap_or_default());
+
+            match (&previous, &current) {
+                (None, Some(_)) => result.push(TaskAction::Create),
+                (Some(_), None) => result.push(TaskAction::Delete),
+                (Some(previous), Some(current)) if previous != current => result.push(TaskAction::Update),
+                _ => {}
+            }
+            previous = current;
+        }
+
+        Ok(result)
+    }
+
+    fn get_changes(&self, since: u64, until: u64) -> Result<Vec<Change>, String> {
+        let (mut changes, _) = self.get_changes_in_commit_order(since, until)?;
+        changes.sort_by_key(|entry| entry.0);
+        Ok(changes)
+    }
+
+    /// Changes in commit order, together with the ID mapping of every renumbering commit
+    /// and the number of changes before it. IDs are the ones of the time of each change.
+    fn get_changes_in_commit_order(&self, since: u64, until: u64) -> Result<(Vec<Change>, Vec<Renumber>), String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let task_ref = match repo.find_reference(&self.get_ref_path()) {
+            Ok(task_ref) => task_ref,
+            Err(_) => return Ok((vec![], vec![])),
+        };
+        let head = map_err!(task_ref.peel_to_commit());
+
+        let mut revwalk = map_err!(repo.revwalk());
+        map_err!(revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE));
+        map_err!(revwalk.push(head.id()));
+        let mailmap = repo.mailmap().ok();
+
+        let mut result = vec![];
+        let mut renumbers = vec![];
+        for oid in revwalk {
+            let commit = map_err!(repo.find_commit(map_err!(oid)));
+            if let Some(renames) = parse_renumber_message(commit.message().unwrap_or_default()) {
+                renumbers.push((result.len(), renames));
+                continue;
+            }
+            let timestamp = commit.time().seconds().max(0) as u64;
+            if timestamp < since || timestamp > until {
+                continue;
+            }
+
+            let author = match &mailmap {
+                Some(mailmap) => map_err!(commit.author_with_mailmap(mailmap)),
+                None => commit.author(),
+            };
+            let author = author.name().unwrap_or_default().to_string();
+            let tree = map_err!(commit.tree());
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(map_err!(parent.tree())),
+                Err(_) => None,
+            };
+            let diff = map_err!(repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None));
+
+            let commit_start = result.len();
+            for delta in diff.deltas() {
+                let path = delta.new_file().path()
+                    .or_else(|| delta.old_file().path())
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let path = match path.strip_prefix(SHARD_PREFIX).and_then(|path| path.split_once('/')) {
+                    Some((_, path)) => path.to_string(),
+                    None => path,
+                };
+                if let Some((comments_dir, _)) = path.split_once('/') {
+                    let id = comments_dir.strip_prefix(COMMENTS_PREFIX).unwrap_or(comments_dir).to_string();
+                    match result[commit_start..].iter_mut().find(|(_, _, _, existing_id, _)| existing_id == &id) {
+                        Some(existing) if existing.2 == TaskAction::Touched => existing.2 = TaskAction::Update,
+                        Some(_) => {},
+                        None => result.push((timestamp, author.clone(), TaskAction::Update, id, commit.id())),
+                    }
+                    continue;
+                }
+
+                let action = match delta.status() {
+                    Delta::Added => TaskAction::Create,
+                    Delta::Deleted => TaskAction::Delete,
+                    Delta::Modified => {
+                        let old_task = get_blob_task(&repo, delta.old_file().id());
+                        let new_task = get_blob_task(&repo, delta.new_file().id());
+                        match (old_task, new_task) {
+                            (Some(old_task), Some(new_task)) if !old_task.is_deleted() && new_task.is_deleted() => TaskAction::Deleted,
+                            (Some(old_task), Some(new_task)) if old_task.is_deleted() && !new_task.is_deleted() => TaskAction::Restore,
+                            (Some(old_task), Some(new_task)) if old_task.get_property(STATUS) != new_task.get_property(STATUS) => TaskAction::UpdateStatus,
+                            (Some(old_task), Some(new_task)) if has_same_content(&old_task, &new_task) => TaskAction::Touched,
+                            _ => TaskAction::Update,
+                        }
+                    },
+                    _ => continue,
+                };
+                if let Some(position) = result[commit_start..].iter().position(|(_, _, _, existing_id, _)| existing_id == &path) {
+                    if action == TaskAction::Touched {
+                        continue;
+                    }
+                    result.remove(commit_start + position);
+                }
+                result.push((timestamp, author.clone(), action, path, commit.id()));
+            }
+        }
+
+        Ok((result, renumbers))
+    }
+
+    /// Checks imported tasks against the strict status list, if it's enabled.
+    pub fn validate_import(&self, tasks: &[Task]) -> Result<(), String> {
+        if !self.is_strict_status() {
+            return Ok(());
+        }
+        for task in tasks {
+            let id = task.get_id().unwrap_or_else(|| String::from("?"));
+            self.validate_status(task.get_property(STATUS).map_or("", |status| status.as_str()))
+                .map_err(|e| format!("Task {id}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Imports tasks in a single commit, replacing tasks with the same IDs. With `renumber`, tasks whose IDs
+    /// are already taken get fresh ones instead and references between imported tasks are rewritten to match.
+    /// Returns the old and new ID of every task.
+    pub fn import_tasks(&self, tasks: Vec<Task>, force: bool, renumber: bool) -> Result<Vec<(String, String)>, String> {
+        if tasks.is_empty() {
+            return Ok(vec![]);
+        }
+        if !force {
+            self.validate_import(&tasks)?;
+        }
+
+        let mut tasks = tasks;
+        for task in tasks.iter_mut() {
+            if !task.has_property(UPDATED) {
+                let created = task.get_property(CREATED).cloned().unwrap_or_else(|| get_current_timestamp().to_string());
+                task.set_property(UPDATED, &created);
+            }
+            self.apply_time_format(task);
+        }
+
+        let split_comments = self.is_split_comments();
+        let sharded = self.is_sharded_layout();
+        let mut remap = vec![];
+        let mut imported = vec![];
+
+        self.commit_tasks(|repo, source_tree| {
+            remap.clear();
+            imported.clear();
+            let mut treebuilder = map_err!(repo.treebuilder(source_tree));
+            for task in &tasks {
+                let tree = map_err!(repo.find_tree(map_err!(treebuilder.write())));
+                let old_id = task.get_id();
+                let new_id = match &old_id {
+                    Some(id) if !renumber || !task_container(repo, &tree, id).is_ok_and(|container| container.get_name(id).is_some()) => id.clone(),
+                    _ => self.get_next_id_from(Some(&tree))?,
+                };
+                let mut task = task.clone();
+                task.set_id(new_id.clone());
+                write_task(repo, &mut treebuilder, &task, split_comments, sharded)?;
+                remap.push((old_id.unwrap_or_else(|| new_id.clone()), new_id));
+                imported.push(task);
+            }
+
+            let mapping = remap.iter().cloned().collect::<HashMap<_, _>>();
+            for task in imported.iter_mut() {
+                let mut changed = false;
+                for prop in REFERENCE_PROPERTIES.iter().chain(&[MERGED_INTO]) {
+                    if let Some(value) = task.get_property(prop) {
+                        let new_value = value.split(',')
+                            .map(|id| id.trim())
+                            .map(|id| mapping.get(id).map(|new_id| new_id.as_str()).unwrap_or(id))
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        if &new_value != value {
+                            task.set_property(prop, &new_value);
+                            changed = true;
+                        }
+                    }
+                }
+                if changed {
+                    write_task(repo, &mut treebuilder, task, split_comments, sharded)?;
+                }
+            }
+
+            Ok((map_err!(treebuilder.write()), format!("Import {} task(s)", imported.len())))
+        })?;
+
+        for task in &imported {
+            self.run_hook("create", &serde_json::to_string(task).unwrap())?;
+        }
+
+        Ok(remap)
+    }
+
+    /// Imports a GitHub issues export in a single commit, issue numbers become the task IDs. Issues whose
+    /// numbers are taken by local tasks are refused, unless `renumber` gives them fresh IDs.
+    pub fn import_github_export(&self, json: &str, force: bool, renumber: bool) -> Result<Vec<(String, String)>, String> {
+        let issues: Vec<GithubExportIssue> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let open_status = self.get_open_status();
+        let closed_status = self.get_closed_status();
+
+        let mut tasks = vec![];
+        for issue in issues {
+            let mut props = HashMap::from([
+                (NAME.to_owned(), issue.title),
+                (DESCRIPTION.to_owned(), issue.body.unwrap_or_default()),
+                (STATUS.to_owned(), if issue.state == "open" { open_status.clone() } else { closed_status.clone() }),
+            ]);
+            if let Some(created) = issue.created_at.as_deref().and_then(parse_rfc3339_to_seconds) {
+                props.insert(CREATED.to_owned(), created.to_string());
+            }
+            if let Some(user) = issue.user {
+                props.insert("author".to_string(), user.login);
+            }
+
+            let mut task = Task::from_properties(issue.number.to_string(), props)?;
+
+            if !issue.labels.is_empty() {
+                let labels = issue.labels.into_iter()
+                    .map(|l| Label::new(l.name, l.color, l.description))
+                    .collect();
+                task.set_labels(labels);
+            }
+
+            if let Ok(comments) = serde_json::from_value::<Vec<GithubExportComment>>(issue.comments) {
+                let comments = comments.into_iter().map(|c| {
+                    let mut props = HashMap::new();
+                    if let Some(user) = c.user {
+                        props.insert("author".to_string(), user.login);
+                    }
+                    if let Some(created) = c.created_at.as_deref().and_then(parse_rfc3339_to_seconds) {
+                        props.insert(CREATED.to_owned(), created.to_string());
+                    }
+                    Comment::new(c.id.to_string(), props, c.body.unwrap_or_default())
+                }).collect::<Vec<_>>();
+                if !comments.is_empty() {
+                    task.set_comments(comments);
+                }
+            }
+
+            tasks.push(task);
+        }
+
+        if !renumber {
+            let mut taken = vec![];
+            for id in tasks.iter().filter_map(|task| task.get_id()) {
+                if self.find_task(&id)?.is_some() {
+                    taken.push(id);
+                }
+            }
+            if !taken.is_empty() {
+                return Err(format!("Task ID(s) {} already exist, use --renumber to import them under new IDs", taken.join(", ")));
+            }
+        }
+
+        self.import_tasks(tasks, force, renumber)
+    }
+
+    pub fn list_remotes(&self, remote: &Option<String>) -> Result<Vec<String>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let remotes = map_err!(repo.remotes());
+        Ok(remotes.iter()
+            .filter(|s| remote.is_none() || remote.as_ref().unwrap().as_str() == s.unwrap())
+            .map(|s| repo.find_remote(s.unwrap()).unwrap().url().unwrap().to_owned())
+            .collect())
+    }
+    pub fn get_config_value(&self, key: &str) -> Result<String, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let config = map_err!(repo.config());
+        Ok(map_err!(config.get_string(key)))
+    }
+
+    /// Asks the configured git credential helper for the password (token) stored for the given URL.
+    pub fn get_credential_password(&self, url: &str) -> Option<String> {
+        let repo = Repository::discover(&self.repository_path).ok()?;
+        let config = repo.config().ok()?;
+        CredentialHelper::new(url).config(&config).execute().map(|(_, password)| password)
+    }
+
+    pub fn get_current_user(&self) -> Result<Option<String>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let me = get_signature(&repo)?;
+        let me = match repo.mailmap() {
+            Ok(mailmap) => map_err!(mailmap.resolve_signature(&me)),
+            Err(_) => me,
+        };
+        match me.name() {
+            Some(name) => Ok(Some(String::from(name))),
+            _ => match me.email() {
+                Some(email) => Ok(Some(String::from(email))),
+                _ => Ok(None),
+            }
+        }
+    }
+    pub fn get_ref_path(&self) -> String {
+        if let Some(namespace) = &self.namespace {
+            return format!("{TASKS_REF_PREFIX}{namespace}");
+        }
+
+        match self.get_config_value("task.ref.template") {
+            Ok(template) => match self.get_current_branch() {
+                Some(branch) => template.replace("{branch}", &branch),
+                None => self.get_static_ref_path(),
+            },
+            _ => self.get_static_ref_path(),
+        }
+    }
+
+    fn get_static_ref_path(&self) -> String {
+        self.get_config_value("task.ref").unwrap_or_else(|_| format!("{TASKS_REF_PREFIX}tasks"))
+    }
+
+    fn is_strict_status(&self) -> bool {
+        self.get_config_value("task.status.strict").is_ok_and(|strict| strict == "true")
+    }
+
+    fn get_known_statuses(&self) -> Vec<String> {
+        let mut statuses = self.get_configured_statuses().into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+        if statuses.is_empty() {
+            statuses = vec!["OPEN".to_string(), "IN_PROGRESS".to_string(), "CLOSED".to_string()];
+        }
+        let enum_values = self.get_config_value("task.properties")
+            .ok()
+            .and_then(|s| serde_json::from_str::<Vec<serde_json::Value>>(&s).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|property| property.get("name").and_then(|name| name.as_str()) == Some(STATUS))
+            .filter_map(|property| property.get("enum_values")?.as_array().cloned())
+            .flatten()
+            .filter_map(|value| Some(value.get("name")?.as_str()?.to_string()));
+        let configured = ["task.status.open", "task.status.in_progress", "task.status.closed"].into_iter()
+            .filter_map(|key| self.get_config_value(key).ok());
+        for status in enum_values.chain(configured) {
+            if !statuses.contains(&status) {
+                statuses.push(status);
+            }
+        }
+        statuses
+    }
+
+    fn get_configured_statuses(&self) -> Vec<(String, bool)> {
+        let statuses = self.get_config_value("task.statuses")
+            .ok()
+            .and_then(|s| serde_json::from_str::<Vec<serde_json::Value>>(&s).ok())
+            .unwrap_or_default();
+        statuses.iter()
+            .filter_map(|s| {
+                let name = s.get("name")?.as_str()?.to_string();
+                let is_done = s.get("is_done").and_then(|v| v.as_bool()).unwrap_or(false);
+                Some((name, is_done))
+            })
+            .collect()
+    }
+
+    /// Creates a task that isn't saved yet, an empty status is replaced with the open status
+    /// (`task.status.open` or the first configured status).
+    pub fn new_task(&self, name: String, description: String, status: String, author: Option<String>) -> Result<Task, String> {
+        let status = match status.is_empty() {
+            true => self.get_open_status(),
+            false => status,
+        };
+        Task::new(name, description, status, author).map_err(|e| e.to_string())
+    }
+
+    /// Same as `Task::from_properties`, an empty or missing status is replaced with the open status.
+    pub fn task_from_properties(&self, id: String, mut props: HashMap<String, String>) -> Result<Task, String> {
+        if props.get(STATUS).is_none_or(|status| status.is_empty()) {
+            props.insert(STATUS.to_string(), self.get_open_status());
+        }
+        Task::from_properties(id, props).map_err(|e| e.to_string())
+    }
+
+    fn get_open_status(&self) -> String {
+        self.get_config_value("task.status.open").unwrap_or_else(|_| {
+            self.get_configured_statuses().into_iter()
+                .next()
+                .map(|(name, _)| name)
+                .unwrap_or_else(|| "OPEN".to_string())
+        })
+    }
+
+    fn get_closed_status(&self) -> String {
+        self.get_config_value("task.status.closed").unwrap_or_else(|_| {
+            self.get_configured_statuses().into_iter()
+                .find(|(_, is_done)| *is_done)
+                .map(|(name, _)| name)
+                .unwrap_or_else(|| "CLOSED".to_string())
+        })
+    }
+
+    fn get_current_branch(&self) -> Option<String> {
+        let repo = Repository::discover(&self.repository_path).ok()?;
+        let head = repo.find_reference("HEAD").ok()?;
+        head.symbolic_target()?.strip_prefix("refs/heads/").map(|branch| branch.to_string())
+    }
+    pub fn set_config_value(&self, key: &str, value: &str) -> Result<(), String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let mut config = map_err!(repo.config());
+        map_err!(config.set_str(key, value));
+        Ok(())
+    }
+
+    pub fn last_sync(&self, connector_type: &str, user: &str, repo: &str) -> Option<u64> {
+        self.get_config_value(&get_sync_key(connector_type, user, repo)).ok()
+            .and_then(|timestamp| timestamp.parse().ok())
+    }
+
+    pub fn set_last_sync(&self, connector_type: &str, user: &str, repo: &str, timestamp: u64) -> Result<(), String> {
+        self.set_config_value(&get_sync_key(connector_type, user, repo), &timestamp.to_string())
+    }
+
+    /// Files `git task attach` queued for the task and not uploaded yet. They are kept in the
+    /// local git config instead of the tasks ref, since the paths only make sense in this clone.
+    pub fn get_pending_attachments(&self, id: &str) -> Vec<String> {
+        self.get_config_value(&get_attachments_key(&self.get_ref_path(), id)).ok()
+            .and_then(|files| serde_json::from_str(&files).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_pending_attachments(&self, id: &str, files: &[String]) -> Result<(), String> {
+        let key = get_attachments_key(&self.get_ref_path(), id);
+        if !files.is_empty() {
+            return self.set_config_value(&key, &serde_json::to_string(files).map_err(|e| e.to_string())?);
+        }
+
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let mut config = map_err!(repo.config());
+        match config.remove(&key) {
+            Err(e) if e.code() != ErrorCode::NotFound => Err(e.message().to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Bookmarks the current state of all tasks as `refs/tasks-snapshots/<tasks ref>/<name>`,
+    /// so each namespace or branch ref has snapshots of its own.
+    pub fn snapshot(&self, name: &str) -> Result<(), String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let snapshot_ref = get_snapshot_ref(&self.get_ref_path(), name)?;
+        let _lock = self.lock()?;
+
+        let task_ref = repo.find_reference(&self.get_ref_path()).map_err(|_| "No tasks to snapshot".to_string())?;
+        let commit = map_err!(task_ref.peel_to_commit());
+        if repo.find_reference(&snapshot_ref).is_ok() {
+            return Err(format!("Snapshot {name} already exists"));
+        }
+        map_err!(repo.reference(&snapshot_ref, commit.id(), false, &format!("Snapshot {name}")));
+
+        Ok(())
+    }
+
+    /// Snapshot names of the current tasks ref with the time of the snapshotted task commit, sorted by name.
+    pub fn list_snapshots(&self) -> Result<Vec<(String, u64)>, String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let prefix = get_snapshot_prefix(&self.get_ref_path());
+        let mut snapshots = vec![];
+        for reference in map_err!(repo.references_glob(&format!("{prefix}*"))) {
+            let reference = map_err!(reference);
+            let Some(name) = reference.name().and_then(|name| name.strip_prefix(&prefix)).map(|name| name.to_string()) else {
+                continue;
+            };
+            let commit = map_err!(reference.peel_to_commit());
+            snapshots.push((name, commit.time().seconds() as u64));
+        }
+        snapshots.sort();
+        Ok(snapshots)
+    }
+
+    /// Resets the tasks ref to a snapshot, changes made after it are no longer on the ref.
+    pub fn checkout_snapshot(&self, name: &str) -> Result<(), String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let snapshot_ref = get_snapshot_ref(&self.get_ref_path(), name)?;
+        let _lock = self.lock()?;
+
+        let snapshot = repo.find_reference(&snapshot_ref).map_err(|_| format!("Snapshot {name} not found"))?;
+        let commit = map_err!(snapshot.peel_to_commit());
+        map_err!(repo.reference(&self.get_ref_path(), commit.id(), true, &format!("Restore snapshot {name}")));
+
+        Ok(())
+    }
+
+    pub fn set_ref_path(&self, ref_path: &str, move_ref: bool) -> Result<(), String> {
+        let repo = map_err!(Repository::discover(&self.repository_path));
+        let _lock = self.lock()?;
+
+        let current_reference = repo.find_reference(&self.get_ref_path());
+        if let Ok(current_reference) = &current_reference {
+            let commit = map_err!(current_reference.peel_to_commit());
+            map_err!(repo.reference(ref_path, commit.id(), true, "task.ref migrated"));
+        }
+
+        let mut config = map_err!(repo.config());
+        map_err!(config.set_str("task.ref", ref_path));
+
+        if move_ref && current_reference.is_ok() {
+            map_err!(current_reference.unwrap().delete());
+        }
+
+        Ok(())
+    }
+}
+fn normalize_name(name: &str) -> Vec<char> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn get_sync_key(connector_type: &str, user: &str, repo: &str) -> String {
+    format!("task.sync/{connector_type}/{user}/{repo}.last")
+}
+
+fn get_attachments_key(ref_path: &str, id: &str) -> String {
+    format!("task.attachments/{ref_path}/{id}.pending")
+}
+
+fn get_current_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn insert_task(repo: &Repository, treebuilder: &mut TreeBuilder, task: &Task, split_comments: bool) -> Result<(), String> {
+    let id = task.get_id().unwrap();
+    let comments_name = format!("{COMMENTS_PREFIX}{id}");
+    let mut task = task.clone();
+
+    if split_comments || map_err!(treebuilder.get(&comments_name)).is_some() {
+        let comments = task.comments.take().unwrap_or_default();
+        if comments.is_empty() {
+            if map_err!(treebuilder.get(&comments_name)).is_some() {
+                map_err!(treebuilder.remove(&comments_name));
+            }
+        } else {
+            let mut comments_builder = map_err!(repo.treebuilder(None));
+            for (i, comment) in comments.iter().enumerate() {
+                let name = comment.get_id().unwrap_or_else(|| (i + 1).to_string());
+                let oid = map_err!(repo.blob(serde_json::to_string(comment).unwrap().as_bytes()));
+                map_err!(comments_builder.insert(&name, oid, FileMode::Blob.into()));
+            }
+            let comments_oid = map_err!(comments_builder.write());
+            map_err!(treebuilder.insert(&comments_name, comments_oid, FileMode::Tree.into()));
+        }
+    }
+
+    let oid = map_err!(repo.blob(serde_json::to_string(&task).unwrap().as_bytes()));
+    map_err!(treebuilder.insert(&id, oid, FileMode::Blob.into()));
+
+    Ok(())
+}
+
+fn shard_name(id: &str) -> String {
+    format!("{SHARD_PREFIX}{}", id.chars().take(2).collect::<String>())
+}
+
+fn tree_name(parent: &Tree, id: Oid) -> String {
+    parent.iter().find(|entry| entry.id() == id).and_then(|entry| entry.name().map(|name| name.to_string())).unwrap_or_default()
+}
+
+/// Tree holding the entries of a task: its shard in the sharded layout, otherwise the task tree itself.
+fn task_container<'r>(repo: &'r Repository, task_tree: &Tree<'r>, id: &str) -> Result<Tree<'r>, String> {
+    match task_tree.get_name(id) {
+        None => match task_tree.get_name(&shard_name(id)) {
+            Some(entry) => Ok(map_err!(repo.find_tree(entry.id()))),
+            None => Ok(task_tree.clone()),
+        },
+        Some(_) => Ok(task_tree.clone()),
+    }
+}
+
+/// The task tree followed by all of its shards, each one laid out like a flat store.
+fn task_containers<'r>(repo: &'r Repository, task_tree: &Tree<'r>) -> Result<Vec<Tree<'r>>, String> {
+    let mut result = vec![task_tree.clone()];
+    for entry in task_tree.iter() {
+        if entry.kind() == Some(ObjectType::Tree) && entry.name().is_some_and(|name| name.starts_with(SHARD_PREFIX)) {
+            result.push(map_err!(repo.find_tree(entry.id())));
+        }
+    }
+    Ok(result)
+}
+
+/// Old to new IDs recorded in the message of a renumbering commit.
+fn parse_renumber_message(message: &str) -> Option<HashMap<String, String>> {
+    let mut lines = message.lines();
+    if lines.next() != Some(RENUMBER_MESSAGE) {
+        return None;
+    }
+    Some(lines.filter_map(|line| line.split_once(RENAME_SEPARATOR))
+        .map(|(old_id, new_id)| (old_id.to_string(), new_id.to_string()))
+        .collect())
+}
+
+/// IDs the task store keeps for itself: the names of split comments and shards.
+fn check_task_id(id: &str) -> Result<(), String> {
+    match id.starts_with(COMMENTS_PREFIX) || id.starts_with(SHARD_PREFIX) {
+        true => Err(format!("Task ID {id} is reserved by the task store")),
+        false => Ok(()),
+    }
+}
+
+fn resolve_repository_path(path: &str) -> Result<String, String> {
+    let path = std::fs::canonicalize(path).map_err(|e| format!("Can't open {path}: {e}"))?;
+    let repo = Repository::discover(&path).map_err(|_| format!("No git repository found at {}", path.display()))?;
+    let root = repo.workdir().unwrap_or_else(|| repo.path());
+    Ok(root.display().to_string())
+}
+
+fn write_task_array<S: Serializer>(serializer: S, tasks: impl Iterator<Item = Result<Task, String>>) -> Result<usize, String> {
+    let mut seq = serializer.serialize_seq(None).map_err(|e| e.to_string())?;
+    let mut count = 0;
+    for task in tasks {
+        seq.serialize_element(&task?).map_err(|e| e.to_string())?;
+        count += 1;
+    }
+    seq.end().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+fn count_task_entries(repo: &Repository, task_tree: &Tree) -> Result<usize, String> {
+    Ok(task_containers(repo, task_tree)?.iter()
+        .map(|tree| tree.iter().filter(|entry| entry.kind() == Some(ObjectType::Blob)).count())
+        .sum())
+}
+
+fn update_shard<F>(repo: &Repository, treebuilder: &mut TreeBuilder, id: &str, update: F) -> Result<(), String>
+where
+    F: FnOnce(&mut TreeBuilder) -> Result<(), String>,
+{
+    let name = shard_name(id);
+    let shard = match map_err!(treebuilder.get(&name)) {
+        Some(entry) => Some(map_err!(repo.find_tree(entry.id()))),
+        None => None,
+    };
+    let mut shard_builder = map_err!(repo.treebuilder(shard.as_ref()));
+    update(&mut shard_builder)?;
+
+    if shard_builder.is_empty() {
+        if shard.is_some() {
+            map_err!(treebuilder.remove(&name));
+        }
+    } else {
+        let oid = map_err!(shard_builder.write());
+        map_err!(treebuilder.insert(&name, oid, FileMode::Tree.into()));
+    }
+    Ok(())
+}
+
+/// Removes a task from both layouts, returns whether its comments were split, or `None` if there was no such task.
+fn remove_task(repo: &Repository, treebuilder: &mut TreeBuilder, id: &str) -> Result<Option<bool>, String> {
+    let remove_from = |treebuilder: &mut TreeBuilder| -> Result<Option<bool>, String> {
+        if map_err!(treebuilder.get(id)).is_none() {
+            return Ok(None);
+        }
+        map_err!(treebuilder.remove(id));
+        let comments_name = format!("{COMMENTS_PREFIX}{id}");
+        let split = map_err!(treebuilder.get(&comments_name)).is_some();
+        if split {
+            map_err!(treebuilder.remove(&comments_name));
+        }
+        Ok(Some(split))
+    };
+
+    let mut result = remove_from(treebuilder)?;
+    if map_err!(treebuilder.get(shard_name(id))).is_some() {
+        update_shard(repo, treebuilder, id, |shard_builder| {
+            if let Some(split) = remove_from(shard_builder)? {
+                result = Some(split || result.unwrap_or(false));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+/// Whether the tasks differ only in the `updated` timestamp.
+fn has_same_content(stored_task: &Task, task: &Task) -> bool {
+    let content = |task: &Task| {
+        let mut task = task.clone();
+        task.props.remove(UPDATED);
+        serde_json::to_value(&task).ok()
+    };
+    content(stored_task) == content(task)
+}
+
+/// Writes a task in the given layout, moving it there if it's stored in the other one.
+fn write_task(repo: &Repository, treebuilder: &mut TreeBuilder, task: &Task, split_comments: bool, sharded: bool) -> Result<(), String> {
+    let id = task.get_id().unwrap();
+    check_task_id(&id)?;
+    let split_comments = split_comments || remove_task(repo, treebuilder, &id)?.unwrap_or(false);
+    match sharded {
+        true => update_shard(repo, treebuilder, &id, |shard_builder| insert_task(repo, shard_builder, task, split_comments)),
+        false => insert_task(repo, treebuilder, task, split_comments),
+    }
+}
+
+fn read_split_comments(repo: &Repository, task_tree: &Tree, id: &str, offset: usize, limit: usize) -> Result<Option<Vec<Comment>>, String> {
+    let entry = match task_tree.get_name(&format!("{COMMENTS_PREFIX}{id}")) {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+    let comments_tree = map_err!(repo.find_tree(entry.id()));
+
+    let names = comments_tree.iter().filter_map(|entry| entry.name().map(|name| name.to_string())).collect::<Vec<_>>();
+    let names = sort_ids(&names.iter().map(|name| name.as_str()).collect::<Vec<_>>());
+
+    let mut comments = vec![];
+    for name in names.into_iter().skip(offset).take(limit) {
+        let entry = comments_tree.get_name(name).unwrap();
+        let blob = map_err!(repo.find_blob(entry.id()));
+        comments.push(serde_json::from_slice(blob.content()).map_err(|e| e.to_string())?);
+    }
+
+    Ok(Some(comments))
+}
+
+fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    let color = color.trim_start_matches('#');
+    if color.len() != 6 {
+        return None;
+    }
+
+    let r = u8::from_str_radix(color.get(0..2)?, 16).ok()?;
+    let g = u8::from_str_radix(color.get(2..4)?, 16).ok()?;
+    let b = u8::from_str_radix(color.get(4..6)?, 16).ok()?;
+    Some((r, g, b))
+}
+
+fn next_comment_id(comments: &[Comment]) -> u64 {
+    comments.iter()
+        .filter_map(|comment| comment.get_id().and_then(|id| id.parse::<u64>().ok()))
+        .max()
+        .unwrap_or(0)
+        .max(comments.len() as u64) + 1
+}
+
+/// Shortest prefix (at least 7 characters) of the content hash not used by another comment.
+fn comment_hash_id(comments: &[Comment], props: &HashMap<String, String>, text: &str) -> String {
+    let field = |name: &str| props.get(name).map(String::as_str).unwrap_or_default();
+    let content = format!("{}\0{}\0{text}", field("author"), field("created"));
+    let hash = Oid::hash_object(ObjectType::Blob, content.as_bytes()).map(|oid| oid.to_string()).unwrap_or_default();
+    (7..hash.len())
+        .map(|len| &hash[..len])
+        .find(|id| !comments.iter().any(|comment| comment.get_id().as_deref() == Some(*id)))
+        .unwrap_or(&hash)
+        .to_string()
+}
+
+fn get_max_id(task_tree: Option<&Tree>, prefix: &str) -> Result<i64, String> {
+    let task_tree = match task_tree {
+        Some(task_tree) => task_tree,
+        None => return Ok(0),
+    };
+
+    let mut result = 0;
+
+    map_err!(task_tree.walk(TreeWalkMode::PreOrder, |_, entry| {
+        let entry_name = entry.name().unwrap();
+        if entry.kind() == Some(ObjectType::Tree) && entry_name.starts_with(SHARD_PREFIX) {
+            return TreeWalkResult::Ok;
+        }
+        match entry_name.strip_prefix(prefix).and_then(|id| id.parse::<i64>().ok()) {
+            Some(id) => {
+                if id > result {
+                    result = id;
+                }
+            },
+            _ => return TreeWalkResult::Skip
+        };
+
+        TreeWalkResult::Ok
+    }));
+
+    Ok(result)
+}
+
+fn run_hook_command(hook: &str, task_json: &str) -> Result<(), String> {
+    let mut child = Command::new(hook)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(task_json.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("exited with {status}")),
+    }
+}
+
+/// Prefix of the snapshot refs taken from the tasks ref `ref_path`.
+fn get_snapshot_prefix(ref_path: &str) -> String {
+    format!("{SNAPSHOTS_REF_PREFIX}{}/", ref_path.strip_prefix("refs/").unwrap_or(ref_path))
+}
+
+fn get_snapshot_ref(ref_path: &str, name: &str) -> Result<String, String> {
+    let snapshot_ref = format!("{}{name}", get_snapshot_prefix(ref_path));
+    match !name.is_empty() && Reference::is_valid_name(&snapshot_ref) {
+        true => Ok(snapshot_ref),
+        false => Err(format!("Invalid snapshot name: {name}")),
+    }
+}
+
+/// A lock is abandoned once the process it names is gone, or, where that can't be told, when it wasn't refreshed for a while.
+fn is_stale_lock(path: &Path) -> bool {
+    is_lock_holder_gone(path) || std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age.as_secs() >= LOCK_STALE_AFTER)
+}
+
+/// Only known where `/proc` lists the running processes. A lock without a PID yet was just taken.
+fn is_lock_holder_gone(path: &Path) -> bool {
+    let proc = Path::new("/proc");
+    std::fs::read_to_string(path).ok()
+        .and_then(|holder| holder.trim().parse::<u32>().ok())
+        .is_some_and(|pid| proc.join("self").exists() && !proc.join(pid.to_string()).exists())
+}
+
+/// Moves an abandoned lock aside before removing it, the rename is atomic so only one of several processes
+/// taking over gets the file. A lock that turns out to be fresh was just taken by another process and is put back.
+fn take_over_stale_lock(path: &Path) {
+    let aside = path.with_extension(format!("lock.{}", uuid::Uuid::new_v4()));
+    if std::fs::rename(path, &aside).is_err() {
+        return;
+    }
+    if !is_stale_lock(&aside) {
+        let _ = std::fs::hard_link(&aside, path);
+    }
+    let _ = std::fs::remove_file(&aside);
+}
+
+/// Keeps a lock that is held across several writes from looking abandoned.
+fn refresh_lock(path: &Path) {
+    let _ = OpenOptions::new().write(true).open(path).and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+fn sign_commit_buffer(program: &str, key: &str, buffer: &str) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(["--status-fd=2", "-bsau", key])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Can't run {program} to sign the commit: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(buffer.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let status = String::from_utf8_lossy(&output.stderr);
+
+    match output.status.success() && status.contains("[GNUPG:] SIG_CREATED ") {
+        true => String::from_utf8(output.stdout).map_err(|e| e.to_string()),
+        false => Err(format!("Failed to sign the commit with key {key}: {}", status.trim())),
+    }
+}
+
+fn sort_ids<'a>(ids: &[&'a str]) -> Vec<&'a str> {
+    let mut ids = ids.to_vec();
+    if ids.iter().all(|id| id.parse::<u64>().is_ok()) {
+        ids.sort_by_key(|id| id.parse::<u64>().unwrap());
+    } else {
+        ids.sort();
+    }
+    ids
+}
+
+fn get_dependencies(task: &Task) -> Vec<String> {
+    task.get_property(DEPENDS_ON)
+        .map(|value| value.split(',').map(|id| id.trim()).filter(|id| !id.is_empty()).map(|id| id.to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn find_dependency_path(graph: &HashMap<String, Vec<String>>, from: &str, to: &str) -> Option<Vec<String>> {
+    let mut parents: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    let mut visited = HashSet::from([from]);
+    while let Some(id) = queue.pop_front() {
+        if id == to {
+            let mut path = vec![id.to_string()];
+            let mut current = id;
+            while let Some(parent) = parents.get(current) {
+                path.push(parent.to_string());
+                current = parent;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in graph.get(id).into_iter().flatten() {
+            if visited.insert(next.as_str()) {
+                parents.insert(next.as_str(), id);
+                queue.push_back(next.as_str());
+            }
+        }
+    }
+    None
+}
+
+fn get_signature(repo: &Repository) -> Result<Signature<'static>, String> {
+    repo.signature().map_err(|e| format!("Set user.name and user.email to modify tasks ({})", e.message()))
+}
+
+fn get_blob_task(repo: &Repository, oid: Oid) -> Option<Task> {
+    let blob = repo.find_blob(oid).ok()?;
+    serde_json::from_slice(blob.content()).ok()
+}
+
+fn parse_rfc3339_to_seconds(datetime: &str) -> Option<u64> {
+    chrono::DateTime::parse_from_rfc3339(datetime).ok().map(|dt| dt.timestamp() as u64)
+}
+
+pub fn parse_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim();
+    value.parse::<u64>().ok().or_else(|| parse_rfc3339_to_seconds(value))
+}
+
+/// Parses an ISO 8601 duration like `P1W`, `P2DT12H` or `PT90M` into seconds.
+/// Years and months have no fixed length and are rejected.
+pub fn parse_duration(value: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid duration: {value}, use ISO 8601 like P1W, P3D or PT48H");
+    let rest = value.trim().strip_prefix(['P', 'p']).filter(|rest| !rest.is_empty()).ok_or_else(invalid)?;
+
+    let mut seconds = 0u64;
+    let mut number = String::new();
+    let mut time = false;
+    for c in rest.chars() {
+        match c.to_ascii_uppercase() {
+            '0'..='9' => number.push(c),
+            'T' if !time && number.is_empty() => time = true,
+            unit => {
+                let multiplier = match (time, unit) {
+                    (false, 'W') => 604800,
+                    (false, 'D') => 86400,
+                    (true, 'H') => 3600,
+                    (true, 'M') => 60,
+                    (true, 'S') => 1,
+                    (false, 'Y' | 'M') => return Err(format!("Invalid duration: {value}, years and months are not supported")),
+                    _ => return Err(invalid()),
+                };
+                let amount = number.parse::<u64>().map_err(|_| invalid())?;
+                seconds = amount.checked_mul(multiplier).and_then(|amount| seconds.checked_add(amount)).ok_or_else(invalid)?;
+                number.clear();
+            }
+        }
+    }
+
+    match number.is_empty() && !rest.to_ascii_uppercase().ends_with('T') {
+        true => Ok(seconds),
+        false => Err(invalid()),
+    }
+}
+
+pub fn format_timestamp(seconds: u64, format: &str) -> String {
+    match format {
+        "rfc3339" => chrono::DateTime::from_timestamp(seconds as i64, 0)
+            .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .unwrap_or_else(|| seconds.to_string()),
+        _ => seconds.to_string(),
+    }
+}
+
+fn serialize_sorted<S: Serializer>(props: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error> {
+    props.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use git2::Repository;
+    use std::collections::HashMap;
+    use std::env::temp_dir;
+    use uuid::Uuid;
+
+    fn init_repo(path: &std::path::Path) -> Repository {
+        let repo = Repository::init(path).unwrap();
+        let mut config = repo.config().unwrap().open_level(ConfigLevel::Local).unwrap();
+        config.set_str("user.name", "Tester").unwrap();
+        config.set_str("user.email", "tester@example.com").unwrap();
+        repo
+    }
+
+    /// Next ID for a new task, as `create_task` picks it.
+    fn next_id(context: &TaskContext) -> String {
+        let repo = Repository::discover(&context.repository_path).unwrap();
+        let task_tree = repo.find_reference(&context.get_ref_path()).ok().map(|task_ref| task_ref.peel_to_tree().unwrap());
+        context.get_next_id_from(task_tree.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_ref_path() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let ref_path = context.get_ref_path();
+        assert!(context.set_ref_path("refs/heads/test-git-task", true).is_ok());
+        assert_eq!(context.get_ref_path(), "refs/heads/test-git-task");
+        assert!(context.set_ref_path(&ref_path, true).is_ok());
+        assert_eq!(context.get_ref_path(), ref_path);
+
+        assert!(repo.is_empty().unwrap());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_ref_path_template() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        context.set_config_value("task.ref.template", "refs/tasks/{branch}").unwrap();
+
+        repo.set_head("refs/heads/main").unwrap();
+        assert_eq!(context.get_ref_path(), "refs/tasks/main");
+        let task = Task::new("Main task".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let task = context.create_task(task).unwrap();
+        let id = task.get_id().unwrap();
+
+        repo.set_head("refs/heads/feature/login").unwrap();
+        assert_eq!(context.get_ref_path(), "refs/tasks/feature/login");
+        assert!(context.find_task(&id).unwrap().is_none());
+
+        repo.set_head("refs/heads/main").unwrap();
+        assert!(context.find_task(&id).unwrap().is_some());
+
+        let commit = repo.find_reference("refs/tasks/main").unwrap().peel_to_commit().unwrap();
+        repo.set_head_detached(commit.id()).unwrap();
+        assert_eq!(context.get_ref_path(), "refs/tasks/tasks");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_update_delete_task() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let id = next_id(&context);
+        let task = Task::construct_task(
+            "Test task".to_string(),
+            "Description goes here".to_string(),
+            "OPEN".to_string(),
+            context.get_current_user().unwrap(),
+            Some(get_current_timestamp()));
+        let create_result = context.create_task(task);
+        assert!(create_result.is_ok());
+        let mut task = create_result.unwrap();
+        assert_eq!(task.get_id(), Some(id.clone()));
+        assert_eq!(task.get_property("name").unwrap(), "Test task");
+        assert_eq!(task.get_property("description").unwrap(), "Description goes here");
+        assert_eq!(task.get_property("status").unwrap(), "OPEN");
+        assert!(task.has_property("created"));
+
+        task.set_property("description", "Updated description");
+        let comment_props = HashMap::from([("author".to_string(), "Some developer".to_string())]);
+        task.add_comment(None, comment_props, "This is a comment".to_string(), context.get_current_user().unwrap());
+        task.set_property("custom_prop", "Custom content");
+        let update_result = context.update_task(task);
+        assert!(update_result.is_ok());
+        assert_eq!(update_result.unwrap(), id.clone());
+
+        let find_result = context.find_task(&id);
+        assert!(find_result.is_ok());
+        let task = find_result.unwrap();
+        assert!(task.is_some());
+        let task = task.unwrap();
+        assert_eq!(task.get_id(), Some(id.clone()));
+        assert_eq!(task.get_property("description").unwrap(), "Updated description");
+        let comments = task.get_comments().clone();
+        assert!(comments.is_some());
+        let comments = comments.unwrap();
+        assert_eq!(comments.len(), 1);
+        let comment = comments.first().unwrap();
+        assert_eq!(comment.get_text(), "This is a comment".to_string());
+        let comment_props = comment.clone().props;
+        assert_eq!(comment_props.get("author").unwrap(), &"Some developer".to_string());
+        assert_eq!(task.get_property("custom_prop").unwrap(), "Custom content");
+
+        let delete_result = context.delete_tasks(&[&id]);
+        assert!(delete_result.is_ok());
+
+        let find_result = context.find_task(&id);
+        assert!(find_result.is_ok());
+        let task = find_result.unwrap();
+        assert!(task.is_none());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_empty_name_or_status() {
+        let task = |name: &str, status: &str| Task::new(name.to_string(), String::new(), status.to_string(), None).err();
+        assert_eq!(task("", "OPEN"), Some("Name is empty"));
+        assert_eq!(task("Name", ""), Some("Status is empty"));
+        assert_eq!(task("", ""), Some("Name and status are empty"));
+        assert!(task("Name", "OPEN").is_none());
+
+        let props = |name: &str| HashMap::from([(NAME.to_string(), name.to_string())]);
+        assert_eq!(Task::from_properties("1".to_string(), props("")).err(), Some("Name and status are empty"));
+        assert_eq!(Task::from_properties("1".to_string(), props("Name")).err(), Some("Status is empty"));
+
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let task = context.new_task("Name".to_string(), String::new(), String::new(), None).unwrap();
+        assert_eq!(task.get_property(STATUS).unwrap(), "OPEN");
+        context.set_config_value("task.status.open", "TODO").unwrap();
+        let task = context.task_from_properties("1".to_string(), props("Name")).unwrap();
+        assert_eq!(task.get_property(STATUS).unwrap(), "TODO");
+        assert_eq!(context.new_task(String::new(), String::new(), String::new(), None).err().unwrap(), "Name is empty");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_comment_id() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        // Create a task first
+        let id = next_id(&context);
+        let task = Task::construct_task(
+            "Test task".to_string(),
+            "Description goes here".to_string(),
+            "OPEN".to_string(),
+            context.get_current_user().unwrap(),
+            Some(get_current_timestamp())
+        );
+        let create_result = context.create_task(task);
+        assert!(create_result.is_ok());
+        let mut task = create_result.unwrap();
+
+        // Add a comment to the task
+        let comment_props = HashMap::from([("author".to_string(), "Some developer".to_string())]);
+        let comment = task.add_comment(
+            Some("1".to_string()),
+            comment_props,
+            "Test comment".to_string(),
+            context.get_current_user().unwrap(),
+        );
+        assert_eq!(comment.get_id().unwrap(), "1");
+        let update_result = context.update_task(task);
+        assert!(update_result.is_ok());
+
+        // Update the comment ID
+        let result = context.update_comment_id(&id, "1", "2");
+        assert!(result.is_ok());
+
+        // Verify the comment ID was updated
+        let updated_task = context.find_task(&id).unwrap().unwrap();
+        let updated_comments = updated_task.get_comments().as_ref().unwrap();
+        assert_eq!(updated_comments.len(), 1);
+        assert_eq!(updated_comments[0].get_id().unwrap(), "2");
+
+        // Reject an id taken by another comment
+        let mut task = updated_task.clone();
+        task.add_comment(Some("3".to_string()), HashMap::new(), "Another comment".to_string(), None);
+        context.update_task(task).unwrap();
+        assert_eq!(context.update_comment_id(&id, "3", "2"), Err("Comment ID 2 already exists".to_string()));
+        assert!(context.update_comment_id(&id, "3", "3").is_ok());
+
+        // Clean up
+        let delete_result = context.delete_tasks(&[&id]);
+        assert!(delete_result.is_ok());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_clear_tasks() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let id = next_id(&context);
+        let task = Task::construct_task(
+            "Test task".to_string(),
+            "Description goes here".to_string(),
+            "OPEN".to_string(),
+            context.get_current_user().unwrap(),
+            Some(get_current_timestamp()));
+        let create_result = context.create_task(task);
+        assert!(create_result.is_ok());
+        let task = create_result.unwrap();
+        assert_eq!(task.get_id(), Some(id.clone()));
+
+        let id = next_id(&context);
+        let task2 = Task::construct_task(
+            "Another task".to_string(),
+            "Another description".to_string(),
+            "IN_PROGRESS".to_string(),
+            context.get_current_user().unwrap(),
+            Some(get_current_timestamp())
+        );
+        let create_result2 = context.create_task(task2);
+        assert!(create_result2.is_ok());
+        let task2 = create_result2.unwrap();
+        assert_eq!(task2.get_id(), Some(id.clone()));
+
+        let id = next_id(&context);
+        let task3 = Task::construct_task(
+            "Third task".to_string(),
+            "Third description".to_string(),
+            "CLOSED".to_string(),
+            context.get_current_user().unwrap(),
+            Some(get_current_timestamp()));
+        let create_result3 = context.create_task(task3);
+        assert!(create_result3.is_ok());
+        let task3 = create_result3.unwrap();
+        assert_eq!(task3.get_id(), Some(id.clone()));
+
+        let clear_result = context.clear_tasks();
+        assert!(clear_result.is_ok());
+        assert_eq!(clear_result.unwrap(), 3);
+
+        let find_result = context.find_task(&id);
+        assert!(find_result.is_ok());
+        let task = find_result.unwrap();
+        assert!(task.is_none());
+
+        let find_result = context.find_task(&task2.get_id().unwrap());
+        assert!(find_result.is_ok());
+        let task = find_result.unwrap();
+        assert!(task.is_none());
+
+        let find_result = context.find_task(&task3.get_id().unwrap());
+        assert!(find_result.is_ok());
+        let task = find_result.unwrap();
+        assert!(task.is_none());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_github_export() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let export = include_str!("../resources/test/github_issues_export.json");
+        let import_result = context.import_github_export(export, false, false);
+        assert_eq!(import_result, Ok(vec![("12".to_string(), "12".to_string()), ("15".to_string(), "15".to_string())]));
+
+        let task = context.find_task("12").unwrap().unwrap();
+        assert_eq!(task.get_property("name").unwrap(), "Crash on empty config");
+        assert_eq!(task.get_property("description").unwrap(), "Steps to reproduce: run without config.");
+        assert_eq!(task.get_property("status").unwrap(), "OPEN");
+        assert_eq!(task.get_property("author").unwrap(), "octocat");
+        assert_eq!(task.get_property("created").unwrap(), "1709287200");
+        let label = task.get_label_by_name("bug").unwrap();
+        assert_eq!(label.get_color(), "d73a4a");
+        assert_eq!(label.get_description(), Some("Something isn't working".to_string()));
+        let comments = task.get_comments().as_ref().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].get_id(), Some("1001".to_string()));
+        assert_eq!(comments[0].get_text(), "Confirmed on Linux.");
+        assert_eq!(comments[0].get_all_properties().get("author").unwrap(), "hubot");
+
+        let task = context.find_task("15").unwrap().unwrap();
+        assert_eq!(task.get_property("status").unwrap(), "CLOSED");
+        assert_eq!(task.get_property("description").unwrap(), "");
+        assert!(task.get_comments().is_none());
+        assert!(task.get_labels().is_none());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_github_export_collision() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Local".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_id("12".to_string());
+        context.create_task(task).unwrap();
+        let head = repo.refname_to_id(&context.get_ref_path()).unwrap();
+
+        let export = include_str!("../resources/test/github_issues_export.json");
+        assert_eq!(context.import_github_export(export, false, false).unwrap_err(), "Task ID(s) 12 already exist, use --renumber to import them under new IDs");
+        assert_eq!(repo.refname_to_id(&context.get_ref_path()).unwrap(), head);
+        assert_eq!(context.find_task("12").unwrap().unwrap().get_property("name").unwrap(), "Local");
+        assert!(context.find_task("15").unwrap().is_none());
+
+        let remap = context.import_github_export(export, false, true).unwrap();
+        assert_eq!(remap, vec![("12".to_string(), "13".to_string()), ("15".to_string(), "15".to_string())]);
+        assert_eq!(context.find_task("12").unwrap().unwrap().get_property("name").unwrap(), "Local");
+        assert_eq!(context.find_task("13").unwrap().unwrap().get_property("name").unwrap(), "Crash on empty config");
+        let commits = repo.find_commit(repo.refname_to_id(&context.get_ref_path()).unwrap()).unwrap();
+        assert_eq!(commits.parent_id(0).unwrap(), head);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_changelog() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let started = get_current_timestamp();
+        assert!(context.changelog(0, u64::MAX).unwrap().is_empty());
+
+        let task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let mut first = context.create_task(task).unwrap();
+        let task = Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let second = context.create_task(task).unwrap();
+
+        first.set_property("description", "Updated");
+        context.update_task(first.clone()).unwrap();
+        first.set_property("status", "CLOSED");
+        context.update_task(first).unwrap();
+        context.delete_tasks(&[&second.get_id().unwrap()]).unwrap();
+
+        let author = context.get_current_user().unwrap().unwrap_or_default();
+        let changelog = context.changelog(0, u64::MAX).unwrap();
+        let entries = changelog.iter().map(|(_, author, action, id)| (author.clone(), action.clone(), id.clone())).collect::<Vec<_>>();
+        assert_eq!(entries, vec![
+            (author.clone(), TaskAction::Create, "1".to_string()),
+            (author.clone(), TaskAction::Create, "2".to_string()),
+            (author.clone(), TaskAction::Update, "1".to_string()),
+            (author.clone(), TaskAction::UpdateStatus, "1".to_string()),
+            (author.clone(), TaskAction::Delete, "2".to_string()),
+        ]);
+        assert!(changelog.windows(2).all(|w| w[0].0 <= w[1].0));
+
+        assert_eq!(context.changelog(started, u64::MAX).unwrap().len(), 5);
+        assert!(context.changelog(0, started.saturating_sub(3600)).unwrap().is_empty());
+        assert!(context.changelog(get_current_timestamp() + 3600, u64::MAX).unwrap().is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_soft_delete_and_restore_task() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let task = Task::new("Keep me".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let kept = context.create_task(task).unwrap().get_id().unwrap();
+        let task = Task::new("Hide me".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let hidden = context.create_task(task).unwrap().get_id().unwrap();
+
+        assert!(context.soft_delete_task(&hidden).is_ok());
+        let ids = context.list_tasks().unwrap().iter().map(|t| t.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![kept.clone()]);
+        assert_eq!(context.list_tasks_include_deleted().unwrap().len(), 2);
+        let task = context.find_task(&hidden).unwrap().unwrap();
+        assert!(task.is_deleted());
+        assert_eq!(task.get_property("name").unwrap(), "Hide me");
+
+        assert!(context.restore_task(&hidden).is_ok());
+        assert_eq!(context.list_tasks().unwrap().len(), 2);
+        assert!(!context.find_task(&hidden).unwrap().unwrap().is_deleted());
+        assert!(context.restore_task(&hidden).is_err());
+
+        let actions = context.changelog(0, u64::MAX).unwrap().into_iter().map(|(_, _, action, _)| action).collect::<Vec<_>>();
+        assert_eq!(actions, vec![TaskAction::Create, TaskAction::Create, TaskAction::Deleted, TaskAction::Restore]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_id_schemes() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let new_task = || Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+
+        assert_eq!(context.create_task(new_task()).unwrap().get_id().unwrap(), "1");
+        context.set_config_value("task.id.scheme", "sequential").unwrap();
+        assert_eq!(context.create_task(new_task()).unwrap().get_id().unwrap(), "2");
+
+        context.set_config_value("task.id.scheme", "prefix-alice").unwrap();
+        assert_eq!(context.create_task(new_task()).unwrap().get_id().unwrap(), "alice-1");
+        assert_eq!(context.create_task(new_task()).unwrap().get_id().unwrap(), "alice-2");
+        context.set_config_value("task.id.scheme", "prefix-bob").unwrap();
+        assert_eq!(context.create_task(new_task()).unwrap().get_id().unwrap(), "bob-1");
+
+        context.set_config_value("task.id.scheme", "uuid").unwrap();
+        let uuid_id = context.create_task(new_task()).unwrap().get_id().unwrap();
+        assert!(Uuid::parse_str(&uuid_id).is_ok());
+        assert!(context.find_task(&uuid_id).unwrap().is_some());
+
+        context.set_config_value("task.id.scheme", "sequential").unwrap();
+        assert_eq!(context.create_task(new_task()).unwrap().get_id().unwrap(), "3");
+
+        assert!(context.delete_tasks(&[&uuid_id, "alice-1", "2"]).is_ok());
+        assert!(context.find_task(&uuid_id).unwrap().is_none());
+        assert_eq!(context.list_tasks().unwrap().len(), 4);
+
+        context.set_config_value("task.id.scheme", "random").unwrap();
+        assert!(context.create_task(new_task()).is_err());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_delete_tasks_with_non_numeric_ids() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for id in ["10", "9", "beta", "alpha.2"] {
+            let mut task = Task::new(format!("Task {id}"), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_id(id.to_string());
+            context.create_task(task).unwrap();
+        }
+
+        assert!(context.delete_tasks(&["10", "9"]).is_ok());
+        let commit = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap();
+        assert_eq!(commit.message().unwrap(), "Delete task 9, 10");
+
+        assert!(context.delete_tasks(&["beta", "alpha.2"]).is_ok());
+        let commit = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap();
+        assert_eq!(commit.message().unwrap(), "Delete task alpha.2, beta");
+        assert!(context.list_tasks().unwrap().is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let marker = repo_dir.join("marker.json");
+        let hook = repo_dir.join("hook.sh");
+        std::fs::write(&hook, format!("#!/bin/sh\ncat > {}\n", marker.display())).unwrap();
+        let failing_hook = repo_dir.join("failing_hook.sh");
+        std::fs::write(&failing_hook, "#!/bin/sh\nexit 1\n").unwrap();
+        for path in [&hook, &failing_hook] {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        context.set_config_value("task.hook.create", &hook.display().to_string()).unwrap();
+        context.set_config_value("task.hook.update", &hook.display().to_string()).unwrap();
+
+        let task = Task::new("Hooked task".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let mut task = context.create_task(task).unwrap();
+        let hooked: Task = serde_json::from_str(&std::fs::read_to_string(&marker).unwrap()).unwrap();
+        assert_eq!(hooked.get_property("name").unwrap(), "Hooked task");
+
+        task.set_property("status", "CLOSED");
+        context.update_task(task.clone()).unwrap();
+        let hooked: Task = serde_json::from_str(&std::fs::read_to_string(&marker).unwrap()).unwrap();
+        assert_eq!(hooked.get_property("status").unwrap(), "CLOSED");
+
+        context.set_config_value("task.hook.update", &failing_hook.display().to_string()).unwrap();
+        task.set_property("status", "OPEN");
+        assert!(context.take_warnings().is_empty());
+        assert!(context.update_task(task.clone()).is_ok());
+        let warnings = context.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with(&format!("Hook {} failed", failing_hook.display())));
+        assert!(context.take_warnings().is_empty());
+
+        context.set_config_value("task.hook.strict", "true").unwrap();
+        task.set_property("status", "IN_PROGRESS");
+        assert!(context.update_task(task.clone()).is_err());
+        let id = task.get_id().unwrap();
+        assert_eq!(context.find_task(&id).unwrap().unwrap().get_property("status").unwrap(), "IN_PROGRESS");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_commit_retries_on_concurrent_update() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let other_context = TaskContext::new(repo_dir.display().to_string());
+
+        let task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+
+        let mut attempts = 0;
+        let result = context.commit_tasks(|repo, source_tree| {
+            attempts += 1;
+            if attempts == 1 {
+                let task = Task::new("Concurrent".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+                other_context.create_task(task).unwrap();
+            }
+            let mut task = Task::new("Mine".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_id("mine".to_string());
+            let oid = repo.blob(serde_json::to_string(&task).unwrap().as_bytes()).unwrap();
+            let mut treebuilder = repo.treebuilder(source_tree).unwrap();
+            treebuilder.insert("mine", oid, FileMode::Blob.into()).unwrap();
+            Ok((treebuilder.write().unwrap(), "Create task mine".to_string()))
+        });
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+
+        let mut names = context.list_tasks().unwrap().iter().map(|t| t.get_property("name").unwrap().clone()).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["Concurrent", "First", "Mine"]);
+
+        let result = context.commit_tasks(|repo, source_tree| {
+            let task = Task::new("Concurrent".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+            other_context.create_task(task).unwrap();
+            let treebuilder = repo.treebuilder(source_tree).unwrap();
+            Ok((treebuilder.write().unwrap(), "Lose every race".to_string()))
+        });
+        assert!(result.unwrap_err().contains("concurrently"));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_label_contrasting_text_color() {
+        let label = |color: Option<&str>| Label::new("label".to_string(), color.map(|c| c.to_string()), None);
+
+        assert_eq!(label(Some("ffffff")).contrasting_text_color(), "black");
+        assert_eq!(label(Some("#fbca04")).contrasting_text_color(), "black");
+        assert_eq!(label(Some("a2eeef")).contrasting_text_color(), "black");
+        assert_eq!(label(Some("000000")).contrasting_text_color(), "white");
+        assert_eq!(label(Some("d73a4a")).contrasting_text_color(), "white");
+        assert_eq!(label(Some("0052cc")).contrasting_text_color(), "white");
+        assert_eq!(label(Some("not a color")).contrasting_text_color(), "white");
+        assert_eq!(label(Some("fff")).contrasting_text_color(), "white");
+        assert_eq!(label(None).contrasting_text_color(), "white");
+    }
+
+    #[test]
+    fn test_find_tasks_by_label() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Crash on start".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_label("Bug".to_string(), None, Some("d73a4a".to_string()));
+        let bug = context.create_task(task).unwrap().get_id().unwrap();
+        let mut task = Task::new("Dark mode".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_label("enhancement".to_string(), None, None);
+        context.create_task(task).unwrap();
+        let task = Task::new("No labels".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+
+        let found = context.find_tasks_by_label("bug").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get_id().unwrap(), bug);
+        assert!(context.find_tasks_by_label("wontfix").unwrap().is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_comments() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Long thread".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        for i in 1..=12 {
+            task.add_comment(Some(i.to_string()), HashMap::new(), format!("Comment {i}"), None);
+        }
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+        let task = Task::new("Quiet".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+
+        let page = context.get_comments_page(&id, 10, 5).unwrap();
+        assert_eq!(page.iter().map(|c| c.get_text()).collect::<Vec<_>>(), vec!["Comment 11", "Comment 12"]);
+
+        assert_eq!(context.split_comments().unwrap(), 1);
+        assert_eq!(context.split_comments().unwrap(), 0);
+
+        let task_tree = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_tree().unwrap();
+        let blob = repo.find_blob(task_tree.get_name(&id).unwrap().id()).unwrap();
+        let stored: Task = serde_json::from_slice(blob.content()).unwrap();
+        assert!(stored.get_comments().is_none());
+        let comments_tree = repo.find_tree(task_tree.get_name(&format!("comments-{id}")).unwrap().id()).unwrap();
+        assert_eq!(comments_tree.len(), 12);
+
+        let page = context.get_comments_page(&id, 0, 3).unwrap();
+        assert_eq!(page.iter().map(|c| c.get_text()).collect::<Vec<_>>(), vec!["Comment 1", "Comment 2", "Comment 3"]);
+        let page = context.get_comments_page(&id, 9, 2).unwrap();
+        assert_eq!(page.iter().map(|c| c.get_text()).collect::<Vec<_>>(), vec!["Comment 10", "Comment 11"]);
+
+        let mut task = context.find_task(&id).unwrap().unwrap();
+        assert_eq!(task.get_comments().as_ref().unwrap().len(), 12);
+        assert_eq!(context.list_tasks().unwrap().len(), 2);
+
+        task.add_comment(Some("13".to_string()), HashMap::new(), "Comment 13".to_string(), None);
+        context.update_task(task).unwrap();
+        let page = context.get_comments_page(&id, 12, 10).unwrap();
+        assert_eq!(page.iter().map(|c| c.get_text()).collect::<Vec<_>>(), vec!["Comment 13"]);
+
+        context.delete_tasks(&[&id]).unwrap();
+        let task_tree = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_tree().unwrap();
+        assert!(task_tree.get_name(&format!("comments-{id}")).is_none());
+        assert_eq!(context.clear_tasks().unwrap(), 1);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_renumber_tasks() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for i in 1..=5 {
+            let mut task = Task::new(format!("Task {i}"), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_property("created", &(1700000000 + i).to_string());
+            if i == 5 {
+                task.set_property("parent", "3");
+                task.set_property("depends_on", "1,3");
+            }
+            context.create_task(task).unwrap();
+        }
+        context.delete_tasks(&["2", "4"]).unwrap();
+
+        let mapping = context.renumber_tasks().unwrap();
+        assert_eq!(mapping, HashMap::from([
+            ("1".to_string(), "1".to_string()),
+            ("3".to_string(), "2".to_string()),
+            ("5".to_string(), "3".to_string()),
+        ]));
+
+        let mut tasks = context.list_tasks().unwrap();
+        tasks.sort_by_key(|task| task.get_id().unwrap());
+        let names = tasks.iter().map(|task| (task.get_id().unwrap(), task.get_property("name").unwrap().clone())).collect::<Vec<_>>();
+        assert_eq!(names, vec![
+            ("1".to_string(), "Task 1".to_string()),
+            ("2".to_string(), "Task 3".to_string()),
+            ("3".to_string(), "Task 5".to_string()),
+        ]);
+        let task = context.find_task("3").unwrap().unwrap();
+        assert_eq!(task.get_property("parent").unwrap(), "2");
+        assert_eq!(task.get_property("depends_on").unwrap(), "1,2");
+
+        context.merge_tasks("2", "3").unwrap();
+        context.delete_tasks(&["1"]).unwrap();
+        let mapping = context.renumber_tasks().unwrap();
+        assert_eq!(mapping["3"], "2");
+        let merged = context.list_tasks_include_deleted().unwrap().into_iter().find(|task| task.get_property("name").unwrap() == "Task 5").unwrap();
+        assert_eq!(merged.get_id().unwrap(), "2");
+        assert_eq!(merged.get_property(MERGED_INTO).unwrap(), "1");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_renumber_tasks_keeps_history() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for i in 1..=4 {
+            let mut task = Task::new(format!("Task {i}"), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_property("created", &(1700000000 + i).to_string());
+            context.create_task(task).unwrap();
+        }
+        let mut task = context.find_task("3").unwrap().unwrap();
+        task.set_property("status", "CLOSED");
+        context.update_task(task).unwrap();
+        context.delete_tasks(&["1"]).unwrap();
+        context.commit_tasks(|repo, source_tree| {
+            let mut treebuilder = repo.treebuilder(source_tree).unwrap();
+            treebuilder.insert("notes", repo.blob(b"not a task").unwrap(), FileMode::Blob.into()).unwrap();
+            Ok((treebuilder.write().unwrap(), "Add notes".to_string()))
+        }).unwrap();
+
+        let mapping = context.renumber_tasks().unwrap();
+        assert_eq!(mapping["3"], "2");
+        assert_eq!(mapping["4"], "3");
+
+        assert_eq!(context.get_task_history("1").unwrap(), vec![TaskAction::Create]);
+        assert_eq!(context.get_task_history("2").unwrap(), vec![TaskAction::Create, TaskAction::UpdateStatus]);
+        assert_eq!(context.get_task_history("3").unwrap(), vec![TaskAction::Create]);
+        let new_task = context.create_task(Task::new("Task 5".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert_eq!(new_task.get_id().unwrap(), "4");
+        assert_eq!(context.get_task_history("4").unwrap(), vec![TaskAction::Create]);
+
+        let task_tree = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_tree().unwrap();
+        assert!(task_tree.get_name("notes").is_some());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_task_summaries() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Chatty".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_comment(None, HashMap::new(), "First!".to_string(), None);
+        task.add_label("bug".to_string(), None, None);
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+        let task = Task::new("Gone".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let gone = context.create_task(task).unwrap().get_id().unwrap();
+        context.soft_delete_task(&gone).unwrap();
+
+        let summaries = context.list_task_summaries().unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].get_id(), Some(id.clone()));
+        assert_eq!(summaries[0].get_property("name").unwrap(), "Chatty");
+        assert_eq!(summaries[0].get_all_properties().len(), 5);
+
+        let task = context.find_task(&id).unwrap().unwrap();
+        assert_eq!(task.get_comments().as_ref().unwrap().len(), 1);
+        assert_eq!(task.get_labels().as_ref().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for name in ["First", "Second", "Third"] {
+            context.create_task(Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        }
+
+        assert_eq!(context.validate_dependency("1", "1").unwrap_err(), "Task 1 can't depend on itself");
+        assert!(context.add_dependency("1", "1").is_err());
+
+        context.add_dependency("2", "1").unwrap();
+        assert_eq!(context.add_dependency("1", "2").unwrap_err(), "Dependency cycle detected: 1 -> 2 -> 1");
+
+        context.add_dependency("3", "2").unwrap();
+        context.add_dependency("3", "2").unwrap();
+        assert_eq!(context.find_task("3").unwrap().unwrap().get_property("depends_on").unwrap(), "2");
+        assert_eq!(context.validate_dependency("1", "3").unwrap_err(), "Dependency cycle detected: 1 -> 3 -> 2 -> 1");
+
+        let blocking = context.get_blocking_tasks("3").unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(blocking, vec!["2", "1"]);
+
+        let mut task = context.find_task("1").unwrap().unwrap();
+        task.set_property("status", "CLOSED");
+        context.update_task(task).unwrap();
+        let blocking = context.get_blocking_tasks("3").unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(blocking, vec!["2"]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_topo_order_tasks() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for (name, depends_on) in [("First", "3"), ("Second", ""), ("Third", ""), ("Fourth", "1, 2"), ("Fifth", "")] {
+            let mut task = Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+            if !depends_on.is_empty() {
+                task.set_property("depends_on", depends_on);
+            }
+            context.create_task(task).unwrap();
+        }
+
+        let order = context.topo_order_tasks().unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(order, vec!["2", "3", "5", "1", "4"]);
+
+        let mut task = context.find_task("3").unwrap().unwrap();
+        task.set_property("depends_on", "4");
+        context.update_task(task).unwrap();
+        assert_eq!(context.topo_order_tasks().err().unwrap(), "Dependency cycle detected: 1 -> 3 -> 4 -> 1");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_label_matching() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for (name, status) in [("First", "OPEN"), ("Second", "OPEN"), ("Third", "CLOSED"), ("Fourth", "OPEN")] {
+            context.create_task(Task::new(name.to_string(), String::new(), status.to_string(), None).unwrap()).unwrap();
+        }
+        let mut task = context.find_task("2").unwrap().unwrap();
+        task.add_label("triage".to_string(), None, None);
+        context.update_task(task).unwrap();
+
+        let filter = TaskFilter { statuses: Some(vec!["OPEN".to_string()]), ..Default::default() };
+        let label = Label::new("triage".to_string(), Some("ededed".to_string()), None);
+        assert_eq!(context.label_matching(&filter, &label, false).unwrap(), 2);
+        assert_eq!(context.label_matching(&filter, &label, false).unwrap(), 0);
+
+        let labeled = context.find_tasks_by_label("triage").unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(sort_ids(&labeled.iter().map(|id| id.as_str()).collect::<Vec<_>>()), vec!["1", "2", "4"]);
+        assert_eq!(context.find_task("2").unwrap().unwrap().get_labels().as_ref().unwrap().len(), 1);
+
+        let filter = TaskFilter { ids: Some(vec!["1".to_string(), "2".to_string(), "3".to_string()]), ..Default::default() };
+        assert_eq!(context.label_matching(&filter, &label, true).unwrap(), 2);
+        let labeled = context.find_tasks_by_label("triage").unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(labeled, vec!["4"]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_without_user_config() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let mut config = repo.config().unwrap().open_level(ConfigLevel::Local).unwrap();
+        config.set_str("user.name", "Someone").unwrap();
+        config.set_str("user.email", "someone@example.com").unwrap();
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_comment(None, HashMap::new(), "Comment".to_string(), None);
+        context.create_task(task).unwrap();
+        context.create_task(Task::new("Second".to_string(), String::new(), "CLOSED".to_string(), None).unwrap()).unwrap();
+
+        // empty values override any global identity, so no signature can be made
+        config.set_str("user.name", "").unwrap();
+        config.set_str("user.email", "").unwrap();
+        assert!(repo.signature().is_err());
+        let context = TaskContext::new(repo_dir.display().to_string());
+        assert!(context.create_task(Task::new("Third".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).is_err());
+
+        assert_eq!(context.list_tasks().unwrap().len(), 2);
+        assert_eq!(context.get_task_history("1").unwrap(), vec![TaskAction::Create]);
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_comments().as_ref().unwrap().len(), 1);
+        assert_eq!(context.list_task_summaries().unwrap().len(), 2);
+        assert_eq!(context.changelog(0, u64::MAX).unwrap().len(), 2);
+        assert_eq!(context.tasks_changed_between(0, u64::MAX).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_bare_repository() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = Repository::init_bare(repo_dir.clone()).unwrap();
+        let mut config = repo.config().unwrap().open_level(ConfigLevel::Local).unwrap();
+        config.set_str("user.name", "Someone").unwrap();
+        config.set_str("user.email", "someone@example.com").unwrap();
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.set_config_value("task.comments.split", "true").unwrap();
+
+        let mut task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), context.get_current_user().unwrap()).unwrap();
+        task.add_comment(None, HashMap::new(), "Comment".to_string(), None);
+        let task = context.create_task(task).unwrap();
+        assert_eq!(task.get_id().unwrap(), "1");
+        context.create_task(Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+
+        let tasks = context.list_tasks().unwrap();
+        assert_eq!(tasks.len(), 2);
+        let mut task = context.find_task("1").unwrap().unwrap();
+        assert_eq!(task.get_comments().as_ref().unwrap().len(), 1);
+        task.set_property("status", "CLOSED");
+        context.update_task(task).unwrap();
+        context.delete_tasks(&["2"]).unwrap();
+        assert_eq!(context.list_tasks().unwrap().len(), 1);
+        assert_eq!(context.changelog(0, u64::MAX).unwrap().len(), 4);
+        assert!(context.validate_store().unwrap().is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_duplicate_candidates() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for name in ["Crash on startup with empty config", "Add dark theme", "crash on start-up with empty config!", "Update README"] {
+            context.create_task(Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        }
+
+        let candidates = context.find_duplicate_candidates(0.8).unwrap();
+        assert_eq!(candidates.len(), 1);
+        let (id, other_id, score) = &candidates[0];
+        assert_eq!((id.as_str(), other_id.as_str()), ("1", "3"));
+        assert!(*score > 0.9 && *score < 1.0);
+
+        assert!(context.find_duplicate_candidates(1.0).unwrap().is_empty());
+        assert_eq!(levenshtein(&normalize_name("kitten"), &normalize_name("Sitting")), 3);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_reorder_labels() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        for name in ["area:ui", "docs", "severity:high"] {
+            task.add_label(name.to_string(), None, None);
+        }
+        context.create_task(task).unwrap();
+
+        context.reorder_labels("1", "severity:high", 0).unwrap();
+        context.reorder_labels("1", "docs", 2).unwrap();
+        let task = context.find_task("1").unwrap().unwrap();
+        let names = task.get_labels().as_ref().unwrap().iter().map(|label| label.get_name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["severity:high", "area:ui", "docs"]);
+
+        assert_eq!(context.reorder_labels("1", "docs", 3), Err("Label index 3 is out of range, task has 3 labels".to_string()));
+        assert_eq!(context.reorder_labels("1", "bug", 0), Err("Label with name 'bug' not found".to_string()));
+        assert!(context.reorder_labels("2", "docs", 0).is_err());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_store() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        assert!(context.validate_store().unwrap().is_empty());
+        context.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        context.create_task(Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert!(context.validate_store().unwrap().is_empty());
+
+        context.commit_tasks(|repo, source_tree| {
+            let mut treebuilder = repo.treebuilder(source_tree).unwrap();
+            let oid = repo.blob(&[0xff, 0xfe, 0x00, 0x01]).unwrap();
+            treebuilder.insert("7", oid, FileMode::Blob.into()).unwrap();
+            let oid = repo.blob(br#"{"action": "update"}"#).unwrap();
+            treebuilder.insert("action-9", oid, FileMode::Blob.into()).unwrap();
+            Ok((treebuilder.write().unwrap(), "Add broken blobs".to_string()))
+        }).unwrap();
+
+        assert_eq!(context.validate_store().unwrap(), vec!["7", "action-9"]);
+        assert!(context.take_warnings().is_empty());
+        assert_eq!(context.list_tasks().unwrap().len(), 2);
+        let warnings = context.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Skipped task entry 7, it can't be read"), "{}", warnings[0]);
+        assert!(warnings[1].starts_with("Skipped task entry action-9, it can't be read"), "{}", warnings[1]);
+        assert_eq!(context.list_task_summaries().unwrap().len(), 2);
+        assert_eq!(context.take_warnings().len(), 2);
+        assert_eq!(context.export_tasks(&TaskFilter::default(), None, false).unwrap().matches("\"id\"").count(), 2);
+        assert_eq!(context.take_warnings().len(), 2);
+        assert!(context.find_task("7").is_err());
+        assert!(context.find_task("1").unwrap().is_some());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_reserved_task_ids() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.create_task(Task::new("Plain".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        let mut task = Task::new("Tricky".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_id("action-5".to_string());
+        context.create_task(task).unwrap();
+
+        for id in ["comments-1", "shard-3"] {
+            let mut task = Task::new("Reserved".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_id(id.to_string());
+            assert_eq!(context.create_task(task.clone()).err().unwrap(), format!("Task ID {id} is reserved by the task store"));
+            assert!(context.import_tasks(vec![task], true, false).is_err());
+        }
+        assert!(context.update_task_id("action-5", "shard-3").is_err());
+        assert_eq!(context.update_task_id("action-5", "1").err().unwrap(), "Task ID 1 already exists");
+        context.update_task_id("action-5", "action-5").unwrap();
+
+        assert_eq!(context.find_task("action-5").unwrap().unwrap().get_property("name").unwrap(), "Tricky");
+        assert!(matches!(context.find_task_by_prefix("action").unwrap(), FindResult::Unique(task) if task.get_id().unwrap() == "action-5"));
+        let ids = context.list_tasks().unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "action-5"]);
+        assert_eq!(context.count_tasks().unwrap(), 2);
+        assert!(context.validate_store().unwrap().is_empty());
+        let changes = context.changelog(0, u64::MAX).unwrap().into_iter().map(|(_, _, _, id)| id).collect::<Vec<_>>();
+        assert_eq!(changes, vec!["1", "action-5"]);
+        assert_eq!(context.create_task(Task::new("Next".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap(), "2");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_comment_id_after_delete() {
+        let mut task = Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        for text in ["First", "Second", "Third"] {
+            task.add_comment(None, HashMap::new(), text.to_string(), None);
+        }
+        task.delete_comment(&"2".to_string()).unwrap();
+
+        let comment = task.add_comment(None, HashMap::new(), "Fourth".to_string(), None);
+        assert_eq!(comment.get_id().unwrap(), "4");
+        let ids = task.get_comments().as_ref().unwrap().iter().map(|c| c.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "3", "4"]);
+
+        task.delete_comment(&"1".to_string()).unwrap();
+        let comment = task.add_comment(None, HashMap::new(), "Fifth".to_string(), None);
+        assert_eq!(comment.get_id().unwrap(), "5");
+    }
+
+    #[test]
+    fn test_delete_matching() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.set_config_value("task.comments.split", "true").unwrap();
+
+        for (name, status) in [("First", "CLOSED"), ("Second", "OPEN"), ("Third", "CLOSED"), ("Fourth", "CLOSED")] {
+            context.create_task(Task::new(name.to_string(), String::new(), status.to_string(), None).unwrap()).unwrap();
+        }
+        let mut task = context.find_task("3").unwrap().unwrap();
+        task.add_comment(None, HashMap::new(), "Done".to_string(), None);
+        context.update_task(task).unwrap();
+
+        let filter = TaskFilter { statuses: Some(vec!["CLOSED".to_string()]), ..Default::default() };
+        let head = repo.refname_to_id(&context.get_ref_path()).unwrap();
+        assert!(repo.find_commit(head).unwrap().tree().unwrap().get_name("comments-3").is_some());
+        assert_eq!(context.delete_matching(&filter, true).unwrap(), vec!["1", "3", "4"]);
+        assert_eq!(context.list_tasks().unwrap().len(), 4);
+        assert_eq!(repo.refname_to_id(&context.get_ref_path()).unwrap(), head);
+
+        assert_eq!(context.delete_matching(&filter, false).unwrap(), vec!["1", "3", "4"]);
+        let remaining = context.list_tasks().unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(remaining, vec!["2"]);
+        let commit = repo.find_commit(repo.refname_to_id(&context.get_ref_path()).unwrap()).unwrap();
+        assert_eq!(commit.parent_id(0).unwrap(), head);
+        assert!(commit.tree().unwrap().get_name("comments-3").is_none());
+
+        assert!(context.delete_matching(&filter, false).unwrap().is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        assert_eq!(format_timestamp(1709287200, "epoch"), "1709287200");
+        assert_eq!(format_timestamp(1709287200, "rfc3339"), "2024-03-01T10:00:00Z");
+        assert_eq!(parse_timestamp("1709287200"), Some(1709287200));
+        assert_eq!(parse_timestamp("2024-03-01T10:00:00Z"), Some(1709287200));
+        assert_eq!(parse_timestamp("2024-03-01T12:00:00+02:00"), Some(1709287200));
+        assert_eq!(parse_timestamp("yesterday"), None);
+
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.set_config_value("task.time.format", "rfc3339").unwrap();
+
+        let mut task = Task::new("Readable".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_property("created", "1709287200");
+        task.add_comment(None, HashMap::from([("created".to_string(), "1709290800".to_string())]), "Note".to_string(), None);
+        let task = context.create_task(task).unwrap();
+        let stored = context.find_task(&task.get_id().unwrap()).unwrap().unwrap();
+        assert_eq!(stored.get_property("created").unwrap(), "2024-03-01T10:00:00Z");
+        assert_eq!(stored.get_comments().as_ref().unwrap()[0].get_all_properties().get("created").unwrap(), "2024-03-01T11:00:00Z");
+
+        let filter = TaskFilter { created_from: Some(1709287200), created_until: Some(1709287200), ..Default::default() };
+        assert!(filter.matches(&stored));
+
+        context.set_config_value("task.time.format", "epoch").unwrap();
+        context.update_task(stored).unwrap();
+        let stored = context.find_task(&task.get_id().unwrap()).unwrap().unwrap();
+        assert_eq!(stored.get_property("created").unwrap(), "1709287200");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_tasks_changed_between() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for name in ["First", "Second"] {
+            context.create_task(Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        }
+
+        let ref_path = context.get_ref_path();
+        let head = repo.find_reference(&ref_path).unwrap().peel_to_commit().unwrap();
+        let mut parent: Option<Commit> = None;
+        for (i, commit) in [head.parent(0).unwrap(), head].into_iter().enumerate() {
+            let signature = Signature::new("Someone", "someone@example.com", &Time::new(1700000000 + i as i64 * 10, 0)).unwrap();
+            let parents = parent.iter().collect::<Vec<_>>();
+            let oid = repo.commit(None, &signature, &signature, commit.message().unwrap(), &commit.tree().unwrap(), &parents).unwrap();
+            parent = Some(repo.find_commit(oid).unwrap());
+        }
+        repo.reference(&ref_path, parent.unwrap().id(), true, "Backdate").unwrap();
+
+        let started = get_current_timestamp();
+        let mut first = context.find_task("1").unwrap().unwrap();
+        first.set_property("description", "Updated");
+        context.update_task(first.clone()).unwrap();
+        first.set_property("status", "CLOSED");
+        context.update_task(first).unwrap();
+        context.create_task(Task::new("Third".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+
+        let changed = context.tasks_changed_between(started, u64::MAX).unwrap().into_iter()
+            .map(|(task, action)| (task.get_id().unwrap(), action))
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![("1".to_string(), TaskAction::UpdateStatus), ("3".to_string(), TaskAction::Create)]);
+
+        let changed = context.tasks_changed_between(1700000000, 1700000005).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].0.get_property("name").unwrap(), "First");
+        assert_eq!(changed[0].0.get_property("status").unwrap(), "OPEN");
+        assert_eq!(changed[0].1, TaskAction::Create);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_mailmap_author() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "jdoe").unwrap();
+        config.set_str("user.email", "jdoe@old.example.com").unwrap();
+        std::fs::write(repo_dir.join(".mailmap"), "Jane Doe <jane@example.com> <jdoe@old.example.com>\n").unwrap();
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        assert_eq!(context.get_current_user().unwrap(), Some("Jane Doe".to_string()));
+
+        let task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), context.get_current_user().unwrap()).unwrap();
+        context.create_task(task).unwrap();
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property("author").unwrap(), "Jane Doe");
+
+        let head = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.author().email().unwrap(), "jdoe@old.example.com");
+        assert_eq!(context.changelog(0, u64::MAX).unwrap()[0].1, "Jane Doe");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_uninitialized_repo() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        assert!(context.list_tasks().unwrap().is_empty());
+        assert!(context.list_tasks_include_deleted().unwrap().is_empty());
+        assert!(context.list_task_summaries().unwrap().is_empty());
+        assert!(context.find_task("1").unwrap().is_none());
+        assert!(context.delete_tasks(&["1", "2"]).is_ok());
+        assert_eq!(context.clear_tasks().unwrap(), 0);
+        assert!(repo.find_reference(&context.get_ref_path()).is_err());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_tasks() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Crash on start".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_comment(None, HashMap::new(), "Seen on Linux".to_string(), None);
+        task.add_label("bug".to_string(), None, None);
+        context.create_task(task).unwrap();
+
+        let mut task = Task::new("App crashes".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_comment(None, HashMap::new(), "Seen on macOS".to_string(), None);
+        task.add_comment(None, HashMap::new(), "Stack trace attached".to_string(), None);
+        task.add_label("bug".to_string(), None, None);
+        task.add_label("crash".to_string(), None, None);
+        context.create_task(task).unwrap();
+
+        let mut task = Task::new("Release".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_property("depends_on", "1,2");
+        context.create_task(task).unwrap();
+
+        assert!(context.merge_tasks("1", "1").is_err());
+        context.merge_tasks("1", "2").unwrap();
+
+        let keep = context.find_task("1").unwrap().unwrap();
+        let comments = keep.get_comments().as_ref().unwrap().iter()
+            .map(|comment| (comment.get_id().unwrap(), comment.get_text()))
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec![
+            ("1".to_string(), "Seen on Linux".to_string()),
+            ("2".to_string(), "Seen on macOS".to_string()),
+            ("3".to_string(), "Stack trace attached".to_string()),
+        ]);
+        let labels = keep.get_labels().as_ref().unwrap().iter().map(|label| label.get_name()).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["bug", "crash"]);
+
+        let merged = context.find_task("2").unwrap().unwrap();
+        assert!(merged.is_deleted());
+        assert_eq!(merged.get_property("merged_into").unwrap(), "1");
+        assert!(context.list_tasks().unwrap().iter().all(|task| task.get_id().unwrap() != "2"));
+
+        assert_eq!(context.find_task("3").unwrap().unwrap().get_property("depends_on").unwrap(), "1");
+        assert!(context.merge_tasks("1", "2").is_err());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_serialize_sorted_props() {
+        let mut props = HashMap::new();
+        for name in ["zeta", "name", "status", "alpha", "created", "description", "mid"] {
+            props.insert(name.to_string(), format!("{name} value"));
+        }
+        let mut task = Task::from_properties("1".to_string(), props.clone()).unwrap();
+        task.add_comment(Some("1".to_string()), props, "Comment".to_string(), None);
+
+        let first = serde_json::to_string(&task).unwrap();
+        let reinserted = serde_json::from_str::<Task>(&first).unwrap();
+        let second = serde_json::to_string(&reinserted).unwrap();
+        assert_eq!(first, second);
+
+        let positions = ["alpha", "created", "description", "mid", "name", "status", "zeta"].iter()
+            .map(|key| first.find(&format!("\"{key}\"")).unwrap())
+            .collect::<Vec<_>>();
+        assert!(positions.is_sorted());
+        let comment = first[first.find("\"comments\"").unwrap()..].to_string();
+        assert!(comment.find("\"alpha\"").unwrap() < comment.find("\"zeta\"").unwrap());
+    }
+
+    #[test]
+    fn test_list_tasks_by_state() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for status in ["OPEN", "CLOSED", "IN_PROGRESS", "WONTFIX", "CLOSED"] {
+            context.create_task(Task::new(format!("{status} task"), String::new(), status.to_string(), None).unwrap()).unwrap();
+        }
+        context.soft_delete_task("1").unwrap();
+        let ids = |state: TaskState| {
+            let mut ids = context.list_tasks_by_state(state).unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids(TaskState::Open), vec!["3", "4"]);
+        assert_eq!(ids(TaskState::Closed), vec!["2", "5"]);
+        assert_eq!(ids(TaskState::All), vec!["2", "3", "4", "5"]);
+
+        context.set_config_value("task.status.closedlike", "WONTFIX").unwrap();
+        assert_eq!(ids(TaskState::Open), vec!["3"]);
+        assert_eq!(ids(TaskState::Closed), vec!["2", "4", "5"]);
+        assert_eq!(ids(TaskState::All), vec!["2", "3", "4", "5"]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_closed_status() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        assert!(context.is_closed_status("CLOSED"));
+        assert!(!context.is_closed_status("OPEN"));
+        assert!(!context.is_closed_status("DONE"));
+
+        context.set_config_value("task.status.closed", "DONE").unwrap();
+        assert!(context.is_closed_status("DONE"));
+        assert!(!context.is_closed_status("CLOSED"));
+
+        context.set_config_value("task.statuses", r#"[{"name":"OPEN","is_done":false},{"name":"REJECTED","is_done":true}]"#).unwrap();
+        assert!(context.is_closed_status("REJECTED"));
+
+        context.set_config_value("task.status.closedlike", "WONTFIX, CLOSED").unwrap();
+        context.set_config_value("task.status.openlike", "DONE").unwrap();
+        assert!(context.is_closed_status("WONTFIX"));
+        assert!(context.is_closed_status("CLOSED"));
+        assert!(!context.is_closed_status("DONE"));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let work = TaskContext::with_namespace(repo_dir.display().to_string(), Some("work".to_string()));
+        let personal = TaskContext::with_namespace(repo_dir.display().to_string(), Some("personal".to_string()));
+        let default = TaskContext::new(repo_dir.display().to_string());
+
+        assert_eq!(work.get_ref_path(), "refs/tasks/work");
+        work.create_task(Task::new("Report".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        work.create_task(Task::new("Review".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        personal.create_task(Task::new("Groceries".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+
+        let names = |context: &TaskContext| context.list_tasks().unwrap().iter().map(|task| task.get_property("name").unwrap().clone()).collect::<Vec<_>>();
+        let mut work_names = names(&work);
+        work_names.sort();
+        assert_eq!(work_names, vec!["Report", "Review"]);
+        assert_eq!(names(&personal), vec!["Groceries"]);
+        assert!(default.list_tasks().unwrap().is_empty());
+
+        default.create_task(Task::new("Default".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert_eq!(default.list_namespaces().unwrap(), vec!["personal", "tasks", "work"]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_strict_status() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let task = |status: &str| Task::new("Task".to_string(), String::new(), status.to_string(), None).unwrap();
+
+        context.create_task(task("OPNE")).unwrap();
+        context.set_config_value("task.status.strict", "true").unwrap();
+
+        assert!(context.create_task(task("OPEN")).is_ok());
+        assert_eq!(context.create_task(task("OPNE")).err().unwrap(), "Unknown status OPNE, did you mean OPEN?");
+        assert_eq!(context.create_task(task("in progres")).err().unwrap(), "Unknown status in progres, did you mean IN_PROGRESS?");
+        assert_eq!(context.create_task(task("WHATEVER")).err().unwrap(), "Unknown status WHATEVER. Known statuses are: OPEN, IN_PROGRESS, CLOSED");
+        assert!(context.create_task_unchecked(task("OPNE")).is_ok());
+
+        context.set_config_value("task.status.closed", "DONE").unwrap();
+        assert!(context.create_task(task("DONE")).is_ok());
+        assert_eq!(context.list_tasks().unwrap().len(), 4);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_task_history_filtered() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let id = context.create_task(Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap();
+        context.create_task(Task::new("Other".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+
+        let mut task = context.find_task(&id).unwrap().unwrap();
+        task.set_property("status", "IN_PROGRESS");
+        context.update_task(task.clone()).unwrap();
+        task.set_property("name", "Renamed");
+        context.update_task(task.clone()).unwrap();
+        task.set_property("status", "CLOSED");
+        context.update_task(task).unwrap();
+
+        let history = context.get_task_history(&id).unwrap();
+        assert_eq!(history, vec![TaskAction::Create, TaskAction::UpdateStatus, TaskAction::Update, TaskAction::UpdateStatus]);
+        assert_eq!(context.get_task_history_filtered(&id, &[TaskAction::UpdateStatus]).unwrap(), vec![TaskAction::UpdateStatus, TaskAction::UpdateStatus]);
+        assert_eq!(context.get_task_history_filtered(&id, &[TaskAction::Update, TaskAction::Create]).unwrap(), vec![TaskAction::Create, TaskAction::Update]);
+        assert!(context.get_task_history_filtered(&id, &[TaskAction::Delete]).unwrap().is_empty());
+        assert!(context.get_task_history_filtered(&id, &[]).unwrap().is_empty());
+        assert!(context.get_task_history_filtered("3", &[TaskAction::UpdateStatus]).unwrap().is_empty());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_comment_history() {
+        for split in ["false", "true"] {
+            let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+            std::fs::create_dir_all(repo_dir.clone()).unwrap();
+            let _repo = init_repo(&repo_dir);
+            let context = TaskContext::new(repo_dir.display().to_string());
+            context.set_config_value("task.comments.split", split).unwrap();
+
+            let task = context.create_task(Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+            let id = task.get_id().unwrap();
+            assert!(context.get_comment_history(&id, "1").unwrap().is_empty());
+
+            let mut task = context.find_task(&id).unwrap().unwrap();
+            task.add_comment(None, HashMap::new(), "First".to_string(), None);
+            task.add_comment(None, HashMap::new(), "Second".to_string(), None);
+            context.update_task(task).unwrap();
+
+            let mut task = context.find_task(&id).unwrap().unwrap();
+            task.set_property("name", "Renamed");
+            context.update_task(task).unwrap();
+
+            let mut task = context.find_task(&id).unwrap().unwrap();
+            let mut comments = task.get_comments().clone().unwrap();
+            comments[0].set_text("First, edited".to_string());
+            task.set_comments(comments);
+            context.update_task(task).unwrap();
+
+            let mut task = context.find_task(&id).unwrap().unwrap();
+            task.delete_comment(&"1".to_string()).unwrap();
+            context.update_task(task).unwrap();
+
+            assert_eq!(context.get_comment_history(&id, "1").unwrap(), vec![TaskAction::Create, TaskAction::Update, TaskAction::Delete]);
+            assert_eq!(context.get_comment_history(&id, "2").unwrap(), vec![TaskAction::Create]);
+            assert!(context.get_comment_history(&id, "3").unwrap().is_empty());
+
+            std::fs::remove_dir_all(repo_dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_add_comment_default_author() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Jane Doe").unwrap();
+        config.set_str("user.email", "jane@example.com").unwrap();
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let id = context.create_task(Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap();
+
+        let comment = context.add_comment(&id, HashMap::new(), "Auto".to_string(), None).unwrap();
+        assert_eq!(comment.get_all_properties().get("author").unwrap(), "Jane Doe");
+        let comment = context.add_comment(&id, HashMap::new(), "Explicit".to_string(), Some("John Roe".to_string())).unwrap();
+        assert_eq!(comment.get_all_properties().get("author").unwrap(), "John Roe");
+        let props = HashMap::from([("author".to_string(), "Imported".to_string())]);
+        let comment = context.add_comment(&id, props, "Imported".to_string(), None).unwrap();
+        assert_eq!(comment.get_all_properties().get("author").unwrap(), "Imported");
+
+        let authors = context.find_task(&id).unwrap().unwrap().get_comments().clone().unwrap().iter()
+            .map(|comment| comment.get_all_properties().get("author").unwrap().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(authors, vec!["Jane Doe", "John Roe", "Imported"]);
+        assert!(context.add_comment("2", HashMap::new(), "Missing".to_string(), None).is_err());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_hashed_comment_ids() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.set_config_value("task.comments.id", "hash").unwrap();
+        let first = context.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap();
+        let second = context.create_task(Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap();
+
+        let props = || HashMap::from([("created".to_string(), "1709290800".to_string())]);
+        let author = || Some("Jane Doe".to_string());
+        let comment = context.add_comment(&first, props(), "Same".to_string(), author()).unwrap();
+        let id = comment.get_id().unwrap();
+        assert_eq!(id.len(), 7);
+        assert_eq!(context.add_comment(&second, props(), "Same".to_string(), author()).unwrap().get_id().unwrap(), id);
+
+        assert_ne!(context.add_comment(&second, props(), "Other".to_string(), author()).unwrap().get_id().unwrap(), id);
+        assert_ne!(context.add_comment(&second, props(), "Same".to_string(), Some("John Roe".to_string())).unwrap().get_id().unwrap(), id);
+
+        let duplicate = context.add_comment(&first, props(), "Same".to_string(), author()).unwrap().get_id().unwrap();
+        assert_eq!(duplicate.len(), 8);
+        assert!(duplicate.starts_with(&id));
+
+        context.set_config_value("task.comments.id", "sequential").unwrap();
+        assert_eq!(context.add_comment(&first, props(), "Plain".to_string(), author()).unwrap().get_id().unwrap(), "3");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_tasks_to_writer() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let filter = TaskFilter::default();
+        assert_eq!(context.export_tasks(&filter, None, true).unwrap(), "[]");
+
+        context.set_config_value("task.comments.split", "true").unwrap();
+        for i in 1..=11 {
+            let status = if i % 2 == 0 { "CLOSED" } else { "OPEN" };
+            let mut task = Task::new(format!("Task {i}"), String::new(), status.to_string(), None).unwrap();
+            task.add_comment(None, HashMap::new(), format!("Comment {i}"), None);
+            context.create_task(task).unwrap();
+        }
+        context.soft_delete_task("3").unwrap();
+
+        for pretty in [true, false] {
+            let mut writer = Vec::new();
+            assert_eq!(context.export_tasks_to(&mut writer, &filter, None, pretty).unwrap(), 10);
+            let exported = String::from_utf8(writer).unwrap();
+            assert_eq!(exported, context.export_tasks(&filter, None, pretty).unwrap());
+
+            let tasks = context.find_matching(&filter).unwrap();
+            let expected = if pretty { serde_json::to_string_pretty(&tasks) } else { serde_json::to_string(&tasks) }.unwrap();
+            assert_eq!(exported, expected);
+        }
+
+        let filter = TaskFilter { statuses: Some(vec!["OPEN".to_string()]), ..TaskFilter::default() };
+        let tasks: Vec<Task> = serde_json::from_str(&context.export_tasks(&filter, Some(3), false).unwrap()).unwrap();
+        assert_eq!(tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>(), vec!["1", "5", "7"]);
+        assert_eq!(tasks[0].get_comments().as_ref().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let blob = r#"{"id":"1","props":{"name":"From the future","status":"OPEN","created":"1700000000"},
+            "comments":[{"id":"1","props":{},"text":"Hi","mood":"cheerful"}],"labels":null,
+            "checklist":[{"item":"Write tests","done":true}]}"#;
+        let task: Task = serde_json::from_str(blob).unwrap();
+        context.create_task(task).unwrap();
+
+        let mut task = context.find_task("1").unwrap().unwrap();
+        task.set_property("name", "Renamed");
+        context.update_task(task).unwrap();
+
+        let saved: serde_json::Value = serde_json::from_str(&context.get_task_json("1", false).unwrap()).unwrap();
+        assert_eq!(saved["props"]["name"], "Renamed");
+        assert_eq!(saved["checklist"], serde_json::json!([{"item": "Write tests", "done": true}]));
+        assert_eq!(saved["comments"][0]["mood"], "cheerful");
+        assert!(saved.get("extra").is_none());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_task_json() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Raw".to_string(), "Show me".to_string(), "OPEN".to_string(), None).unwrap();
+        task.add_label("debug".to_string(), None, Some("00ff00".to_string()));
+        task.add_comment(None, HashMap::new(), "First".to_string(), None);
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+
+        let pretty = context.get_task_json(&id, true).unwrap();
+        let compact = context.get_task_json(&id, false).unwrap();
+        assert!(pretty.contains('\n'));
+        assert!(!compact.contains('\n'));
+
+        let original = context.find_task(&id).unwrap().unwrap();
+        let parsed: Task = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(parsed.get_id(), original.get_id());
+        assert_eq!(parsed.get_all_properties(), original.get_all_properties());
+        assert!(parsed.get_comments() == original.get_comments());
+        assert_eq!(parsed.get_labels(), original.get_labels());
+
+        assert_eq!(context.get_task_json("42", true).unwrap_err(), "Task 42 not found");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_task_bundle() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Bundled".to_string(), "Details".to_string(), "OPEN".to_string(), None).unwrap();
+        task.add_comment(None, HashMap::new(), "First".to_string(), Some("Jane".to_string()));
+        task.add_label("bug".to_string(), Some("Broken".to_string()), Some("ff0000".to_string()));
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+        let mut stored = context.find_task(&id).unwrap().unwrap();
+        stored.set_property("status", "CLOSED");
+        context.update_task(stored).unwrap();
+
+        let bundle = context.export_task_bundle(&id, false).unwrap();
+        assert!(!bundle.contains("history"));
+        let with_history = context.export_task_bundle(&id, true).unwrap();
+        let history = serde_json::from_str::<serde_json::Value>(&with_history).unwrap()["history"].as_array().unwrap().len();
+        assert_eq!(history, 2);
+        assert!(context.export_task_bundle("42", false).is_err());
+
+        let new_id = context.import_task_bundle(&with_history).unwrap();
+        assert_ne!(new_id, id);
+        let original = context.find_task(&id).unwrap().unwrap();
+        let imported = context.find_task(&new_id).unwrap().unwrap();
+        assert_eq!(imported.get_all_properties(), original.get_all_properties());
+        assert!(imported.get_comments() == original.get_comments());
+        assert_eq!(imported.get_labels(), original.get_labels());
+
+        let other_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(other_dir.clone()).unwrap();
+        let _other_repo = init_repo(&other_dir);
+        let other = TaskContext::new(other_dir.display().to_string());
+        assert_eq!(other.import_task_bundle(&bundle).unwrap(), id);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+        std::fs::remove_dir_all(other_dir).unwrap();
+    }
+
+    #[test]
+    fn test_in_memory_store() {
+        let store = InMemoryTaskStore::new();
+        for (name, status, author) in [("First", "OPEN", "jane"), ("Second", "CLOSED", "john"), ("Third", "OPEN", "jane"), ("Fourth", "OPEN", "jane")] {
+            let task = Task::new(name.to_string(), String::new(), status.to_string(), Some(author.to_string())).unwrap();
+            store.create_task(task).unwrap();
+        }
+        let mut task = store.find_task("4").unwrap().unwrap();
+        task.set_property(DELETED, "true");
+        store.update_task(task).unwrap();
+        store.create_task(Task::from_properties("10".to_string(), HashMap::from([
+            (NAME.to_string(), "Tenth".to_string()),
+            (STATUS.to_string(), "OPEN".to_string()),
+        ])).unwrap()).unwrap();
+
+        let filter = TaskFilter { statuses: Some(vec!["OPEN".to_string()]), ..Default::default() };
+        let ids = store.find_matching(&filter).unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "3", "10"]);
+        let filter = TaskFilter { author: Some("JOHN".to_string()), ..Default::default() };
+        assert_eq!(store.find_matching(&filter).unwrap().len(), 1);
+
+        let stats = store.get_stats().unwrap();
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.by_status, HashMap::from([("OPEN".to_string(), 3), ("CLOSED".to_string(), 1)]));
+        assert_eq!(stats.by_author, HashMap::from([("jane".to_string(), 2), ("john".to_string(), 1)]));
+
+        store.delete_tasks(&["1", "10"]).unwrap();
+        assert_eq!(store.get_stats().unwrap().total, 2);
+        assert!(store.find_task("1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_updated_timestamp() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Task".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_property(CREATED, "1000");
+        let task = context.create_task(task).unwrap();
+        assert_eq!(task.get_updated(), Some(1000));
+
+        let mut task = context.find_task(&task.get_id().unwrap()).unwrap().unwrap();
+        assert_eq!(task.get_updated(), Some(1000));
+        task.set_property(NAME, "Renamed task");
+        context.update_task(task.clone()).unwrap();
+
+        let task = context.find_task(&task.get_id().unwrap()).unwrap().unwrap();
+        assert!(task.get_updated().unwrap() > 1000);
+        assert_eq!(task.get_property(CREATED).unwrap(), "1000");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_task_by_prefix() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for id in ["1", "120", "125", "abc1"] {
+            let mut task = Task::new(format!("Task {id}"), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_id(id.to_string());
+            task.add_comment(None, HashMap::new(), "Comment".to_string(), None);
+            context.create_task(task).unwrap();
+        }
+        context.set_config_value("task.comments.split", "true").unwrap();
+        context.split_comments().unwrap();
+
+        match context.find_task_by_prefix("ab").unwrap() {
+            FindResult::Unique(task) => assert_eq!(task.get_id().unwrap(), "abc1"),
+            _ => panic!("Expected a unique match"),
+        }
+        match context.find_task_by_prefix("1").unwrap() {
+            FindResult::Unique(task) => assert_eq!(task.get_id().unwrap(), "1"),
+            _ => panic!("Expected an exact match"),
+        }
+        match context.find_task_by_prefix("12").unwrap() {
+            FindResult::Ambiguous(ids) => assert_eq!(ids, vec!["120", "125"]),
+            _ => panic!("Expected an ambiguous match"),
+        }
+        assert!(matches!(context.find_task_by_prefix("7").unwrap(), FindResult::NotFound));
+        assert!(matches!(context.find_task_by_prefix("comm").unwrap(), FindResult::NotFound));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let make_context = || {
+            let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+            std::fs::create_dir_all(repo_dir.clone()).unwrap();
+            let _repo = init_repo(&repo_dir);
+            (repo_dir.clone(), TaskContext::new(repo_dir.display().to_string()))
+        };
+        let (source_dir, source) = make_context();
+        let (target_dir, target) = make_context();
+
+        let recorder = RecordingTaskStore::new(source.clone());
+        let first = recorder.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        let mut second = recorder.create_task(Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        recorder.create_task(Task::new("Third".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        second.set_property(STATUS, "CLOSED");
+        second.add_label("done".to_string(), None, None);
+        recorder.update_task(second).unwrap();
+        recorder.delete_tasks(&[&first.get_id().unwrap()]).unwrap();
+        assert_eq!(recorder.get_operations().len(), 5);
+
+        target.replay(&recorder.get_log().unwrap()).unwrap();
+
+        let summarize = |context: &TaskContext| context.list_tasks().unwrap().iter()
+            .map(|task| (task.get_id().unwrap(), task.get_property(NAME).unwrap().clone(), task.get_property(STATUS).unwrap().clone(), task.get_labels().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(summarize(&target), summarize(&source));
+        assert_eq!(summarize(&target).len(), 2);
+        assert!(target.replay("not a log").is_err());
+
+        std::fs::remove_dir_all(source_dir).unwrap();
+        std::fs::remove_dir_all(target_dir).unwrap();
+    }
 
-        let current_reference = repo.find_reference(&self.get_ref_path());
-        if let Ok(current_reference) = &current_reference {
-            let commit = map_err!(current_reference.peel_to_commit());
-            map_err!(repo.reference(ref_path, commit.id(), true, "task.ref migrated"));
+    #[test]
+    fn test_signed_commits() {
+        if Command::new("gpg").arg("--version").output().is_err() {
+            return;
         }
 
-        let mut config = map_err!(repo.config());
-        map_err!(config.set_str("task.ref", ref_path));
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        let gpg_home = repo_dir.join("gnupg");
+        std::fs::create_dir_all(gpg_home.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
 
-        if move_ref && current_reference.is_ok() {
-            map_err!(current_reference.unwrap().delete());
+        let generated = Command::new("gpg")
+            .args(["--homedir", &gpg_home.display().to_string(), "--batch", "--passphrase", "", "--quick-gen-key", "Test <test@example.com>", "ed25519", "sign", "never"])
+            .output()
+            .unwrap();
+        assert!(generated.status.success());
+        let gpg_program = repo_dir.join("gpg.sh");
+        std::fs::write(&gpg_program, format!("#!/bin/sh\nexec gpg --homedir {} \"$@\"\n", gpg_home.display())).unwrap();
+        Command::new("chmod").arg("+x").arg(&gpg_program).status().unwrap();
+
+        let task = Task::new("Unsigned".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+        let head = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap().id();
+        assert!(repo.extract_signature(&head, None).is_err());
+
+        context.set_config_value("task.commit.sign", "true").unwrap();
+        context.set_config_value("gpg.program", &gpg_program.display().to_string()).unwrap();
+        let task = Task::new("Still unsigned, no key".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+        let head = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap().id();
+        assert!(repo.extract_signature(&head, None).is_err());
+
+        context.set_config_value("user.signingkey", "test@example.com").unwrap();
+        let task = Task::new("Signed".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+        context.delete_tasks(&[&id]).unwrap();
+        let head = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap();
+        let (signature, _) = repo.extract_signature(&head.id(), None).unwrap();
+        assert!(signature.as_str().unwrap().starts_with("-----BEGIN PGP SIGNATURE-----"));
+        assert_eq!(head.parent(0).unwrap().message().unwrap(), format!("Create task {id}"));
+        assert!(repo.extract_signature(&head.parent_id(0).unwrap(), None).is_ok());
+
+        let _ = Command::new("gpgconf").args(["--homedir", &gpg_home.display().to_string(), "--kill", "gpg-agent"]).status();
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_scoped_labels() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut task = Task::new("Scoped".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_label("bug".to_string(), None, None);
+        task.add_scoped_label("priority::low".to_string(), None, None);
+        task.add_scoped_label("Priority::high".to_string(), None, None);
+        task.add_scoped_label("team::ui::web".to_string(), None, None);
+        let names = task.get_labels().as_ref().unwrap().iter().map(|label| label.get_name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["bug", "Priority::high", "team::ui::web"]);
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+        let task = Task::new("Unscoped".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+
+        let found = context.find_tasks_by_label("priority::").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get_id().unwrap(), id);
+        assert_eq!(context.find_tasks_by_label("team::ui::").unwrap().len(), 1);
+        assert!(context.find_tasks_by_label("team::").unwrap().is_empty());
+        assert!(context.find_tasks_by_label("priority::low").unwrap().is_empty());
+
+        let filter = TaskFilter::default();
+        let medium = Label::new("priority::medium".to_string(), None, None);
+        context.label_matching(&filter, &medium, false).unwrap();
+        assert_eq!(context.find_tasks_by_label("priority::").unwrap().len(), 2);
+        assert_eq!(context.find_tasks_by_label("priority::high").unwrap().len(), 1);
+
+        context.set_config_value("task.labels.scoped", "true").unwrap();
+        let low = Label::new("priority::low".to_string(), None, None);
+        context.label_matching(&filter, &low, false).unwrap();
+        assert_eq!(context.find_tasks_by_label("priority::low").unwrap().len(), 2);
+        assert!(context.find_tasks_by_label("priority::medium").unwrap().is_empty());
+        assert!(context.find_tasks_by_label("priority::high").unwrap().is_empty());
+        assert_eq!(context.find_task(&id).unwrap().unwrap().get_labels().as_ref().unwrap().len(), 3);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_label_definitions() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        assert!(context.list_label_definitions().unwrap().is_empty());
+        context.define_label(Label::new("bug".to_string(), Some("d73a4a".to_string()), Some("Something isn't working".to_string()))).unwrap();
+        context.define_label(Label::new("docs".to_string(), Some("0075ca".to_string()), None)).unwrap();
+        context.define_label(Label::new("bug".to_string(), Some("ff0000".to_string()), Some("Broken".to_string()))).unwrap();
+        assert_eq!(context.list_label_definitions().unwrap().len(), 2);
+
+        let mut task = Task::new("Registry".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let label = context.add_label(&mut task, "bug".to_string(), None, None);
+        assert_eq!(label.get_color(), "ff0000");
+        assert_eq!(label.get_description().unwrap(), "Broken");
+        let label = context.add_label(&mut task, "docs".to_string(), Some("Manual".to_string()), Some("00ff00".to_string()));
+        assert_eq!(label.get_color(), "00ff00");
+        let label = context.add_label(&mut task, "unknown".to_string(), None, None);
+        assert_eq!(label.get_color(), "");
+        assert_eq!(task.get_label_by_name("bug").unwrap().get_color(), "ff0000");
+
+        let id = context.create_task(Task::new("Bulk".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap();
+        context.label_matching(&TaskFilter::default(), &Label::new("docs".to_string(), None, None), false).unwrap();
+        assert_eq!(context.find_task(&id).unwrap().unwrap().get_label_by_name("docs").unwrap().get_color(), "0075ca");
+
+        context.delete_label_definition("bug").unwrap();
+        assert!(context.delete_label_definition("bug").is_err());
+        assert_eq!(context.list_label_definitions().unwrap(), vec![Label::new("docs".to_string(), Some("0075ca".to_string()), None)]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_sharded_layout() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let root = || repo.find_reference(&context.get_ref_path()).unwrap().peel_to_tree().unwrap();
+
+        for i in 1..=12 {
+            let mut task = Task::new(format!("Flat {i}"), String::new(), "OPEN".to_string(), None).unwrap();
+            task.add_comment(None, HashMap::new(), format!("Comment {i}"), None);
+            context.create_task(task).unwrap();
         }
+        context.set_config_value("task.comments.split", "true").unwrap();
+        context.split_comments().unwrap();
+        context.set_config_value("task.comments.split", "false").unwrap();
+
+        context.set_config_value("task.store.layout", "sharded").unwrap();
+        let mut task = Task::new("Sharded".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.add_label("new".to_string(), None, None);
+        let task = context.create_task(task).unwrap();
+        assert_eq!(task.get_id().unwrap(), "13");
+        assert!(root().get_name("13").is_none());
+        assert!(root().get_path(std::path::Path::new("shard-13/13")).is_ok());
+        assert_eq!(context.find_task("13").unwrap().unwrap().get_property(NAME).unwrap(), "Sharded");
+        assert_eq!(context.list_tasks().unwrap().len(), 13);
+
+        let mut task = context.find_task("5").unwrap().unwrap();
+        task.set_property(NAME, "Moved on update");
+        context.update_task(task).unwrap();
+        assert!(root().get_name("5").is_none());
+        assert!(root().get_path(std::path::Path::new("shard-5/comments-5")).is_ok());
+        assert_eq!(context.find_task("5").unwrap().unwrap().get_comments().as_ref().unwrap().len(), 1);
+
+        assert_eq!(context.migrate_store_layout().unwrap(), 11);
+        assert_eq!(context.migrate_store_layout().unwrap(), 0);
+        assert!(root().iter().all(|entry| entry.name().unwrap().starts_with(SHARD_PREFIX)));
+        let ids = context.list_tasks().unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>();
+        assert_eq!(sort_ids(&ids.iter().map(|id| id.as_str()).collect::<Vec<_>>()), (1..=13).map(|i| i.to_string()).collect::<Vec<_>>());
+        assert_eq!(context.find_task("12").unwrap().unwrap().get_comments().as_ref().unwrap()[0].get_text(), "Comment 12");
+        assert_eq!(context.get_comments_page("12", 0, 10).unwrap().len(), 1);
+        assert!(context.validate_store().unwrap().is_empty());
+        assert!(matches!(context.find_task_by_prefix("1").unwrap(), FindResult::Unique(_)));
+        assert!(matches!(context.find_task_by_prefix("Flat").unwrap(), FindResult::NotFound));
+
+        context.delete_tasks(&["13"]).unwrap();
+        assert!(root().get_name("shard-13").is_none());
+        assert!(context.delete_tasks(&["13"]).is_err());
+        let task = Task::new("Next".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        assert_eq!(context.create_task(task).unwrap().get_id().unwrap(), "13");
+
+        context.set_config_value("task.store.layout", "flat").unwrap();
+        assert_eq!(context.migrate_store_layout().unwrap(), 13);
+        assert!(root().iter().all(|entry| !entry.name().unwrap().starts_with(SHARD_PREFIX)));
+        assert_eq!(context.list_tasks().unwrap().len(), 13);
+        assert!(root().get_name("comments-12").is_some());
 
-        Ok(())
+        std::fs::remove_dir_all(repo_dir).unwrap();
     }
-}
-fn get_current_timestamp() -> u64 {
-    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
-}
 
-#[cfg(test)]
-mod test {
-    use crate::*;
-    use git2::Repository;
-    use std::collections::HashMap;
-    use std::env::temp_dir;
-    use uuid::Uuid;
+    #[test]
+    fn test_query_tasks_counted() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        assert_eq!(context.count_tasks().unwrap(), 0);
+
+        for (name, status) in [("One", "OPEN"), ("Two", "CLOSED"), ("Three", "OPEN"), ("Four", "CLOSED"), ("Five", "OPEN")] {
+            let task = Task::new(name.to_string(), String::new(), status.to_string(), None).unwrap();
+            context.create_task(task).unwrap();
+        }
+        context.set_config_value("task.store.layout", "sharded").unwrap();
+        let task = Task::new("Six".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        context.create_task(task).unwrap();
+
+        let filter = TaskFilter { statuses: Some(vec!["OPEN".to_string()]), ..Default::default() };
+        let (tasks, filtered_count, total_count) = context.query_tasks_counted(&filter).unwrap();
+        assert_eq!(tasks.iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>(), vec!["1", "3", "5", "6"]);
+        assert_eq!(filtered_count, 4);
+        assert_eq!(total_count, 6);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
 
     #[test]
-    fn test_ref_path() {
+    fn test_task_lock() {
         let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
         std::fs::create_dir_all(repo_dir.clone()).unwrap();
-        let repo = Repository::init(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
         let context = TaskContext::new(repo_dir.display().to_string());
+        let lock_path = repo_dir.join(".git").join(LOCK_FILE);
+        let new_task = |name: &str| Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+
+        let lock = context.lock().unwrap();
+        assert!(lock_path.exists());
+        let writer = {
+            let context = context.clone();
+            let task = new_task("Blocked");
+            std::thread::spawn(move || {
+                let started = Instant::now();
+                context.create_task(task).unwrap();
+                started.elapsed()
+            })
+        };
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(context.list_tasks().unwrap().is_empty());
+        drop(lock);
+        assert!(writer.join().unwrap() >= Duration::from_millis(300));
+        assert!(!lock_path.exists());
+
+        let writers = (0..2).map(|i| {
+            let context = context.clone();
+            std::thread::spawn(move || {
+                for j in 0..10 {
+                    let task = Task::new(format!("Writer {i} task {j}"), String::new(), "OPEN".to_string(), None).unwrap();
+                    context.create_task(task).unwrap();
+                }
+            })
+        }).collect::<Vec<_>>();
+        writers.into_iter().for_each(|writer| writer.join().unwrap());
+        assert_eq!(context.list_tasks().unwrap().len(), 21);
+
+        let reentrant = context.lock().unwrap();
+        assert!(context.create_task(new_task("Reentrant")).is_ok());
+        assert!(lock_path.exists());
+        drop(reentrant);
+        assert!(!lock_path.exists());
+
+        context.set_config_value("task.lock.timeout", "0").unwrap();
+        let alive = std::process::id();
+        std::fs::write(&lock_path, alive.to_string()).unwrap();
+        let error = context.create_task(new_task("Timed out")).err().unwrap();
+        assert!(error.contains(&format!("is held by process {alive}")));
+
+        if Path::new("/proc/self").exists() {
+            let mut child = Command::new("true").spawn().unwrap();
+            child.wait().unwrap();
+            std::fs::write(&lock_path, child.id().to_string()).unwrap();
+            assert!(context.lock().is_ok());
+            assert!(!lock_path.exists());
+            std::fs::write(&lock_path, alive.to_string()).unwrap();
+        }
 
-        let ref_path = context.get_ref_path();
-        assert!(context.set_ref_path("refs/heads/test-git-task", true).is_ok());
-        assert_eq!(context.get_ref_path(), "refs/heads/test-git-task");
-        assert!(context.set_ref_path(&ref_path, true).is_ok());
-        assert_eq!(context.get_ref_path(), ref_path);
+        std::fs::File::options().write(true).open(&lock_path).unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(LOCK_STALE_AFTER + 1)).unwrap();
+        assert!(context.create_task(new_task("Stale")).is_ok());
+        assert!(!lock_path.exists());
+        assert_eq!(context.list_tasks().unwrap().len(), 23);
+
+        let make_stale = || std::fs::File::options().write(true).open(&lock_path).unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(LOCK_STALE_AFTER + 1)).unwrap();
+        let held = context.lock().unwrap();
+        make_stale();
+        assert!(context.create_task(new_task("Refreshed")).is_ok());
+        assert!(!is_stale_lock(&lock_path));
+        drop(held);
+
+        std::fs::write(&lock_path, "4242").unwrap();
+        make_stale();
+        context.set_config_value("task.lock.timeout", "10").unwrap();
+        let writers = (0..4).map(|i| {
+            let context = context.clone();
+            std::thread::spawn(move || context.create_task(Task::new(format!("Takeover {i}"), String::new(), "OPEN".to_string(), None).unwrap()).is_ok())
+        }).collect::<Vec<_>>();
+        assert!(writers.into_iter().all(|writer| writer.join().unwrap()));
+        assert!(!lock_path.exists());
+        assert_eq!(context.list_tasks().unwrap().len(), 28);
+        assert_eq!(std::fs::read_dir(repo_dir.join(".git")).unwrap().filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(LOCK_FILE)).count(), 0);
 
-        assert!(repo.is_empty().unwrap());
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        assert!(context.snapshot("empty").is_err());
+
+        context.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        context.create_task(Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        context.snapshot("v1.0").unwrap();
+        assert_eq!(context.snapshot("v1.0").unwrap_err(), "Snapshot v1.0 already exists");
+        assert!(context.snapshot("bad..name").is_err());
+
+        let mut task = context.find_task("1").unwrap().unwrap();
+        task.set_property(NAME, "Renamed");
+        context.update_task(task).unwrap();
+        context.delete_tasks(&["2"]).unwrap();
+        context.create_task(Task::new("Third".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+
+        let snapshots = context.list_snapshots().unwrap();
+        assert_eq!(snapshots.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["v1.0"]);
+        assert_eq!(context.list_namespaces().unwrap(), vec!["tasks"]);
+
+        assert_eq!(context.checkout_snapshot("v2.0").unwrap_err(), "Snapshot v2.0 not found");
+        context.checkout_snapshot("v1.0").unwrap();
+        let mut names = context.list_tasks().unwrap().iter().map(|task| task.get_property(NAME).unwrap().clone()).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["First", "Second"]);
+        assert_eq!(next_id(&context), "3");
+        assert!(Repository::open(&repo_dir).unwrap().find_reference("refs/tasks-snapshots/tasks/tasks/v1.0").is_ok());
+
+        let work = TaskContext::with_namespace(repo_dir.display().to_string(), Some("work".to_string()));
+        work.create_task(Task::new("Elsewhere".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert!(work.list_snapshots().unwrap().is_empty());
+        assert_eq!(work.checkout_snapshot("v1.0").unwrap_err(), "Snapshot v1.0 not found");
+        work.snapshot("v1.0").unwrap();
+        context.checkout_snapshot("v1.0").unwrap();
+        assert_eq!(context.list_tasks().unwrap().len(), 2);
+        assert_eq!(context.list_namespaces().unwrap(), vec!["tasks", "work"]);
 
         std::fs::remove_dir_all(repo_dir).unwrap();
     }
 
     #[test]
-    fn test_create_update_delete_task() {
+    fn test_has_tasks() {
         let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
         std::fs::create_dir_all(repo_dir.clone()).unwrap();
-        let _repo = Repository::init(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
         let context = TaskContext::new(repo_dir.display().to_string());
+        assert!(!context.has_tasks().unwrap());
 
-        let id = context.get_next_id().unwrap_or_else(|_| "1".to_string());
-        let task = Task::construct_task(
-            "Test task".to_string(),
-            "Description goes here".to_string(),
-            "OPEN".to_string(),
-            context.get_current_user().unwrap(),
-            Some(get_current_timestamp()));
-        let create_result = context.create_task(task);
-        assert!(create_result.is_ok());
-        let mut task = create_result.unwrap();
-        assert_eq!(task.get_id(), Some(id.clone()));
-        assert_eq!(task.get_property("name").unwrap(), "Test task");
-        assert_eq!(task.get_property("description").unwrap(), "Description goes here");
-        assert_eq!(task.get_property("status").unwrap(), "OPEN");
-        assert!(task.has_property("created"));
+        context.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert!(context.has_tasks().unwrap());
 
-        task.set_property("description", "Updated description");
-        let comment_props = HashMap::from([("author".to_string(), "Some developer".to_string())]);
-        task.add_comment(None, comment_props, "This is a comment".to_string(), context.get_current_user().unwrap());
-        task.set_property("custom_prop", "Custom content");
-        let update_result = context.update_task(task);
-        assert!(update_result.is_ok());
-        assert_eq!(update_result.unwrap(), id.clone());
+        context.clear_tasks().unwrap();
+        assert!(!context.has_tasks().unwrap());
 
-        let find_result = context.find_task(&id);
-        assert!(find_result.is_ok());
-        let task = find_result.unwrap();
-        assert!(task.is_some());
-        let task = task.unwrap();
-        assert_eq!(task.get_id(), Some(id.clone()));
-        assert_eq!(task.get_property("description").unwrap(), "Updated description");
-        let comments = task.get_comments().clone();
-        assert!(comments.is_some());
-        let comments = comments.unwrap();
-        assert_eq!(comments.len(), 1);
-        let comment = comments.first().unwrap();
-        assert_eq!(comment.get_text(), "This is a comment".to_string());
-        let comment_props = comment.clone().props;
-        assert_eq!(comment_props.get("author").unwrap(), &"Some developer".to_string());
-        assert_eq!(task.get_property("custom_prop").unwrap(), "Custom content");
+        context.set_config_value("task.store.layout", "sharded").unwrap();
+        context.create_task(Task::new("Sharded".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert!(context.has_tasks().unwrap());
 
-        let delete_result = context.delete_tasks(&[&id]);
-        assert!(delete_result.is_ok());
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
 
-        let find_result = context.find_task(&id);
-        assert!(find_result.is_ok());
-        let task = find_result.unwrap();
-        assert!(task.is_none());
+    #[test]
+    fn test_update_tasks() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        for name in ["First", "Second", "Third"] {
+            context.create_task(Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        }
+        let batch = |status: &str| {
+            let mut tasks = context.list_tasks().unwrap();
+            tasks.iter_mut().for_each(|task| task.set_property(STATUS, status));
+            let mut missing = Task::new("Missing".to_string(), String::new(), status.to_string(), None).unwrap();
+            missing.set_id("42".to_string());
+            tasks.insert(1, missing);
+            tasks
+        };
+        let statuses = || {
+            let mut statuses = context.list_tasks().unwrap().iter().map(|task| task.get_property(STATUS).unwrap().clone()).collect::<Vec<_>>();
+            statuses.dedup();
+            statuses
+        };
+
+        assert_eq!(context.update_tasks(batch("CLOSED"), BatchMode::Atomic).unwrap_err(), "Task 42: not found");
+        assert_eq!(statuses(), vec!["OPEN"]);
+
+        let report = context.update_tasks(batch("CLOSED"), BatchMode::BestEffort).unwrap();
+        let mut updated = report.updated.clone();
+        updated.sort();
+        assert_eq!(updated, vec!["1", "2", "3"]);
+        assert_eq!(report.failed, vec![("42".to_string(), "not found".to_string())]);
+        assert_eq!(statuses(), vec!["CLOSED"]);
+        assert!(context.find_task("42").unwrap().is_none());
+
+        context.set_config_value("task.status.strict", "true").unwrap();
+        let mut tasks = batch("IN_PROGRESS");
+        tasks[0].set_property(STATUS, "DONE");
+        tasks.remove(1);
+        assert!(context.update_tasks(tasks.clone(), BatchMode::Atomic).is_err());
+        assert_eq!(statuses(), vec!["CLOSED"]);
+
+        let report = context.update_tasks(tasks, BatchMode::BestEffort).unwrap();
+        assert_eq!(report.updated.len(), 2);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].1.starts_with("Unknown status DONE"));
+        assert_eq!(context.list_tasks().unwrap().iter().filter(|task| task.get_property(STATUS).unwrap() == "IN_PROGRESS").count(), 2);
 
         std::fs::remove_dir_all(repo_dir).unwrap();
     }
 
     #[test]
-    fn test_update_comment_id() {
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("P1W"), Ok(604800));
+        assert_eq!(parse_duration("P3D"), Ok(3 * 86400));
+        assert_eq!(parse_duration("PT48H"), Ok(48 * 3600));
+        assert_eq!(parse_duration("P1DT12H30M15S"), Ok(86400 + 12 * 3600 + 30 * 60 + 15));
+        assert_eq!(parse_duration("pt90m"), Ok(5400));
+        for invalid in ["", "P", "PT", "1W", "P1", "PW", "P1H", "P1DT", "P1.5D", "P1WX", "P99999999999999999W", "P1DT18446744073709551615S"] {
+            assert!(parse_duration(invalid).unwrap_err().starts_with("Invalid duration"), "{invalid}");
+        }
+        assert_eq!(parse_duration("P1M").unwrap_err(), "Invalid duration: P1M, years and months are not supported");
+
+        let mut task = Task::new("Due".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        assert_eq!(task.due_in(), None);
+        task.set_property("due", &(get_current_timestamp() + parse_duration("PT48H").unwrap()).to_string());
+        assert!((48 * 3600 - 5..=48 * 3600).contains(&task.due_in().unwrap()));
+        task.set_property("due", "2000-01-01T00:00:00Z");
+        assert!(task.due_in().unwrap() < 0);
+    }
+
+    #[test]
+    fn test_touch_task() {
         let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
         std::fs::create_dir_all(repo_dir.clone()).unwrap();
-        let _repo = Repository::init(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
         let context = TaskContext::new(repo_dir.display().to_string());
 
-        // Create a task first
-        let id = context.get_next_id().unwrap_or_else(|_| "1".to_string());
-        let task = Task::construct_task(
-            "Test task".to_string(),
-            "Description goes here".to_string(),
-            "OPEN".to_string(),
-            context.get_current_user().unwrap(),
-            Some(get_current_timestamp())
-        );
-        let create_result = context.create_task(task);
-        assert!(create_result.is_ok());
-        let mut task = create_result.unwrap();
+        let mut task = Task::new("Stale".to_string(), "Untouched".to_string(), "OPEN".to_string(), None).unwrap();
+        task.set_property(UPDATED, "1700000000");
+        task.add_label("bug".to_string(), None, None);
+        let id = context.create_task(task).unwrap().get_id().unwrap();
+        let before = context.find_task(&id).unwrap().unwrap();
+
+        context.touch_task(&id).unwrap();
+        let after = context.find_task(&id).unwrap().unwrap();
+        assert!(after.get_updated().unwrap() > before.get_updated().unwrap());
+        assert!(has_same_content(&before, &after));
+        let mut props = after.get_all_properties().clone();
+        props.insert(UPDATED.to_string(), "1700000000".to_string());
+        assert_eq!(&props, before.get_all_properties());
+
+        context.set_config_value("task.comments.split", "true").unwrap();
+        context.add_comment(&id, HashMap::new(), "Not a touch".to_string(), None).unwrap();
+        let actions = context.changelog(0, u64::MAX).unwrap().into_iter().map(|(_, _, action, _)| action).collect::<Vec<_>>();
+        assert_eq!(actions, vec![TaskAction::Create, TaskAction::Touched, TaskAction::Update]);
+        assert_eq!(context.touch_task("42").unwrap_err(), "Task 42 not found");
 
-        // Add a comment to the task
-        let comment_props = HashMap::from([("author".to_string(), "Some developer".to_string())]);
-        let comment = task.add_comment(
-            Some("1".to_string()),
-            comment_props,
-            "Test comment".to_string(),
-            context.get_current_user().unwrap(),
-        );
-        assert_eq!(comment.get_id().unwrap(), "1");
-        let update_result = context.update_task(task);
-        assert!(update_result.is_ok());
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
 
-        // Update the comment ID
-        let result = context.update_comment_id(&id, "1", "2");
-        assert!(result.is_ok());
+    #[test]
+    fn test_noop_update_skips_commit() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let head = || repo.refname_to_id(&context.get_ref_path()).unwrap();
 
-        // Verify the comment ID was updated
-        let updated_task = context.find_task(&id).unwrap().unwrap();
-        let updated_comments = updated_task.get_comments().as_ref().unwrap();
-        assert_eq!(updated_comments.len(), 1);
-        assert_eq!(updated_comments[0].get_id().unwrap(), "2");
+        let task = Task::new("Unchanged".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        let mut task = context.create_task(task).unwrap();
+        let id = task.get_id().unwrap();
+        let created = head();
 
-        // Clean up
-        let delete_result = context.delete_tasks(&[&id]);
-        assert!(delete_result.is_ok());
+        assert_eq!(context.update_task_if_changed(task.clone()).unwrap(), None);
+        assert_eq!(context.update_task(task.clone()).unwrap(), id);
+        assert_eq!(head(), created);
+
+        task.set_property("status", "CLOSED");
+        assert_eq!(context.update_task_if_changed(task.clone()).unwrap(), Some(id.clone()));
+        let updated = head();
+        assert_ne!(updated, created);
+
+        context.set_config_value("task.commit.empty", "true").unwrap();
+        assert_eq!(context.update_task_if_changed(task.clone()).unwrap(), Some(id));
+        assert_ne!(head(), updated);
 
         std::fs::remove_dir_all(repo_dir).unwrap();
     }
 
     #[test]
-    fn test_clear_tasks() {
+    fn test_fingerprint() {
+        let mut task = Task::new("Name".to_string(), "Description".to_string(), "OPEN".to_string(), None).unwrap();
+        task.add_label("bug".to_string(), None, Some("d73a4a".to_string()));
+        task.add_label("docs".to_string(), None, None);
+        task.add_comment(Some("1".to_string()), HashMap::new(), "Comment".to_string(), None);
+        let fingerprint = task.fingerprint();
+        assert_eq!(fingerprint.len(), 40);
+
+        let mut copy: Task = serde_json::from_str(&serde_json::to_string(&task).unwrap()).unwrap();
+        assert_eq!(copy.fingerprint(), fingerprint);
+
+        copy.set_id("100".to_string());
+        copy.set_property(CREATED, "1");
+        copy.touch();
+        copy.set_property("synced_labels", "bug,docs");
+        copy.set_labels(task.get_labels().as_ref().unwrap().iter().rev().cloned().collect());
+        assert_eq!(copy.fingerprint(), fingerprint);
+
+        copy.set_property(NAME, "Renamed");
+        assert_ne!(copy.fingerprint(), fingerprint);
+
+        let mut copy = task.clone();
+        copy.add_comment(None, HashMap::new(), "Another comment".to_string(), None);
+        assert_ne!(copy.fingerprint(), fingerprint);
+
+        let mut copy = task.clone();
+        copy.set_property("priority", "high");
+        assert_ne!(copy.fingerprint(), fingerprint);
+
+        let mut copy = task.clone();
+        copy.set_labels(vec![]);
+        assert_ne!(copy.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_list_tasks_at() {
         let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
         std::fs::create_dir_all(repo_dir.clone()).unwrap();
-        let _repo = Repository::init(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
         let context = TaskContext::new(repo_dir.display().to_string());
+        let names = |tasks: Vec<Task>| {
+            let mut names = tasks.iter().map(|task| task.get_property(NAME).unwrap().clone()).collect::<Vec<_>>();
+            names.sort();
+            names
+        };
 
-        let id = context.get_next_id().unwrap_or_else(|_| "1".to_string());
-        let task = Task::construct_task(
-            "Test task".to_string(),
-            "Description goes here".to_string(),
-            "OPEN".to_string(),
-            context.get_current_user().unwrap(),
-            Some(get_current_timestamp()));
-        let create_result = context.create_task(task);
-        assert!(create_result.is_ok());
-        let task = create_result.unwrap();
-        assert_eq!(task.get_id(), Some(id.clone()));
+        context.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        let first_commit = repo.find_reference(&context.get_ref_path()).unwrap().peel_to_commit().unwrap().id().to_string();
+        context.create_task(Task::new("Second".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        context.delete_tasks(&["1"]).unwrap();
 
-        let id = context.get_next_id().unwrap_or_else(|_| "2".to_string());
-        let task2 = Task::construct_task(
-            "Another task".to_string(),
-            "Another description".to_string(),
-            "IN_PROGRESS".to_string(),
-            context.get_current_user().unwrap(),
-            Some(get_current_timestamp())
-        );
-        let create_result2 = context.create_task(task2);
-        assert!(create_result2.is_ok());
-        let task2 = create_result2.unwrap();
-        assert_eq!(task2.get_id(), Some(id.clone()));
+        assert_eq!(names(context.list_tasks_at(&first_commit).unwrap()), vec!["First"]);
+        assert_eq!(names(context.list_tasks_at(&first_commit[..8]).unwrap()), vec!["First"]);
+        assert_eq!(names(context.list_tasks_at("refs/tasks/tasks~1").unwrap()), vec!["First", "Second"]);
+        assert_eq!(names(context.list_tasks_at("refs/tasks/tasks").unwrap()), vec!["Second"]);
+        assert!(context.list_tasks_at("no-such-revision").err().unwrap().starts_with("Unknown revision no-such-revision"));
 
-        let id = context.get_next_id().unwrap_or_else(|_| "3".to_string());
-        let task3 = Task::construct_task(
-            "Third task".to_string(),
-            "Third description".to_string(),
-            "CLOSED".to_string(),
-            context.get_current_user().unwrap(),
-            Some(get_current_timestamp()));
-        let create_result3 = context.create_task(task3);
-        assert!(create_result3.is_ok());
-        let task3 = create_result3.unwrap();
-        assert_eq!(task3.get_id(), Some(id.clone()));
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
 
-        let clear_result = context.clear_tasks();
-        assert!(clear_result.is_ok());
-        assert_eq!(clear_result.unwrap(), 3);
+    #[test]
+    fn test_repository_path_resolution() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.create_task(Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+
+        let nested_dir = repo_dir.join("src").join("nested");
+        std::fs::create_dir_all(nested_dir.clone()).unwrap();
+        let nested = TaskContext::new(nested_dir.display().to_string());
+        assert_eq!(nested.repository_path, context.repository_path);
+        assert_eq!(nested.list_tasks().unwrap().len(), 1);
+
+        let cwd = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&nested_dir).unwrap();
+        let from_cwd = TaskContext::from_cwd();
+        std::env::set_current_dir(cwd).unwrap();
+        assert_eq!(from_cwd.unwrap().list_tasks().unwrap().len(), 1);
+
+        let signature = Signature::now("Someone", "someone@example.com").unwrap();
+        let tree = repo.find_tree(repo.treebuilder(None).unwrap().write().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial commit", &tree, &[]).unwrap();
+        let worktree_dir = temp_dir().join(Uuid::new_v4().to_string());
+        repo.worktree("linked", &worktree_dir, None).unwrap();
+        let linked = TaskContext::new(worktree_dir.join(".").display().to_string());
+        assert_eq!(std::path::Path::new(&linked.repository_path), std::fs::canonicalize(&worktree_dir).unwrap());
+        assert_eq!(linked.list_tasks().unwrap().len(), 1);
+        linked.create_task(Task::new("From worktree".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        assert_eq!(context.list_tasks().unwrap().len(), 2);
+
+        let no_repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(no_repo_dir.clone()).unwrap();
+        assert!(TaskContext::open(&no_repo_dir.display().to_string()).err().unwrap().starts_with("No git repository found at"));
+        assert!(TaskContext::open(&no_repo_dir.join("missing").display().to_string()).err().unwrap().starts_with("Can't open"));
+
+        std::fs::remove_dir_all(no_repo_dir).unwrap();
+        std::fs::remove_dir_all(worktree_dir).unwrap();
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
 
-        let find_result = context.find_task(&id);
-        assert!(find_result.is_ok());
-        let task = find_result.unwrap();
-        assert!(task.is_none());
+    #[test]
+    fn test_import_tasks_remaps_ids() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        for name in ["Existing 1", "Existing 2"] {
+            context.create_task(Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        }
 
-        let find_result = context.find_task(&task2.get_id().unwrap());
-        assert!(find_result.is_ok());
-        let task = find_result.unwrap();
-        assert!(task.is_none());
+        let task = |id: &str, props: &[(&str, &str)]| {
+            let mut task = Task::new(format!("Imported {id}"), String::new(), "OPEN".to_string(), None).unwrap();
+            task.set_id(id.to_string());
+            props.iter().for_each(|(name, value)| task.set_property(name, value));
+            task
+        };
+        let tasks = vec![
+            task("1", &[]),
+            task("2", &[("parent", "1"), (DEPENDS_ON, "1, 3, 99")]),
+            task("3", &[(MERGED_INTO, "2"), (DELETED, "true")]),
+            task("7", &[("parent", "2")]),
+        ];
+
+        let remap = context.import_tasks(tasks.clone(), false, true).unwrap();
+        assert_eq!(remap, vec![
+            ("1".to_string(), "3".to_string()),
+            ("2".to_string(), "4".to_string()),
+            ("3".to_string(), "5".to_string()),
+            ("7".to_string(), "7".to_string()),
+        ]);
 
-        let find_result = context.find_task(&task3.get_id().unwrap());
-        assert!(find_result.is_ok());
-        let task = find_result.unwrap();
-        assert!(task.is_none());
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property(NAME).unwrap(), "Existing 1");
+        assert_eq!(context.find_task("3").unwrap().unwrap().get_property(NAME).unwrap(), "Imported 1");
+        let task = context.find_task("4").unwrap().unwrap();
+        assert_eq!(task.get_property(NAME).unwrap(), "Imported 2");
+        assert_eq!(task.get_property("parent").unwrap(), "3");
+        assert_eq!(task.get_property(DEPENDS_ON).unwrap(), "3,5,99");
+        assert_eq!(context.find_task("5").unwrap().unwrap().get_property(MERGED_INTO).unwrap(), "4");
+        assert_eq!(context.find_task("7").unwrap().unwrap().get_property("parent").unwrap(), "4");
+        assert_eq!(context.list_tasks().unwrap().len(), 5);
+
+        let remap = context.import_tasks(tasks, false, false).unwrap();
+        assert!(remap.iter().all(|(old_id, new_id)| old_id == new_id));
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property(NAME).unwrap(), "Imported 1");
+        assert_eq!(context.find_task("2").unwrap().unwrap().get_property("parent").unwrap(), "1");
+        assert_eq!(context.list_tasks().unwrap().len(), 4);
+
+        context.set_config_value("task.status.strict", "true").unwrap();
+        let mut invalid = Task::new("Invalid".to_string(), String::new(), "UNKNOWN".to_string(), None).unwrap();
+        invalid.set_id("8".to_string());
+        assert!(context.validate_import(std::slice::from_ref(&invalid)).is_err());
+        assert!(context.import_tasks(vec![invalid.clone()], false, false).is_err());
+        assert_eq!(context.import_tasks(vec![invalid], true, false).unwrap(), vec![("8".to_string(), "8".to_string())]);
 
         std::fs::remove_dir_all(repo_dir).unwrap();
     }
-}
\ No newline at end of file
+}
@@ -10,16 +10,21 @@ use std::process::ExitCode;
 
 use clap::{Parser, Subcommand};
 use gittask::TaskContext;
-use crate::operations::{task_clear, task_create, task_delete, task_edit, task_export, task_get, task_import, task_list, task_pull, task_push, task_replace, task_set, task_show, task_stats, task_status, task_unset};
+use crate::operations::{OpOutcome, task_attach, task_clear, task_create, task_delete, task_edit, task_export, task_get, task_import, task_list, task_peek, task_pull, task_push, task_replace, task_set, task_show, task_stats, task_status, task_touch, task_unset};
 use crate::operations::comment::*;
 use crate::operations::config::*;
+use crate::operations::config::labels::*;
 use crate::operations::config::properties::*;
 use crate::operations::config::status::*;
 use crate::operations::label::*;
+use crate::operations::snapshot::*;
 
 #[derive(Parser)]
 #[command(version, about = "Local-first task manager/bug tracker within your git repository which can sync issues from/to GitHub or Gitlab.", arg_required_else_help(true))]
 struct Args {
+    /// Task namespace stored under refs/tasks/<namespace> instead of the configured ref
+    #[arg(long, global = true)]
+    namespace: Option<String>,
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -171,6 +176,11 @@ enum Command {
         /// property name
         prop_name: String,
     },
+    /// Refresh the updated timestamp without changing anything else
+    Touch {
+        /// one or more task IDs (comma separated, including ranges like 1..10)
+        ids: String,
+    },
     /// Edit a property
     Edit {
         /// task ID
@@ -193,9 +203,15 @@ enum Command {
     Import {
         /// one or more task IDs (comma separated, including ranges like 1..10)
         ids: Option<String>,
-        /// Input format (only JSON is currently supported)
+        /// Input format (json or github for a GitHub issues export)
         #[arg(short, long)]
         format: Option<String>,
+        /// Accept unknown statuses when task.status.strict is enabled
+        #[arg(long)]
+        force: bool,
+        /// Give tasks whose IDs are already taken new ones instead of replacing them
+        #[arg(long)]
+        renumber: bool,
     },
     /// Export tasks
     Export {
@@ -236,6 +252,29 @@ enum Command {
         /// Don't import task labels
         #[arg(long, aliases = ["nl"])]
         no_labels: bool,
+        /// Ignore the last sync checkpoint and fetch all issues
+        #[arg(long, conflicts_with = "ids")]
+        full: bool,
+    },
+    /// Show a remote task without importing it
+    Peek {
+        /// remote task ID
+        id: String,
+        /// Use this remote if there are several of them
+        #[arg(short, long)]
+        remote: Option<String>,
+        /// Use this remote connector (github, gitlab, jira)
+        #[arg(long = "connector", aliases = ["conn"])]
+        connector_type: Option<String>,
+        /// Don't fetch task comments
+        #[arg(long, aliases = ["nc"])]
+        no_comments: bool,
+        /// Don't fetch task labels
+        #[arg(long, aliases = ["nl"])]
+        no_labels: bool,
+        /// Disable colors
+        #[arg(long)]
+        no_color: bool,
     },
     /// Push task status to the remote source (e.g., GitHub)
     Push {
@@ -253,10 +292,20 @@ enum Command {
         /// Don't create task labels
         #[arg(long, aliases = ["nl"])]
         no_labels: bool,
+        /// Replace all remote labels with local ones, including labels not managed by git-task
+        #[arg(long, conflicts_with = "no_labels")]
+        replace_labels: bool,
         /// Disable colors
         #[arg(long)]
         no_color: bool,
     },
+    /// Attach a file, the next push uploads it and links it in the task description
+    Attach {
+        /// task ID
+        id: String,
+        /// file to upload
+        file: String,
+    },
     /// Show total task count and count by status
     Stats {
         /// Disable colors
@@ -290,6 +339,11 @@ enum Command {
         #[command(subcommand)]
         subcommand: ConfigCommand,
     },
+    /// Bookmark and restore the whole task set
+    Snapshot {
+        #[command(subcommand)]
+        subcommand: SnapshotCommand,
+    },
 }
 
 #[derive(Subcommand)]
@@ -405,6 +459,34 @@ enum LabelCommand {
         #[arg(long = "connector", aliases = ["conn"])]
         connector_type: Option<String>,
     },
+    /// Move a label to another position
+    #[clap(visible_aliases(["mv"]))]
+    Move {
+        /// task ID
+        task_id: String,
+        /// label name
+        name: String,
+        /// new position, 0 is the first one
+        index: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// Snapshot current tasks
+    #[clap(visible_aliases(["new"]))]
+    Create {
+        /// snapshot name
+        name: String,
+    },
+    /// List snapshots
+    List,
+    /// Reset tasks to a snapshot
+    #[clap(visible_aliases(["checkout"]))]
+    Restore {
+        /// snapshot name
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -437,6 +519,34 @@ enum ConfigCommand {
         #[command(subcommand)]
         subcommand: PropertiesCommand,
     },
+    /// Configure label definitions shared by all tasks
+    Labels {
+        #[command(subcommand)]
+        subcommand: LabelsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum LabelsCommand {
+    /// Define a label or update its definition
+    #[clap(visible_aliases(["add", "create", "new"]))]
+    Set {
+        /// label name
+        name: String,
+        /// label color
+        color: Option<String>,
+        /// Label description
+        #[arg(short, long, aliases = ["desc"])]
+        description: Option<String>,
+    },
+    /// Delete a label definition
+    #[clap(visible_aliases(["del", "remove", "rem"]))]
+    Delete {
+        /// label name
+        name: String,
+    },
+    /// List label definitions
+    List,
 }
 
 #[derive(Subcommand)]
@@ -488,6 +598,15 @@ enum StatusCommand {
         #[arg(short, long)]
         pretty: bool,
     },
+    /// Compare statuses of the remote source with the local ones
+    Remote {
+        /// Use this remote if there are several of them
+        #[arg(short, long)]
+        remote: Option<String>,
+        /// Use this remote connector (github, gitlab, jira, redmine)
+        #[arg(long = "connector", aliases = ["conn"])]
+        connector_type: Option<String>,
+    },
     /// Reset status configuration to default
     Reset,
 }
@@ -642,8 +761,8 @@ enum PropertiesCondFormatCommand {
 fn main() -> ExitCode {
     let _ = enable_ansi_support::enable_ansi_support();
     let args = Args::parse();
-    let context = TaskContext::new(".".to_string());
-    let success = match args.command {
+    let context = TaskContext::with_namespace(".".to_string(), args.namespace);
+    let outcome = match args.command {
         Some(Command::List { status, keyword, from, until, author, columns, sort, limit, no_color }) => task_list(&context, status, keyword, from, until, author, columns, sort, limit, no_color),
         Some(Command::Show { id, no_color }) => task_show(&context, id, no_color),
         Some(Command::Create { name, description, no_desc, push, remote, connector_type: connector }) => task_create(&context, name, description, no_desc, push, &remote, &connector),
@@ -652,26 +771,33 @@ fn main() -> ExitCode {
         Some(Command::Set { ids, prop_name, value, push, remote, connector_type: connector, no_color }) => task_set(&context, ids, prop_name, value, push, &remote, &connector, no_color),
         Some(Command::Replace { ids, prop_name, search, replace, regex, push, remote, connector_type: connector, no_color }) => task_replace(&context, ids, prop_name, search, replace, regex, push, &remote, &connector, no_color),
         Some(Command::Unset { ids, prop_name }) => task_unset(&context, ids, prop_name),
+        Some(Command::Touch { ids }) => task_touch(&context, ids),
         Some(Command::Edit { id, prop_name }) => task_edit(&context, id, prop_name),
         Some(Command::Comment { subcommand }) => task_comment(&context, subcommand),
         Some(Command::Label { subcommand }) => task_label(&context, subcommand),
-        Some(Command::Import { ids, format }) => task_import(&context, ids, format),
+        Some(Command::Import { ids, format, force, renumber }) => task_import(&context, ids, format, force, renumber),
         Some(Command::Export { ids, status, limit, format, pretty }) => task_export(&context, ids, status, limit, format, pretty),
-        Some(Command::Pull { ids, limit, status, remote, connector_type: connector, no_comments, no_labels }) => task_pull(&context, ids, limit, status, &remote, &connector, no_comments, no_labels),
-        Some(Command::Push { ids, remote, connector_type: connector, no_comments, no_labels, no_color }) => task_push(&context, ids, &remote, &connector, no_comments, no_labels, no_color),
+        Some(Command::Pull { ids, limit, status, remote, connector_type: connector, no_comments, no_labels, full }) => task_pull(&context, ids, limit, status, &remote, &connector, no_comments, no_labels, full),
+        Some(Command::Peek { id, remote, connector_type: connector, no_comments, no_labels, no_color }) => task_peek(&context, id, &remote, &connector, no_comments, no_labels, no_color),
+        Some(Command::Push { ids, remote, connector_type: connector, no_comments, no_labels, replace_labels, no_color }) => task_push(&context, ids, &remote, &connector, no_comments, no_labels, replace_labels, no_color),
+        Some(Command::Attach { id, file }) => task_attach(&context, id, file),
         Some(Command::Stats { no_color }) => task_stats(&context, no_color),
         Some(Command::Delete { ids, status, push, remote, connector_type: connector }) => task_delete(&context, ids, status, push, &remote, &connector),
         Some(Command::Clear) => task_clear(&context),
         Some(Command::Config { subcommand }) => task_config(&context, subcommand),
-        None => false
+        Some(Command::Snapshot { subcommand }) => task_snapshot(&context, subcommand),
+        None => OpOutcome::UserError
     };
-    if success { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+    for warning in context.take_warnings() {
+        eprintln!("WARNING: {warning}");
+    }
+    outcome.into()
 }
 
 fn task_comment(
     context: &TaskContext,
     subcommand: CommentCommand
-) -> bool {
+) -> OpOutcome {
     match subcommand {
         CommentCommand::Add { task_id, text, push, remote, connector_type: connector } => task_comment_add(&context, task_id, text, push, &remote, &connector),
         CommentCommand::Set { task_id, comment_id, text, push, remote, connector_type: connector } => task_comment_set(&context, task_id, comment_id, text, push, &remote, &connector),
@@ -680,24 +806,42 @@ fn task_comment(
     }
 }
 
-fn task_label(context: &TaskContext, subcommand: LabelCommand) -> bool {
+fn task_label(context: &TaskContext, subcommand: LabelCommand) -> OpOutcome {
     match subcommand {
         LabelCommand::Add { task_id, name, color, description, push, remote, connector_type: connector } => task_label_add(&context, task_id, name, color, description, push, &remote, &connector),
         LabelCommand::Delete { task_id, name, push, remote, connector_type: connector } => task_label_delete(&context, task_id, name, push, &remote, &connector),
+        LabelCommand::Move { task_id, name, index } => task_label_move(&context, task_id, name, index),
+    }
+}
+
+fn task_snapshot(context: &TaskContext, subcommand: SnapshotCommand) -> OpOutcome {
+    match subcommand {
+        SnapshotCommand::Create { name } => task_snapshot_create(context, name),
+        SnapshotCommand::List => task_snapshot_list(context),
+        SnapshotCommand::Restore { name } => task_snapshot_restore(context, name),
     }
 }
 
-fn task_config(context: &TaskContext, subcommand: ConfigCommand) -> bool {
+fn task_config(context: &TaskContext, subcommand: ConfigCommand) -> OpOutcome {
     match subcommand {
         ConfigCommand::Get { param } => task_config_get(&context, param),
         ConfigCommand::Set { param, value, move_ref } => task_config_set(&context, param, value, move_ref),
         ConfigCommand::List => task_config_list(&context),
         ConfigCommand::Status { subcommand } => task_config_status(&context, subcommand),
         ConfigCommand::Properties { subcommand } => task_config_properties(&context, subcommand),
+        ConfigCommand::Labels { subcommand } => task_config_labels(context, subcommand),
+    }
+}
+
+fn task_config_labels(context: &TaskContext, subcommand: LabelsCommand) -> OpOutcome {
+    match subcommand {
+        LabelsCommand::Set { name, color, description } => task_config_labels_set(context, name, color, description),
+        LabelsCommand::Delete { name } => task_config_labels_delete(context, name),
+        LabelsCommand::List => task_config_labels_list(context),
     }
 }
 
-fn task_config_status(context: &TaskContext, subcommand: StatusCommand) -> bool {
+fn task_config_status(context: &TaskContext, subcommand: StatusCommand) -> OpOutcome {
     match subcommand {
         StatusCommand::Add { name, shortcut, color, is_done } => task_config_status_add(&context, name, shortcut, color, is_done),
         StatusCommand::Delete { name, force } => task_config_status_delete(&context, name, force),
@@ -707,10 +851,11 @@ fn task_config_status(context: &TaskContext, subcommand: StatusCommand) -> bool
         StatusCommand::Import => task_config_status_import(&context),
         StatusCommand::Export { pretty } => task_config_status_export(&context, pretty),
         StatusCommand::Reset => task_config_status_reset(&context),
+        StatusCommand::Remote { remote, connector_type: connector } => task_config_status_remote(context, &remote, &connector),
     }
 }
 
-fn task_config_properties(context: &TaskContext, subcommand: PropertiesCommand) -> bool {
+fn task_config_properties(context: &TaskContext, subcommand: PropertiesCommand) -> OpOutcome {
     match subcommand {
         PropertiesCommand::Add { name, value_type, color, style, enum_values, cond_format } => task_config_properties_add(&context, name, value_type, color, style, enum_values, cond_format),
         PropertiesCommand::Delete { name, force } => task_config_properties_delete(&context, name, force),
@@ -725,7 +870,7 @@ fn task_config_properties(context: &TaskContext, subcommand: PropertiesCommand)
     }
 }
 
-fn task_config_properties_enum(context: &TaskContext, subcommand: PropertiesEnumCommand) -> bool {
+fn task_config_properties_enum(context: &TaskContext, subcommand: PropertiesEnumCommand) -> OpOutcome {
     match subcommand {
         PropertiesEnumCommand::List { name } => task_config_properties_enum_list(&context, name),
         PropertiesEnumCommand::Add { name, enum_value_name, enum_value_color, enum_value_style } => task_config_properties_enum_add(&context, name, enum_value_name, enum_value_color, enum_value_style),
@@ -735,7 +880,7 @@ fn task_config_properties_enum(context: &TaskContext, subcommand: PropertiesEnum
     }
 }
 
-fn task_config_properties_cond_format(context: &TaskContext, subcommand: PropertiesCondFormatCommand) -> bool {
+fn task_config_properties_cond_format(context: &TaskContext, subcommand: PropertiesCondFormatCommand) -> OpOutcome {
     match subcommand {
         PropertiesCondFormatCommand::List { name } => task_config_properties_cond_format_list(&context, name),
         PropertiesCondFormatCommand::Add { name, cond_format_expr, cond_format_color, cond_format_style } => task_config_properties_cond_format_add(&context, name, cond_format_expr, cond_format_color, cond_format_style),
@@ -1,20 +1,44 @@
 pub(crate) mod comment;
 pub(crate) mod config;
 pub(crate) mod label;
+pub(crate) mod snapshot;
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use chrono::{Local, TimeZone};
 use nu_ansi_term::Color::DarkGray;
 use regex::Regex;
 
-use gittask::{Comment, Label, Task, TaskContext};
+use gittask::{parse_duration, parse_timestamp, Comment, FindResult, Label, Task, TaskContext, TaskFilter, TaskStats, TaskStore};
 
-use crate::connectors::{get_matching_remote_connectors, RemoteConnector, RemoteTaskState};
+use crate::connectors::{describe_matching_remotes, find_marked_comment, fit_comment_for_push, fit_task_for_push, get_matching_remote_connectors, get_remote_task_state, merge_labels_for_push, parse_remote_id, merge_pulled_labels, record_synced_labels, unsupported_comment_features, unsupported_message, upload_pending_attachments, RemoteConnector, RemoteTaskState, DUE, PRIORITY, REMOTE_MISSING, STATE_REASON, SYNCED_LABELS, URL, WEIGHT};
 use crate::property::PropertyManager;
 use crate::status::StatusManager;
-use crate::util::{capitalize, colorize_string, error_message, get_text_from_editor, parse_date, parse_ids, read_from_pipe, str_to_color, success_message};
+use crate::util::{capitalize, colorize_string, error_message, get_text_from_editor, label_style, parse_date, parse_ids, read_from_pipe, success_message, system_error_message};
+
+/// Result of a command, mapped to the process exit code: 0 on success, 1 for user errors
+/// (bad input, unknown task) and 2 for system errors (storage or remote failures).
+/// Ordered by severity, so `max` keeps the worst outcome of a batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum OpOutcome {
+    Success,
+    UserError,
+    SystemError,
+}
+
+impl From<OpOutcome> for ExitCode {
+    fn from(outcome: OpOutcome) -> Self {
+        match outcome {
+            OpOutcome::Success => ExitCode::SUCCESS,
+            OpOutcome::UserError => ExitCode::from(1),
+            OpOutcome::SystemError => ExitCode::from(2),
+        }
+    }
+}
 
 pub(crate) fn task_create(
     context: &TaskContext,
@@ -24,7 +48,7 @@ pub(crate) fn task_create(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>
-) -> bool {
+) -> OpOutcome {
     let description = match description {
         Some(description) => description,
         None => match no_desc {
@@ -34,40 +58,45 @@ pub(crate) fn task_create(
     };
 
     let status_manager = StatusManager::new(&context);
-    let task = Task::new(
+    let mut task = Task::new(
         name,
         description,
         status_manager.get_starting_status(),
         context.get_current_user().unwrap_or(None),
-    );
+    ).unwrap();
 
-    match context.create_task(task.unwrap()) {
+    if let Some(due_in) = context.get_config_value("task.due.default").ok().and_then(|duration| parse_duration(&duration).ok()) {
+        let created = task.get_property("created").and_then(|created| parse_timestamp(created)).unwrap_or(0);
+        if let Some(due) = created.checked_add(due_in) {
+            task.set_property(DUE, &due.to_string());
+        }
+    }
+
+    match context.create_task(task) {
         Ok(task) => {
             println!("Task ID {} created", task.get_id().unwrap());
-            let mut success = false;
+            let mut outcome = OpOutcome::Success;
             if push {
                 match get_user_repo(&context, remote, connector_type) {
+                    Ok((connector, _, _)) if !connector.capabilities().push_tasks => outcome = error_message(unsupported_message(connector.as_ref(), "pushing tasks")),
                     Ok((connector, user, repo)) => {
-                        match connector.create_remote_task(&user, &repo, &task) {
+                        match fit_task_for_push(context, connector.as_ref(), &task).and_then(|task| connector.create_remote_task(&user, &repo, &task)) {
                             Ok(id) => {
                                 println!("Sync: Created REMOTE task ID {id}");
                                 match context.update_task_id(&task.get_id().unwrap(), &id) {
-                                    Ok(_) => {
-                                        println!("Task ID {} -> {} updated", task.get_id().unwrap(), id);
-                                        success = true;
-                                    },
-                                    Err(e) => eprintln!("ERROR: {e}")
+                                    Ok(_) => println!("Task ID {} -> {} updated", task.get_id().unwrap(), id),
+                                    Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                                 }
                             },
-                            Err(e) => eprintln!("ERROR: {e}")
+                            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                         }
                     },
-                    Err(e) => eprintln!("ERROR: {e}")
+                    Err(e) => outcome = error_message(format!("ERROR: {e}"))
                 }
             }
-            success
+            outcome
         },
-        Err(e) => error_message(format!("ERROR: {e}"))
+        Err(e) => system_error_message(format!("ERROR: {e}"))
     }
 }
 
@@ -79,15 +108,15 @@ pub(crate) fn task_status(
     remote: &Option<String>,
     connector_type: &Option<String>,
     no_color: bool,
-) -> bool {
+) -> OpOutcome {
     let status_manager = StatusManager::new(&context);
     let status = status_manager.get_full_status_name(&status);
 
     task_set(context, ids, "status".to_string(), status.clone(), push, remote, connector_type, no_color)
 }
 
-pub(crate) fn task_get(context: &TaskContext, id: String, prop_name: String) -> bool {
-    match context.find_task(&id) {
+pub(crate) fn task_get<S: TaskStore>(store: &S, id: String, prop_name: String) -> OpOutcome {
+    match store.find_task(&id) {
         Ok(Some(task)) => {
             match task.get_property(&prop_name) {
                 Some(value) => success_message(format!("{value}")),
@@ -95,7 +124,7 @@ pub(crate) fn task_get(context: &TaskContext, id: String, prop_name: String) ->
             }
         },
         Ok(None) => error_message(format!("Task ID {id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
@@ -108,8 +137,9 @@ pub(crate) fn task_set(
     remote: &Option<String>,
     connector_type: &Option<String>,
     no_color: bool
-) -> bool {
+) -> OpOutcome {
     let ids = parse_ids(ids);
+    let mut outcome = OpOutcome::Success;
     match prop_name.as_str() {
         "id" => {
             for id in &ids {
@@ -118,11 +148,11 @@ pub(crate) fn task_set(
                         println!("Task ID {id} -> {value} updated");
 
                         if push {
-                            task_push(&context, value.clone(), remote, connector_type, false, false, no_color);
+                            outcome = outcome.max(task_push(context, value.clone(), remote, connector_type, false, false, false, no_color));
                         }
                     },
                     Err(e) => {
-                        error_message(format!("ERROR: {e}"));
+                        outcome = error_message(format!("ERROR: {e}"));
                     }
                 }
             }
@@ -131,6 +161,17 @@ pub(crate) fn task_set(
             for id in &ids {
                 match context.find_task(&id) {
                     Ok(Some(mut task)) => {
+                        if prop_name == "depends_on" {
+                            let validated = value.split(',')
+                                .map(|dep_id| dep_id.trim())
+                                .filter(|dep_id| !dep_id.is_empty())
+                                .try_for_each(|dep_id| context.validate_dependency(id, dep_id));
+                            if let Err(e) = validated {
+                                outcome = error_message(format!("ERROR: {e}"));
+                                continue;
+                            }
+                        }
+
                         task.set_property(&prop_name, &value);
 
                         match context.update_task(task) {
@@ -138,26 +179,26 @@ pub(crate) fn task_set(
                                 println!("Task ID {id} updated");
 
                                 if push {
-                                    task_push(&context, id.to_string(), remote, connector_type, false, false, no_color);
+                                    outcome = outcome.max(task_push(context, id.to_string(), remote, connector_type, false, false, false, no_color));
                                 }
                             },
                             Err(e) => {
-                                error_message(format!("ERROR: {e}"));
+                                outcome = system_error_message(format!("ERROR: {e}"));
                             },
                         }
                     },
                     Ok(None) => {
-                        error_message(format!("Task ID {id} not found"));
+                        outcome = error_message(format!("Task ID {id} not found"));
                     },
                     Err(e) =>{
-                        error_message(format!("ERROR: {e}"));
+                        outcome = system_error_message(format!("ERROR: {e}"));
                     }
                 }
             }
         }
     }
 
-    true
+    outcome
 }
 
 pub(crate) fn task_replace(
@@ -171,12 +212,13 @@ pub(crate) fn task_replace(
     remote: &Option<String>,
     connector_type: &Option<String>,
     no_color: bool
-) -> bool {
+) -> OpOutcome {
     let ids = parse_ids(ids);
     let regex = match regex {
         true => Some(Box::new(Regex::new(search.as_str()).unwrap())),
         false => None
     };
+    let mut outcome = OpOutcome::Success;
     for id in ids {
         match context.find_task(&id) {
             Ok(Some(mut task)) => {
@@ -190,63 +232,81 @@ pub(crate) fn task_replace(
                         Ok(_) => {
                             println!("Task ID {id} updated");
                             if push {
-                                task_push(&context, id.to_string(), remote, connector_type, false, false, no_color);
+                                outcome = outcome.max(task_push(context, id.to_string(), remote, connector_type, false, false, false, no_color));
                             }
                         },
-                        Err(e) => eprintln!("ERROR: {e}")
+                        Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                     }
                 } else {
-                    eprintln!("Task ID {id}: property not found")
+                    outcome = error_message(format!("Task ID {id}: property not found"))
                 }
             },
-            _ => {}
+            Ok(None) => outcome = error_message(format!("Task ID {id} not found")),
+            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
         }
     }
 
-    true
+    outcome
 }
 
-pub(crate) fn task_unset(context: &TaskContext, ids: String, prop_name: String) -> bool {
+pub(crate) fn task_unset<S: TaskStore>(store: &S, ids: String, prop_name: String) -> OpOutcome {
     let ids = parse_ids(ids);
+    let mut outcome = OpOutcome::Success;
     for id in ids {
-        match context.find_task(&id) {
+        match store.find_task(&id) {
             Ok(Some(mut task)) => {
                 if task.delete_property(&prop_name) {
-                    match context.update_task(task) {
+                    match store.update_task(task) {
                         Ok(_) => println!("Task ID {id} updated"),
-                        Err(e) => eprintln!("ERROR: {e}")
+                        Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                     }
                 } else {
-                    eprintln!("Task ID {id}: property not found")
+                    outcome = error_message(format!("Task ID {id}: property not found"))
                 }
             },
-            Ok(None) => eprintln!("Task ID {id} not found"),
-            Err(e) => eprintln!("ERROR: {e}")
+            Ok(None) => outcome = error_message(format!("Task ID {id} not found")),
+            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
         }
     };
 
-    true
+    outcome
 }
 
-pub(crate) fn task_edit(context: &TaskContext, id: String, prop_name: String) -> bool {
+pub(crate) fn task_touch(context: &TaskContext, ids: String) -> OpOutcome {
+    let ids = parse_ids(ids);
+    let mut outcome = OpOutcome::Success;
+    for id in ids {
+        match context.find_task(&id) {
+            Ok(Some(_)) => match context.touch_task(&id) {
+                Ok(_) => println!("Task ID {id} touched"),
+                Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
+            },
+            Ok(None) => outcome = error_message(format!("Task ID {id} not found")),
+            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
+        }
+    }
+
+    outcome
+}
+
+fn change_task_id(context: &TaskContext, id: &str, new_id: &str) -> OpOutcome {
+    match context.find_task(new_id) {
+        Ok(Some(_)) if new_id != id => error_message(format!("Task ID {new_id} already exists")),
+        Ok(_) => match context.update_task_id(id, new_id) {
+            Ok(_) => success_message(format!("Task ID {id} -> {new_id} updated")),
+            Err(e) => system_error_message(format!("ERROR: {e}")),
+        },
+        Err(e) => system_error_message(format!("ERROR: {e}")),
+    }
+}
+
+pub(crate) fn task_edit(context: &TaskContext, id: String, prop_name: String) -> OpOutcome {
     match context.find_task(&id) {
         Ok(Some(mut task)) => {
             match prop_name.as_str() {
                 "id" => {
                     match get_text_from_editor(&context, Some(&task.get_id().unwrap())) {
-                        Some(text) => {
-                            task.set_id(text.clone());
-                            match context.update_task(task) {
-                                Ok(_) => {
-                                    println!("Task ID {id} -> {text} updated");
-                                    if let Err(e) = context.delete_tasks(&[&id]) {
-                                        eprintln!("ERROR: {e}");
-                                    }
-                                    true
-                                },
-                                Err(e) => error_message(format!("ERROR: {e}")),
-                            }
-                        },
+                        Some(text) => change_task_id(context, &id, &text),
                         None => error_message("Editing failed".to_string()),
                     }
                 },
@@ -258,7 +318,7 @@ pub(crate) fn task_edit(context: &TaskContext, id: String, prop_name: String) ->
                                     task.set_property(&prop_name, &text);
                                     match context.update_task(task) {
                                         Ok(_) => success_message(format!("Task ID {id} updated")),
-                                        Err(e) => error_message(format!("ERROR: {e}")),
+                                        Err(e) => system_error_message(format!("ERROR: {e}")),
                                     }
                                 },
                                 None => error_message("Editing failed".to_string()),
@@ -270,48 +330,60 @@ pub(crate) fn task_edit(context: &TaskContext, id: String, prop_name: String) ->
             }
         },
         Ok(None) => error_message(format!("Task ID {id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
-pub(crate) fn task_import(context: &TaskContext, ids: Option<String>, format: Option<String>) -> bool {
-    if let Some(format) = format {
-        if format.to_lowercase() != "json" {
-            return error_message("Only JSON format is supported".to_string());
-        }
+pub(crate) fn task_import(context: &TaskContext, ids: Option<String>, format: Option<String>, force: bool, renumber: bool) -> OpOutcome {
+    let format = format.unwrap_or_else(|| String::from("json")).to_lowercase();
+    if format != "json" && format != "github" {
+        return error_message("Only JSON and GitHub export formats are supported".to_string());
     }
 
     if let Some(input) = read_from_pipe() {
-        import_from_input(context, ids, &input)
+        match format.as_str() {
+            "github" => match context.import_github_export(&input, force, renumber) {
+                Ok(remap) => report_imported(remap),
+                Err(e) => error_message(format!("ERROR: {e}")),
+            },
+            _ => import_from_input(context, ids, &input, force, renumber)
+        }
     } else {
         error_message("Can't read from pipe".to_string())
     }
 }
 
-fn import_from_input(context: &TaskContext, ids: Option<String>, input: &String) -> bool {
+fn import_from_input(context: &TaskContext, ids: Option<String>, input: &str, force: bool, renumber: bool) -> OpOutcome {
     if let Ok(tasks) = serde_json::from_str::<Vec<Task>>(input) {
         let ids = ids.map(parse_ids);
+        let tasks = tasks.into_iter()
+            .filter(|task| ids.as_ref().is_none_or(|ids| task.get_id().is_some_and(|id| ids.contains(&id))))
+            .collect::<Vec<_>>();
 
-        for task in tasks {
-            let id = task.get_id().unwrap().to_string();
-
-            if let Some(ids) = &ids {
-                if !ids.contains(&id) {
-                    continue;
-                }
-            }
+        if !force && let Err(e) = context.validate_import(&tasks) {
+            return error_message(format!("ERROR: {e}"));
+        }
 
-            match context.create_task(task) {
-                Ok(_) => println!("Task ID {id} imported"),
-                Err(e) => eprintln!("ERROR: {e}"),
-            }
+        match context.import_tasks(tasks, true, renumber) {
+            Ok(remap) => report_imported(remap),
+            Err(e) => system_error_message(format!("ERROR: {e}")),
         }
-        true
     } else {
         error_message("Can't deserialize input".to_string())
     }
 }
 
+fn report_imported(remap: Vec<(String, String)>) -> OpOutcome {
+    for (old_id, new_id) in remap {
+        if old_id == new_id {
+            println!("Task ID {old_id} imported");
+        } else {
+            println!("Task ID {old_id} imported as {new_id}");
+        }
+    }
+    OpOutcome::Success
+}
+
 pub(crate) fn task_pull(
     context: &TaskContext,
     ids: Option<String>,
@@ -321,7 +393,8 @@ pub(crate) fn task_pull(
     connector_type: &Option<String>,
     no_comments: bool,
     no_labels: bool,
-) -> bool {
+    full: bool,
+) -> OpOutcome {
     match get_user_repo(&context, remote, connector_type) {
         Ok((connector, user, repo)) => {
             println!("Pulling tasks from {user}/{repo}...");
@@ -329,56 +402,47 @@ pub(crate) fn task_pull(
             let ids = ids.map(parse_ids);
 
             let status_manager = StatusManager::new(&context);
-            let mut task_statuses = vec![
-                status_manager.get_starting_status(),
-                status_manager.get_final_status(),
-            ];
-            if let Some(status_in_progress) = status_manager.get_in_progress_status() {
-                task_statuses.insert(1, status_in_progress);
-            }
+            let task_statuses = get_task_statuses(&status_manager);
+            let state = match status {
+                Some(s) => {
+                    let status = status_manager.get_full_status_name(&s);
+                    get_remote_task_state(&status_manager, &status, &status, None)
+                },
+                None => RemoteTaskState::All
+            };
 
-            if ids.is_some() {
-                for id in ids.unwrap() {
-                    match connector.get_remote_task(&user, &repo, &id, !no_comments, !no_labels, &task_statuses) {
-                        Ok(task) => {
-                            match import_remote_task(&context, task, no_comments) {
-                                Ok(Some(id)) => println!("Task ID {id} updated"),
-                                Ok(None) => println!("Task ID {id} skipped, nothing to update"),
-                                Err(e) => eprintln!("ERROR: {e}"),
-                            }
-                        },
-                        Err(e) => eprintln!("Task ID {id}: {e}")
-                    }
+            let show_progress = std::io::stderr().is_terminal();
+            let render_progress = |current: usize, total: usize| eprint!("\rFetching tasks: {current}/{total}");
+            let progress: Option<&dyn Fn(usize, usize)> = if show_progress { Some(&render_progress) } else { None };
+            let options = PullOptions { limit, state, full, no_comments, no_labels, progress };
+
+            if let Some(ids) = ids {
+                if !connector.capabilities().fetch_tasks {
+                    return error_message(unsupported_message(connector.as_ref(), "fetching tasks"));
                 }
-                true
+                pull_task_ids(context, connector.as_ref(), &user, &repo, ids, &options, &task_statuses)
             } else {
-                let state = match status {
-                    Some(s) => {
-                        let status = status_manager.get_full_status_name(&s);
-                        let is_done = status_manager.get_property(&status, "is_done").unwrap().parse::<bool>().unwrap();
-                        if is_done { RemoteTaskState::Closed(status.clone(), status) } else { RemoteTaskState::Open(status.clone(), status) }
-                    },
-                    None => RemoteTaskState::All
-                };
-
-                let tasks = connector.list_remote_tasks(&user, &repo, !no_comments, !no_labels, limit, state, &task_statuses);
-                match tasks {
-                    Ok(tasks) => {
-                        if tasks.is_empty() {
+                let results = pull_changed_tasks(context, connector.as_ref(), &user, &repo, &options, &task_statuses);
+                if show_progress {
+                    eprintln!();
+                }
+                match results {
+                    Ok(results) => {
+                        if results.is_empty() {
                             success_message("No tasks found".to_string())
                         } else {
-                            for task in tasks {
-                                let task_id = task.get_id().unwrap();
-                                match import_remote_task(&context, task, no_comments) {
+                            let mut outcome = OpOutcome::Success;
+                            for (task_id, result) in results {
+                                match result {
                                     Ok(Some(id)) => println!("Task ID {id} updated"),
                                     Ok(None) => println!("Task ID {task_id} skipped, nothing to update"),
-                                    Err(e) => eprintln!("ERROR: {e}"),
+                                    Err(e) => outcome = system_error_message(format!("ERROR: {e}")),
                                 }
                             }
-                            true
+                            outcome
                         }
                     },
-                    Err(e) => error_message(format!("ERROR: {e}"))
+                    Err(e) => system_error_message(format!("ERROR: {e}"))
                 }
             }
         },
@@ -386,23 +450,136 @@ pub(crate) fn task_pull(
     }
 }
 
+/// What `git task pull` fetches, `limit`, `state` and `full` only apply when pulling changed tasks.
+struct PullOptions<'a> {
+    limit: Option<usize>,
+    state: RemoteTaskState,
+    full: bool,
+    no_comments: bool,
+    no_labels: bool,
+    progress: Option<&'a dyn Fn(usize, usize)>,
+}
+
+fn pull_task_ids(
+    context: &TaskContext,
+    connector: &dyn RemoteConnector,
+    user: &String,
+    repo: &String,
+    ids: Vec<String>,
+    options: &PullOptions,
+    task_statuses: &Vec<String>
+) -> OpOutcome {
+    let mut outcome = OpOutcome::Success;
+    for id in ids {
+        match connector.get_remote_task(user, repo, &id, !options.no_comments, !options.no_labels, task_statuses) {
+            Ok(Some(mut task)) => {
+                task.set_property(URL, &connector.task_url(user, repo, &id));
+                match import_remote_task(context, task, options.no_comments) {
+                    Ok(Some(id)) => println!("Task ID {id} updated"),
+                    Ok(None) => println!("Task ID {id} skipped, nothing to update"),
+                    Err(e) => outcome = system_error_message(format!("ERROR: {e}")),
+                }
+            },
+            Ok(None) => match handle_missing_remote_task(context, &id) {
+                Some(Ok(message)) => println!("{message}"),
+                Some(Err(e)) => outcome = system_error_message(format!("ERROR: {e}")),
+                None => outcome = error_message(format!("Task ID {id} not found on the remote")),
+            },
+            Err(e) => outcome = system_error_message(format!("Task ID {id}: {e}")),
+        }
+    }
+    outcome
+}
+
+type ImportResult = (String, Result<Option<String>, String>);
+
+/// Applies `task.sync.onremotemissing` to a synced local task whose remote counterpart
+/// is not found: `keep` leaves it, `mark` sets the `remote_missing` property and `delete`
+/// soft-deletes it. Returns `None` when the policy isn't set or doesn't apply.
+fn handle_missing_remote_task(context: &TaskContext, id: &str) -> Option<Result<String, String>> {
+    let policy = context.get_config_value("task.sync.onremotemissing").ok()?;
+    let mut task = context.find_task(id).ok().flatten()?;
+    if !task.has_property(URL) && !task.has_property(SYNCED_LABELS) {
+        return None;
+    }
+
+    match policy.as_str() {
+        "mark" => {
+            task.set_property(REMOTE_MISSING, "true");
+            Some(context.update_task(task).map(|id| format!("Task ID {id} marked, it's missing on the remote")))
+        },
+        "delete" => Some(context.soft_delete_task(id).map(|_| format!("Task ID {id} deleted, it's missing on the remote"))),
+        _ => Some(Ok(format!("Task ID {id} kept, it's missing on the remote"))),
+    }
+}
+
+fn pull_changed_tasks(
+    context: &TaskContext,
+    connector: &dyn RemoteConnector,
+    user: &String,
+    repo: &String,
+    options: &PullOptions,
+    task_statuses: &Vec<String>
+) -> Result<Vec<ImportResult>, String> {
+    let since = if options.full { None } else { context.last_sync(connector.type_name(), user, repo) };
+    let complete = options.limit.is_none() && options.state == RemoteTaskState::All;
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    let tasks = connector.list_remote_tasks(user, repo, !options.no_comments, !options.no_labels, options.limit, options.state.clone(), since, task_statuses, options.progress)?;
+    let results = tasks.into_iter()
+        .map(|mut task| {
+            let task_id = task.get_id().unwrap();
+            task.set_property(URL, &connector.task_url(user, repo, &task_id));
+            (task_id, import_remote_task(context, task, options.no_comments))
+        })
+        .collect::<Vec<_>>();
+
+    if complete && results.iter().all(|(_, result)| result.is_ok()) {
+        context.set_last_sync(connector.type_name(), user, repo, started)?;
+    }
+
+    Ok(results)
+}
+
 fn import_remote_task(context: &TaskContext, remote_task: Task, no_comments: bool) -> Result<Option<String>, String> {
     match context.find_task(&remote_task.get_id().unwrap()) {
         Ok(Some(mut local_task)) => {
+            let labels = remote_task.get_labels().as_ref()
+                .map(|remote_labels| merge_pulled_labels(local_task.get_labels().as_ref(), remote_labels));
             if local_task.get_property("name") == remote_task.get_property("name")
                 && local_task.get_property("description") == remote_task.get_property("description")
                 && local_task.get_property("status") == remote_task.get_property("status")
+                && local_task.get_property(STATE_REASON) == remote_task.get_property(STATE_REASON)
+                && [URL, PRIORITY, WEIGHT, DUE].iter().all(|prop| remote_task.get_property(prop).is_none_or(|value| local_task.get_property(prop).is_some_and(|local| {
+                    local == value || (*prop == DUE && parse_timestamp(local).is_some_and(|local| parse_timestamp(value) == Some(local)))
+                })))
+                && labels.as_ref().is_none_or(|labels| local_task.get_labels().as_ref() == Some(labels))
+                && !local_task.has_property(REMOTE_MISSING)
                 && (no_comments || comments_are_equal(local_task.get_comments(), remote_task.get_comments())) {
                 Ok(None)
             } else {
                 local_task.set_property("name", remote_task.get_property("name").unwrap());
                 local_task.set_property("description", remote_task.get_property("description").unwrap());
                 local_task.set_property("status", remote_task.get_property("status").unwrap());
+                local_task.delete_property(REMOTE_MISSING);
+                match remote_task.get_property(STATE_REASON) {
+                    Some(state_reason) => local_task.set_property(STATE_REASON, state_reason),
+                    None => { local_task.delete_property(STATE_REASON); },
+                }
+                for prop in [URL, PRIORITY, WEIGHT, DUE] {
+                    if let Some(value) = remote_task.get_property(prop) {
+                        local_task.set_property(prop, value);
+                    }
+                }
                 if !no_comments {
                     if let Some(comments) = remote_task.get_comments() {
                         local_task.set_comments(comments.to_vec());
                     }
                 }
+                if let Some(labels) = labels {
+                    local_task.set_labels(labels);
+                    record_synced_labels(&mut local_task);
+                }
 
                 match context.update_task(local_task) {
                     Ok(id) => Ok(Some(id)),
@@ -410,7 +587,13 @@ fn import_remote_task(context: &TaskContext, remote_task: Task, no_comments: boo
                 }
             }
         },
-        Ok(None) => match context.create_task(remote_task) {
+        Ok(None) => match context.create_task({
+            let mut remote_task = remote_task;
+            if remote_task.get_labels().is_some() {
+                record_synced_labels(&mut remote_task);
+            }
+            remote_task
+        }) {
             Ok(local_task) => Ok(Some(local_task.get_id().unwrap())),
             Err(e) => Err(e),
         },
@@ -419,25 +602,91 @@ fn import_remote_task(context: &TaskContext, remote_task: Task, no_comments: boo
 }
 
 fn comments_are_equal(local_comments: &Option<Vec<Comment>>, remote_comments: &Option<Vec<Comment>>) -> bool {
+    let normalize = |comments: &Vec<Comment>| comments.iter()
+        .map(|comment| {
+            let mut props = comment.get_all_properties().clone();
+            if let Some(created) = props.get("created").and_then(|created| parse_timestamp(created)) {
+                props.insert("created".to_string(), created.to_string());
+            }
+            (comment.get_id(), props, comment.get_text())
+        })
+        .collect::<Vec<_>>();
     (local_comments.is_none() && remote_comments.is_none())
     || (local_comments.is_some() && remote_comments.is_some()
-        && local_comments.clone().unwrap() == remote_comments.clone().unwrap()
+        && normalize(local_comments.as_ref().unwrap()) == normalize(remote_comments.as_ref().unwrap())
     )
 }
 
+pub(crate) fn task_peek(
+    context: &TaskContext,
+    id: String,
+    remote: &Option<String>,
+    connector_type: &Option<String>,
+    no_comments: bool,
+    no_labels: bool,
+    no_color: bool,
+) -> OpOutcome {
+    match get_user_repo(context, remote, connector_type) {
+        Ok((connector, _, _)) if !connector.capabilities().fetch_tasks => error_message(unsupported_message(connector.as_ref(), "fetching tasks")),
+        Ok((connector, user, repo)) => match peek_remote_task(context, connector.as_ref(), &user, &repo, &id, !no_comments, !no_labels) {
+            Ok(Some(task)) => {
+                let no_color = check_no_color(context, no_color);
+                print_task(context, task, no_color);
+                OpOutcome::Success
+            },
+            Ok(None) => error_message(format!("Task ID {id} not found on the remote")),
+            Err(e) => system_error_message(format!("Task ID {id}: {e}")),
+        },
+        Err(e) => error_message(format!("ERROR: {e}"))
+    }
+}
+
+/// Fetches a remote task for display only, nothing is written to the tasks ref.
+fn peek_remote_task(
+    context: &TaskContext,
+    connector: &dyn RemoteConnector,
+    user: &String,
+    repo: &String,
+    id: &String,
+    with_comments: bool,
+    with_labels: bool,
+) -> Result<Option<Task>, String> {
+    let task_statuses = get_task_statuses(&StatusManager::new(context));
+    let task = connector.get_remote_task(user, repo, id, with_comments, with_labels, &task_statuses)?;
+    Ok(task.map(|mut task| {
+        task.set_property(URL, &connector.task_url(user, repo, id));
+        task
+    }))
+}
+
+fn get_task_statuses(status_manager: &StatusManager) -> Vec<String> {
+    let mut task_statuses = vec![
+        status_manager.get_starting_status(),
+        status_manager.get_final_status(),
+    ];
+    if let Some(status_in_progress) = status_manager.get_in_progress_status() {
+        task_statuses.insert(1, status_in_progress);
+    }
+    task_statuses
+}
+
 fn get_user_repo(context: &TaskContext,
                  remote: &Option<String>,
                  connector_type: &Option<String>
 ) -> Result<(Box<dyn RemoteConnector>, String, String), String> {
     match context.list_remotes(remote) {
         Ok(remotes) => {
-            let mut user_repo = get_matching_remote_connectors(&context, remotes, connector_type);
+            let mut user_repo = get_matching_remote_connectors(context, remotes.clone(), connector_type);
             if user_repo.is_empty() {
                 return Err("No passing remotes".to_string());
             }
 
             if user_repo.len() > 1 {
-                return Err("More than one passing remote found. Please specify with --remote and/or --connector option.".to_owned());
+                let matched = describe_matching_remotes(context, remotes, connector_type).iter()
+                    .map(|m| format!("  {} ({}: {}/{})", m.url, m.connector_type, m.user, m.repo))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                return Err(format!("More than one passing remote found. Please specify with --remote and/or --connector option.\n{matched}"));
             }
 
             Ok(user_repo.remove(0))
@@ -446,60 +695,51 @@ fn get_user_repo(context: &TaskContext,
     }
 }
 
-pub(crate) fn task_export(context: &TaskContext, ids: Option<String>, status: Option<Vec<String>>, limit: Option<usize>, format: Option<String>, pretty: bool) -> bool {
+pub(crate) fn task_export(context: &TaskContext, ids: Option<String>, status: Option<Vec<String>>, limit: Option<usize>, format: Option<String>, pretty: bool) -> OpOutcome {
     if let Some(format) = format {
         if format.to_lowercase() != "json" {
             return error_message("Only JSON format is supported".to_string());
         }
     }
 
-    match context.list_tasks() {
-        Ok(mut tasks) => {
-            let mut result = vec![];
-            tasks.sort_by_key(|task| task.get_id().unwrap().parse::<u64>().unwrap_or(0));
-
-            let status_manager = StatusManager::new(&context);
-            let statuses = match status {
-                Some(statuses) => Some(statuses.iter().map(|s| status_manager.get_full_status_name(s)).collect::<Vec<_>>()),
-                None => None
-            };
-
-            let ids = ids.map(parse_ids);
-
-            let mut count = 0;
-            for task in tasks {
-                if let Some(ids) = &ids {
-                    if !ids.contains(&task.get_id().unwrap()) {
-                        continue;
-                    }
-                }
-
-                if let Some(ref statuses) = statuses {
-                    let task_status = task.get_property("status").unwrap();
-                    if !statuses.contains(&task_status) {
-                        continue;
-                    }
-                }
-
-                if let Some(limit) = limit {
-                    if count >= limit {
-                        break;
-                    }
-                }
+    let status_manager = StatusManager::new(context);
+    let filter = TaskFilter {
+        ids: ids.map(parse_ids),
+        statuses: status.map(|statuses| statuses.iter().map(|s| status_manager.get_full_status_name(s)).collect()),
+        ..TaskFilter::default()
+    };
 
-                result.push(task);
-                count += 1;
-            }
+    let mut stdout = std::io::stdout().lock();
+    match context.export_tasks_to(&mut stdout, &filter, limit, pretty) {
+        Ok(_) => {
+            let _ = writeln!(stdout);
+            OpOutcome::Success
+        },
+        Err(e) => system_error_message(format!("ERROR: {e}"))
+    }
+}
 
-            let func = if pretty { serde_json::to_string_pretty } else { serde_json::to_string };
+/// Queues a file for the task, the next push uploads it and links it in the issue description.
+pub(crate) fn task_attach(context: &TaskContext, id: String, file: String) -> OpOutcome {
+    match context.find_task(&id) {
+        Ok(Some(_)) => {},
+        Ok(None) => return error_message(format!("Task ID {id} not found")),
+        Err(e) => return system_error_message(format!("ERROR: {e}")),
+    }
+    let file = match std::fs::canonicalize(&file) {
+        Ok(path) if path.is_file() => path.display().to_string(),
+        Ok(_) => return error_message(format!("ERROR: {file} is not a file")),
+        Err(e) => return error_message(format!("ERROR: can't read {file}: {e}")),
+    };
 
-            if let Ok(result) = func(&result) {
-                success_message(result)
-            } else {
-                error_message("ERROR serializing task list".to_string())
-            }
-        },
-        Err(e) => error_message(format!("ERROR: {e}"))
+    let mut files = context.get_pending_attachments(&id);
+    if files.contains(&file) {
+        return error_message(format!("{file} is already attached to task ID {id}"));
+    }
+    files.push(file);
+    match context.set_pending_attachments(&id, &files) {
+        Ok(_) => success_message(format!("Attachment queued for task ID {id}, it is uploaded on the next push")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
@@ -510,135 +750,224 @@ pub(crate) fn task_push(
     connector_type: &Option<String>,
     no_comments: bool,
     no_labels: bool,
+    replace_labels: bool,
     no_color: bool
-) -> bool {
+) -> OpOutcome {
     let ids = parse_ids(ids);
 
     match get_user_repo(&context, remote, connector_type) {
-        Ok((connector, user, repo)) => {
-            let status_manager = StatusManager::new(&context);
-            let mut task_statuses = vec![
-                status_manager.get_starting_status(),
-                status_manager.get_final_status(),
-            ];
-            if let Some(status_in_progress) = status_manager.get_in_progress_status() {
-                task_statuses.insert(1, status_in_progress);
-            }
+        Ok((connector, _, _)) if !connector.capabilities().push_tasks => error_message(unsupported_message(connector.as_ref(), "pushing tasks")),
+        Ok((connector, user, repo)) => push_tasks(context, connector.as_ref(), &user, &repo, ids, no_comments, no_labels, replace_labels, no_color),
+        Err(e) => error_message(format!("ERROR: {e}"))
+    }
+}
 
-            let no_color = check_no_color(&context, no_color);
-            for id in ids {
-                println!("Sync: task ID {id}");
-                if let Ok(Some(local_task)) = context.find_task(&id) {
-                    println!("Sync: LOCAL task ID {id} found");
-                    let remote_task = connector.get_remote_task(&user, &repo, &id, !no_comments, !no_labels, &task_statuses);
-                    if let Ok(remote_task) = remote_task {
-                        println!("Sync: REMOTE task ID {id} found");
-
-                        let local_status = local_task.get_property("status").unwrap();
-                        let local_name = local_task.get_property("name").unwrap();
-                        let local_text = local_task.get_property("description").unwrap();
-
-                        let remote_status = remote_task.get_property("status").unwrap();
-                        let remote_name = remote_task.get_property("name").unwrap();
-                        let remote_text = remote_task.get_property("description").unwrap();
-
-                        if local_name != remote_name || local_text != remote_text || local_status != remote_status {
-                            if local_status != remote_status {
-                                println!("{}: {} -> {}", id, status_manager.format_status(remote_status, no_color), status_manager.format_status(local_status, no_color));
-                            }
-                            let state = if status_manager.is_done(local_status) { 
-                                RemoteTaskState::Closed(local_status.to_string(), remote_status.to_string()) 
-                            } else { 
-                                RemoteTaskState::Open(local_status.to_string(), remote_status.to_string()) 
-                            };
-
-                            match connector.update_remote_task(
-                                &user,
-                                &repo,
-                                &local_task,
-                                if !no_labels { local_task.get_labels().into() } else { None },
-                                state
-                            ) {
-                                Ok(_) => {
-                                    println!("Sync: REMOTE task ID {id} has been updated");
-                                },
-                                Err(e) => eprintln!("ERROR: {e}")
-                            }
-                        } else {
-                            if !no_comments {
-                                let mut comments_updated = false;
-                                let remote_comment_ids: Vec<String> = remote_task.get_comments().as_ref().unwrap_or(&vec![]).iter().map(|comment| comment.get_id().unwrap()).collect();
-                                for comment in local_task.get_comments().as_ref().unwrap_or(&vec![]) {
-                                    let local_comment_id = comment.get_id().unwrap();
-                                    if !remote_comment_ids.contains(&local_comment_id) {
-                                        create_remote_comment(context, &connector, &user, &repo, &id, &comment);
-                                        comments_updated = true;
-                                    }
-                                }
-                                if !comments_updated {
-                                    println!("Nothing to sync");
+fn push_tasks(
+    context: &TaskContext,
+    connector: &dyn RemoteConnector,
+    user: &String,
+    repo: &String,
+    ids: Vec<String>,
+    no_comments: bool,
+    no_labels: bool,
+    replace_labels: bool,
+    no_color: bool
+) -> OpOutcome {
+    let capabilities = connector.capabilities();
+    if !no_comments && !capabilities.comments {
+        println!("Sync: {} connector does not support comments, they are not pushed", connector.type_name());
+    }
+    let no_comments = no_comments || !capabilities.comments;
+    let no_labels = no_labels || !capabilities.labels;
+    let mut skipped_features = vec![];
+    let mut outcome = OpOutcome::Success;
+
+    let status_manager = StatusManager::new(context);
+    let task_statuses = get_task_statuses(&status_manager);
+
+    let no_color = check_no_color(context, no_color);
+    for id in ids {
+        println!("Sync: task ID {id}");
+        if let Ok(Some(local_task)) = context.find_task(&id) {
+            println!("Sync: LOCAL task ID {id} found");
+            let local_task = match upload_pending_attachments(context, connector, user, repo, local_task) {
+                Ok(local_task) => local_task,
+                Err(e) => {
+                    outcome = system_error_message(format!("ERROR: {e}"));
+                    continue;
+                }
+            };
+            let local_only = parse_remote_id(&id).is_err();
+            let remote_task = match local_only {
+                true => Ok(None),
+                false => connector.get_remote_task(user, repo, &id, !no_comments, !no_labels, &task_statuses),
+            };
+            let missing = match remote_task {
+                Ok(None) if !local_only => handle_missing_remote_task(context, &id),
+                _ => None,
+            };
+            if let Ok(Some(remote_task)) = remote_task {
+                println!("Sync: REMOTE task ID {id} found");
+
+                let local_status = local_task.get_property("status").unwrap();
+                let local_name = local_task.get_property("name").unwrap();
+                let local_text = local_task.get_property("description").unwrap();
+
+                let remote_status = remote_task.get_property("status").unwrap();
+                let remote_name = remote_task.get_property("name").unwrap();
+                let remote_text = remote_task.get_property("description").unwrap();
+
+                let local_state_reason = local_task.get_property(STATE_REASON);
+                let state_reason_changed = status_manager.is_closed_like(local_status).unwrap_or(false)
+                    && local_state_reason.is_some()
+                    && local_state_reason != remote_task.get_property(STATE_REASON);
+
+                if local_name != remote_name || local_text != remote_text || local_status != remote_status || state_reason_changed {
+                    if local_status != remote_status {
+                        println!("{}: {} -> {}", id, status_manager.format_status(remote_status, no_color), status_manager.format_status(local_status, no_color));
+                    }
+                    let state = get_remote_task_state(&status_manager, local_status, remote_status, local_state_reason.cloned());
+
+                    let labels = merge_labels_for_push(
+                        local_task.get_labels().as_deref().unwrap_or_default(),
+                        remote_task.get_labels().as_deref().unwrap_or_default(),
+                        local_task.get_property(SYNCED_LABELS),
+                        replace_labels
+                    );
+                    match fit_task_for_push(context, connector, &local_task).and_then(|task| connector.update_remote_task(
+                        user,
+                        repo,
+                        &task,
+                        if !no_labels { Some(&labels) } else { None },
+                        state
+                    )) {
+                        Ok(_) => {
+                            println!("Sync: REMOTE task ID {id} has been updated");
+                            if !no_labels {
+                                let mut local_task = local_task.clone();
+                                record_synced_labels(&mut local_task);
+                                if let Err(e) = context.update_task(local_task) {
+                                    outcome = system_error_message(format!("ERROR: {e}"));
                                 }
-                            } else {
-                                println!("Nothing to sync");
                             }
+                        },
+                        Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
+                    }
+                } else {
+                    if !no_comments {
+                        let remote_comment_ids: Vec<String> = remote_task.get_comments().as_ref().unwrap_or(&vec![]).iter().map(|comment| comment.get_id().unwrap()).collect();
+                        let comments = local_task.get_comments().as_deref().unwrap_or_default().iter()
+                            .filter(|comment| !remote_comment_ids.contains(&comment.get_id().unwrap()));
+                        match push_new_comments(context, connector, user, repo, &id, comments, &mut skipped_features) {
+                            Some(OpOutcome::Success) => {},
+                            Some(comments_outcome) => outcome = comments_outcome,
+                            None => println!("Nothing to sync"),
                         }
                     } else {
-                        eprintln!("Sync: REMOTE task ID {id} NOT found");
+                        println!("Nothing to sync");
+                    }
+                }
+            } else if let Some(result) = missing {
+                match result {
+                    Ok(message) => println!("Sync: {message}"),
+                    Err(e) => outcome = system_error_message(format!("ERROR: {e}")),
+                }
+            } else if let Err(e) = remote_task {
+                outcome = system_error_message(format!("ERROR: {e}"));
+            } else {
+                eprintln!("Sync: REMOTE task ID {id} NOT found");
 
-                        let local_task = match no_labels {
-                            true => {
-                                let mut local_task = local_task;
-                                local_task.set_labels(vec![]);
-                                local_task
-                            },
-                            false => local_task
-                        };
+                let local_task = match no_labels {
+                    true => {
+                        let mut local_task = local_task;
+                        local_task.set_labels(vec![]);
+                        local_task
+                    },
+                    false => local_task
+                };
 
-                        match connector.create_remote_task(&user, &repo, &local_task) {
-                            Ok(id) => {
-                                println!("Sync: Created REMOTE task ID {id}");
-                                if local_task.get_id().unwrap() != id {
-                                    match context.update_task_id(&local_task.get_id().unwrap(), &id) {
-                                        Ok(_) => println!("Task ID {} -> {} updated", local_task.get_id().unwrap(), id),
-                                        Err(e) => eprintln!("ERROR: {e}"),
-                                    }
-                                }
+                match fit_task_for_push(context, connector, &local_task).and_then(|task| connector.create_remote_task(user, repo, &task)) {
+                    Ok(id) => {
+                        println!("Sync: Created REMOTE task ID {id}");
+                        if local_task.get_id().unwrap() != id {
+                            match context.update_task_id(&local_task.get_id().unwrap(), &id) {
+                                Ok(_) => println!("Task ID {} -> {} updated", local_task.get_id().unwrap(), id),
+                                Err(e) => outcome = system_error_message(format!("ERROR: {e}")),
+                            }
+                        }
 
-                                if !no_comments {
-                                    if let Some(comments) = local_task.get_comments() {
-                                        if !comments.is_empty() {
-                                            for comment in comments {
-                                                create_remote_comment(context, &connector, &user, &repo, &id, &comment);
-                                            }
-                                        }
-                                    }
-                                }
-                            },
-                            Err(e) => eprintln!("ERROR: {e}")
+                        if !no_comments {
+                            let comments = local_task.get_comments().as_deref().unwrap_or_default();
+                            if let Some(comments_outcome) = push_new_comments(context, connector, user, repo, &id, comments.iter(), &mut skipped_features)
+                                && comments_outcome != OpOutcome::Success {
+                                outcome = comments_outcome;
+                            }
                         }
-                    }
-                } else {
-                    eprintln!("Sync: LOCAL task ID {id} NOT found")
+                    },
+                    Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                 }
             }
-            true
-        },
-        Err(e) => error_message(format!("ERROR: {e}"))
+        } else {
+            outcome = error_message(format!("Sync: LOCAL task ID {id} NOT found"));
+        }
+    }
+    if !skipped_features.is_empty() {
+        println!("Sync: {} connector does not support comment {}, comments relying on it were not pushed", connector.type_name(), skipped_features.join(", "));
     }
+    outcome
 }
 
-fn create_remote_comment(context: &TaskContext, connector: &Box<dyn RemoteConnector>, user: &String, repo: &String, id: &String, comment: &Comment) {
-    let local_comment_id = comment.get_id().unwrap();
-    match connector.create_remote_comment(user, repo, id, comment) {
-        Ok(remote_comment_id) => {
-            println!("Created REMOTE comment ID {}", remote_comment_id);
-            match context.update_comment_id(&id, &local_comment_id, &remote_comment_id) {
-                Ok(_) => println!("Comment ID {} -> {} updated", local_comment_id, remote_comment_id),
-                Err(e) => eprintln!("ERROR: {e}"),
+/// Creates the comments on the remote, comments relying on features the connector lacks are held
+/// back and the features collected in `skipped_features`. Failures are reported in a single note.
+/// The remote comments are listed once per task, so comments created by an interrupted push aren't duplicated.
+/// Returns `None` when there was no comment to push.
+fn push_new_comments<'a>(
+    context: &TaskContext,
+    connector: &dyn RemoteConnector,
+    user: &String,
+    repo: &String,
+    id: &String,
+    comments: impl Iterator<Item = &'a Comment>,
+    skipped_features: &mut Vec<&'static str>
+) -> Option<OpOutcome> {
+    let mut outcome = None;
+    let mut errors = vec![];
+    let mut remote_comments = None;
+    for comment in comments {
+        outcome = Some(OpOutcome::Success);
+        let unsupported = unsupported_comment_features(connector, comment);
+        if !unsupported.is_empty() {
+            for feature in unsupported {
+                if !skipped_features.contains(&feature) {
+                    skipped_features.push(feature);
+                }
             }
-        },
-        Err(e) => eprintln!("ERROR creating REMOTE comment: {}", e)
+            continue;
+        }
+        let remote_comments = remote_comments.get_or_insert_with(|| connector.list_remote_comment_texts(user, repo, id));
+        let result = remote_comments.as_deref().map_err(|e| e.clone())
+            .and_then(|remote_comments| create_remote_comment(context, connector, user, repo, id, comment, remote_comments));
+        if let Err(e) = result {
+            errors.push(format!("{}: {e}", comment.get_id().unwrap_or_default()));
+        }
+    }
+    if !errors.is_empty() {
+        outcome = Some(system_error_message(format!("ERROR: {} comment(s) of task ID {id} were not pushed ({})", errors.len(), errors.join("; "))));
     }
+    outcome
+}
+
+fn create_remote_comment(context: &TaskContext, connector: &dyn RemoteConnector, user: &String, repo: &String, id: &String, comment: &Comment, remote_comments: &[(String, String)]) -> Result<(), String> {
+    let local_comment_id = comment.get_id().unwrap();
+    let comment = fit_comment_for_push(context, connector, comment)?;
+    let remote_comment_id = match find_marked_comment(remote_comments, &comment) {
+        Some(remote_comment_id) => remote_comment_id,
+        None => connector.create_remote_comment(user, repo, id, &comment)?,
+    };
+    println!("Created REMOTE comment ID {}", remote_comment_id);
+    context.update_comment_id(id, &local_comment_id, &remote_comment_id)?;
+    println!("Comment ID {} -> {} updated", local_comment_id, remote_comment_id);
+    Ok(())
 }
 
 pub(crate) fn task_delete(
@@ -648,7 +977,7 @@ pub(crate) fn task_delete(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     let ids = match status {
         Some(statuses) => {
             match context.list_tasks() {
@@ -677,44 +1006,45 @@ pub(crate) fn task_delete(
     match context.delete_tasks(&ids) {
         Ok(_) => {
             println!("Task(s) {} deleted", ids.join(", "));
-            let mut success = false;
+            let mut outcome = OpOutcome::Success;
             if push {
                 match get_user_repo(&context, remote, connector_type) {
+                    Ok((connector, _, _)) if !connector.capabilities().delete_tasks => outcome = error_message(unsupported_message(connector.as_ref(), "deleting tasks")),
                     Ok((connector, user, repo)) => {
                         for id in ids {
                             match connector.delete_remote_task(&user, &repo, &id.to_string()) {
                                 Ok(_) => println!("Sync: REMOTE task ID {id} has been deleted"),
-                                Err(e) => eprintln!("ERROR: {e}")
+                                Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                             }
                         }
-                        success = true;
                     },
-                    Err(e) => eprintln!("ERROR: {e}"),
+                    Err(e) => outcome = error_message(format!("ERROR: {e}")),
                 }
             }
 
-            success
+            outcome
         },
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
-pub(crate) fn task_clear(context: &TaskContext, ) -> bool {
+pub(crate) fn task_clear(context: &TaskContext, ) -> OpOutcome {
     match context.clear_tasks() {
         Ok(task_count) => success_message(format!("{task_count} task(s) deleted")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
-pub(crate) fn task_show(context: &TaskContext, id: String, no_color: bool) -> bool {
-    match context.find_task(&id) {
-        Ok(Some(task)) => {
+pub(crate) fn task_show(context: &TaskContext, id: String, no_color: bool) -> OpOutcome {
+    match context.find_task_by_prefix(&id) {
+        Ok(FindResult::Unique(task)) => {
             let no_color = check_no_color(&context, no_color);
             print_task(&context, task, no_color);
-            true
+            OpOutcome::Success
         },
-        Ok(None) => error_message(format!("Task ID {id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Ok(FindResult::Ambiguous(ids)) => error_message(format!("Task ID {id} is ambiguous, candidates are: {}", ids.join(", "))),
+        Ok(FindResult::NotFound) => error_message(format!("Task ID {id} not found")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
@@ -811,13 +1141,13 @@ fn print_label(label: &Label, no_color: bool) {
     match no_color {
         true => print!("{}", label.get_name()),
         false => {
-            let color = str_to_color(label.get_color().as_str(), &None);
-            print!("{} ", color.paint(label.get_name()));
+            let style = label_style(label);
+            print!("{} ", style.paint(label.get_name()));
         }
     }
 }
 
-fn make_comparison(first: &Task, second: &Task, prop: &str, value_type: &str) -> Ordering {
+fn make_comparison(first: &Task, second: &Task, prop: &str, sort_type: &str) -> Ordering {
     match prop {
         "id" => {
             let first_value = match first.get_id() {
@@ -832,7 +1162,13 @@ fn make_comparison(first: &Task, second: &Task, prop: &str, value_type: &str) ->
             first_value.cmp(&second_value)
         },
         _ => {
-            match value_type {
+            match sort_type {
+                "datetime" | "date" => {
+                    let first_value = first.get_property(prop).and_then(|value| parse_timestamp(value)).unwrap_or(0);
+                    let second_value = second.get_property(prop).and_then(|value| parse_timestamp(value)).unwrap_or(0);
+
+                    first_value.cmp(&second_value)
+                },
                 "integer" => {
                     let first_value = match first.get_property(prop) {
                         Some(value) => value.parse::<u64>().unwrap_or(0),
@@ -845,6 +1181,12 @@ fn make_comparison(first: &Task, second: &Task, prop: &str, value_type: &str) ->
 
                     first_value.cmp(&second_value)
                 },
+                "numeric" => {
+                    let first_value = first.get_property(prop).and_then(|value| value.trim().parse::<f64>().ok()).unwrap_or(0.0);
+                    let second_value = second.get_property(prop).and_then(|value| value.trim().parse::<f64>().ok()).unwrap_or(0.0);
+
+                    first_value.total_cmp(&second_value)
+                },
                 _ => {
                     let first_value = match first.get_property(prop) {
                         Some(value) => value.to_lowercase(),
@@ -871,7 +1213,7 @@ pub(crate) fn task_list(context: &TaskContext,
              columns: Option<Vec<String>>,
              sort: Option<Vec<String>>,
              limit: Option<usize>,
-             no_color: bool) -> bool {
+             no_color: bool) -> OpOutcome {
     match context.list_tasks() {
         Ok(mut tasks) => {
             let prop_manager = PropertyManager::new(&context);
@@ -893,12 +1235,12 @@ pub(crate) fn task_list(context: &TaskContext,
                             let comparison;
                             if s.to_lowercase().ends_with(" desc") {
                                 s = s[..(s.len() - "desc".len())].trim();
-                                comparison = make_comparison(b, a, s, &prop_manager.get_parameter(&s, "value_type").unwrap_or_else(|| String::from("")));
+                                comparison = make_comparison(b, a, s, &prop_manager.get_sort_type(s));
                             } else {
                                 if s.to_lowercase().ends_with(" asc") {
                                     s = s[..(s.len() - "asc".len())].trim();
                                 }
-                                comparison = make_comparison(a, b, s, &prop_manager.get_parameter(&s, "value_type").unwrap_or_else(|| String::from("")));
+                                comparison = make_comparison(a, b, s, &prop_manager.get_sort_type(s));
                             }
 
                             if ordering.is_none() {
@@ -954,7 +1296,7 @@ pub(crate) fn task_list(context: &TaskContext,
                 if from.is_some() || until.is_some() {
                     let created = task.get_property("created");
                     if let Some(created) = created {
-                        let created = Local.timestamp_opt(created.parse().unwrap(), 0).unwrap();
+                        let created = Local.timestamp_opt(parse_timestamp(created).unwrap_or(0) as i64, 0).unwrap();
 
                         if from.is_some() {
                             if created < from.unwrap().earliest().unwrap() {
@@ -989,10 +1331,10 @@ pub(crate) fn task_list(context: &TaskContext,
                 count += 1;
             }
 
-            true
+            OpOutcome::Success
         },
         Err(e) => {
-            error_message(format!("ERROR: {e}"))
+            system_error_message(format!("ERROR: {e}"))
         }
     }
 }
@@ -1042,31 +1384,16 @@ fn print_column(
                     &empty_string
                 })
             };
-            print!("{} ", prop_manager.format_value(column, value, context, prop_manager.get_properties(), no_color))
+            print!("{} ", prop_manager.format_list_value(column, value, context, prop_manager.get_properties(), no_color))
         },
     }
 }
 
-pub(crate) fn task_stats(context: &TaskContext, no_color: bool) -> bool {
-    match context.list_tasks() {
-        Ok(tasks) => {
-            let mut total = 0;
-            let mut status_stats = HashMap::<String, i32>::new();
-            let mut author_stats = HashMap::<String, i32>::new();
+pub(crate) fn task_stats(context: &TaskContext, no_color: bool) -> OpOutcome {
+    match context.get_stats() {
+        Ok(TaskStats { total, by_status: status_stats, by_author: author_stats }) => {
             let no_color = check_no_color(&context, no_color);
 
-            for task in tasks {
-                total += 1;
-
-                if let Some(status) = task.get_property("status") {
-                    status_stats.entry(status.to_owned()).and_modify(|count| *count += 1).or_insert(1);
-                }
-
-                if let Some(author) = task.get_property("author") {
-                    author_stats.entry(author.to_owned()).and_modify(|count| *count += 1).or_insert(1);
-                }
-            }
-
             println!("Total tasks: {total}");
             println!();
 
@@ -1082,18 +1409,20 @@ pub(crate) fn task_stats(context: &TaskContext, no_color: bool) -> bool {
                 println!("Top 10 authors:");
 
                 let prop_manager = PropertyManager::new(&context);
-                let empty_context = HashMap::new();
 
                 let mut author_stats = author_stats.iter().collect::<Vec<_>>();
                 author_stats.sort_by(|a, b| b.1.cmp(a.1));
 
-                for author in author_stats.iter().take(10) {
-                    println!("{}: {}", prop_manager.format_value("author", &author.0, &empty_context, &vec![], no_color), author.1);
+                for (author, count) in author_stats.iter().take(10) {
+                    match prop_manager.resolve_style("author", author) {
+                        Some(style_spec) if !no_color => println!("{}: {}", style_spec.style.paint(author.as_str()), count),
+                        _ => println!("{author}: {count}"),
+                    }
                 }
             }
-            true
+            OpOutcome::Success
         },
-        Err(e) => error_message(format!("ERROR: {e}"))
+        Err(e) => system_error_message(format!("ERROR: {e}"))
     }
 }
 
@@ -1106,5 +1435,418 @@ fn check_no_color(context: &TaskContext, no_color: bool) -> bool {
 fn extract_task_properties(task: &Task) -> HashMap<String, String> {
     let mut context = task.get_all_properties().to_owned();
     context.insert("id".to_string(), task.get_id().unwrap());
+    if let Some(labels) = task.get_labels() {
+        context.insert("labels".to_string(), labels.iter().map(|label| label.get_name()).collect::<Vec<_>>().join(","));
+    }
     context
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::env::temp_dir;
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use gittask::InMemoryTaskStore;
+    use crate::connectors::{with_comment_marker, VISIBILITY};
+    use super::*;
+
+    #[test]
+    fn test_get_and_unset_in_memory() {
+        let store = InMemoryTaskStore::new();
+        let mut task = Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        task.set_property("estimate", "3");
+        store.create_task(task).unwrap();
+
+        assert_eq!(task_get(&store, "1".to_string(), "estimate".to_string()), OpOutcome::Success);
+        assert_eq!(task_get(&store, "2".to_string(), "estimate".to_string()), OpOutcome::UserError);
+        assert_eq!(task_unset(&store, "1,2".to_string(), "estimate".to_string()), OpOutcome::UserError);
+        assert_eq!(store.find_task("1").unwrap().unwrap().get_property("estimate"), None);
+        assert_eq!(task_get(&store, "1".to_string(), "estimate".to_string()), OpOutcome::UserError);
+    }
+
+    struct MockConnector {
+        tasks: RefCell<Vec<Task>>,
+        requested_since: RefCell<Vec<Option<u64>>>,
+        comments: RefCell<Vec<Comment>>,
+        failing: RefCell<Vec<String>>,
+    }
+
+    impl RemoteConnector for MockConnector {
+        fn type_name(&self) -> &str {
+            "mock"
+        }
+
+        fn supports_remote(&self, _url: &str) -> Option<(String, String)> {
+            None
+        }
+
+        fn task_url(&self, user: &String, repo: &String, task_id: &String) -> String {
+            format!("https://example.com/{user}/{repo}/{task_id}")
+        }
+
+        fn list_remote_tasks(&self, _user: &String, _repo: &String, _with_comments: bool, _with_labels: bool, _limit: Option<usize>, _state: RemoteTaskState, since: Option<u64>, _task_statuses: &Vec<String>, _progress: Option<&dyn Fn(usize, usize)>) -> Result<Vec<Task>, String> {
+            self.requested_since.borrow_mut().push(since);
+            Ok(self.tasks.take())
+        }
+
+        fn get_remote_task(&self, _user: &String, _repo: &String, task_id: &String, _with_comments: bool, _with_labels: bool, _task_statuses: &Vec<String>) -> Result<Option<Task>, String> {
+            parse_remote_id(task_id)?;
+            if self.failing.borrow().contains(task_id) {
+                return Err(format!("Request timed out (error sending request for url (https://example.com/api/v4/projects/team404/issues/{task_id}))"));
+            }
+            Ok(self.tasks.borrow().iter().find(|task| task.get_id().as_ref() == Some(task_id)).cloned())
+        }
+
+        fn create_remote_task(&self, _user: &String, _repo: &String, task: &Task) -> Result<String, String> {
+            self.tasks.borrow_mut().push(task.clone());
+            Ok(self.tasks.borrow().len().to_string())
+        }
+
+        fn create_remote_comment(&self, _user: &String, _repo: &String, _task_id: &String, comment: &Comment) -> Result<String, String> {
+            if self.failing.borrow().contains(&comment.get_text()) {
+                return Err("500 Internal Server Error".to_string());
+            }
+            self.comments.borrow_mut().push(comment.clone());
+            Ok(format!("10{}", self.comments.borrow().len()))
+        }
+
+        fn list_remote_comment_texts(&self, _user: &String, _repo: &String, _task_id: &String) -> Result<Vec<(String, String)>, String> {
+            Ok(self.comments.borrow().iter().enumerate().map(|(i, comment)| (format!("10{}", i + 1), with_comment_marker(comment))).collect())
+        }
+
+        fn create_remote_label(&self, _user: &String, _repo: &String, _task_id: &String, _label: &Label) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn update_remote_task(&self, _user: &String, _repo: &String, _task: &Task, _labels: Option<&Vec<Label>>, _state: RemoteTaskState) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn update_remote_comment(&self, _user: &String, _repo: &String, _task_id: &String, _comment_id: &String, _comment: &Comment) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn delete_remote_task(&self, _user: &String, _repo: &String, _task_id: &String) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn delete_remote_comment(&self, _user: &String, _repo: &String, _task_id: &String, _comment_id: &String) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn delete_remote_label(&self, _user: &String, _repo: &String, _task_id: &String, _name: &String) -> Result<(), String> {
+            unimplemented!()
+        }
+    }
+
+    /// Pulls every task state without comments and labels.
+    fn pull_options(limit: Option<usize>, full: bool) -> PullOptions<'static> {
+        PullOptions { limit, state: RemoteTaskState::All, full, no_comments: true, no_labels: true, progress: None }
+    }
+
+    fn remote_task(id: &str, name: &str) -> Task {
+        let props = HashMap::from([
+            ("name".to_string(), name.to_string()),
+            ("description".to_string(), String::new()),
+            ("status".to_string(), "OPEN".to_string()),
+            ("created".to_string(), "1700000000".to_string()),
+        ]);
+        Task::from_properties(id.to_string(), props).unwrap()
+    }
+
+    #[test]
+    fn test_pull_changed_tasks_checkpoint() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let task_statuses = vec!["OPEN".to_string(), "CLOSED".to_string()];
+        let connector = MockConnector {
+            tasks: RefCell::new(vec![remote_task("1", "First"), remote_task("2", "Second")]),
+            requested_since: RefCell::new(vec![]),
+            comments: RefCell::new(vec![]),
+            failing: RefCell::new(vec![]),
+        };
+
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert_eq!(context.last_sync("mock", &user, &repo), None);
+        let results = pull_changed_tasks(&context, &connector, &user, &repo, &pull_options(None, false), &task_statuses).unwrap();
+        assert_eq!(results.len(), 2);
+        let checkpoint = context.last_sync("mock", &user, &repo).unwrap();
+        assert!(checkpoint >= started);
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property(URL).unwrap(), "https://example.com/jhspetersson/git-task/1");
+
+        connector.tasks.replace(vec![remote_task("3", "Third")]);
+        let results = pull_changed_tasks(&context, &connector, &user, &repo, &pull_options(None, false), &task_statuses).unwrap();
+        assert_eq!(results.into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec!["3"]);
+        assert_eq!(*connector.requested_since.borrow(), vec![None, Some(checkpoint)]);
+        assert_eq!(context.list_tasks().unwrap().len(), 3);
+
+        pull_changed_tasks(&context, &connector, &user, &repo, &pull_options(Some(10), true), &task_statuses).unwrap();
+        assert_eq!(connector.requested_since.borrow().last(), Some(&None));
+        assert!(context.last_sync("mock", &user, &repo).unwrap() >= checkpoint);
+        assert_eq!(context.last_sync("github", &user, &repo), None);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_remote_task_policy() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let task_statuses = vec!["OPEN".to_string(), "CLOSED".to_string()];
+        let connector = MockConnector {
+            tasks: RefCell::new(vec![remote_task("1", "First"), remote_task("2", "Second"), remote_task("3", "Third")]),
+            requested_since: RefCell::new(vec![]),
+            comments: RefCell::new(vec![]),
+            failing: RefCell::new(vec![]),
+        };
+        pull_changed_tasks(&context, &connector, &user, &repo, &pull_options(None, false), &task_statuses).unwrap();
+        context.create_task(remote_task("4", "Local only")).unwrap();
+        let missing = |id: &str| {
+            assert!(connector.get_remote_task(&user, &repo, &id.to_string(), false, false, &task_statuses).unwrap().is_none());
+            handle_missing_remote_task(&context, id)
+        };
+
+        assert!(missing("1").is_none());
+
+        context.set_config_value("task.sync.onremotemissing", "keep").unwrap();
+        assert_eq!(missing("1").unwrap().unwrap(), "Task ID 1 kept, it's missing on the remote");
+        assert!(!context.find_task("1").unwrap().unwrap().has_property(REMOTE_MISSING));
+        assert!(missing("4").is_none());
+
+        context.set_config_value("task.sync.onremotemissing", "mark").unwrap();
+        assert_eq!(missing("2").unwrap().unwrap(), "Task ID 2 marked, it's missing on the remote");
+        assert_eq!(context.find_task("2").unwrap().unwrap().get_property(REMOTE_MISSING).unwrap(), "true");
+        connector.tasks.replace(vec![remote_task("2", "Second")]);
+        assert_eq!(import_remote_task(&context, connector.get_remote_task(&user, &repo, &"2".to_string(), false, false, &task_statuses).unwrap().unwrap(), true).unwrap(), Some("2".to_string()));
+        assert!(!context.find_task("2").unwrap().unwrap().has_property(REMOTE_MISSING));
+        connector.tasks.replace(vec![]);
+
+        context.set_config_value("task.sync.onremotemissing", "delete").unwrap();
+        connector.failing.replace(vec!["404".to_string()]);
+        let mut unreachable = remote_task("404", "Unreachable");
+        unreachable.set_property(URL, "https://example.com/jhspetersson/git-task/404");
+        context.create_task(unreachable).unwrap();
+        assert_eq!(pull_task_ids(&context, &connector, &user, &repo, vec!["404".to_string()], &pull_options(None, false), &task_statuses), OpOutcome::SystemError);
+        assert_eq!(missing("3").unwrap().unwrap(), "Task ID 3 deleted, it's missing on the remote");
+        assert_eq!(context.list_tasks().unwrap().iter().map(|task| task.get_id().unwrap()).collect::<Vec<_>>(), vec!["1", "2", "4", "404"]);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_peek_remote_task() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.create_task(remote_task("1", "Local")).unwrap();
+        let head = repo.refname_to_id(&context.get_ref_path()).unwrap();
+        let (user, repo_name) = ("jhspetersson".to_string(), "git-task".to_string());
+        let connector = MockConnector {
+            tasks: RefCell::new(vec![remote_task("7", "Remote")]),
+            requested_since: RefCell::new(vec![]),
+            comments: RefCell::new(vec![]),
+            failing: RefCell::new(vec![]),
+        };
+
+        let task = peek_remote_task(&context, &connector, &user, &repo_name, &"7".to_string(), true, true).unwrap().unwrap();
+        assert_eq!(task.get_id().unwrap(), "7");
+        assert_eq!(task.get_property(URL).unwrap(), "https://example.com/jhspetersson/git-task/7");
+        assert!(peek_remote_task(&context, &connector, &user, &repo_name, &"8".to_string(), true, true).unwrap().is_none());
+
+        assert_eq!(repo.refname_to_id(&context.get_ref_path()).unwrap(), head);
+        assert!(context.find_task("7").unwrap().is_none());
+        assert_eq!(context.list_tasks().unwrap().len(), 1);
+
+        repo.remote("origin", "https://redmine.example.com/projects/git-task").unwrap();
+        let redmine = Some("redmine".to_string());
+        assert_eq!(task_peek(&context, "7".to_string(), &None, &redmine, true, true, true), OpOutcome::UserError);
+        assert_eq!(task_pull(&context, Some("7".to_string()), None, None, &None, &redmine, true, true, false), OpOutcome::UserError);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_push_comments_with_unsupported_features() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let connector = MockConnector {
+            tasks: RefCell::new(vec![]),
+            requested_since: RefCell::new(vec![]),
+            comments: RefCell::new(vec![]),
+            failing: RefCell::new(vec![]),
+        };
+        assert!(!connector.capabilities().reactions && !connector.capabilities().comment_visibility);
+
+        let mut task = remote_task("1", "Commented");
+        task.add_comment(None, HashMap::from([("reactions".to_string(), "+1:2".to_string())]), "Liked".to_string(), None);
+        task.add_comment(None, HashMap::from([(VISIBILITY.to_string(), "role:Developers".to_string())]), "Internal".to_string(), None);
+        task.add_comment(None, HashMap::new(), "Plain".to_string(), None);
+        let task = context.create_task(task).unwrap();
+        let comments = task.get_comments().clone().unwrap();
+        let id = task.get_id().unwrap();
+
+        let mut skipped = vec![];
+        assert_eq!(push_new_comments(&context, &connector, &user, &repo, &id, comments[..2].iter(), &mut skipped), Some(OpOutcome::Success));
+        assert_eq!(skipped, vec![VISIBILITY]);
+        let pushed = connector.comments.take();
+        assert_eq!(pushed.iter().map(|comment| comment.get_text()).collect::<Vec<_>>(), vec!["Liked"]);
+
+        let stored = context.find_task(&id).unwrap().unwrap().get_comments().clone().unwrap();
+        assert_eq!(stored.iter().map(|comment| comment.get_id().unwrap()).collect::<Vec<_>>(), vec!["101", comments[1].get_id().unwrap().as_str(), comments[2].get_id().unwrap().as_str()]);
+        assert_eq!(stored[0].get_all_properties().get("reactions").unwrap(), "+1:2");
+        assert_eq!(push_new_comments(&context, &connector, &user, &repo, &id, stored[3..].iter(), &mut skipped), None);
+
+        connector.failing.replace(vec!["Plain".to_string()]);
+        assert_eq!(push_new_comments(&context, &connector, &user, &repo, &id, stored[2..].iter(), &mut skipped), Some(OpOutcome::SystemError));
+        assert!(connector.comments.take().is_empty());
+
+        connector.failing.replace(vec![]);
+        connector.comments.replace(vec![stored[0].clone(), stored[2].clone()]);
+        assert_eq!(push_new_comments(&context, &connector, &user, &repo, &id, stored[2..].iter(), &mut skipped), Some(OpOutcome::Success));
+        assert_eq!(connector.comments.take().len(), 2);
+        assert_eq!(context.find_task(&id).unwrap().unwrap().get_comments().as_ref().unwrap()[2].get_id().unwrap(), "102");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_sort_as_numeric() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let mut prop_manager = PropertyManager::new(&context);
+        prop_manager.add_property("rank".to_string(), "string".to_string(), "White".to_string(), None, None, None).unwrap();
+
+        let mut tasks = ["10", "2", "1"].iter().enumerate().map(|(i, rank)| {
+            let mut task = remote_task(&(i + 1).to_string(), rank);
+            task.set_property("rank", rank);
+            task
+        }).collect::<Vec<_>>();
+        let ranks = |tasks: &Vec<Task>| tasks.iter().map(|task| task.get_property("rank").unwrap().clone()).collect::<Vec<_>>();
+
+        tasks.sort_by(|a, b| make_comparison(a, b, "rank", &prop_manager.get_sort_type("rank")));
+        assert_eq!(ranks(&tasks), vec!["1", "10", "2"]);
+
+        prop_manager.set_parameter(&"rank".to_string(), &"sort_as".to_string(), &"numeric".to_string()).unwrap();
+        assert_eq!(prop_manager.get_parameter("rank", "sort_as").unwrap(), "numeric");
+        tasks.sort_by(|a, b| make_comparison(a, b, "rank", &prop_manager.get_sort_type("rank")));
+        assert_eq!(ranks(&tasks), vec!["1", "2", "10"]);
+
+        assert!(prop_manager.set_parameter(&"rank".to_string(), &"sort_as".to_string(), &"random".to_string()).is_err());
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_op_outcome() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        context.create_task(remote_task("1", "First")).unwrap();
+
+        assert_eq!(task_get(&context, "1".to_string(), "name".to_string()), OpOutcome::Success);
+        assert_eq!(task_get(&context, "2".to_string(), "name".to_string()), OpOutcome::UserError);
+        assert_eq!(task_show(&context, "2".to_string(), true), OpOutcome::UserError);
+        assert_eq!(task_unset(&context, "1,2".to_string(), "description".to_string()), OpOutcome::UserError);
+
+        assert_eq!(OpOutcome::Success.max(OpOutcome::UserError).max(OpOutcome::SystemError), OpOutcome::SystemError);
+        assert_eq!(task_set(&context, "1".to_string(), "name".to_string(), "Renamed".to_string(), false, &None, &None, true), OpOutcome::Success);
+        assert_eq!(task_set(&context, "1".to_string(), "name".to_string(), "Pushed".to_string(), true, &None, &None, true), OpOutcome::UserError);
+        assert_eq!(task_replace(&context, "1".to_string(), "name".to_string(), "Pushed".to_string(), "Replaced".to_string(), false, true, &None, &None, true), OpOutcome::UserError);
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property("name").unwrap(), "Replaced");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_pull_push_outcome() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let connector = MockConnector {
+            tasks: RefCell::new(vec![remote_task("1", "First")]),
+            requested_since: RefCell::new(vec![]),
+            comments: RefCell::new(vec![]),
+            failing: RefCell::new(vec!["3".to_string()]),
+        };
+        let task_statuses = vec!["OPEN".to_string(), "CLOSED".to_string()];
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(pull_task_ids(&context, &connector, &user, &repo, ids(&["1"]), &pull_options(None, false), &task_statuses), OpOutcome::Success);
+        assert_eq!(pull_task_ids(&context, &connector, &user, &repo, ids(&["4", "1"]), &pull_options(None, false), &task_statuses), OpOutcome::UserError);
+        assert_eq!(pull_task_ids(&context, &connector, &user, &repo, ids(&["3"]), &pull_options(None, false), &task_statuses), OpOutcome::SystemError);
+
+        context.create_task(remote_task("2", "Local only")).unwrap();
+        context.create_task(remote_task("3", "Unreachable")).unwrap();
+        assert_eq!(push_tasks(&context, &connector, &user, &repo, ids(&["1", "2"]), true, true, false, true), OpOutcome::Success);
+        assert_eq!(connector.tasks.borrow().len(), 2);
+        assert_eq!(push_tasks(&context, &connector, &user, &repo, ids(&["9"]), true, true, false, true), OpOutcome::UserError);
+        assert_eq!(push_tasks(&context, &connector, &user, &repo, ids(&["3"]), true, true, false, true), OpOutcome::SystemError);
+        assert_eq!(connector.tasks.borrow().len(), 2);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_push_uuid_task() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let (user, repo) = ("jhspetersson".to_string(), "git-task".to_string());
+        let connector = MockConnector {
+            tasks: RefCell::new(vec![]),
+            requested_since: RefCell::new(vec![]),
+            comments: RefCell::new(vec![]),
+            failing: RefCell::new(vec![]),
+        };
+
+        context.set_config_value("task.id.scheme", "uuid").unwrap();
+        let id = context.create_task(Task::new("Offline".to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap().get_id().unwrap();
+        assert!(parse_remote_id(&id).is_err());
+
+        assert_eq!(push_tasks(&context, &connector, &user, &repo, vec![id.clone()], true, true, false, true), OpOutcome::Success);
+        assert_eq!(connector.tasks.borrow().len(), 1);
+        assert!(context.find_task(&id).unwrap().is_none());
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property("name").unwrap(), "Offline");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_change_task_id() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        for name in ["First", "Second"] {
+            context.create_task(Task::new(name.to_string(), String::new(), "OPEN".to_string(), None).unwrap()).unwrap();
+        }
+
+        assert_eq!(change_task_id(&context, "1", "2"), OpOutcome::UserError);
+        assert_eq!(context.find_task("1").unwrap().unwrap().get_property("name").unwrap(), "First");
+        assert_eq!(context.find_task("2").unwrap().unwrap().get_property("name").unwrap(), "Second");
+
+        assert_eq!(change_task_id(&context, "1", "1"), OpOutcome::Success);
+        assert_eq!(change_task_id(&context, "1", "5"), OpOutcome::Success);
+        assert!(context.find_task("1").unwrap().is_none());
+        assert_eq!(context.find_task("5").unwrap().unwrap().get_property("name").unwrap(), "First");
+        assert_eq!(change_task_id(&context, "1", "6"), OpOutcome::SystemError);
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+}
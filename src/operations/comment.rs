@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use gittask::TaskContext;
-use crate::operations::get_user_repo;
-use crate::util::{error_message, get_text_from_editor};
+use crate::connectors::{fit_comment_for_push, unsupported_message};
+use crate::operations::{get_user_repo, OpOutcome};
+use crate::util::{error_message, get_text_from_editor, system_error_message};
 
 pub(crate) fn task_comment_add(
     context: &TaskContext,
@@ -10,47 +11,44 @@ pub(crate) fn task_comment_add(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     match context.find_task(&task_id) {
-        Ok(Some(mut task)) => {
+        Ok(Some(_)) => {
             let text = text.or_else(|| get_text_from_editor(&context, None));
             if text.is_none() {
                 return error_message("No text specified".to_string());
             }
             let text = text.unwrap();
 
-            let comment = task.add_comment(None, HashMap::new(), text, context.get_current_user().unwrap_or(None));
-            match context.update_task(task) {
-                Ok(_) => {
+            match context.add_comment(&task_id, HashMap::new(), text, None) {
+                Ok(comment) => {
                     println!("Task ID {task_id} updated");
-                    let mut success = false;
+                    let mut outcome = OpOutcome::Success;
                     if push {
                         match get_user_repo(&context, remote, connector_type) {
+                            Ok((connector, _, _)) if !connector.capabilities().comments => outcome = error_message(unsupported_message(connector.as_ref(), "comments")),
                             Ok((connector, user, repo)) => {
-                                match connector.create_remote_comment(&user, &repo, &task_id, &comment) {
+                                match fit_comment_for_push(context, connector.as_ref(), &comment).and_then(|comment| connector.create_remote_comment(&user, &repo, &task_id, &comment)) {
                                     Ok(remote_comment_id) => {
                                         println!("Created REMOTE comment ID {}", remote_comment_id);
                                         match context.update_comment_id(&task_id, &comment.get_id().unwrap(), &remote_comment_id) {
-                                            Ok(_) => {
-                                                println!("Comment ID {} -> {} updated", &comment.get_id().unwrap(), remote_comment_id);
-                                                success = true;
-                                            },
-                                            Err(e) => eprintln!("ERROR: {e}"),
+                                            Ok(_) => println!("Comment ID {} -> {} updated", &comment.get_id().unwrap(), remote_comment_id),
+                                            Err(e) => outcome = system_error_message(format!("ERROR: {e}")),
                                         }
                                     },
-                                    Err(e) => eprintln!("ERROR creating REMOTE comment: {e}")
+                                    Err(e) => outcome = system_error_message(format!("ERROR creating REMOTE comment: {e}"))
                                 }
                             },
-                            Err(e) => eprintln!("ERROR: {e}"),
+                            Err(e) => outcome = error_message(format!("ERROR: {e}")),
                         }
                     }
-                    success
+                    outcome
                 },
-                Err(e) => error_message(format!("ERROR: {e}")),
+                Err(e) => system_error_message(format!("ERROR: {e}")),
             }
         },
         Ok(None) => error_message(format!("Task ID {task_id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
@@ -62,7 +60,7 @@ pub(crate) fn task_comment_set(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     match context.find_task(&task_id) {
         Ok(Some(mut task)) => {
             let mut comments = task.get_comments().clone();
@@ -75,33 +73,32 @@ pub(crate) fn task_comment_set(
             }
             let comment = comment.unwrap();
             comment.set_text(text.clone());
+            let comment = comment.clone();
             task.set_comments(comments.unwrap());
 
             match context.update_task(task) {
                 Ok(_) => {
                     println!("Task ID {task_id} updated");
-                    let mut success = false;
+                    let mut outcome = OpOutcome::Success;
                     if push {
                         match get_user_repo(&context, remote, connector_type) {
+                            Ok((connector, _, _)) if !connector.capabilities().comments => outcome = error_message(unsupported_message(connector.as_ref(), "comments")),
                             Ok((connector, user, repo)) => {
-                                match connector.update_remote_comment(&user, &repo, &task_id, &comment_id, &text) {
-                                    Ok(_) => {
-                                        println!("Sync: REMOTE comment ID {comment_id} has been updated");
-                                        success = true;
-                                    },
-                                    Err(e) => eprintln!("ERROR: {e}")
+                                match fit_comment_for_push(context, connector.as_ref(), &comment).and_then(|comment| connector.update_remote_comment(&user, &repo, &task_id, &comment_id, &comment)) {
+                                    Ok(_) => println!("Sync: REMOTE comment ID {comment_id} has been updated"),
+                                    Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                                 }
                             },
-                            Err(e) => eprintln!("ERROR: {e}"),
+                            Err(e) => outcome = error_message(format!("ERROR: {e}")),
                         }
                     }
-                    success
+                    outcome
                 },
-                Err(e) => error_message(format!("ERROR: {e}")),
+                Err(e) => system_error_message(format!("ERROR: {e}")),
             }
         },
         Ok(None) => error_message(format!("Task ID {task_id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}"))
+        Err(e) => system_error_message(format!("ERROR: {e}"))
     }
 }
 
@@ -112,7 +109,7 @@ pub(crate) fn task_comment_edit(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     match context.find_task(&task_id) {
         Ok(Some(mut task)) => {
             let mut comments = task.get_comments().clone();
@@ -127,36 +124,35 @@ pub(crate) fn task_comment_edit(
             match get_text_from_editor(&context, Some(&comment.get_text())) {
                 Some(text) => {
                     comment.set_text(text.clone());
+                    let comment = comment.clone();
                     task.set_comments(comments.unwrap());
 
                     match context.update_task(task) {
                         Ok(_) => {
                             println!("Task ID {task_id} updated");
-                            let mut success = false;
+                            let mut outcome = OpOutcome::Success;
                             if push {
                                 match get_user_repo(&context, remote, connector_type) {
+                                    Ok((connector, _, _)) if !connector.capabilities().comments => outcome = error_message(unsupported_message(connector.as_ref(), "comments")),
                                     Ok((connector, user, repo)) => {
-                                        match connector.update_remote_comment(&user, &repo, &task_id, &comment_id, &text) {
-                                            Ok(_) => {
-                                                println!("Sync: REMOTE comment ID {comment_id} has been updated");
-                                                success = true;
-                                            },
-                                            Err(e) => eprintln!("ERROR: {e}")
+                                        match fit_comment_for_push(context, connector.as_ref(), &comment).and_then(|comment| connector.update_remote_comment(&user, &repo, &task_id, &comment_id, &comment)) {
+                                            Ok(_) => println!("Sync: REMOTE comment ID {comment_id} has been updated"),
+                                            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                                         }
                                     },
-                                    Err(e) => eprintln!("ERROR: {e}"),
+                                    Err(e) => outcome = error_message(format!("ERROR: {e}")),
                                 }
                             }
-                            success
+                            outcome
                         },
-                        Err(e) => error_message(format!("ERROR: {e}")),
+                        Err(e) => system_error_message(format!("ERROR: {e}")),
                     }
                 },
                 None => error_message("No text specified".to_string())
             }
         },
         Ok(None) => error_message(format!("Task ID {task_id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}"))
+        Err(e) => system_error_message(format!("ERROR: {e}"))
     }
 }
 
@@ -167,7 +163,7 @@ pub(crate) fn task_comment_delete(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     match context.find_task(&task_id) {
         Ok(Some(mut task)) => {
             match task.delete_comment(&comment_id) {
@@ -175,30 +171,28 @@ pub(crate) fn task_comment_delete(
                     match context.update_task(task) {
                         Ok(_) => {
                             println!("Task ID {task_id} updated");
-                            let mut success = false;
+                            let mut outcome = OpOutcome::Success;
                             if push {
                                 match get_user_repo(&context, remote, connector_type) {
+                                    Ok((connector, _, _)) if !connector.capabilities().comments => outcome = error_message(unsupported_message(connector.as_ref(), "comments")),
                                     Ok((connector, user, repo)) => {
                                         match connector.delete_remote_comment(&user, &repo, &task_id, &comment_id) {
-                                            Ok(_) => {
-                                                println!("Sync: REMOTE comment ID {comment_id} has been deleted");
-                                                success = true;
-                                            },
-                                            Err(e) => eprintln!("ERROR: {e}")
+                                            Ok(_) => println!("Sync: REMOTE comment ID {comment_id} has been deleted"),
+                                            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                                         }
                                     },
-                                    Err(e) => eprintln!("ERROR: {e}"),
+                                    Err(e) => outcome = error_message(format!("ERROR: {e}")),
                                 }
                             }
-                            success
+                            outcome
                         },
-                        Err(e) => error_message(format!("ERROR: {e}")),
+                        Err(e) => system_error_message(format!("ERROR: {e}")),
                     }
                 },
                 Err(e) => error_message(format!("ERROR: {e}")),
             }
         },
         Ok(None) => error_message(format!("Task ID {task_id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
\ No newline at end of file
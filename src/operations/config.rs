@@ -1,18 +1,42 @@
 use crate::connectors::get_config_options_from_connectors;
-use crate::util::{error_message, success_message};
-use gittask::TaskContext;
+use crate::property::PropertyManager;
+use crate::operations::OpOutcome;
+use crate::util::{error_message, success_message, system_error_message, DisplayTimeZone};
+use gittask::{parse_duration, TaskContext};
 
 pub(crate) mod status;
 pub(crate) mod properties;
+pub(crate) mod labels;
 
-pub(crate) fn task_config_get(context: &TaskContext, param: String) -> bool {
+pub(crate) fn task_config_get(context: &TaskContext, param: String) -> OpOutcome {
     match param.as_str() {
         "task.list.columns" => success_message(format!("{}", context.get_config_value(&param).unwrap_or_else(|_| String::from("id, created, status, name")))),
         "task.list.sort" => success_message(format!("{}", context.get_config_value(&param).unwrap_or_else(|_| String::from("id desc")))),
         "task.status.open" => success_message(format!("{}", context.get_config_value(&param).unwrap_or_else(|_| String::from("OPEN")))),
         "task.status.in_progress" => success_message(format!("{}", context.get_config_value(&param).unwrap_or_else(|_| String::from("IN_PROGRESS")))),
         "task.status.closed" => success_message(format!("{}", context.get_config_value(&param).unwrap_or_else(|_| String::from("CLOSED")))),
+        "task.status.openlike" | "task.status.closedlike" => success_message(context.get_config_value(&param).unwrap_or_default()),
         "task.ref" => success_message(format!("{}", context.get_ref_path())),
+        "task.ref.template" => {
+            match context.get_config_value(&param) {
+                Ok(value) => success_message(value),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.hook.create" | "task.hook.update" | "task.hook.strict" | "task.comments.split" | "task.status.strict" | "task.commit.sign" | "task.commit.empty" | "task.labels.scoped" => {
+            match context.get_config_value(&param) {
+                Ok(value) => success_message(value),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.id.scheme" => success_message(context.get_config_value(&param).unwrap_or_else(|_| String::from("sequential"))),
+        "task.comments.id" => success_message(context.get_config_value(&param).unwrap_or_else(|_| String::from("sequential"))),
+        "task.time.format" => success_message(context.get_config_value(&param).unwrap_or_else(|_| String::from("epoch"))),
+        "task.due.default" => success_message(context.get_config_value(&param).unwrap_or_default()),
+        "task.time.tz" => success_message(context.get_config_value(&param).unwrap_or_else(|_| String::from("local"))),
+        "task.store.layout" => success_message(context.get_config_value(&param).unwrap_or_else(|_| String::from("flat"))),
+        "task.lock.timeout" => success_message(context.get_config_value(&param).unwrap_or_else(|_| String::from("10"))),
+        "task.sync.onremotemissing" => success_message(context.get_config_value(&param).unwrap_or_default()),
         _ => {
             if get_config_options_from_connectors(&context).contains(&param) {
                 match context.get_config_value(&param) {
@@ -26,9 +50,15 @@ pub(crate) fn task_config_get(context: &TaskContext, param: String) -> bool {
     }
 }
 
-pub(crate) fn task_config_set(context: &TaskContext, param: String, value: String, move_ref: bool) -> bool {
+pub(crate) fn task_config_set(context: &TaskContext, param: String, value: String, move_ref: bool) -> OpOutcome {
     match param.as_str() {
         "task.list.columns" => {
+            let prop_manager = PropertyManager::new(context);
+            let known_properties = prop_manager.get_properties().iter().map(|p| p.get_name().to_string()).collect::<Vec<_>>();
+            if let Err(e) = validate_columns(&value, &known_properties) {
+                return error_message(e);
+            }
+
             match context.set_config_value(&param, &value) {
                 Ok(_) => success_message(format!("{param} has been updated")),
                 Err(e) => error_message(format!("ERROR: {e}"))
@@ -58,6 +88,19 @@ pub(crate) fn task_config_set(context: &TaskContext, param: String, value: Strin
                 Err(e) => error_message(format!("ERROR: {e}"))
             }
         },
+        "task.status.openlike" | "task.status.closedlike" => {
+            let other = if param == "task.status.openlike" { "task.status.closedlike" } else { "task.status.openlike" };
+            let other_statuses = context.get_config_value(other).unwrap_or_default();
+            let other_statuses = other_statuses.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+            if let Some(status) = value.split(',').map(|s| s.trim()).find(|s| other_statuses.contains(s)) {
+                return error_message(format!("Status {status} is already listed in {other}"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
         "task.ref" => {
             let value = match value {
                 value if !value.contains('/') => "refs/heads/".to_string() + value.as_str(),
@@ -70,8 +113,114 @@ pub(crate) fn task_config_set(context: &TaskContext, param: String, value: Strin
                 Err(e) => error_message(format!("ERROR: {e}"))
             }
         },
+        "task.ref.template" => {
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.hook.create" | "task.hook.update" | "task.hook.strict" | "task.comments.split" | "task.status.strict" | "task.commit.sign" | "task.commit.empty" | "task.labels.scoped" => {
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.store.layout" => {
+            if value != "flat" && value != "sharded" {
+                return error_message(format!("Unknown store layout: {value}, use flat or sharded"));
+            }
+
+            match context.set_config_value(&param, &value).and_then(|_| context.migrate_store_layout()) {
+                Ok(count) => success_message(format!("{param} has been updated, {count} task(s) moved")),
+                Err(e) => system_error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.sync.onremotemissing" => {
+            if !["keep", "mark", "delete"].contains(&value.as_str()) {
+                return error_message(format!("Unknown policy: {value}, use keep, mark or delete"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.time.format" => {
+            if value != "epoch" && value != "rfc3339" {
+                return error_message(format!("Unknown time format: {value}, use epoch or rfc3339"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.due.default" => {
+            if let Err(e) = parse_duration(&value) {
+                return error_message(e);
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.time.tz" => {
+            if DisplayTimeZone::parse(&value).is_none() {
+                return error_message(format!("Unknown time zone: {value}, use local, utc or an offset like +02:00"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.http.timeout" => {
+            if !value.parse::<u64>().is_ok_and(|seconds| seconds > 0) {
+                return error_message(format!("Invalid timeout: {value}, use a positive number of seconds"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.lock.timeout" => {
+            if value.parse::<u64>().is_err() {
+                return error_message(format!("Invalid timeout: {value}, use a number of seconds"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.comments.id" => {
+            if value != "sequential" && value != "hash" {
+                return error_message(format!("Unknown comment id scheme: {value}, use sequential or hash"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
+        "task.id.scheme" => {
+            if value != "sequential" && value != "uuid" && value.strip_prefix("prefix-").is_none_or(|prefix| prefix.is_empty()) {
+                return error_message(format!("Unknown id scheme: {value}, use sequential, uuid or prefix-<name>"));
+            }
+
+            match context.set_config_value(&param, &value) {
+                Ok(_) => success_message(format!("{param} has been updated")),
+                Err(e) => error_message(format!("ERROR: {e}"))
+            }
+        },
         _ => {
             if get_config_options_from_connectors(&context).contains(&param) {
+                if param.ends_with(".maxlength") && value.parse::<usize>().is_err() {
+                    return error_message(format!("Invalid length: {value}, use a number of characters"));
+                }
+
                 match context.set_config_value(&param, &value) {
                     Ok(_) => success_message(format!("{param} has been updated")),
                     Err(e) => error_message(format!("ERROR: {e}"))
@@ -83,7 +232,62 @@ pub(crate) fn task_config_set(context: &TaskContext, param: String, value: Strin
     }
 }
 
-pub(crate) fn task_config_list(context: &TaskContext, ) -> bool {
+pub(crate) fn task_config_list(context: &TaskContext, ) -> OpOutcome {
     let from_connectors = get_config_options_from_connectors(&context).join("\n");
-    success_message("task.list.columns\ntask.list.sort\ntask.status.open\ntask.status.closed\ntask.ref\n".to_string() + &from_connectors)
-}
\ No newline at end of file
+    success_message("task.list.columns\ntask.list.sort\ntask.status.open\ntask.status.closed\ntask.status.openlike\ntask.status.closedlike\ntask.status.strict\ntask.ref\ntask.ref.template\ntask.id.scheme\ntask.hook.create\ntask.hook.update\ntask.hook.strict\ntask.comments.split\ntask.comments.id\ntask.commit.sign\ntask.commit.empty\ntask.labels.scoped\ntask.store.layout\ntask.lock.timeout\ntask.sync.onremotemissing\ntask.time.format\ntask.time.tz\ntask.due.default\n".to_string() + &from_connectors)
+}
+
+const BUILTIN_COLUMNS: [&str; 7] = ["id", "created", "status", "name", "labels", "author", "description"];
+
+fn validate_columns(columns: &str, known_properties: &[String]) -> Result<(), String> {
+    let unknown = columns.split(',')
+        .map(|column| column.trim())
+        .filter(|column| !BUILTIN_COLUMNS.contains(column) && !known_properties.iter().any(|p| p == column))
+        .collect::<Vec<_>>();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        let mut valid = BUILTIN_COLUMNS.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        valid.extend(known_properties.iter().filter(|p| !BUILTIN_COLUMNS.contains(&p.as_str())).cloned());
+        Err(format!("Unknown column(s): {}. Valid columns are: {}", unknown.join(", "), valid.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use super::*;
+
+    #[test]
+    fn test_config_set_maxlength() {
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        assert_eq!(task_config_set(&context, "task.github.maxlength".to_string(), "60000".to_string(), false), OpOutcome::Success);
+        assert_eq!(task_config_set(&context, "task.github.maxlength".to_string(), "60k".to_string(), false), OpOutcome::UserError);
+        assert_eq!(context.get_config_value("task.github.maxlength").unwrap(), "60000");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_columns() {
+        let known_properties = vec!["id".to_string(), "priority".to_string()];
+        assert!(validate_columns("id, created, status, name", &known_properties).is_ok());
+        assert!(validate_columns("id,author,priority,labels", &known_properties).is_ok());
+    }
+
+    #[test]
+    fn test_validate_columns_unknown() {
+        let known_properties = vec!["priority".to_string()];
+        let error = validate_columns("id, stauts, name", &known_properties).unwrap_err();
+        assert!(error.contains("Unknown column(s): stauts"));
+        assert!(error.contains("status"));
+        assert!(error.contains("priority"));
+    }
+}
@@ -0,0 +1,30 @@
+use gittask::{Label, TaskContext};
+use crate::operations::OpOutcome;
+use crate::util::{error_message, success_message, system_error_message};
+
+pub(crate) fn task_config_labels_set(context: &TaskContext, name: String, color: Option<String>, description: Option<String>) -> OpOutcome {
+    match context.define_label(Label::new(name.clone(), color, description)) {
+        Ok(_) => success_message(format!("Label {name} defined")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
+    }
+}
+
+pub(crate) fn task_config_labels_delete(context: &TaskContext, name: String) -> OpOutcome {
+    match context.delete_label_definition(&name) {
+        Ok(_) => success_message(format!("Label {name} definition deleted")),
+        Err(e) => error_message(format!("ERROR: {e}")),
+    }
+}
+
+pub(crate) fn task_config_labels_list(context: &TaskContext) -> OpOutcome {
+    match context.list_label_definitions() {
+        Ok(definitions) => {
+            println!("Name\tColor\tDescription");
+            definitions.iter().for_each(|label| {
+                println!("{}\t{}\t{}", label.get_name(), label.get_color(), label.get_description().unwrap_or_default());
+            });
+            OpOutcome::Success
+        },
+        Err(e) => system_error_message(format!("ERROR: {e}")),
+    }
+}
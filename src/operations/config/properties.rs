@@ -1,8 +1,9 @@
 use gittask::TaskContext;
+use crate::operations::OpOutcome;
 use crate::property::PropertyManager;
 use crate::util::{error_message, read_from_pipe, success_message};
 
-pub(crate) fn task_config_properties_add(context: &TaskContext, name: String, value_type: String, color: String, style: Option<String>, enum_values: Option<Vec<String>>, cond_format: Option<Vec<String>>) -> bool {
+pub(crate) fn task_config_properties_add(context: &TaskContext, name: String, value_type: String, color: String, style: Option<String>, enum_values: Option<Vec<String>>, cond_format: Option<Vec<String>>) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.add_property(name.clone(), value_type, color, style, enum_values, cond_format) {
         Ok(_) => success_message(format!("Property {name} has been added")),
@@ -10,7 +11,7 @@ pub(crate) fn task_config_properties_add(context: &TaskContext, name: String, va
     }
 }
 
-pub(crate) fn task_config_properties_delete(context: &TaskContext, name: String, force: bool) -> bool {
+pub(crate) fn task_config_properties_delete(context: &TaskContext, name: String, force: bool) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
 
     if !force {
@@ -28,7 +29,7 @@ pub(crate) fn task_config_properties_delete(context: &TaskContext, name: String,
     }
 }
 
-pub(crate) fn task_config_properties_get(context: &TaskContext, name: String, param: String) -> bool {
+pub(crate) fn task_config_properties_get(context: &TaskContext, name: String, param: String) -> OpOutcome {
     let prop_manager = PropertyManager::new(&context);
     match prop_manager.get_parameter(&name, &param) {
         Some(value) => success_message(value),
@@ -36,37 +37,47 @@ pub(crate) fn task_config_properties_get(context: &TaskContext, name: String, pa
     }
 }
 
-pub(crate) fn task_config_properties_set(context: &TaskContext, name: String, param: String, value: String) -> bool {
+pub(crate) fn task_config_properties_set(context: &TaskContext, name: String, param: String, value: String) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.set_parameter(&name, &param, &value) {
         Ok(_) => {
             println!("{name} {param} has been updated");
 
             if param.as_str() == "name" {
-                match context.list_tasks() {
-                    Ok(tasks) => {
-                        for mut task in tasks {
-                            if task.has_property(&name) {
-                                let task_prop_value = task.get_property(&name).unwrap().clone();
-                                task.set_property(&value, &task_prop_value);
-                                task.delete_property(&name);
-                                if let Err(e) = context.update_task(task) {
-                                    eprintln!("ERROR: {e}");
-                                }
-                            }
-                        }
-                    },
-                    Err(e) => eprintln!("ERROR: {e}")
+                for warning in rename_task_property(context, &name, &value) {
+                    eprintln!("WARNING: {warning}");
                 }
             }
 
-            true
+            OpOutcome::Success
         },
         Err(e) => error_message(format!("ERROR: {e}"))
     }
 }
 
-pub(crate) fn task_config_properties_list(context: &TaskContext) -> bool {
+/// Moves the property value of every task to a new name. Tasks that
+/// can't be updated don't stop the rename and are returned as warnings.
+pub(crate) fn rename_task_property(context: &TaskContext, name: &str, new_name: &str) -> Vec<String> {
+    let tasks = match context.list_tasks() {
+        Ok(tasks) => tasks,
+        Err(e) => return vec![format!("Can't rename property {name} in tasks: {e}")]
+    };
+
+    let mut warnings = vec![];
+    for mut task in tasks {
+        if let Some(task_prop_value) = task.get_property(name).cloned() {
+            let id = task.get_id().unwrap_or_default();
+            task.set_property(new_name, &task_prop_value);
+            task.delete_property(name);
+            if let Err(e) = context.update_task(task) {
+                warnings.push(format!("Task {id}: {e}"));
+            }
+        }
+    }
+    warnings
+}
+
+pub(crate) fn task_config_properties_list(context: &TaskContext) -> OpOutcome {
     let prop_manager = PropertyManager::new(&context);
     println!("Name\tValue type\tColor\tStyle\tEnum values");
     prop_manager.get_properties().iter().for_each(|property| {
@@ -81,10 +92,10 @@ pub(crate) fn task_config_properties_list(context: &TaskContext) -> bool {
         };
         println!("{}\t{}\t{}\t{}\t{}", property.get_name(), property.get_value_type(), property.get_color(), property.get_style().unwrap_or_else(|| ""), enums);
     });
-    true
+    OpOutcome::Success
 }
 
-pub(crate) fn task_config_properties_import(context: &TaskContext) -> bool {
+pub(crate) fn task_config_properties_import(context: &TaskContext) -> OpOutcome {
     if let Some(input) = read_from_pipe() {
         match PropertyManager::parse_properties(input) {
             Ok(statuses) => {
@@ -101,7 +112,7 @@ pub(crate) fn task_config_properties_import(context: &TaskContext) -> bool {
     }
 }
 
-pub(crate) fn task_config_properties_export(context: &TaskContext, pretty: bool) -> bool {
+pub(crate) fn task_config_properties_export(context: &TaskContext, pretty: bool) -> OpOutcome {
     let prop_manager = PropertyManager::new(&context);
     let func = if pretty { serde_json::to_string_pretty } else { serde_json::to_string };
 
@@ -112,7 +123,7 @@ pub(crate) fn task_config_properties_export(context: &TaskContext, pretty: bool)
     }
 }
 
-pub(crate) fn task_config_properties_reset(context: &TaskContext) -> bool {
+pub(crate) fn task_config_properties_reset(context: &TaskContext) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.set_defaults() {
         Ok(_) => success_message("Properties have been reset".to_string()),
@@ -120,7 +131,7 @@ pub(crate) fn task_config_properties_reset(context: &TaskContext) -> bool {
     }
 }
 
-pub(crate) fn task_config_properties_enum_list(context: &TaskContext, name: String) -> bool {
+pub(crate) fn task_config_properties_enum_list(context: &TaskContext, name: String) -> OpOutcome {
     let prop_manager = PropertyManager::new(&context);
     let property = prop_manager.get_properties().iter().find(|saved_prop| saved_prop.get_name() == name);
     match property {
@@ -130,7 +141,7 @@ pub(crate) fn task_config_properties_enum_list(context: &TaskContext, name: Stri
                     for enum_value in enum_values {
                         println!("{} {} {}", enum_value.get_name(), enum_value.get_color(), enum_value.get_style().unwrap_or_else(|| ""));
                     }
-                    true
+                    OpOutcome::Success
                 },
                 None => error_message("Property has no enum values".to_string())
             }
@@ -139,7 +150,7 @@ pub(crate) fn task_config_properties_enum_list(context: &TaskContext, name: Stri
     }
 }
 
-pub(crate) fn task_config_properties_enum_add(context: &TaskContext, name: String, enum_value_name: String, enum_value_color: String, enum_value_style: Option<String>) -> bool {
+pub(crate) fn task_config_properties_enum_add(context: &TaskContext, name: String, enum_value_name: String, enum_value_color: String, enum_value_style: Option<String>) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.add_enum_property(name, enum_value_name, enum_value_color, enum_value_style) {
         Ok(_) => success_message("Property enum has been added".to_string()),
@@ -147,7 +158,7 @@ pub(crate) fn task_config_properties_enum_add(context: &TaskContext, name: Strin
     }
 }
 
-pub(crate) fn task_config_properties_enum_get(context: &TaskContext, property: String, enum_value_name: String, parameter: String) -> bool {
+pub(crate) fn task_config_properties_enum_get(context: &TaskContext, property: String, enum_value_name: String, parameter: String) -> OpOutcome {
     let prop_manager = PropertyManager::new(&context);
     match prop_manager.get_enum_parameter(property, enum_value_name, parameter) {
         Ok(s) => success_message(s),
@@ -155,7 +166,7 @@ pub(crate) fn task_config_properties_enum_get(context: &TaskContext, property: S
     }
 }
 
-pub(crate) fn task_config_properties_enum_set(context: &TaskContext, name: String, enum_value_name: String, enum_value_color: String, enum_value_style: Option<String>) -> bool {
+pub(crate) fn task_config_properties_enum_set(context: &TaskContext, name: String, enum_value_name: String, enum_value_color: String, enum_value_style: Option<String>) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.set_enum_property(name, enum_value_name, enum_value_color, enum_value_style) {
         Ok(_) => success_message("Property enum has been updated".to_string()),
@@ -163,7 +174,7 @@ pub(crate) fn task_config_properties_enum_set(context: &TaskContext, name: Strin
     }
 }
 
-pub(crate) fn task_config_properties_enum_delete(context: &TaskContext, name: String, enum_value_name: String) -> bool {
+pub(crate) fn task_config_properties_enum_delete(context: &TaskContext, name: String, enum_value_name: String) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.delete_enum_property(name, enum_value_name) {
         Ok(_) => success_message("Property enum has been deleted".to_string()),
@@ -171,7 +182,7 @@ pub(crate) fn task_config_properties_enum_delete(context: &TaskContext, name: St
     }
 }
 
-pub(crate) fn task_config_properties_cond_format_list(context: &TaskContext, name: String) -> bool {
+pub(crate) fn task_config_properties_cond_format_list(context: &TaskContext, name: String) -> OpOutcome {
     let prop_manager = PropertyManager::new(&context);
     let property = prop_manager.get_properties().iter().find(|saved_prop| saved_prop.get_name() == name);
     match property {
@@ -181,7 +192,7 @@ pub(crate) fn task_config_properties_cond_format_list(context: &TaskContext, nam
                     for cond_format_value in cond_format {
                         println!("{} {} {}", cond_format_value.get_condition(), cond_format_value.get_color(), cond_format_value.get_style().unwrap_or_else(|| ""));
                     }
-                    true
+                    OpOutcome::Success
                 },
                 None => error_message("Property has no conditional formatting".to_string())
             }
@@ -190,7 +201,7 @@ pub(crate) fn task_config_properties_cond_format_list(context: &TaskContext, nam
     }
 }
 
-pub(crate) fn task_config_properties_cond_format_add(context: &TaskContext, name: String, cond_format_expr: String, cond_format_color: String, cond_format_style: Option<String>) -> bool {
+pub(crate) fn task_config_properties_cond_format_add(context: &TaskContext, name: String, cond_format_expr: String, cond_format_color: String, cond_format_style: Option<String>) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.add_cond_format(name, cond_format_expr, cond_format_color, cond_format_style) {
         Ok(_) => success_message("Property conditional formatting has been added".to_string()),
@@ -198,10 +209,53 @@ pub(crate) fn task_config_properties_cond_format_add(context: &TaskContext, name
     }
 }
 
-pub(crate) fn task_config_properties_cond_format_clear(context: &TaskContext, name: String) -> bool {
+pub(crate) fn task_config_properties_cond_format_clear(context: &TaskContext, name: String) -> OpOutcome {
     let mut prop_manager = PropertyManager::new(&context);
     match prop_manager.clear_cond_format(name) {
         Ok(_) => success_message("Property conditional formatting has been cleared".to_string()),
         Err(e) => error_message(format!("ERROR: {e}"))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use crate::util::init_test_repo;
+    use uuid::Uuid;
+    use gittask::Task;
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rename_task_property_warnings() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo_dir = temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+
+        let mut first = Task::new("First".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        first.set_property("estimate", "1");
+        let first = context.create_task(first).unwrap();
+        let mut second = Task::new("Rejected".to_string(), String::new(), "OPEN".to_string(), None).unwrap();
+        second.set_property("estimate", "2");
+        let second = context.create_task(second).unwrap();
+
+        let hook = repo_dir.join("hook.sh");
+        std::fs::write(&hook, "#!/bin/sh\n! grep -q Rejected\n").unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        context.set_config_value("task.hook.update", &hook.display().to_string()).unwrap();
+        context.set_config_value("task.hook.strict", "true").unwrap();
+
+        let warnings = rename_task_property(&context, "estimate", "points");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with(&format!("Task {}: Hook", second.get_id().unwrap())));
+
+        let first = context.find_task(&first.get_id().unwrap()).unwrap().unwrap();
+        assert_eq!(first.get_property("points").unwrap(), "1");
+        assert!(!first.has_property("estimate"));
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+}
@@ -1,9 +1,11 @@
+use crate::connectors::map_remote_statuses;
+use crate::operations::{get_user_repo, OpOutcome};
 use crate::status;
 use crate::status::StatusManager;
-use crate::util::{error_message, read_from_pipe, success_message};
+use crate::util::{error_message, read_from_pipe, success_message, system_error_message};
 use gittask::TaskContext;
 
-pub(crate) fn task_config_status_add(context: &TaskContext, name: String, shortcut: String, color: String, is_done: Option<bool>) -> bool {
+pub(crate) fn task_config_status_add(context: &TaskContext, name: String, shortcut: String, color: String, is_done: Option<bool>) -> OpOutcome {
     let mut status_manager = StatusManager::new(&context);
     match status_manager.add_status(name, shortcut, color, is_done.unwrap_or(false)) {
         Ok(_) => success_message("Status has been added".to_string()),
@@ -11,7 +13,7 @@ pub(crate) fn task_config_status_add(context: &TaskContext, name: String, shortc
     }
 }
 
-pub(crate) fn task_config_status_delete(context: &TaskContext, name: String, force: bool) -> bool {
+pub(crate) fn task_config_status_delete(context: &TaskContext, name: String, force: bool) -> OpOutcome {
     let mut status_manager = StatusManager::new(&context);
     let name = status_manager.get_full_status_name(&name);
 
@@ -30,7 +32,7 @@ pub(crate) fn task_config_status_delete(context: &TaskContext, name: String, for
     }
 }
 
-pub(crate) fn task_config_status_get(context: &TaskContext, name: String, param: String) -> bool {
+pub(crate) fn task_config_status_get(context: &TaskContext, name: String, param: String) -> OpOutcome {
     let status_manager = StatusManager::new(&context);
     let name = status_manager.get_full_status_name(&name);
     match status_manager.get_property(&name, &param) {
@@ -39,7 +41,7 @@ pub(crate) fn task_config_status_get(context: &TaskContext, name: String, param:
     }
 }
 
-pub(crate) fn task_config_status_set(context: &TaskContext, name: String, param: String, value: String) -> bool {
+pub(crate) fn task_config_status_set(context: &TaskContext, name: String, param: String, value: String) -> OpOutcome {
     let mut status_manager = StatusManager::new(&context);
     let name = status_manager.get_full_status_name(&name);
     match status_manager.set_property(&name, &param, &value) {
@@ -63,22 +65,22 @@ pub(crate) fn task_config_status_set(context: &TaskContext, name: String, param:
                 }
             }
 
-            true
+            OpOutcome::Success
         },
         Err(e) => error_message(format!("ERROR: {e}"))
     }
 }
 
-pub(crate) fn task_config_status_list(context: &TaskContext) -> bool {
+pub(crate) fn task_config_status_list(context: &TaskContext) -> OpOutcome {
     let status_manager = StatusManager::new(&context);
     println!("Name\tShortcut\tColor\tStyle\tIs DONE");
     status_manager.get_statuses().iter().for_each(|status| {
         println!("{}\t{}\t{}\t{}\t{}", status.get_name(), status.get_shortcut(), status.get_color(), status.get_style().unwrap_or_else(|| ""), status.is_done());
     });
-    true
+    OpOutcome::Success
 }
 
-pub(crate) fn task_config_status_import(context: &TaskContext) -> bool {
+pub(crate) fn task_config_status_import(context: &TaskContext) -> OpOutcome {
     if let Some(input) = read_from_pipe() {
         match status::parse_statuses(input) {
             Ok(statuses) => {
@@ -95,7 +97,7 @@ pub(crate) fn task_config_status_import(context: &TaskContext) -> bool {
     }
 }
 
-pub(crate) fn task_config_status_export(context: &TaskContext, pretty: bool) -> bool {
+pub(crate) fn task_config_status_export(context: &TaskContext, pretty: bool) -> OpOutcome {
     let status_manager = StatusManager::new(&context);
     let func = if pretty { serde_json::to_string_pretty } else { serde_json::to_string };
 
@@ -106,10 +108,35 @@ pub(crate) fn task_config_status_export(context: &TaskContext, pretty: bool) ->
     }
 }
 
-pub(crate) fn task_config_status_reset(context: &TaskContext) -> bool {
+pub(crate) fn task_config_status_reset(context: &TaskContext) -> OpOutcome {
     let mut status_manager = StatusManager::new(&context);
     match status_manager.set_defaults() {
         Ok(_) => success_message("Statuses have been reset".to_string()),
         Err(e) => error_message(format!("ERROR: {e}"))
     }
 }
+
+pub(crate) fn task_config_status_remote(context: &TaskContext, remote: &Option<String>, connector_type: &Option<String>) -> OpOutcome {
+    let (connector, user, repo) = match get_user_repo(context, remote, connector_type) {
+        Ok(user_repo) => user_repo,
+        Err(e) => return error_message(format!("ERROR: {e}")),
+    };
+    let remote_statuses = match connector.list_remote_statuses(&user, &repo) {
+        Ok(statuses) => statuses,
+        Err(e) => return system_error_message(format!("ERROR: {e}")),
+    };
+
+    let status_manager = StatusManager::new(context);
+    let local_statuses = status_manager.get_statuses().iter().map(|status| status.get_name().to_string()).collect::<Vec<_>>();
+    let mapping = map_remote_statuses(&remote_statuses, &local_statuses);
+    println!("Remote\tLocal");
+    for (remote_status, local_status) in &mapping {
+        println!("{remote_status}\t{}", local_status.as_deref().unwrap_or("(unmapped)"));
+    }
+
+    let unmapped = mapping.iter().filter(|(_, local_status)| local_status.is_none()).count();
+    if unmapped > 0 {
+        println!("{unmapped} remote status(es) unmapped, add them with: git task config status add");
+    }
+    OpOutcome::Success
+}
@@ -1,6 +1,7 @@
 use gittask::TaskContext;
-use crate::operations::get_user_repo;
-use crate::util::{error_message};
+use crate::connectors::unsupported_message;
+use crate::operations::{get_user_repo, OpOutcome};
+use crate::util::{error_message, success_message, system_error_message};
 
 pub(crate) fn task_label_add(
     context: &TaskContext,
@@ -11,35 +12,33 @@ pub(crate) fn task_label_add(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     match context.find_task(&task_id) {
         Ok(Some(mut task)) => {
-            let label = task.add_label(name.clone(), description.clone(), color.clone());
+            let label = context.add_label(&mut task, name.clone(), description.clone(), color.clone());
             match context.update_task(task) {
                 Ok(_) => {
                     println!("Task ID {task_id} updated");
-                    let mut success = false;
+                    let mut outcome = OpOutcome::Success;
                     if push {
                         match get_user_repo(&context, remote, connector_type) {
+                            Ok((connector, _, _)) if !connector.capabilities().labels => outcome = error_message(unsupported_message(connector.as_ref(), "labels")),
                             Ok((connector, user, repo)) => {
                                 match connector.create_remote_label(&user, &repo, &task_id, &label) {
-                                    Ok(_) => {
-                                        println!("Added REMOTE label {}", label.get_name());
-                                        success = true;
-                                    },
-                                    Err(e) => eprintln!("ERROR adding REMOTE label: {e}")
+                                    Ok(_) => println!("Added REMOTE label {}", label.get_name()),
+                                    Err(e) => outcome = system_error_message(format!("ERROR adding REMOTE label: {e}"))
                                 }
                             },
-                            Err(e) => eprintln!("ERROR: {e}"),
+                            Err(e) => outcome = error_message(format!("ERROR: {e}")),
                         }
                     }
-                    success
+                    outcome
                 },
-                Err(e) => error_message(format!("ERROR: {e}")),
+                Err(e) => system_error_message(format!("ERROR: {e}")),
             }
         },
         Ok(None) => error_message(format!("Task ID {task_id} not found")),
-        Err(e) => error_message(format!("ERROR: {e}")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
     }
 }
 
@@ -50,7 +49,7 @@ pub(crate) fn task_label_delete(
     push: bool,
     remote: &Option<String>,
     connector_type: &Option<String>,
-) -> bool {
+) -> OpOutcome {
     match context.find_task(&task_id) {
         Ok(Some(mut task)) => {
             match task.delete_label(&name) {
@@ -58,30 +57,35 @@ pub(crate) fn task_label_delete(
                     match context.update_task(task) {
                         Ok(_) => {
                             println!("Task ID {task_id} updated");
-                            let mut success = false;
+                            let mut outcome = OpOutcome::Success;
                             if push {
                                 match get_user_repo(&context, remote, connector_type) {
+                                    Ok((connector, _, _)) if !connector.capabilities().labels => outcome = error_message(unsupported_message(connector.as_ref(), "labels")),
                                     Ok((connector, user, repo)) => {
                                         match connector.delete_remote_label(&user, &repo, &task_id, &name) {
-                                            Ok(_) => {
-                                                println!("Sync: REMOTE label '{name}' has been deleted");
-                                                success = true;
-                                            },
-                                            Err(e) => eprintln!("ERROR: {e}")
+                                            Ok(_) => println!("Sync: REMOTE label '{name}' has been deleted"),
+                                            Err(e) => outcome = system_error_message(format!("ERROR: {e}"))
                                         }
                                     },
-                                    Err(e) => eprintln!("ERROR: {e}"),
+                                    Err(e) => outcome = error_message(format!("ERROR: {e}")),
                                 }
                             }
-                            success
+                            outcome
                         },
-                        Err(e) => error_message(format!("ERROR: {e}")),
+                        Err(e) => system_error_message(format!("ERROR: {e}")),
                     }
                 },
                 Err(e) => error_message(format!("ERROR: {e}")),
             }
         },
         Ok(None) => error_message(format!("Task ID {task_id} not found")),
+        Err(e) => system_error_message(format!("ERROR: {e}")),
+    }
+}
+
+pub(crate) fn task_label_move(context: &TaskContext, task_id: String, name: String, index: usize) -> OpOutcome {
+    match context.reorder_labels(&task_id, &name, index) {
+        Ok(_) => success_message(format!("Task ID {task_id} updated")),
         Err(e) => error_message(format!("ERROR: {e}")),
     }
-}
\ No newline at end of file
+}
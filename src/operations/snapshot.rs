@@ -0,0 +1,30 @@
+use gittask::TaskContext;
+use crate::operations::OpOutcome;
+use crate::util::{error_message, format_datetime, success_message, system_error_message, DisplayTimeZone};
+
+pub(crate) fn task_snapshot_create(context: &TaskContext, name: String) -> OpOutcome {
+    match context.snapshot(&name) {
+        Ok(_) => success_message(format!("Snapshot {name} created")),
+        Err(e) => error_message(format!("ERROR: {e}")),
+    }
+}
+
+pub(crate) fn task_snapshot_list(context: &TaskContext) -> OpOutcome {
+    match context.list_snapshots() {
+        Ok(snapshots) => {
+            let tz = DisplayTimeZone::from_config(context);
+            for (name, time) in snapshots {
+                println!("{name}\t{}", format_datetime(time, tz));
+            }
+            OpOutcome::Success
+        },
+        Err(e) => system_error_message(format!("ERROR: {e}")),
+    }
+}
+
+pub(crate) fn task_snapshot_restore(context: &TaskContext, name: String) -> OpOutcome {
+    match context.checkout_snapshot(&name) {
+        Ok(_) => success_message(format!("Tasks restored from snapshot {name}")),
+        Err(e) => error_message(format!("ERROR: {e}")),
+    }
+}
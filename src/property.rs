@@ -1,10 +1,15 @@
 use std::collections::HashMap;
 
-use evalexpr::{ContextWithMutableVariables, HashMapContext};
-use nu_ansi_term::AnsiString;
+use evalexpr::{ContextWithMutableFunctions, ContextWithMutableVariables, Function, HashMapContext, Value};
+use nu_ansi_term::{AnsiString, Color, Style};
 use serde::{Deserialize, Serialize};
-use gittask::TaskContext;
-use crate::util::{format_datetime, str_to_color};
+use gittask::{parse_timestamp, TaskContext};
+use crate::util::{format_datetime, format_datetime_with, str_to_ansi_color, str_to_color, validate_datetime_format, DisplayTimeZone};
+
+const RESERVED_PROPERTY_NAMES: [&str; 4] = ["id", "created", "updated", "labels"];
+
+/// Recommended values of the `priority` property, from the lowest, with their colors.
+pub(crate) const PRIORITY_LEVELS: [(&str, &str); 4] = [("low", "Green"), ("medium", "Yellow"), ("high", "LightRed"), ("critical", "Red")];
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -40,6 +45,37 @@ impl std::str::FromStr for PropertyValueType {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PropertySortType {
+    Numeric,
+    Date,
+    String,
+}
+
+impl std::fmt::Display for PropertySortType {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PropertySortType::Numeric => write!(formatter, "numeric"),
+            PropertySortType::Date => write!(formatter, "date"),
+            PropertySortType::String => write!(formatter, "string"),
+        }
+    }
+}
+
+impl std::str::FromStr for PropertySortType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PropertySortType, String> {
+        match s.to_lowercase().as_str() {
+            "numeric" => Ok(PropertySortType::Numeric),
+            "date" => Ok(PropertySortType::Date),
+            "string" => Ok(PropertySortType::String),
+            _ => Err("Error parsing sort type. Supported types are: numeric, date, string".to_string()),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Property {
     name: String,
@@ -48,6 +84,12 @@ pub struct Property {
     style: Option<String>,
     enum_values: Option<Vec<PropertyEnumValue>>,
     cond_format: Option<Vec<PropertyCondFormat>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sort_as: Option<PropertySortType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_width: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    datefmt: Option<String>,
 }
 
 impl Property {
@@ -74,6 +116,20 @@ impl Property {
     pub(crate) fn get_cond_format(&self) -> &Option<Vec<PropertyCondFormat>> {
         &self.cond_format
     }
+
+    fn render_value(&self, value: &str, truncate: bool, tz: DisplayTimeZone) -> String {
+        let value = match (&self.value_type, &self.datefmt) {
+            (_, Some(datefmt)) => format_datetime_with(parse_timestamp(value).unwrap_or(0), datefmt, tz),
+            (PropertyValueType::DateTime, None) => format_datetime(parse_timestamp(value).unwrap_or(0), tz),
+            _ => value.to_string()
+        };
+        match self.max_width {
+            Some(max_width) if truncate && value.chars().count() > max_width => {
+                value.chars().take(max_width.saturating_sub(1)).collect::<String>() + "…"
+            },
+            _ => value
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -142,9 +198,17 @@ impl PropertyCondFormat {
     }
 }
 
+/// Effective color of a property value and the full terminal style to paint it with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleSpec {
+    pub color: Color,
+    pub style: Style,
+}
+
 pub struct PropertyManager {
     context: TaskContext,
     properties: Vec<Property>,
+    tz: DisplayTimeZone,
 }
 
 impl PropertyManager {
@@ -154,6 +218,7 @@ impl PropertyManager {
         PropertyManager {
             properties,
             context: context.clone(),
+            tz: DisplayTimeZone::from_config(context),
         }
     }
 
@@ -166,6 +231,9 @@ impl PropertyManager {
                 style: None,
                 enum_values: None,
                 cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
             },
             Property {
                 name: "name".to_string(),
@@ -174,6 +242,9 @@ impl PropertyManager {
                 style: None,
                 enum_values: None,
                 cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
             },
             Property {
                 name: "created".to_string(),
@@ -182,6 +253,20 @@ impl PropertyManager {
                 style: None,
                 enum_values: None,
                 cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
+            },
+            Property {
+                name: "updated".to_string(),
+                value_type: PropertyValueType::DateTime,
+                color: "239".to_string(),
+                style: None,
+                enum_values: None,
+                cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
             },
             Property {
                 name: "author".to_string(),
@@ -190,6 +275,9 @@ impl PropertyManager {
                 style: None,
                 enum_values: None,
                 cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
             },
             Property {
                 name: "description".to_string(),
@@ -198,6 +286,24 @@ impl PropertyManager {
                 style: None,
                 enum_values: None,
                 cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
+            },
+            Property {
+                name: "priority".to_string(),
+                value_type: PropertyValueType::String,
+                color: "Default".to_string(),
+                style: None,
+                enum_values: Some(PRIORITY_LEVELS.iter().map(|(name, color)| PropertyEnumValue {
+                    name: name.to_string(),
+                    color: color.to_string(),
+                    style: None,
+                }).collect()),
+                cond_format: None,
+                sort_as: None,
+                max_width: None,
+                datefmt: None,
             },
         ]
     }
@@ -232,33 +338,114 @@ impl PropertyManager {
     }
 
     pub fn parse_properties(input: String) -> Result<Vec<Property>, String> {
-        let result: Vec<Property> = serde_json::from_str(&input).map_err(|e| e.to_string())?;
+        let value: serde_json::Value = serde_json::from_str(&input).map_err(|e| format!("Invalid JSON: {e}"))?;
+        Self::validate_schema(&value)?;
+        let result: Vec<Property> = serde_json::from_value(value).map_err(|e| e.to_string())?;
         Ok(result)
     }
 
+    fn validate_schema(value: &serde_json::Value) -> Result<(), String> {
+        let properties = value.as_array().ok_or_else(|| "Expected a JSON array of properties".to_string())?;
+        let mut names = vec![];
+        for (index, property) in properties.iter().enumerate() {
+            let property = property.as_object().ok_or_else(|| format!("property at index {index} is not an object"))?;
+            let name = match property.get("name").and_then(|name| name.as_str()) {
+                Some(name) if !name.is_empty() => name,
+                _ => return Err(format!("property at index {index} missing name")),
+            };
+            if names.contains(&name) {
+                return Err(format!("property '{name}' is duplicated at index {index}"));
+            }
+            names.push(name);
+
+            let string_field = |field: &str, required: bool| -> Result<Option<&str>, String> {
+                match property.get(field) {
+                    Some(serde_json::Value::String(value)) => Ok(Some(value)),
+                    None | Some(serde_json::Value::Null) if !required => Ok(None),
+                    None | Some(serde_json::Value::Null) => Err(format!("property '{name}' missing {field} at index {index}")),
+                    Some(_) => Err(format!("property '{name}' has non-string {field} at index {index}")),
+                }
+            };
+            if let Some(value_type) = string_field("value_type", true)? {
+                value_type.parse::<PropertyValueType>().map_err(|_| format!("property '{name}' has unknown value_type '{value_type}' at index {index}"))?;
+            }
+            string_field("color", true)?;
+            string_field("style", false)?;
+            if let Some(datefmt) = string_field("datefmt", false)? {
+                validate_datetime_format(datefmt).map_err(|_| format!("property '{name}' has invalid datefmt '{datefmt}' at index {index}"))?;
+            }
+            if let Some(sort_as) = string_field("sort_as", false)? {
+                sort_as.parse::<PropertySortType>().map_err(|_| format!("property '{name}' has unknown sort_as '{sort_as}' at index {index}"))?;
+            }
+            match property.get("max_width") {
+                None | Some(serde_json::Value::Null) => {},
+                Some(max_width) if max_width.as_u64().is_some_and(|width| width > 0) => {},
+                Some(_) => return Err(format!("property '{name}' has invalid max_width at index {index}, expected a positive integer")),
+            }
+
+            for (field, required) in [("enum_values", ["name", "color"]), ("cond_format", ["condition", "color"])] {
+                let entries = match property.get(field) {
+                    None | Some(serde_json::Value::Null) => continue,
+                    Some(serde_json::Value::Array(entries)) => entries,
+                    Some(_) => return Err(format!("property '{name}' has invalid {field} at index {index}, expected an array")),
+                };
+                for (entry_index, entry) in entries.iter().enumerate() {
+                    let entry = entry.as_object().ok_or_else(|| format!("property '{name}' has invalid {field} entry {entry_index} at index {index}, expected an object"))?;
+                    for key in required {
+                        if !entry.get(key).is_some_and(|value| value.is_string()) {
+                            return Err(format!("property '{name}' {field} entry {entry_index} missing {key} at index {index}"));
+                        }
+                    }
+                    if !entry.get("style").is_none_or(|style| style.is_string() || style.is_null()) {
+                        return Err(format!("property '{name}' {field} entry {entry_index} has non-string style at index {index}"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn format_value<'a>(&self, property: &'a str, value: &'a str, context: &HashMap<String, String>, properties: &Vec<Property>, no_color: bool) -> AnsiString<'a> {
+        self.format_value_with(property, value, context, properties, no_color, false)
+    }
+
+    pub fn format_list_value<'a>(&self, property: &'a str, value: &'a str, context: &HashMap<String, String>, properties: &Vec<Property>, no_color: bool) -> AnsiString<'a> {
+        self.format_value_with(property, value, context, properties, no_color, true)
+    }
+
+    fn format_value_with<'a>(&self, property: &'a str, value: &'a str, context: &HashMap<String, String>, properties: &Vec<Property>, no_color: bool, truncate: bool) -> AnsiString<'a> {
         match self.properties.iter().find(|p| p.name == property) {
             Some(property) => {
-                let value = match property.value_type {
-                    PropertyValueType::DateTime => format_datetime(value.parse().unwrap_or(0)),
-                    _ => value.to_string()
-                };
+                let raw_value = value.to_string();
+                let value = property.render_value(value, truncate, self.tz);
                 match no_color {
                     true => value.into(),
-                    false => {
-                        let (color, style) = Self::find_cond_format(&property.cond_format, context, properties)
-                            .or_else(|| Self::find_enum_value(&property.enum_values, &value))
-                            .or_else(|| Some((&property.color, &None))).unwrap();
-                        let color = str_to_color(&color, style);
-                        color.paint(value)
-                    }
+                    false => Self::resolve_property_style(property, &raw_value, context, properties).style.paint(value)
                 }
             },
             None => value.into()
         }
     }
 
-    fn find_cond_format<'a>(cond_format: &'a Option<Vec<PropertyCondFormat>>, context: &'a HashMap<String, String>, properties: &Vec<Property>) -> Option<(&'a String, &'a Option<String>)> {
+    /// Style of a value from the conditional formatting of the property, then its enum values,
+    /// then the property color. Conditions only see the value of this property.
+    pub fn resolve_style(&self, property: &str, value: &str) -> Option<StyleSpec> {
+        let property = self.properties.iter().find(|p| p.name == property)?;
+        let context = HashMap::from([(property.name.clone(), value.to_string())]);
+        Some(Self::resolve_property_style(property, value, &context, &self.properties))
+    }
+
+    fn resolve_property_style(property: &Property, value: &str, context: &HashMap<String, String>, properties: &[Property]) -> StyleSpec {
+        let (color, style) = Self::find_cond_format(&property.cond_format, context, properties)
+            .or_else(|| Self::find_enum_value(&property.enum_values, value))
+            .unwrap_or((&property.color, &None));
+        StyleSpec {
+            color: str_to_ansi_color(color),
+            style: str_to_color(color, style),
+        }
+    }
+
+    fn find_cond_format<'a>(cond_format: &'a Option<Vec<PropertyCondFormat>>, context: &'a HashMap<String, String>, properties: &[Property]) -> Option<(&'a String, &'a Option<String>)> {
         let mut eval_context = HashMapContext::new();
         context.into_iter().for_each(|(k, v)| {
             let property = properties.iter().find(|p| p.name == k.as_str());
@@ -279,6 +466,14 @@ impl PropertyManager {
             }
         });
 
+        let labels = context.get("labels")
+            .map(|labels| labels.split(',').map(|label| label.to_lowercase()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        eval_context.set_function("has_label".into(), Function::new(move |argument| {
+            let name = argument.as_string()?.to_lowercase();
+            Ok(Value::Boolean(labels.contains(&name)))
+        })).unwrap();
+
         match cond_format {
             Some(cond_format) => {
                 cond_format.iter()
@@ -290,11 +485,11 @@ impl PropertyManager {
         }
     }
 
-    fn find_enum_value<'a>(enum_values: &'a Option<Vec<PropertyEnumValue>>, value: &'a String) -> Option<(&'a String, &'a Option<String>)> {
+    fn find_enum_value<'a>(enum_values: &'a Option<Vec<PropertyEnumValue>>, value: &str) -> Option<(&'a String, &'a Option<String>)> {
         match enum_values {
             Some(enum_values) => {
                 enum_values.iter()
-                    .find(|pev| pev.name == *value)
+                    .find(|pev| pev.name == value)
                     .map(|pev| Some((&pev.color, &pev.style)))
                     .unwrap_or_else(|| None)
             },
@@ -310,12 +505,25 @@ impl PropertyManager {
                     "value_type" => Some(saved_prop.value_type.to_string()),
                     "color" => Some(saved_prop.color.clone()),
                     "style" => saved_prop.style.clone(),
+                    "sort_as" => saved_prop.sort_as.as_ref().map(|sort_as| sort_as.to_string()),
+                    "max_width" => saved_prop.max_width.map(|max_width| max_width.to_string()),
+                    "datefmt" => saved_prop.datefmt.clone(),
                     _ => None
                 }
             } else { None }
         })
     }
 
+    pub fn get_sort_type(&self, property: &str) -> String {
+        self.properties.iter()
+            .find(|saved_prop| saved_prop.name == property)
+            .map(|saved_prop| match &saved_prop.sort_as {
+                Some(sort_as) => sort_as.to_string(),
+                None => saved_prop.value_type.to_string(),
+            })
+            .unwrap_or_default()
+    }
+
     pub fn set_parameter(&mut self, property: &String, parameter: &String, value: &String) -> Result<(), String> {
         let properties = self.properties.clone();
         let property = self.properties.iter_mut().find(|saved_prop| {
@@ -348,6 +556,27 @@ impl PropertyManager {
                     "style" => {
                         saved_prop.style = Some(value.clone()); Ok(())
                     },
+                    "sort_as" => {
+                        match value.parse::<PropertySortType>() {
+                            Ok(sort_as) => {
+                                saved_prop.sort_as = Some(sort_as);
+                                Ok(())
+                            },
+                            Err(e) => Err(e)
+                        }
+                    },
+                    "max_width" => {
+                        match value.parse::<usize>() {
+                            Ok(max_width) if max_width > 0 => {
+                                saved_prop.max_width = Some(max_width);
+                                Ok(())
+                            },
+                            _ => Err("max_width must be a positive integer".to_string())
+                        }
+                    },
+                    "datefmt" => {
+                        validate_datetime_format(value).map(|_| saved_prop.datefmt = Some(value.clone()))
+                    },
                     _ => Err("Unknown property".to_string())
                 };
                 match set_result {
@@ -365,9 +594,7 @@ impl PropertyManager {
     }
 
     pub fn add_property(&mut self, name: String, value_type: String, color: String, style: Option<String>, enum_values: Option<Vec<String>>, cond_format: Option<Vec<String>>) -> Result<(), String> {
-        if name.to_lowercase() == "id" || name.to_lowercase() == "labels" {
-            return Err(format!("`{}` is a reserved property name", name));
-        }
+        Self::validate_property_name(&name)?;
 
         let property = Property {
             name,
@@ -376,11 +603,28 @@ impl PropertyManager {
             color,
             enum_values: enum_values.map_or_else(|| None, |enum_values| Some(PropertyEnumValue::from(enum_values))),
             cond_format: cond_format.map_or_else(|| None, |cond_format| Some(PropertyCondFormat::from(cond_format))),
+            sort_as: None,
+            max_width: None,
+            datefmt: None,
         };
         self.properties.push(property);
         self.save_config()
     }
 
+    /// Names end up as JSON keys, column names and config values, so only a safe charset is allowed.
+    fn validate_property_name(name: &str) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("Property name is empty".to_string());
+        }
+        if let Some(c) = name.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '_' && *c != '-') {
+            return Err(format!("Invalid character `{c}` in property name `{name}`, use letters, digits, underscores and hyphens"));
+        }
+        if RESERVED_PROPERTY_NAMES.contains(&name.to_lowercase().as_str()) {
+            return Err(format!("`{}` is a reserved property name", name));
+        }
+        Ok(())
+    }
+
     pub fn delete_property(&mut self, name: &String) -> Result<(), String> {
         let prev_prop_count = self.properties.len();
         self.properties.retain(|s| s.name != *name);
@@ -498,4 +742,164 @@ impl PropertyManager {
             None => Err("Property not found".to_string())
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_label_cond_format() {
+        let cond_format = Some(vec![
+            PropertyCondFormat {
+                condition: "has_label(\"urgent\")".to_string(),
+                color: "Red".to_string(),
+                style: Some("bold".to_string()),
+            },
+            PropertyCondFormat {
+                condition: "has_label(\"docs\")".to_string(),
+                color: "Blue".to_string(),
+                style: None,
+            },
+        ]);
+        let properties = PropertyManager::get_defaults();
+
+        let context = HashMap::from([
+            ("name".to_string(), "Fix crash".to_string()),
+            ("labels".to_string(), "docs,Urgent".to_string()),
+        ]);
+        let (color, style) = PropertyManager::find_cond_format(&cond_format, &context, &properties).unwrap();
+        assert_eq!(color, "Red");
+        assert_eq!(style.as_deref(), Some("bold"));
+
+        let context = HashMap::from([("name".to_string(), "Fix typo".to_string())]);
+        assert!(PropertyManager::find_cond_format(&cond_format, &context, &properties).is_none());
+    }
+
+    #[test]
+    fn test_resolve_style() {
+        let mut properties = PropertyManager::get_defaults();
+        let priority = properties.iter_mut().find(|p| p.name == "priority").unwrap();
+        priority.cond_format = Some(vec![PropertyCondFormat {
+            condition: "priority == \"critical\"".to_string(),
+            color: "Magenta".to_string(),
+            style: Some("bold,underline".to_string()),
+        }]);
+        let prop_manager = PropertyManager {
+            context: TaskContext::new(std::env::temp_dir().display().to_string()),
+            properties,
+            tz: DisplayTimeZone::Local,
+        };
+
+        let style_spec = prop_manager.resolve_style("priority", "high").unwrap();
+        assert_eq!(style_spec, StyleSpec { color: Color::LightRed, style: Color::LightRed.normal() });
+
+        let style_spec = prop_manager.resolve_style("priority", "critical").unwrap();
+        assert_eq!(style_spec.color, Color::Magenta);
+        assert_eq!(style_spec.style, Color::Magenta.bold().underline());
+
+        assert_eq!(prop_manager.resolve_style("priority", "someday").unwrap().color, Color::Default);
+        assert_eq!(prop_manager.resolve_style("author", "jhspetersson").unwrap().color, Color::Cyan);
+        assert!(prop_manager.resolve_style("estimate", "3").is_none());
+    }
+
+    #[test]
+    fn test_render_value() {
+        let mut properties = PropertyManager::get_defaults();
+        let description = properties.iter_mut().find(|p| p.name == "description").unwrap();
+        description.max_width = Some(8);
+        let description = description.clone();
+        let tz = DisplayTimeZone::Local;
+        assert_eq!(description.render_value("Short", true, tz), "Short");
+        assert_eq!(description.render_value("Exactly8", true, tz), "Exactly8");
+        assert_eq!(description.render_value("A rather long description", true, tz), "A rathe…");
+        assert_eq!(description.render_value("A rather long description", false, tz), "A rather long description");
+
+        let created = properties.iter_mut().find(|p| p.name == "created").unwrap();
+        created.datefmt = Some("%Y-%m".to_string());
+        assert_eq!(created.render_value("1700000000", true, tz), "2023-11");
+        assert_eq!(created.render_value("", true, tz), "");
+
+        let name = properties.iter_mut().find(|p| p.name == "name").unwrap();
+        assert_eq!(name.render_value("1700000000", true, tz), "1700000000");
+        name.datefmt = Some("%Y".to_string());
+        assert_eq!(name.render_value("1700000000", true, tz), "2023");
+
+        let mut prop_manager = PropertyManager {
+            context: TaskContext::new(std::env::temp_dir().display().to_string()),
+            properties: PropertyManager::get_defaults(),
+            tz,
+        };
+        assert_eq!(prop_manager.set_parameter(&"created".to_string(), &"datefmt".to_string(), &"%Q".to_string()), Err("Invalid date format `%Q`".to_string()));
+        assert!(prop_manager.get_properties().iter().all(|p| p.datefmt.is_none()));
+    }
+
+    #[test]
+    fn test_validate_property_name() {
+        for name in ["estimate", "due_date", "story-points", "Sprint2"] {
+            assert!(PropertyManager::validate_property_name(name).is_ok(), "{name}");
+        }
+        assert_eq!(PropertyManager::validate_property_name("").unwrap_err(), "Property name is empty");
+        for name in ["due date", "a.b", "refs/x", "naïve"] {
+            assert!(PropertyManager::validate_property_name(name).unwrap_err().starts_with("Invalid character"), "{name}");
+        }
+        for name in ["id", "Created", "updated", "labels"] {
+            assert_eq!(PropertyManager::validate_property_name(name).unwrap_err(), format!("`{name}` is a reserved property name"));
+        }
+
+        let mut prop_manager = PropertyManager {
+            context: TaskContext::new(std::env::temp_dir().display().to_string()),
+            properties: PropertyManager::get_defaults(),
+            tz: DisplayTimeZone::Local,
+        };
+        assert!(prop_manager.add_property("a.b".to_string(), "string".to_string(), "White".to_string(), None, None, None).is_err());
+        assert_eq!(prop_manager.get_properties().len(), PropertyManager::get_defaults().len());
+    }
+
+    #[test]
+    fn test_parse_properties_schema() {
+        let valid = r#"[
+            {"name": "id", "value_type": "integer", "color": "DarkGray", "style": null, "enum_values": null, "cond_format": null},
+            {"name": "priority", "value_type": "string", "color": "Default", "style": "bold",
+             "enum_values": [{"name": "HIGH", "color": "Red", "style": null}], "cond_format": [{"condition": "priority == \"HIGH\"", "color": "Red", "style": null}],
+             "sort_as": "numeric", "max_width": 10}
+        ]"#;
+        assert_eq!(PropertyManager::parse_properties(valid.to_string()).unwrap().len(), 2);
+
+        let error = |input: &str| PropertyManager::parse_properties(input.to_string()).err().unwrap();
+        assert!(error("{").starts_with("Invalid JSON"));
+        assert_eq!(error(r#"{"name": "id"}"#), "Expected a JSON array of properties");
+        assert_eq!(error(r#"["id"]"#), "property at index 0 is not an object");
+        assert_eq!(error(r#"[{"value_type": "string", "color": "Red"}]"#), "property at index 0 missing name");
+        assert_eq!(
+            error(r#"[{"name": "a", "value_type": "string", "color": "Red"}, {"name": "x", "color": "Red"}]"#),
+            "property 'x' missing value_type at index 1"
+        );
+        assert_eq!(error(r#"[{"name": "x", "value_type": "float", "color": "Red"}]"#), "property 'x' has unknown value_type 'float' at index 0");
+        assert_eq!(error(r#"[{"name": "x", "value_type": "string", "color": 5}]"#), "property 'x' has non-string color at index 0");
+        assert_eq!(
+            error(r#"[{"name": "x", "value_type": "string", "color": "Red", "enum_values": {"HIGH": "Red"}}]"#),
+            "property 'x' has invalid enum_values at index 0, expected an array"
+        );
+        assert_eq!(
+            error(r#"[{"name": "x", "value_type": "string", "color": "Red", "enum_values": [{"name": "HIGH"}]}]"#),
+            "property 'x' enum_values entry 0 missing color at index 0"
+        );
+        assert_eq!(
+            error(r#"[{"name": "x", "value_type": "string", "color": "Red", "cond_format": [["x > 1", "Red"]]}]"#),
+            "property 'x' has invalid cond_format entry 0 at index 0, expected an object"
+        );
+        assert_eq!(
+            error(r#"[{"name": "x", "value_type": "string", "color": "Red", "max_width": 0}]"#),
+            "property 'x' has invalid max_width at index 0, expected a positive integer"
+        );
+        assert_eq!(
+            error(r#"[{"name": "x", "value_type": "string", "color": "Red"}, {"name": "x", "value_type": "text", "color": "Red"}]"#),
+            "property 'x' is duplicated at index 1"
+        );
+        assert_eq!(
+            error(r#"[{"name": "x", "value_type": "string", "color": "Red", "datefmt": "%Q"}]"#),
+            "property 'x' has invalid datefmt '%Q' at index 0"
+        );
+    }
+}
@@ -179,10 +179,17 @@ impl StatusManager {
         }
     }
 
-    pub fn is_done(&self, status: &str) -> bool {
-        self.statuses.iter().find_map(|saved_status| {
-            if saved_status.name == status { Some(saved_status.is_done) } else { None }
-        }).unwrap_or(false)
+    pub fn is_closed_like(&self, status: &str) -> Option<bool> {
+        let listed = |param: &str| self.context.get_config_value(param)
+            .is_ok_and(|value| value.split(',').any(|s| s.trim() == status));
+
+        if self.context.is_closed_status(status) {
+            Some(true)
+        } else if listed("task.status.openlike") || status == self.get_starting_status() || self.get_in_progress_status().is_some_and(|s| s == status) {
+            Some(false)
+        } else {
+            None
+        }
     }
 
     pub fn get_property(&self, status: &str, property: &str) -> Option<String> {
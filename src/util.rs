@@ -5,10 +5,13 @@ use std::iter::Iterator;
 use std::process::Command;
 use std::time::{Duration, UNIX_EPOCH};
 
-use chrono::{DateTime, Local, MappedLocalTime, NaiveDate, TimeZone, Utc};
+use chrono::{DateTime, FixedOffset, Local, MappedLocalTime, NaiveDate, TimeZone, Utc};
+use chrono::format::{Item, StrftimeItems};
 use nu_ansi_term::{Color, Style};
 use nu_ansi_term::Color::{Black, Blue, Cyan, DarkGray, Default, Fixed, Green, LightBlue, LightCyan, LightGray, LightGreen, LightMagenta, LightPurple, LightRed, LightYellow, Magenta, Purple, Red, White, Yellow};
-use gittask::TaskContext;
+use gittask::{Label, TaskContext};
+
+use crate::operations::OpOutcome;
 
 pub trait ExpandRange {
     fn expand_range(self) -> impl Iterator<Item = String>;
@@ -48,8 +51,8 @@ pub fn capitalize(s: &str) -> String {
     }
 }
 
-pub fn str_to_color(color: &str, style: &Option<String>) -> Style {
-    let color = match color.to_lowercase().as_str() {
+pub fn str_to_ansi_color(color: &str) -> Color {
+    match color.to_lowercase().as_str() {
         "black" => Black,
         "darkgray" | "darkgrey" => DarkGray,
         "red" => Red,
@@ -77,7 +80,11 @@ pub fn str_to_color(color: &str, style: &Option<String>) -> Style {
                 }
             }
         }
-    };
+    }
+}
+
+pub fn str_to_color(color: &str, style: &Option<String>) -> Style {
+    let color = str_to_ansi_color(color);
 
     match style {
         Some(s) => {
@@ -99,6 +106,13 @@ pub fn str_to_color(color: &str, style: &Option<String>) -> Style {
     }
 }
 
+pub fn label_style(label: &Label) -> Style {
+    match str_to_rgb(&label.get_color()) {
+        Some(background) => str_to_color(label.contrasting_text_color(), &None).on(background),
+        None => str_to_color(label.get_color().as_str(), &None),
+    }
+}
+
 fn str_to_rgb(color: &str) -> Option<Color> {
     let color = color.trim_start_matches('#');
 
@@ -413,14 +427,55 @@ pub fn colorize_string(s: &str, color: Color, no_color: bool) -> String {
     if no_color { s.to_string() } else { color.paint(s).to_string() }
 }
 
-pub fn format_datetime(seconds: u64) -> String {
+/// Time zone timestamps are displayed in, configured with `task.time.tz`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayTimeZone {
+    Local,
+    Utc,
+    Offset(FixedOffset),
+}
+
+impl DisplayTimeZone {
+    /// Parses `local`, `utc` or a fixed offset like `+02:00`.
+    pub fn parse(value: &str) -> Option<DisplayTimeZone> {
+        match value.trim().to_lowercase().as_str() {
+            "local" => Some(DisplayTimeZone::Local),
+            "utc" | "z" => Some(DisplayTimeZone::Utc),
+            value => value.parse::<FixedOffset>().ok().map(DisplayTimeZone::Offset),
+        }
+    }
+
+    pub fn from_config(context: &TaskContext) -> DisplayTimeZone {
+        context.get_config_value("task.time.tz").ok()
+            .and_then(|value| DisplayTimeZone::parse(&value))
+            .unwrap_or(DisplayTimeZone::Local)
+    }
+}
+
+pub fn format_datetime(seconds: u64, tz: DisplayTimeZone) -> String {
+    format_datetime_with(seconds, "%Y-%m-%d %H:%M", tz)
+}
+
+pub fn validate_datetime_format(format: &str) -> Result<(), String> {
+    match StrftimeItems::new(format).any(|item| item == Item::Error) {
+        true => Err(format!("Invalid date format `{format}`")),
+        false => Ok(()),
+    }
+}
+
+pub fn format_datetime_with(seconds: u64, format: &str, tz: DisplayTimeZone) -> String {
     if seconds == 0 {
         return String::new();
     }
+    // chrono panics on unknown specifiers, formats saved before validation fall back to the default
+    let format = if validate_datetime_format(format).is_ok() { format } else { "%Y-%m-%d %H:%M" };
 
     let seconds = UNIX_EPOCH + Duration::from_secs(seconds);
-    let datetime = DateTime::<Local>::from(seconds);
-    datetime.format("%Y-%m-%d %H:%M").to_string()
+    match tz {
+        DisplayTimeZone::Local => DateTime::<Local>::from(seconds).format(format).to_string(),
+        DisplayTimeZone::Utc => DateTime::<Utc>::from(seconds).format(format).to_string(),
+        DisplayTimeZone::Offset(offset) => DateTime::<Utc>::from(seconds).with_timezone(&offset).format(format).to_string(),
+    }
 }
 
 pub fn parse_date(date: Option<String>) -> Option<MappedLocalTime<DateTime<Local>>> {
@@ -485,14 +540,28 @@ pub fn get_text_from_editor(context: &TaskContext, text: Option<&String>) -> Opt
     Some(contents)
 }
 
-pub fn success_message(message: String) -> bool {
+pub fn success_message(message: String) -> OpOutcome {
     println!("{message}");
-    true
+    OpOutcome::Success
 }
 
-pub fn error_message(message: String) -> bool {
+pub fn error_message(message: String) -> OpOutcome {
     eprintln!("{message}");
-    false
+    OpOutcome::UserError
+}
+
+pub fn system_error_message(message: String) -> OpOutcome {
+    eprintln!("{message}");
+    OpOutcome::SystemError
+}
+
+#[cfg(test)]
+pub(crate) fn init_test_repo(path: &std::path::Path) -> git2::Repository {
+    let repo = git2::Repository::init(path).unwrap();
+    let mut config = repo.config().unwrap().open_level(git2::ConfigLevel::Local).unwrap();
+    config.set_str("user.name", "Tester").unwrap();
+    config.set_str("user.email", "tester@example.com").unwrap();
+    repo
 }
 
 #[cfg(test)]
@@ -700,4 +769,30 @@ mod tests {
         let result = color_str_to_rgb_str(input);
         assert_eq!(result, expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_format_datetime_in_configured_time_zone() {
+        let repo_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(repo_dir.clone()).unwrap();
+        let _repo = init_test_repo(&repo_dir);
+        let context = TaskContext::new(repo_dir.display().to_string());
+        let seconds = 1_700_000_000;
+
+        context.set_config_value("task.time.tz", "utc").unwrap();
+        assert_eq!(format_datetime(seconds, DisplayTimeZone::from_config(&context)), "2023-11-14 22:13");
+
+        context.set_config_value("task.time.tz", "+05:30").unwrap();
+        let tz = DisplayTimeZone::from_config(&context);
+        assert_eq!(format_datetime(seconds, tz), "2023-11-15 03:43");
+        assert_eq!(format_datetime_with(seconds, "%H:%M %:z", tz), "03:43 +05:30");
+        assert_eq!(format_datetime_with(seconds, "%Q", tz), "2023-11-15 03:43");
+        assert!(validate_datetime_format("%Q").is_err());
+        assert!(validate_datetime_format("%Y-%m-%d").is_ok());
+
+        context.set_config_value("task.time.tz", "Mars/Olympus").unwrap();
+        assert_eq!(DisplayTimeZone::from_config(&context), DisplayTimeZone::Local);
+        assert_eq!(format_datetime(0, DisplayTimeZone::Utc), "");
+
+        std::fs::remove_dir_all(repo_dir).unwrap();
+    }
+}